@@ -0,0 +1,827 @@
+// ============================================================
+// Anabat 過零 (ZC) 文件讀取
+// 支援文件類型 129–132：文本頭、數據信息表、132 版的二進制時間戳與 GPS，
+// 間隔數據解碼為 (時間, 頻率) 點序列，可交給
+// SpectrogramEngine::compute_dots_image 以相同色彩映射渲染
+// ============================================================
+
+use std::fmt;
+
+use wasm_bindgen::prelude::*;
+
+use crate::guano::GuanoMetadata;
+use crate::timestamp::Timestamp;
+use crate::wamd::GpsPosition;
+
+/// 支援的最舊與最新文件類型
+pub const ANABAT_MIN_FILE_TYPE: u8 = 129;
+pub const ANABAT_MAX_FILE_TYPE: u8 = 132;
+
+/// RES1 的標準值：此時間隔計數單位為 1 µs
+const STANDARD_RES1: u16 = 25000;
+
+// 文本頭欄位 (偏移, 長度)
+const FIELD_TAPE: (usize, usize) = (0x06, 8);
+const FIELD_DATE: (usize, usize) = (0x0E, 8);
+const FIELD_LOCATION: (usize, usize) = (0x16, 40);
+const FIELD_SPECIES: (usize, usize) = (0x3E, 50);
+const FIELD_SPECIFICATION: (usize, usize) = (0x70, 16);
+const FIELD_NOTE1: (usize, usize) = (0x80, 73);
+const FIELD_NOTE2: (usize, usize) = (0xC9, 80);
+const HEADER_LEN: usize = 0x119;
+
+/// 132 版附加數據信息的偏移（時間戳、設備 ID、GPS）
+const EXTENDED_INFO_OFFSET: usize = 0x120;
+const EXTENDED_INFO_LEN: usize = 48;
+
+/// Anabat 解析錯誤
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AnabatError {
+    /// 文件短於固定文本頭
+    TooShort,
+    /// 不支援的文件類型
+    UnsupportedFileType(u8),
+    /// 數據信息表或數據指針超出文件範圍
+    InvalidPointer,
+    /// 分頻比為 0
+    InvalidDivRatio,
+}
+
+impl fmt::Display for AnabatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnabatError::TooShort => write!(f, "file is too short for an Anabat header"),
+            AnabatError::UnsupportedFileType(t) => write!(f, "unsupported Anabat file type {}", t),
+            AnabatError::InvalidPointer => write!(f, "data pointer is out of range"),
+            AnabatError::InvalidDivRatio => write!(f, "division ratio is zero"),
+        }
+    }
+}
+
+impl std::error::Error for AnabatError {}
+
+/// Anabat 文件頭
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AnabatHeader {
+    /// 文件類型 (129–132)
+    pub file_type: u8,
+    pub tape: String,
+    /// 文本日期欄位（132 版以前通常為 YYYYMMDD）
+    pub date: String,
+    pub location: String,
+    pub species: String,
+    pub specification: String,
+    pub note1: String,
+    pub note2: String,
+    /// 間隔數據在文件中的偏移
+    pub data_pointer: usize,
+    /// 時間解析度參數，25000 表示計數單位為 1 µs
+    pub res1: u16,
+    /// 分頻比（每個間隔包含的過零週期數）
+    pub div_ratio: u8,
+    pub vres: u8,
+    /// 錄音開始時間（僅 132 版，設備本地時間）
+    pub timestamp: Option<Timestamp>,
+    /// 設備 ID（僅 132 版）
+    pub device_id: Option<String>,
+    /// 原始 GPS 文本（僅 132 版）
+    pub gps: Option<String>,
+    pub position: Option<GpsPosition>,
+}
+
+/// 一個過零點
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ZcDot {
+    /// 時間 (秒，自文件開始)
+    pub time: f64,
+    /// 頻率 (Hz)
+    pub frequency: f64,
+    /// 被狀態字節標記為關閉（Anabat 預設不顯示）
+    pub off: bool,
+}
+
+/// 解析後的 Anabat 文件
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AnabatFile {
+    pub header: AnabatHeader,
+    pub dots: Vec<ZcDot>,
+    /// 所有間隔的總時長 (秒)
+    pub duration: f64,
+}
+
+/// 文本欄位：去除 NUL 填充與首尾空白
+fn text_field(bytes: &[u8], (offset, len): (usize, usize)) -> String {
+    let field = &bytes[offset..offset + len];
+    let end = field.iter().position(|&b| b == 0).unwrap_or(len);
+    String::from_utf8_lossy(&field[..end]).trim().to_string()
+}
+
+/// 解析 GPS 文本
+///
+/// Anabat 記錄器寫出的格式不一，例如 "WGS84 33.12345 S 115.12345 E 40"
+/// 或 "WGS84,-33.12345,115.12345"。取前兩個數字為緯度與經度，
+/// 其後緊接的 N/S/E/W 字母決定符號。
+fn parse_gps_text(text: &str) -> Option<GpsPosition> {
+    let tokens: Vec<&str> = text
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+        .collect();
+
+    let mut values: Vec<f64> = Vec::with_capacity(3);
+    let mut i = 0;
+    while i < tokens.len() && values.len() < 3 {
+        // 允許半球字母直接接在數字後面，例如 "33.12345S"
+        let token = tokens[i];
+        let (number, suffix) = match token.chars().last() {
+            Some(c) if matches!(c, 'N' | 'S' | 'E' | 'W') && token.len() > 1 => (&token[..token.len() - 1], Some(c)),
+            _ => (token, None),
+        };
+        if let Ok(mut value) = number.parse::<f64>() {
+            let hemisphere = suffix.or_else(|| match tokens.get(i + 1) {
+                Some(&"N") | Some(&"S") | Some(&"E") | Some(&"W") => {
+                    i += 1;
+                    tokens[i].chars().next()
+                }
+                _ => None,
+            });
+            if matches!(hemisphere, Some('S') | Some('W')) {
+                value = -value.abs();
+            }
+            values.push(value);
+        }
+        i += 1;
+    }
+
+    if values.len() < 2 || values[0].abs() > 90.0 || values[1].abs() > 180.0 {
+        return None;
+    }
+    Some(GpsPosition {
+        latitude: values[0],
+        longitude: values[1],
+        altitude: values.get(2).copied(),
+    })
+}
+
+/// 解析 132 版的附加數據信息
+fn parse_extended_info(bytes: &[u8], header: &mut AnabatHeader) {
+    let Some(info) = bytes.get(EXTENDED_INFO_OFFSET..EXTENDED_INFO_OFFSET + EXTENDED_INFO_LEN) else {
+        return;
+    };
+    let year = u16::from_le_bytes([info[0], info[1]]) as i32;
+    let hundredths = info[7] as u32;
+    let microseconds = u16::from_le_bytes([info[8], info[9]]) as u32;
+    header.timestamp = Timestamp::new(
+        year,
+        info[2] as u32,
+        info[3] as u32,
+        info[4] as u32,
+        info[5] as u32,
+        info[6] as u32,
+        (hundredths * 10_000 + microseconds).min(999_999),
+        None,
+    );
+
+    let text = |data: &[u8]| {
+        let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+        let text = String::from_utf8_lossy(&data[..end]).trim().to_string();
+        (!text.is_empty()).then_some(text)
+    };
+    header.device_id = text(&info[10..16]);
+    header.gps = text(&info[16..48]);
+    header.position = header.gps.as_deref().and_then(parse_gps_text);
+}
+
+/// 解碼間隔數據
+///
+/// 每個間隔以下列形式之一存放（計數單位由 RES1 決定）：
+/// - `0xxxxxxx`：7 位有符號差值，加到上一個間隔
+/// - `100xxxxx` + 1 字節：13 位絕對值
+/// - `101xxxxx` + 2 字節：21 位絕對值
+/// - `110xxxxx` + 3 字節：29 位絕對值
+/// - `111xxxss` + 1 字節 N：狀態字節，狀態 ss 作用於其後 N 個點（1 = 關閉）
+///
+/// # Returns
+/// (點序列, 總時長秒)
+fn decode_intervals(data: &[u8], res1: u16, div_ratio: u8) -> (Vec<ZcDot>, f64) {
+    // 計數 → 秒
+    let tick = 1e-6 * STANDARD_RES1 as f64 / res1.max(1) as f64;
+    let mut dots = Vec::with_capacity(data.len());
+    let mut pos = 0;
+    let mut interval: i64 = 0;
+    let mut elapsed: i64 = 0;
+    let mut off_remaining = 0usize;
+
+    while pos < data.len() {
+        let byte = data[pos];
+        let extra = match byte >> 5 {
+            0..=3 => 0,
+            4 => 1,
+            5 => 2,
+            6 => 3,
+            _ => 1,
+        };
+        if pos + extra >= data.len() {
+            break;
+        }
+
+        let accum = data[pos + 1..=pos + extra]
+            .iter()
+            .fold((byte & 0x1F) as i64, |acc, &b| (acc << 8) | b as i64);
+        pos += extra + 1;
+
+        match byte >> 5 {
+            0..=3 => {
+                // 7 位有符號：0x40..0x7F 表示負值
+                let delta = (byte & 0x7F) as i64;
+                interval += if delta >= 0x40 { delta - 0x80 } else { delta };
+            }
+            4..=6 => interval = accum,
+            _ => {
+                let status = byte & 0x03;
+                let count = data[pos - 1] as usize;
+                off_remaining = if status == 1 { count } else { 0 };
+                continue;
+            }
+        }
+
+        if interval <= 0 {
+            // 無效間隔：不產生點，也不推進時間
+            off_remaining = off_remaining.saturating_sub(1);
+            continue;
+        }
+        elapsed += interval;
+        let seconds = interval as f64 * tick;
+        dots.push(ZcDot {
+            time: elapsed as f64 * tick,
+            frequency: div_ratio as f64 / seconds,
+            off: off_remaining > 0,
+        });
+        off_remaining = off_remaining.saturating_sub(1);
+    }
+
+    (dots, elapsed as f64 * tick)
+}
+
+/// 解析 Anabat ZC 文件
+///
+/// # Arguments
+/// * `bytes` - 文件內容
+///
+/// # Returns
+/// 文件頭與點序列，或解析錯誤
+pub fn parse_anabat(bytes: &[u8]) -> Result<AnabatFile, AnabatError> {
+    if bytes.len() < HEADER_LEN {
+        return Err(AnabatError::TooShort);
+    }
+    let file_type = bytes[3];
+    if !(ANABAT_MIN_FILE_TYPE..=ANABAT_MAX_FILE_TYPE).contains(&file_type) {
+        return Err(AnabatError::UnsupportedFileType(file_type));
+    }
+
+    // 數據信息表：1 字節保留、數據指針、RES1、分頻比、VRES
+    let info_pointer = u16::from_le_bytes([bytes[0], bytes[1]]) as usize;
+    let info = bytes.get(info_pointer..info_pointer + 7).ok_or(AnabatError::InvalidPointer)?;
+    let data_pointer = u16::from_le_bytes([info[1], info[2]]) as usize;
+    let res1 = u16::from_le_bytes([info[3], info[4]]);
+    let div_ratio = info[5];
+    if data_pointer > bytes.len() || data_pointer < HEADER_LEN {
+        return Err(AnabatError::InvalidPointer);
+    }
+    if div_ratio == 0 {
+        return Err(AnabatError::InvalidDivRatio);
+    }
+
+    let mut header = AnabatHeader {
+        file_type,
+        tape: text_field(bytes, FIELD_TAPE),
+        date: text_field(bytes, FIELD_DATE),
+        location: text_field(bytes, FIELD_LOCATION),
+        species: text_field(bytes, FIELD_SPECIES),
+        specification: text_field(bytes, FIELD_SPECIFICATION),
+        note1: text_field(bytes, FIELD_NOTE1),
+        note2: text_field(bytes, FIELD_NOTE2),
+        data_pointer,
+        res1,
+        div_ratio,
+        vres: info[6],
+        ..AnabatHeader::default()
+    };
+    if file_type >= 132 {
+        parse_extended_info(bytes, &mut header);
+    }
+
+    let (dots, duration) = decode_intervals(&bytes[data_pointer..], res1, div_ratio);
+    Ok(AnabatFile { header, dots, duration })
+}
+
+/// 寫入一個間隔：與上一間隔的差值在 7 位範圍內時用 1 字節，否則用最短的絕對值形式
+fn write_interval(out: &mut Vec<u8>, interval: i64, previous: i64) {
+    let delta = interval - previous;
+    if (-64..=63).contains(&delta) {
+        out.push((delta & 0x7F) as u8);
+    } else if interval < 1 << 13 {
+        out.extend_from_slice(&[0x80 | (interval >> 8) as u8, interval as u8]);
+    } else if interval < 1 << 21 {
+        out.extend_from_slice(&[0xA0 | (interval >> 16) as u8, (interval >> 8) as u8, interval as u8]);
+    } else {
+        let interval = interval.min((1 << 29) - 1);
+        out.extend_from_slice(&[
+            0xC0 | (interval >> 24) as u8,
+            (interval >> 16) as u8,
+            (interval >> 8) as u8,
+            interval as u8,
+        ]);
+    }
+}
+
+/// 寫入定長文本欄位（以空白填充，過長時截斷）
+fn write_text_field(out: &mut [u8], (offset, len): (usize, usize), text: &str) {
+    let field = &mut out[offset..offset + len];
+    field.fill(b' ');
+    let bytes = text.as_bytes();
+    let n = bytes.len().min(len);
+    field[..n].copy_from_slice(&bytes[..n]);
+}
+
+/// 將點序列編碼為 132 版 Anabat ZC 文件
+///
+/// 每個點的間隔由頻率推算（分頻比 / 頻率）。若點之前的間隔開始時間明顯晚於上一個點，
+/// 中間的空白寫成一個關閉的間隔，讀取時時間軸保持不變。
+/// 計數單位固定為 1 µs（RES1 = 25000）；文件類型、數據指針與 RES1 忽略 header 中的值。
+///
+/// # Arguments
+/// * `header` - 文件頭欄位（文本、分頻比、時間戳、設備 ID、GPS）
+/// * `dots` - 按時間排序的點序列
+///
+/// # Returns
+/// 完整的文件內容
+pub fn encode_anabat(header: &AnabatHeader, dots: &[ZcDot]) -> Vec<u8> {
+    let data_pointer = EXTENDED_INFO_OFFSET + EXTENDED_INFO_LEN;
+    let info_pointer = HEADER_LEN + 1;
+    let div_ratio = header.div_ratio.max(1);
+    let mut out = vec![0u8; data_pointer];
+
+    out[0..2].copy_from_slice(&(info_pointer as u16).to_le_bytes());
+    out[3] = ANABAT_MAX_FILE_TYPE;
+    write_text_field(&mut out, FIELD_TAPE, &header.tape);
+    write_text_field(&mut out, FIELD_DATE, &header.date);
+    write_text_field(&mut out, FIELD_LOCATION, &header.location);
+    write_text_field(&mut out, FIELD_SPECIES, &header.species);
+    write_text_field(&mut out, FIELD_SPECIFICATION, &header.specification);
+    write_text_field(&mut out, FIELD_NOTE1, &header.note1);
+    write_text_field(&mut out, FIELD_NOTE2, &header.note2);
+
+    // 數據信息表
+    out[info_pointer + 1..info_pointer + 3].copy_from_slice(&(data_pointer as u16).to_le_bytes());
+    out[info_pointer + 3..info_pointer + 5].copy_from_slice(&STANDARD_RES1.to_le_bytes());
+    out[info_pointer + 5] = div_ratio;
+    out[info_pointer + 6] = header.vres;
+
+    // 132 版附加數據信息
+    let info = &mut out[EXTENDED_INFO_OFFSET..data_pointer];
+    if let Some(ts) = &header.timestamp {
+        info[0..2].copy_from_slice(&(ts.year.clamp(0, u16::MAX as i32) as u16).to_le_bytes());
+        info[2] = ts.month as u8;
+        info[3] = ts.day as u8;
+        info[4] = ts.hour as u8;
+        info[5] = ts.minute as u8;
+        info[6] = ts.second as u8;
+        info[7] = (ts.microsecond / 10_000) as u8;
+        info[8..10].copy_from_slice(&((ts.microsecond % 10_000) as u16).to_le_bytes());
+    }
+    if let Some(id) = &header.device_id {
+        let n = id.len().min(6);
+        info[10..10 + n].copy_from_slice(&id.as_bytes()[..n]);
+    }
+    let gps = header.gps.clone().or_else(|| {
+        header.position.map(|p| {
+            format!(
+                "WGS84 {:.5} {} {:.5} {}",
+                p.latitude.abs(),
+                if p.latitude < 0.0 { 'S' } else { 'N' },
+                p.longitude.abs(),
+                if p.longitude < 0.0 { 'W' } else { 'E' }
+            )
+        })
+    });
+    if let Some(gps) = gps {
+        let n = gps.len().min(32);
+        info[16..16 + n].copy_from_slice(&gps.as_bytes()[..n]);
+    }
+
+    // 間隔數據 (µs)
+    let mut last_event: i64 = 0;
+    let mut previous: i64 = 0;
+    for dot in dots {
+        if dot.frequency <= 0.0 || !dot.frequency.is_finite() {
+            continue;
+        }
+        let event = (dot.time * 1e6).round() as i64;
+        let mut interval = ((div_ratio as f64 / dot.frequency) * 1e6).round().max(1.0) as i64;
+        let start = event - interval;
+        // 四捨五入到 µs 會產生 1 個計數的誤差，只有明顯的空白才寫成關閉的間隔
+        if start - last_event > interval / 2 {
+            out.extend_from_slice(&[0xE1, 1]);
+            write_interval(&mut out, start - last_event, previous);
+            previous = start - last_event;
+        } else {
+            interval = event - last_event;
+            if interval <= 0 {
+                continue;
+            }
+        }
+        if dot.off {
+            out.extend_from_slice(&[0xE1, 1]);
+        }
+        write_interval(&mut out, interval, previous);
+        previous = interval;
+        last_event = event;
+    }
+
+    out
+}
+
+impl AnabatFile {
+    /// 映射為 GUANO 元數據
+    ///
+    /// 時間戳、位置、物種與註記寫入標準欄位，其餘頭欄位寫入 `Anabat|` 命名空間。
+    pub fn to_guano(&self) -> GuanoMetadata {
+        let header = &self.header;
+        let mut guano = GuanoMetadata::new();
+        guano.set("Make", "Titley Scientific");
+
+        if let Some(id) = &header.device_id {
+            guano.set("Serial", id);
+        }
+        if let Some(ts) = &header.timestamp {
+            guano.set_timestamp_value(ts);
+        }
+        if let Some(position) = &header.position {
+            guano.set_location(position.latitude, position.longitude);
+            if let Some(altitude) = position.altitude {
+                guano.set("Loc Elevation", &altitude.to_string());
+            }
+        }
+        if !header.species.is_empty() {
+            guano.set("Species Manual ID", &header.species);
+        }
+        let note: Vec<&str> = [header.note1.as_str(), header.note2.as_str()]
+            .into_iter()
+            .filter(|s| !s.is_empty())
+            .collect();
+        if !note.is_empty() {
+            guano.set("Note", &note.join("\n"));
+        }
+        guano.set("Length", &format!("{:.6}", self.duration));
+
+        let mut set = |key: &str, value: &str| {
+            if !value.is_empty() {
+                guano.set(key, value);
+            }
+        };
+        set("Anabat|File Type", &header.file_type.to_string());
+        set("Anabat|Div Ratio", &header.div_ratio.to_string());
+        set("Anabat|Tape", &header.tape);
+        set("Anabat|Date", &header.date);
+        set("Anabat|Location", &header.location);
+        set("Anabat|Specification", &header.specification);
+
+        guano
+    }
+}
+
+/// AnabatReader: 在 Rust 端讀取 Anabat ZC 文件
+///
+/// 點的時間與頻率可直接傳給 SpectrogramEngine::compute_dots_image。
+#[wasm_bindgen]
+pub struct AnabatReader {
+    file: AnabatFile,
+    error: Option<AnabatError>,
+}
+
+#[wasm_bindgen]
+impl AnabatReader {
+    /// 解析 Anabat ZC 文件
+    ///
+    /// # Arguments
+    /// * `bytes` - 文件內容 (Uint8Array)
+    #[wasm_bindgen(constructor)]
+    pub fn new(bytes: &[u8]) -> AnabatReader {
+        match parse_anabat(bytes) {
+            Ok(file) => AnabatReader { file, error: None },
+            Err(err) => AnabatReader {
+                file: AnabatFile::default(),
+                error: Some(err),
+            },
+        }
+    }
+
+    /// 是否成功解析
+    #[wasm_bindgen]
+    pub fn is_valid(&self) -> bool {
+        self.error.is_none()
+    }
+
+    /// 解析失敗的原因（成功時為空字串）
+    #[wasm_bindgen]
+    pub fn get_error(&self) -> String {
+        self.error.as_ref().map(|e| e.to_string()).unwrap_or_default()
+    }
+
+    /// 文件類型 (129–132)
+    #[wasm_bindgen]
+    pub fn get_file_type(&self) -> u8 {
+        self.file.header.file_type
+    }
+
+    /// 分頻比
+    #[wasm_bindgen]
+    pub fn get_div_ratio(&self) -> u8 {
+        self.file.header.div_ratio
+    }
+
+    /// 總時長 (秒)
+    #[wasm_bindgen]
+    pub fn get_duration(&self) -> f64 {
+        self.file.duration
+    }
+
+    /// 點數量
+    ///
+    /// # Arguments
+    /// * `include_off` - 是否包含被標記為關閉的點
+    #[wasm_bindgen]
+    pub fn get_num_dots(&self, include_off: bool) -> usize {
+        self.file.dots.iter().filter(|d| include_off || !d.off).count()
+    }
+
+    /// 點的時間 (秒)
+    ///
+    /// # Arguments
+    /// * `include_off` - 是否包含被標記為關閉的點
+    ///
+    /// # Returns
+    /// Float32Array，與 get_frequencies 一一對應
+    #[wasm_bindgen]
+    pub fn get_times(&self, include_off: bool) -> Vec<f32> {
+        self.file
+            .dots
+            .iter()
+            .filter(|d| include_off || !d.off)
+            .map(|d| d.time as f32)
+            .collect()
+    }
+
+    /// 點的頻率 (Hz)
+    ///
+    /// # Arguments
+    /// * `include_off` - 是否包含被標記為關閉的點
+    ///
+    /// # Returns
+    /// Float32Array，與 get_times 一一對應
+    #[wasm_bindgen]
+    pub fn get_frequencies(&self, include_off: bool) -> Vec<f32> {
+        self.file
+            .dots
+            .iter()
+            .filter(|d| include_off || !d.off)
+            .map(|d| d.frequency as f32)
+            .collect()
+    }
+
+    #[wasm_bindgen]
+    pub fn get_tape(&self) -> String {
+        self.file.header.tape.clone()
+    }
+
+    #[wasm_bindgen]
+    pub fn get_date(&self) -> String {
+        self.file.header.date.clone()
+    }
+
+    #[wasm_bindgen]
+    pub fn get_location(&self) -> String {
+        self.file.header.location.clone()
+    }
+
+    #[wasm_bindgen]
+    pub fn get_species(&self) -> String {
+        self.file.header.species.clone()
+    }
+
+    #[wasm_bindgen]
+    pub fn get_specification(&self) -> String {
+        self.file.header.specification.clone()
+    }
+
+    /// 兩行註記（以換行連接）
+    #[wasm_bindgen]
+    pub fn get_notes(&self) -> String {
+        format!("{}\n{}", self.file.header.note1, self.file.header.note2)
+            .trim()
+            .to_string()
+    }
+
+    /// 錄音開始時間 (ISO 8601，僅 132 版)
+    #[wasm_bindgen]
+    pub fn get_timestamp(&self) -> Option<String> {
+        self.file.header.timestamp.map(|ts| ts.to_string())
+    }
+
+    /// 文件頭映射的 GUANO 元數據
+    #[wasm_bindgen]
+    pub fn get_metadata(&self) -> GuanoMetadata {
+        self.file.to_guano()
+    }
+
+    /// 釋放點序列
+    #[wasm_bindgen]
+    pub fn release_memory(&mut self) {
+        self.file.dots = Vec::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 手工構造的文件：數據信息表緊接文本頭，間隔數據從 0x150 開始
+    fn fixture(file_type: u8, res1: u16, div_ratio: u8, data: &[u8]) -> Vec<u8> {
+        let data_pointer = EXTENDED_INFO_OFFSET + EXTENDED_INFO_LEN;
+        let mut bytes = vec![0u8; data_pointer];
+        bytes[0..2].copy_from_slice(&0x11Au16.to_le_bytes());
+        bytes[3] = file_type;
+        bytes[0x11B..0x11D].copy_from_slice(&(data_pointer as u16).to_le_bytes());
+        bytes[0x11D..0x11F].copy_from_slice(&res1.to_le_bytes());
+        bytes[0x11F] = div_ratio;
+        bytes.extend_from_slice(data);
+        bytes
+    }
+
+    fn intervals(file: &AnabatFile) -> Vec<i64> {
+        let mut previous = 0.0;
+        file.dots
+            .iter()
+            .map(|d| {
+                let interval = ((d.time - previous) * 1e6).round() as i64;
+                previous = d.time;
+                interval
+            })
+            .collect()
+    }
+
+    #[test]
+    fn decodes_interval_forms() {
+        let data = [
+            0x81, 0xF4, // 13 位絕對值 500
+            0x0A, // +10 → 510
+            0x7E, // -2 → 508
+            0xA0, 0x03, 0xE8, // 21 位絕對值 1000
+            0xC0, 0x01, 0x86, 0xA0, // 29 位絕對值 100000
+            0x80, 0x00, // 0：無效間隔，不產生點
+            0x40, // -64 → 仍 ≤ 0
+            0x82, 0x00, // 512
+            0xA0, 0x01, // 截斷的 21 位值被忽略
+        ];
+        let file = parse_anabat(&fixture(130, 25000, 8, &data)).unwrap();
+        assert_eq!(intervals(&file), vec![500, 510, 508, 1000, 100_000, 512]);
+        assert!((file.duration - 103_030e-6).abs() < 1e-9);
+        assert!((file.dots[0].frequency - 16_000.0).abs() < 1e-6);
+        assert!((file.dots[4].frequency - 80.0).abs() < 1e-9);
+        assert!(file.dots.iter().all(|d| !d.off));
+
+        // RES1 = 50000 時計數單位為 0.5 µs
+        let file = parse_anabat(&fixture(129, 50000, 16, &[0x83, 0xE8])).unwrap();
+        assert!((file.dots[0].time - 500e-6).abs() < 1e-12);
+        assert!((file.dots[0].frequency - 32_000.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn status_bytes_mark_dots_off() {
+        let data = [
+            0xE1, 2, // 其後 2 個點關閉
+            0x81, 0x00, 0x01, 0x01, 0x01, //
+            0xE1, 3, // 3 個點關閉，但被下一個狀態字節提前結束
+            0x01, 0xE0, 0, 0x01, //
+        ];
+        let file = parse_anabat(&fixture(131, 25000, 8, &data)).unwrap();
+        let off: Vec<bool> = file.dots.iter().map(|d| d.off).collect();
+        assert_eq!(off, vec![true, true, false, false, true, false]);
+        assert_eq!(intervals(&file), vec![256, 257, 258, 259, 260, 261]);
+    }
+
+    #[test]
+    fn reads_header_fields() {
+        let mut bytes = fixture(132, 25000, 8, &[0x81, 0xF4]);
+        write_text_field(&mut bytes, FIELD_TAPE, "TAPE01");
+        write_text_field(&mut bytes, FIELD_DATE, "20240501");
+        write_text_field(&mut bytes, FIELD_LOCATION, "Site A");
+        write_text_field(&mut bytes, FIELD_SPECIES, "Nyctophilus");
+        write_text_field(&mut bytes, FIELD_SPECIFICATION, "SD2");
+        write_text_field(&mut bytes, FIELD_NOTE1, "first note");
+        bytes[FIELD_NOTE2.0..FIELD_NOTE2.0 + 6].copy_from_slice(b"second");
+        let info = &mut bytes[EXTENDED_INFO_OFFSET..];
+        info[0..2].copy_from_slice(&2024u16.to_le_bytes());
+        info[2..8].copy_from_slice(&[5, 1, 21, 30, 15, 25]);
+        info[8..10].copy_from_slice(&1234u16.to_le_bytes());
+        info[10..16].copy_from_slice(b"A12345");
+        info[16..45].copy_from_slice(b"WGS84 33.12345 S 115.54321 E ");
+
+        let file = parse_anabat(&bytes).unwrap();
+        let header = &file.header;
+        assert_eq!(header.file_type, 132);
+        assert_eq!((header.data_pointer, header.res1, header.div_ratio), (0x150, 25000, 8));
+        assert_eq!(header.tape, "TAPE01");
+        assert_eq!(header.date, "20240501");
+        assert_eq!(header.location, "Site A");
+        assert_eq!(header.species, "Nyctophilus");
+        assert_eq!(header.specification, "SD2");
+        assert_eq!(header.note1, "first note");
+        assert_eq!(header.note2, "second");
+        assert_eq!(header.timestamp.unwrap().to_string(), "2024-05-01T21:30:15.251234");
+        assert_eq!(header.device_id.as_deref(), Some("A12345"));
+        let position = header.position.unwrap();
+        assert_eq!((position.latitude, position.longitude), (-33.12345, 115.54321));
+
+        // 132 版以前不讀取附加數據信息
+        bytes[3] = 131;
+        assert_eq!(parse_anabat(&bytes).unwrap().header.timestamp, None);
+    }
+
+    #[test]
+    fn parses_gps_text_variants() {
+        let p = parse_gps_text("WGS84,-33.12345,115.12345").unwrap();
+        assert_eq!((p.latitude, p.longitude, p.altitude), (-33.12345, 115.12345, None));
+        let p = parse_gps_text("WGS84 51.5N 0.12W 40").unwrap();
+        assert_eq!((p.latitude, p.longitude, p.altitude), (51.5, -0.12, Some(40.0)));
+        assert!(parse_gps_text("WGS84 95.0 N 10.0 E").is_none());
+        assert!(parse_gps_text("no fix").is_none());
+    }
+
+    #[test]
+    fn rejects_invalid_files() {
+        assert_eq!(parse_anabat(&[0u8; 100]), Err(AnabatError::TooShort));
+        assert_eq!(parse_anabat(&fixture(128, 25000, 8, &[])), Err(AnabatError::UnsupportedFileType(128)));
+        assert_eq!(parse_anabat(&fixture(130, 25000, 0, &[])), Err(AnabatError::InvalidDivRatio));
+
+        let mut bytes = fixture(130, 25000, 8, &[]);
+        bytes[0x11B..0x11D].copy_from_slice(&0x100u16.to_le_bytes());
+        assert_eq!(parse_anabat(&bytes), Err(AnabatError::InvalidPointer));
+        bytes[0..2].copy_from_slice(&0xFFF0u16.to_le_bytes());
+        assert_eq!(parse_anabat(&bytes), Err(AnabatError::InvalidPointer));
+    }
+
+    #[test]
+    fn encode_parse_round_trip() {
+        // 兩段 FM 掃頻，中間相隔 50 ms，第二段的前兩個點關閉
+        let div_ratio = 8u8;
+        let mut dots = Vec::new();
+        let mut time = 0.01;
+        for segment in 0..2 {
+            for i in 0..40 {
+                let frequency = 60_000.0 - i as f64 * 1000.0;
+                time += div_ratio as f64 / frequency;
+                dots.push(ZcDot {
+                    time,
+                    frequency,
+                    off: segment == 1 && i < 2,
+                });
+            }
+            time += 0.05;
+        }
+        let header = AnabatHeader {
+            species: "Myotis".to_string(),
+            note1: "round trip".to_string(),
+            div_ratio,
+            timestamp: Timestamp::parse("2024-05-01T21:30:15.25"),
+            device_id: Some("A12345".to_string()),
+            position: Some(GpsPosition {
+                latitude: -33.5,
+                longitude: 115.25,
+                altitude: None,
+            }),
+            ..AnabatHeader::default()
+        };
+
+        let file = parse_anabat(&encode_anabat(&header, &dots)).unwrap();
+        assert_eq!(file.header.file_type, 132);
+        assert_eq!(file.header.div_ratio, div_ratio);
+        assert_eq!(file.header.species, "Myotis");
+        assert_eq!(file.header.note1, "round trip");
+        assert_eq!(file.header.timestamp.unwrap().to_string(), "2024-05-01T21:30:15.250000");
+        assert_eq!(file.header.device_id.as_deref(), Some("A12345"));
+        assert_eq!(file.header.position, header.position);
+
+        // 每段前的空白各寫成一個關閉的間隔
+        assert_eq!(file.dots.len(), dots.len() + 2);
+        for dot in &dots {
+            let decoded = file.dots.iter().find(|d| (d.time - dot.time).abs() < 1e-6).unwrap();
+            // 時間量化為 1 µs，間隔最多相差一個計數
+            let error = div_ratio as f64 / decoded.frequency - div_ratio as f64 / dot.frequency;
+            assert!(error.abs() <= 1.0e-6 + 1e-9, "{:?} {:?}", dot, decoded);
+            assert_eq!(decoded.off, dot.off);
+        }
+        assert_eq!(file.dots.iter().filter(|d| d.off).count(), 4);
+        assert!((file.duration - dots.last().unwrap().time).abs() < 1e-6);
+    }
+}
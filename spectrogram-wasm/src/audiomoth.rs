@@ -0,0 +1,511 @@
+// ============================================================
+// AudioMoth 註釋元數據
+// AudioMoth 將錄音時間、時區、設備 ID、增益、電池電壓與溫度以自由文本
+// 寫入 LIST/INFO 的 ICMT（註釋）與 IART（藝術家）子塊，此處解析為結構化資料，
+// 並映射為 GUANO 欄位
+// ============================================================
+
+use wasm_bindgen::prelude::*;
+
+use crate::guano::GuanoMetadata;
+use crate::timestamp::Timestamp;
+use crate::wav::{parse_wav, read_info_entries};
+
+/// 取出 `marker` 之後的文本
+fn after<'a>(text: &'a str, marker: &str) -> Option<&'a str> {
+    text.find(marker).map(|idx| &text[idx + marker.len()..])
+}
+
+/// 取出下一個以空白分隔的單詞，並去除尾部標點
+fn next_word(text: &str) -> Option<&str> {
+    text.split_whitespace()
+        .next()
+        .map(|w| w.trim_end_matches(['.', ',', ';', ')']))
+        .filter(|w| !w.is_empty())
+}
+
+/// 解析帶單位後綴的數字（例如 "4.2V"、"22.3C"）
+fn number_with_unit(word: &str, unit: char) -> Option<f32> {
+    word.trim_end_matches(unit).parse().ok()
+}
+
+/// 解析 "UTC"、"UTC+8"、"UTC-5:30"、"UTC+10:00" 形式的時區
+fn parse_utc_zone(zone: &str) -> Option<i32> {
+    let offset = zone.trim().strip_prefix("UTC")?;
+    if offset.is_empty() {
+        return Some(0);
+    }
+    let sign = match offset.as_bytes()[0] {
+        b'+' => 1,
+        b'-' => -1,
+        _ => return None,
+    };
+    let (hours, minutes) = match offset[1..].split_once(':') {
+        Some((h, m)) => (h.parse::<i32>().ok()?, m.parse::<i32>().ok()?),
+        None => (offset[1..].parse::<i32>().ok()?, 0),
+    };
+    if !(0..=14).contains(&hours) || !(0..60).contains(&minutes) {
+        return None;
+    }
+    Some(sign * (hours * 60 + minutes))
+}
+
+/// 解析 "HH:MM:SS DD/MM/YYYY (UTC+8)"
+fn parse_recorded_at(text: &str) -> Option<Timestamp> {
+    let mut words = text.split_whitespace();
+    let time = words.next()?;
+    let date = words.next()?;
+
+    let mut time_parts = time.split(':').map(|v| v.parse::<u32>().ok());
+    let hour = time_parts.next()??;
+    let minute = time_parts.next()??;
+    let second = time_parts.next().flatten().unwrap_or(0);
+
+    let mut date_parts = date.split('/').map(|v| v.parse::<u32>().ok());
+    let day = date_parts.next()??;
+    let month = date_parts.next()??;
+    let year = date_parts.next()?? as i32;
+
+    let zone = text.find('(').and_then(|start| {
+        let end = text[start..].find(')')? + start;
+        parse_utc_zone(&text[start + 1..end])
+    });
+    Timestamp::new(year, month, day, hour, minute, second, 0, zone)
+}
+
+/// 解析 AudioMoth 文件名 "YYYYMMDD_HHMMSS"（UTC）
+///
+/// # Arguments
+/// * `file_name` - 文件名（可帶副檔名與路徑）
+pub fn parse_audiomoth_file_name(file_name: &str) -> Option<Timestamp> {
+    let name = file_name.rsplit(['/', '\\']).next()?;
+    let stem = name.split('.').next()?;
+    let (date, time) = stem.split_once('_')?;
+    // 先確認為 ASCII 數字再按字節切片，非 ASCII 文件名不會落在字元中間
+    let time = time.get(..6)?;
+    if date.len() != 8 || !date.bytes().chain(time.bytes()).all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let digits = |s: &str| s.parse::<u32>().ok();
+    Timestamp::new(
+        digits(&date[0..4])? as i32,
+        digits(&date[4..6])?,
+        digits(&date[6..8])?,
+        digits(&time[0..2])?,
+        digits(&time[2..4])?,
+        digits(&time[4..6])?,
+        0,
+        Some(0),
+    )
+}
+
+/// AudioMothMetadata: 從 ICMT / IART 註釋解析的 AudioMoth 元數據
+#[wasm_bindgen]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AudioMothMetadata {
+    timestamp: Option<Timestamp>,
+    device_id: String,
+    gain: String,
+    battery_voltage: Option<f32>,
+    /// 電池電壓超出量測範圍時的描述（"less than" / "greater than"）
+    battery_bound: String,
+    temperature: Option<f32>,
+    amplitude_threshold: Option<f32>,
+    filter: String,
+    stop_reason: String,
+    comment: String,
+}
+
+#[wasm_bindgen]
+impl AudioMothMetadata {
+    /// 從 WAV 文件的 LIST/INFO 中讀取 AudioMoth 元數據
+    ///
+    /// # Arguments
+    /// * `bytes` - WAV 文件內容 (Uint8Array)
+    ///
+    /// # Returns
+    /// 元數據；不是 AudioMoth 文件時 `is_valid()` 為 false
+    #[wasm_bindgen]
+    pub fn from_wav(bytes: &[u8]) -> AudioMothMetadata {
+        Self::read_from_wav(bytes).unwrap_or_default()
+    }
+
+    /// 解析 ICMT 註釋文本
+    ///
+    /// # Arguments
+    /// * `comment` - ICMT 內容
+    /// * `artist` - IART 內容（可為空字串）
+    #[wasm_bindgen]
+    pub fn parse(comment: &str, artist: &str) -> AudioMothMetadata {
+        let mut metadata = AudioMothMetadata {
+            comment: comment.to_string(),
+            ..Default::default()
+        };
+
+        metadata.timestamp = after(comment, "Recorded at ").and_then(parse_recorded_at);
+
+        metadata.device_id = after(comment, "by AudioMoth ")
+            .or_else(|| after(artist, "AudioMoth "))
+            .and_then(next_word)
+            .unwrap_or_default()
+            .to_string();
+
+        // 增益的幾種寫法："at medium gain"、"at gain setting 2"、"at medium gain setting"
+        if let Some(rest) = after(comment, "at gain setting ") {
+            metadata.gain = next_word(rest).unwrap_or_default().to_string();
+        } else if let Some(idx) = comment.find(" gain") {
+            metadata.gain = comment[..idx].rsplit(' ').next().unwrap_or_default().to_string();
+        }
+
+        if let Some(rest) = after(comment, "battery state was ").or_else(|| after(comment, "battery was ")) {
+            let rest = rest.trim_start();
+            let rest = ["less than ", "greater than "]
+                .iter()
+                .find(|bound| rest.starts_with(**bound))
+                .map_or(rest, |bound| {
+                    metadata.battery_bound = bound.trim().to_string();
+                    &rest[bound.len()..]
+                });
+            metadata.battery_voltage = next_word(rest).and_then(|w| number_with_unit(w, 'V'));
+        }
+
+        metadata.temperature = after(comment, "temperature was ")
+            .and_then(next_word)
+            .and_then(|w| number_with_unit(w, 'C'));
+
+        metadata.amplitude_threshold = after(comment, "Amplitude threshold was ")
+            .and_then(next_word)
+            .and_then(|w| w.parse().ok());
+
+        if let Some(idx) = comment.find("filter applied") {
+            let start = comment[..idx].rfind(". ").map_or(0, |p| p + 2);
+            // 截止頻率本身含小數點，以句末的 ". " 或字串結尾作為句子結束
+            let end = comment[idx..].find(". ").map_or(comment.len(), |p| idx + p);
+            metadata.filter = comment[start..end].trim().trim_end_matches('.').to_string();
+        }
+
+        if let Some(rest) = after(comment, "due to ") {
+            metadata.stop_reason = rest.split('.').next().unwrap_or_default().trim().to_string();
+        }
+
+        metadata
+    }
+
+    /// 是否為有效的 AudioMoth 註釋（至少包含錄音時間或設備 ID）
+    #[wasm_bindgen]
+    pub fn is_valid(&self) -> bool {
+        self.timestamp.is_some() || !self.device_id.is_empty()
+    }
+
+    /// 錄音開始時間的 ISO 8601 字串
+    #[wasm_bindgen]
+    pub fn get_timestamp(&self) -> String {
+        self.timestamp.map(|ts| ts.to_string()).unwrap_or_default()
+    }
+
+    /// 錄音開始時間的 Unix 時間 (秒)
+    #[wasm_bindgen]
+    pub fn get_timestamp_unix(&self) -> Option<f64> {
+        self.timestamp.and_then(|ts| ts.unix_seconds())
+    }
+
+    /// UTC 偏移 (分鐘)
+    #[wasm_bindgen]
+    pub fn get_utc_offset_minutes(&self) -> Option<i32> {
+        self.timestamp.and_then(|ts| ts.utc_offset_minutes)
+    }
+
+    /// 設備 ID（16 位十六進制）
+    #[wasm_bindgen]
+    pub fn get_device_id(&self) -> String {
+        self.device_id.clone()
+    }
+
+    /// 增益設置（例如 "medium" 或舊韌體的數字 "2"）
+    #[wasm_bindgen]
+    pub fn get_gain(&self) -> String {
+        self.gain.clone()
+    }
+
+    /// 電池電壓 (V)
+    #[wasm_bindgen]
+    pub fn get_battery_voltage(&self) -> Option<f32> {
+        self.battery_voltage
+    }
+
+    /// 電池電壓超出量測範圍時為 "less than" 或 "greater than"，否則為空字串
+    #[wasm_bindgen]
+    pub fn get_battery_bound(&self) -> String {
+        self.battery_bound.clone()
+    }
+
+    /// 溫度 (°C)
+    #[wasm_bindgen]
+    pub fn get_temperature(&self) -> Option<f32> {
+        self.temperature
+    }
+
+    /// 振幅門限（僅在啟用振幅觸發時存在）
+    #[wasm_bindgen]
+    pub fn get_amplitude_threshold(&self) -> Option<f32> {
+        self.amplitude_threshold
+    }
+
+    /// 濾波器描述（例如 "Band-pass filter applied with cut-off frequencies of 20.0kHz and 120.0kHz"）
+    #[wasm_bindgen]
+    pub fn get_filter(&self) -> String {
+        self.filter.clone()
+    }
+
+    /// 錄音提前停止的原因（例如 "switch position change"、"low voltage"），正常結束時為空字串
+    #[wasm_bindgen]
+    pub fn get_stop_reason(&self) -> String {
+        self.stop_reason.clone()
+    }
+
+    /// 原始 ICMT 文本
+    #[wasm_bindgen]
+    pub fn get_comment(&self) -> String {
+        self.comment.clone()
+    }
+
+    /// 錄音的 [開始, 結束) Unix 時間 (秒)
+    ///
+    /// # Arguments
+    /// * `duration_sec` - 文件時長 (秒)
+    ///
+    /// # Returns
+    /// [start, end]；無時間戳時為空
+    #[wasm_bindgen]
+    pub fn get_recording_bounds(&self, duration_sec: f64) -> Vec<f64> {
+        match self.get_timestamp_unix() {
+            Some(start) => vec![start, start + duration_sec.max(0.0)],
+            None => Vec::new(),
+        }
+    }
+
+    /// 映射為 GUANO 元數據
+    ///
+    /// # Arguments
+    /// * `sample_rate` - WAV 頭中的採樣率 (Hz)，0 表示不寫入 Samplerate
+    #[wasm_bindgen]
+    pub fn to_guano(&self, sample_rate: u32) -> GuanoMetadata {
+        let mut guano = GuanoMetadata::new();
+        guano.set("Make", "Open Acoustic Devices");
+        guano.set("Model", "AudioMoth");
+        if !self.device_id.is_empty() {
+            guano.set("Serial", &self.device_id);
+        }
+        if let Some(ts) = &self.timestamp {
+            guano.set_timestamp_value(ts);
+        }
+        if sample_rate > 0 {
+            guano.set("Samplerate", &sample_rate.to_string());
+        }
+        if let Some(temperature) = self.temperature {
+            guano.set("Temperature Int", &temperature.to_string());
+        }
+        if !self.gain.is_empty() {
+            guano.set("OAD|Gain", &self.gain);
+        }
+        if let Some(voltage) = self.battery_voltage {
+            let value = if self.battery_bound.is_empty() {
+                voltage.to_string()
+            } else {
+                format!("{} {}", self.battery_bound, voltage)
+            };
+            guano.set("OAD|Battery Voltage", &value);
+        }
+        if let Some(threshold) = self.amplitude_threshold {
+            guano.set("OAD|Amplitude Threshold", &threshold.to_string());
+        }
+        if !self.filter.is_empty() {
+            guano.set("OAD|Filter", &self.filter);
+        }
+        if !self.stop_reason.is_empty() {
+            guano.set("OAD|Recording Stop Reason", &self.stop_reason);
+        }
+        guano
+    }
+}
+
+impl AudioMothMetadata {
+    /// 從 WAV 文件的 LIST/INFO 中讀取 AudioMoth 元數據；不是 AudioMoth 文件時返回 None
+    pub fn read_from_wav(bytes: &[u8]) -> Option<AudioMothMetadata> {
+        let info = parse_wav(bytes).ok()?;
+        Self::from_info_entries(&read_info_entries(bytes, &info))
+    }
+
+    /// 從 LIST/INFO 條目中讀取 AudioMoth 元數據；不是 AudioMoth 文件時返回 None
+    pub fn from_info_entries(entries: &[([u8; 4], String)]) -> Option<AudioMothMetadata> {
+        let find = |id: &[u8; 4]| entries.iter().find(|(entry_id, _)| entry_id == id).map(|(_, text)| text.as_str());
+
+        let comment = find(b"ICMT").unwrap_or_default();
+        let artist = find(b"IART").unwrap_or_default();
+        if !comment.contains("AudioMoth") && !artist.contains("AudioMoth") {
+            return None;
+        }
+        Some(Self::parse(comment, artist)).filter(|m| m.is_valid())
+    }
+
+    /// 錄音開始時間
+    pub fn timestamp(&self) -> Option<Timestamp> {
+        self.timestamp
+    }
+}
+
+/// 將多個固定長度文件合併為連續錄音段
+///
+/// AudioMoth 在一個錄音時段內按固定時長切分文件（例如每 55 秒一個文件、間隔 5 秒）。
+/// 此函數按開始時間排序後，把間隔不超過 `max_gap_sec` 的相鄰文件合併為一段，
+/// 用於在整晚的時間軸上標示錄音覆蓋範圍與空檔。
+///
+/// # Arguments
+/// * `start_times` - 每個文件的開始 Unix 時間 (秒)
+/// * `durations` - 每個文件的時長 (秒)
+/// * `max_gap_sec` - 視為連續的最大間隔 (秒)
+///
+/// # Returns
+/// 扁平化的 [start0, end0, start1, end1, ...]
+#[wasm_bindgen]
+pub fn merge_recording_bounds(start_times: &[f64], durations: &[f64], max_gap_sec: f64) -> Vec<f64> {
+    let mut files: Vec<(f64, f64)> = start_times
+        .iter()
+        .zip(durations.iter())
+        .filter(|(start, duration)| start.is_finite() && duration.is_finite())
+        .map(|(&start, &duration)| (start, start + duration.max(0.0)))
+        .collect();
+    files.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let max_gap = max_gap_sec.max(0.0);
+    let mut segments: Vec<(f64, f64)> = Vec::new();
+    for (start, end) in files {
+        match segments.last_mut() {
+            Some(last) if start - last.1 <= max_gap => last.1 = last.1.max(end),
+            _ => segments.push((start, end)),
+        }
+    }
+
+    segments.into_iter().flat_map(|(start, end)| [start, end]).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMMENT: &str = "Recorded at 21:00:00 01/05/2024 (UTC+8) by AudioMoth 24F3190360FFAB12 at medium gain \
+        while battery was 4.2V and temperature was 22.3C. Amplitude threshold was 0.5. \
+        Band-pass filter applied with cut-off frequencies of 20.0kHz and 120.0kHz. \
+        Recording stopped due to switch position change.";
+
+    #[test]
+    fn parses_comment() {
+        let metadata = AudioMothMetadata::parse(COMMENT, "");
+        assert!(metadata.is_valid());
+        assert_eq!(metadata.get_timestamp(), "2024-05-01T21:00:00+08:00");
+        assert_eq!(metadata.get_utc_offset_minutes(), Some(480));
+        assert_eq!(metadata.get_device_id(), "24F3190360FFAB12");
+        assert_eq!(metadata.get_gain(), "medium");
+        assert_eq!(metadata.get_battery_voltage(), Some(4.2));
+        assert_eq!(metadata.get_battery_bound(), "");
+        assert_eq!(metadata.get_temperature(), Some(22.3));
+        assert_eq!(metadata.get_amplitude_threshold(), Some(0.5));
+        assert_eq!(
+            metadata.get_filter(),
+            "Band-pass filter applied with cut-off frequencies of 20.0kHz and 120.0kHz"
+        );
+        assert_eq!(metadata.get_stop_reason(), "switch position change");
+    }
+
+    #[test]
+    fn parses_old_firmware_comment() {
+        // 舊韌體：數字增益、"battery state was less than"、UTC 無偏移、設備 ID 只在 IART
+        let comment = "Recorded at 03:04:05 29/02/2020 (UTC) at gain setting 2 while battery state was less than 3.6V.";
+        let metadata = AudioMothMetadata::parse(comment, "AudioMoth 0FE081F80FE081F0");
+        assert_eq!(metadata.get_timestamp(), "2020-02-29T03:04:05Z");
+        assert_eq!(metadata.get_device_id(), "0FE081F80FE081F0");
+        assert_eq!(metadata.get_gain(), "2");
+        assert_eq!(metadata.get_battery_bound(), "less than");
+        assert_eq!(metadata.get_battery_voltage(), Some(3.6));
+        assert_eq!(metadata.get_temperature(), None);
+        assert_eq!(metadata.get_stop_reason(), "");
+
+        let guano = metadata.to_guano(0);
+        assert_eq!(guano.get("OAD|Battery Voltage").as_deref(), Some("less than 3.6"));
+        assert_eq!(guano.get("Samplerate"), None);
+    }
+
+    #[test]
+    fn maps_to_guano() {
+        let guano = AudioMothMetadata::parse(COMMENT, "").to_guano(250_000);
+        assert_eq!(guano.get("Make").as_deref(), Some("Open Acoustic Devices"));
+        assert_eq!(guano.get("Model").as_deref(), Some("AudioMoth"));
+        assert_eq!(guano.get("Serial").as_deref(), Some("24F3190360FFAB12"));
+        assert_eq!(guano.get("Samplerate").as_deref(), Some("250000"));
+        assert_eq!(guano.get("Temperature Int").as_deref(), Some("22.3"));
+        assert_eq!(guano.get("OAD|Gain").as_deref(), Some("medium"));
+        assert_eq!(guano.get("OAD|Recording Stop Reason").as_deref(), Some("switch position change"));
+        assert_eq!(guano.get_timestamp(), "2024-05-01T21:00:00+08:00");
+    }
+
+    #[test]
+    fn rejects_non_audiomoth_entries() {
+        let entries = [(*b"ICMT", "Recorded with something else".to_string())];
+        assert!(AudioMothMetadata::from_info_entries(&entries).is_none());
+        // 提到 AudioMoth 但沒有時間與設備 ID
+        let entries = [(*b"ICMT", "AudioMoth".to_string())];
+        assert!(AudioMothMetadata::from_info_entries(&entries).is_none());
+
+        let entries = [(*b"IART", "AudioMoth 24F3190360FFAB12".to_string()), (*b"ICMT", COMMENT.to_string())];
+        let metadata = AudioMothMetadata::from_info_entries(&entries).unwrap();
+        assert_eq!(metadata.get_device_id(), "24F3190360FFAB12");
+    }
+
+    #[test]
+    fn parses_utc_zones() {
+        assert_eq!(parse_utc_zone("UTC"), Some(0));
+        assert_eq!(parse_utc_zone("UTC+8"), Some(480));
+        assert_eq!(parse_utc_zone("UTC-5:30"), Some(-330));
+        assert_eq!(parse_utc_zone("UTC+10:00"), Some(600));
+        assert_eq!(parse_utc_zone("UTC+15"), None);
+        assert_eq!(parse_utc_zone("UTC8"), None);
+        assert_eq!(parse_utc_zone("GMT"), None);
+    }
+
+    #[test]
+    fn parses_file_names() {
+        let ts = parse_audiomoth_file_name("20230506_102030.WAV").unwrap();
+        assert_eq!(ts.to_string(), "2023-05-06T10:20:30Z");
+        assert_eq!(parse_audiomoth_file_name("/data/night 1/20230506_102030.WAV"), Some(ts));
+        assert_eq!(parse_audiomoth_file_name("D:\\moth\\20230506_102030.wav"), Some(ts));
+        assert_eq!(parse_audiomoth_file_name("20230506_102030"), Some(ts));
+    }
+
+    #[test]
+    fn rejects_invalid_file_names() {
+        for name in [
+            "2023年5_102030.WAV",
+            "20230506_10時3.WAV",
+            "20230506_10203時.WAV",
+            "+0230506_102030.WAV",
+            "20230506_1020.WAV",
+            "2023050_102030.WAV",
+            "20231306_102030.WAV",
+            "20230506_250000.WAV",
+            "20230506102030.WAV",
+            "",
+        ] {
+            assert_eq!(parse_audiomoth_file_name(name), None, "{}", name);
+        }
+    }
+
+    #[test]
+    fn merges_recording_bounds() {
+        // 55 秒文件、5 秒間隔；第 4 個文件之前有較長空檔，輸入順序打亂且含 NaN
+        let starts = [120.0, 0.0, 60.0, 300.0, f64::NAN];
+        let durations = [55.0, 55.0, 55.0, 55.0, 55.0];
+        assert_eq!(merge_recording_bounds(&starts, &durations, 5.0), [0.0, 175.0, 300.0, 355.0]);
+        assert_eq!(merge_recording_bounds(&starts, &durations, 1.0).len(), 8);
+        assert!(merge_recording_bounds(&[], &[], 5.0).is_empty());
+    }
+}
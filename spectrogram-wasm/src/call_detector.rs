@@ -0,0 +1,2370 @@
+// ============================================================
+// 蝙蝠叫聲偵測與參數測量
+// 移植 batCallDetector.js 的 BatCallDetector 流程：快速能量掃描、
+// 每個叫聲的高解析度頻譜圖、High / Low Frequency 閾值搜索與參數測量，
+// 使偵測可在 worker 中以原生速度執行，也可在瀏覽器外使用
+// ============================================================
+
+use num_complex::Complex;
+use rustfft::FftPlanner;
+use wasm_bindgen::prelude::*;
+
+use crate::create_window;
+use crate::iir::{design_sos, sosfilt, FilterBand, FilterDesign};
+use crate::call_shape::{classify_shape, CallShape, ShapeConfig};
+use crate::harmonics::{analyze_harmonics, HarmonicAnalysis, HarmonicConfig};
+use crate::noise_floor::{zonal_noise_floors, NoiseStatistic, MIN_NOISE_FLOOR_DB};
+use crate::ridge::{extract_ridge, ContourPoint, RidgeConfig};
+use crate::segment_scan::{scan_segments, SegmentScanConfig};
+
+/// 閾值搜索的最低測試值 (dB，相對峰值功率)
+const MIN_TEST_THRESHOLD_DB: i32 = -100;
+/// 閾值搜索允許的最大斷層幀數
+const MAX_GAP_FRAMES: usize = 1;
+/// 噪聲區域寬度 (Hz)
+const NOISE_ZONE_WIDTH_HZ: f64 = 10000.0;
+/// 噪聲基底相對於眾數的偏移 (dB)
+const NOISE_FLOOR_OFFSET_DB: f32 = -2.0;
+/// 估計噪聲基底所用的起始幀數
+const NOISE_MAP_FRAMES: usize = 5;
+/// 選取範圍偵測時，叫聲前後保留的時間 (毫秒)
+const DETECT_PADDING_MS: f64 = 3.0;
+/// 全文件偵測時，叫聲前後保留的時間 (毫秒)
+const FULL_FILE_CALL_PADDING_MS: f64 = 5.0;
+/// 峰值時間相距小於此值 (秒) 的候選視為回聲
+const ECHO_MIN_GAP_S: f64 = 0.030;
+/// 峰值幀在 -12 dB 處的瞬時頻寬上限 (kHz)，超過時視為寬頻噪聲
+const MAX_INST_BANDWIDTH_KHZ: f64 = 20.0;
+/// 低頻觸及此頻率 (Hz) 時視為噪聲並廢棄叫聲
+const MIN_LOW_FREQ_HZ: f64 = 10000.0;
+/// Start / End Frequency 追蹤時每幀允許的最大跳變 (Hz)
+const TRACE_MAX_JUMP_HZ: f64 = 2000.0;
+/// 自動高通濾波器的階數
+const HIGHPASS_ORDER: usize = 4;
+/// 提取頻率輪廓時，在叫聲頻率範圍外額外搜索的寬度 (kHz)
+const CONTOUR_BAND_MARGIN_KHZ: f64 = 2.0;
+/// RMS 信噪比中，信號閾值位於叫聲框動態範圍的比例（由最小值起算）
+const SNR_SIGNAL_THRESHOLD_RATIO: f32 = 0.25;
+/// 無法計算 RMS 信噪比時，備用噪聲基底的下限 (dB)
+const MIN_ROBUST_NOISE_FLOOR_DB: f32 = -80.0;
+
+/// 偵測配置（對應 DEFAULT_DETECTION_CONFIG）
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetectionConfig {
+    /// 叫聲能量閾值 (dB，相對頻譜圖最大值)
+    pub call_threshold_db: f32,
+    /// 最短叫聲時長 (毫秒)
+    pub min_call_duration_ms: f64,
+    /// FFT 大小
+    pub fft_size: usize,
+    /// 跳步佔 FFT 大小的百分比
+    pub hop_percent: f64,
+    /// 窗函數名稱
+    pub window_type: String,
+    /// 偵測頻帶下限 (kHz)
+    pub flow_khz: f64,
+    /// 偵測頻帶上限 (kHz)
+    pub fhigh_khz: f64,
+    /// 時間擴展倍數（1 表示實時錄音）
+    pub time_expansion: f64,
+    /// 全文件掃描時橋接的最大斷層 (毫秒)
+    pub max_gap_bridge_ms: f64,
+    /// 全文件掃描的遲滯 (dB)
+    pub scan_hysteresis_db: f32,
+}
+
+impl Default for DetectionConfig {
+    fn default() -> Self {
+        DetectionConfig {
+            call_threshold_db: -24.0,
+            min_call_duration_ms: 1.0,
+            fft_size: 1024,
+            hop_percent: 3.125,
+            window_type: "hann".to_string(),
+            flow_khz: 10.0,
+            fhigh_khz: 150.0,
+            time_expansion: 1.0,
+            max_gap_bridge_ms: 0.0,
+            scan_hysteresis_db: 0.0,
+        }
+    }
+}
+
+/// 叫聲類型（依頻寬分類）
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallType {
+    /// 恆頻 (頻寬 < 5 kHz)
+    Cf,
+    /// 調頻 (頻寬 > 20 kHz)
+    Fm,
+    /// 混合型
+    CfFm,
+}
+
+impl CallType {
+    /// 依頻寬 (kHz) 分類
+    pub fn from_bandwidth(bandwidth_khz: f64) -> CallType {
+        if bandwidth_khz < 5.0 {
+            CallType::Cf
+        } else if bandwidth_khz > 20.0 {
+            CallType::Fm
+        } else {
+            CallType::CfFm
+        }
+    }
+
+    /// 類型名稱 ("CF", "FM", "CF-FM")
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CallType::Cf => "CF",
+            CallType::Fm => "FM",
+            CallType::CfFm => "CF-FM",
+        }
+    }
+}
+
+/// 叫聲品質（依 SNR 分級）
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallQuality {
+    /// SNR < 10 dB
+    VeryPoor,
+    /// 10 ~ 15 dB
+    Poor,
+    /// 15 ~ 20 dB
+    Normal,
+    /// 20 ~ 30 dB
+    Good,
+    /// >= 30 dB
+    Excellent,
+}
+
+impl CallQuality {
+    /// 依 SNR (dB) 分級
+    pub fn from_snr(snr_db: f64) -> CallQuality {
+        if snr_db < 10.0 {
+            CallQuality::VeryPoor
+        } else if snr_db < 15.0 {
+            CallQuality::Poor
+        } else if snr_db < 20.0 {
+            CallQuality::Normal
+        } else if snr_db < 30.0 {
+            CallQuality::Good
+        } else {
+            CallQuality::Excellent
+        }
+    }
+
+    /// 品質名稱 ("Very Poor", "Poor", "Normal", "Good", "Excellent")
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CallQuality::VeryPoor => "Very Poor",
+            CallQuality::Poor => "Poor",
+            CallQuality::Normal => "Normal",
+            CallQuality::Good => "Good",
+            CallQuality::Excellent => "Excellent",
+        }
+    }
+}
+
+/// 一個叫聲的測量結果
+///
+/// 頻率以 kHz 表示；各頻率的時間 (毫秒) 以 Start Frequency 所在幀為 0。
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BatCall {
+    /// 開始時間 (秒)，即 Start Frequency 的時間
+    pub start_time: f64,
+    /// 結束時間 (秒)，即 End Frequency 的時間
+    pub end_time: f64,
+    /// 時長 (毫秒)
+    pub duration_ms: f64,
+    /// 峰值頻率 (kHz)
+    pub peak_freq_khz: f64,
+    /// 峰值頻率的時間 (毫秒)
+    pub peak_time_ms: f64,
+    /// 峰值功率 (dB)
+    pub peak_power_db: f64,
+    /// 最高頻率 (kHz)
+    pub high_freq_khz: f64,
+    /// 最高頻率的時間 (毫秒)
+    pub high_time_ms: f64,
+    /// 起始頻率 (kHz)
+    pub start_freq_khz: f64,
+    /// 結束頻率 (kHz)
+    pub end_freq_khz: f64,
+    /// 最低頻率 (kHz)
+    pub low_freq_khz: f64,
+    /// 最低頻率的時間 (毫秒)
+    pub low_time_ms: f64,
+    /// 特徵頻率 (kHz)：叫聲最後 40% 中斜率最小處
+    pub characteristic_freq_khz: f64,
+    /// 特徵頻率的時間 (毫秒)
+    pub characteristic_time_ms: f64,
+    /// 膝點頻率 (kHz)：FM 轉為平緩處
+    pub knee_freq_khz: Option<f64>,
+    /// 膝點的時間 (毫秒)
+    pub knee_time_ms: Option<f64>,
+    /// 踵點頻率 (kHz)：平緩轉為 FM 下降處
+    pub heel_freq_khz: Option<f64>,
+    /// 踵點的時間 (毫秒)
+    pub heel_time_ms: Option<f64>,
+    /// 頻寬 = 最高頻率 - 最低頻率 (kHz)
+    pub bandwidth_khz: f64,
+    /// 叫聲類型
+    pub call_type: CallType,
+    /// High Frequency 實際使用的閾值 (dB，相對峰值功率)
+    pub high_freq_threshold_db: f64,
+    /// Low Frequency 實際使用的閾值 (dB，相對峰值功率)
+    pub low_freq_threshold_db: f64,
+    /// High Frequency 閾值搜索觸及 -100 dB，已改用 -30 dB 安全閾值
+    pub high_freq_warning: bool,
+    /// Low Frequency 閾值搜索觸及 -100 dB，已改用 -30 dB 安全閾值
+    pub low_freq_warning: bool,
+    /// 信噪比 (dB)
+    pub snr_db: f64,
+    /// 依 SNR 分級的品質
+    pub quality: CallQuality,
+}
+
+#[wasm_bindgen]
+impl BatCall {
+    /// 叫聲類型名稱 ("CF", "FM", "CF-FM")
+    #[wasm_bindgen]
+    pub fn get_call_type_name(&self) -> String {
+        self.call_type.as_str().to_string()
+    }
+
+    /// 品質名稱 ("Very Poor", "Poor", "Normal", "Good", "Excellent")
+    #[wasm_bindgen]
+    pub fn get_quality_name(&self) -> String {
+        self.quality.as_str().to_string()
+    }
+}
+
+impl BatCall {
+    /// 時間擴展校正：頻率乘以倍數，時間除以倍數
+    ///
+    /// # Arguments
+    /// * `factor` - 時間擴展倍數，不大於 1 時不做任何處理
+    pub fn apply_time_expansion(&mut self, factor: f64) {
+        if factor <= 1.0 {
+            return;
+        }
+        for freq in [
+            &mut self.peak_freq_khz,
+            &mut self.high_freq_khz,
+            &mut self.start_freq_khz,
+            &mut self.end_freq_khz,
+            &mut self.low_freq_khz,
+            &mut self.characteristic_freq_khz,
+            &mut self.bandwidth_khz,
+        ] {
+            *freq *= factor;
+        }
+        for time in [
+            &mut self.start_time,
+            &mut self.end_time,
+            &mut self.duration_ms,
+            &mut self.peak_time_ms,
+            &mut self.high_time_ms,
+            &mut self.low_time_ms,
+            &mut self.characteristic_time_ms,
+        ] {
+            *time /= factor;
+        }
+        for freq in [&mut self.knee_freq_khz, &mut self.heel_freq_khz].into_iter().flatten() {
+            *freq *= factor;
+        }
+        for time in [&mut self.knee_time_ms, &mut self.heel_time_ms].into_iter().flatten() {
+            *time /= factor;
+        }
+    }
+}
+
+// ------------------------------------------------------------
+// 頻譜圖
+// ------------------------------------------------------------
+
+/// 對每個不做中心填充的幀執行 FFT
+///
+/// 第 k 幀覆蓋樣本 [k * hop_size, k * hop_size + fft_size)。
+fn for_each_frame_spectrum<F>(audio_data: &[f32], fft_size: usize, hop_size: usize, window_type: &str, mut f: F)
+where
+    F: FnMut(usize, &[Complex<f32>]),
+{
+    if fft_size == 0 || hop_size == 0 || audio_data.len() < fft_size {
+        return;
+    }
+    let window = create_window(window_type, fft_size, 0.16);
+    let fft = FftPlanner::new().plan_fft_forward(fft_size);
+    let mut buffer = vec![Complex::default(); fft_size];
+    let num_frames = (audio_data.len() - fft_size) / hop_size + 1;
+    for frame_idx in 0..num_frames {
+        let start = frame_idx * hop_size;
+        for i in 0..fft_size {
+            buffer[i] = Complex::new(audio_data[start + i] * window[i], 0.0);
+        }
+        fft.process(&mut buffer);
+        f(frame_idx, &buffer);
+    }
+}
+
+/// 偵測用的功率頻譜圖
+///
+/// 功率 = 10 * log10(|X|^2 / N)，|X| 與 SpectrogramEngine::compute_spectrogram 的幅度相同，
+/// 只保留 [flow, fhigh] 內的頻率箱；幀時間取幀中心。
+struct PowerSpectrogram {
+    /// 功率 (dB)，幀優先排列
+    power_db: Vec<f32>,
+    num_bins: usize,
+    /// 每幀中心時間 (秒)
+    times: Vec<f64>,
+    /// 每個頻率箱的頻率 (Hz)
+    freqs: Vec<f64>,
+    /// 頻率解析度 (Hz)
+    freq_resolution: f64,
+    /// 跳步 (樣本)
+    hop_size: usize,
+}
+
+impl PowerSpectrogram {
+    fn compute(audio_data: &[f32], sample_rate: f32, config: &DetectionConfig) -> Option<PowerSpectrogram> {
+        let fft_size = config.fft_size;
+        let hop_size = (fft_size as f64 * config.hop_percent / 100.0).floor() as usize;
+        if fft_size < 2 || hop_size == 0 || audio_data.len() < fft_size || sample_rate <= 0.0 {
+            return None;
+        }
+
+        let sr = sample_rate as f64;
+        let freq_resolution = sr / fft_size as f64;
+        let min_bin = (config.flow_khz * 1000.0 / freq_resolution).floor().max(0.0) as usize;
+        let max_bin = ((config.fhigh_khz * 1000.0 / freq_resolution).floor().max(0.0) as usize).min(fft_size / 2 - 1);
+        if max_bin < min_bin {
+            return None;
+        }
+        let num_bins = max_bin - min_bin + 1;
+
+        let num_frames = (audio_data.len() - fft_size) / hop_size + 1;
+        let mut power_db = Vec::with_capacity(num_frames * num_bins);
+        let mut times = Vec::with_capacity(num_frames);
+        let scale = 2.0 / fft_size as f32;
+        for_each_frame_spectrum(audio_data, fft_size, hop_size, &config.window_type, |frame_idx, spectrum| {
+            for c in &spectrum[min_bin..=max_bin] {
+                let magnitude = c.norm() * scale;
+                let psd = magnitude * magnitude / fft_size as f32;
+                power_db.push(10.0 * psd.max(1e-16).log10());
+            }
+            times.push((frame_idx as f64 * hop_size as f64 + fft_size as f64 / 2.0) / sr);
+        });
+
+        let freqs = (min_bin..=max_bin).map(|b| b as f64 * freq_resolution).collect();
+        Some(PowerSpectrogram {
+            power_db,
+            num_bins,
+            times,
+            freqs,
+            freq_resolution,
+            hop_size,
+        })
+    }
+
+    fn num_frames(&self) -> usize {
+        self.times.len()
+    }
+
+    fn frame(&self, frame_idx: usize) -> &[f32] {
+        &self.power_db[frame_idx * self.num_bins..(frame_idx + 1) * self.num_bins]
+    }
+
+    /// 全局最大功率及其 (幀, 頻率箱)
+    fn global_peak(&self, first_frame: usize, last_frame: usize) -> (f32, usize, usize) {
+        let mut peak = (f32::NEG_INFINITY, first_frame, 0);
+        for f in first_frame..=last_frame {
+            for (b, &p) in self.frame(f).iter().enumerate() {
+                if p > peak.0 {
+                    peak = (p, f, b);
+                }
+            }
+        }
+        peak
+    }
+}
+
+/// 頻譜圖中屬於一個叫聲的連續幀
+struct CallView<'a> {
+    spec: &'a PowerSpectrogram,
+    first_frame: usize,
+    num_frames: usize,
+}
+
+impl CallView<'_> {
+    fn frame(&self, idx: usize) -> &[f32] {
+        self.spec.frame(self.first_frame + idx)
+    }
+
+    fn time(&self, idx: usize) -> f64 {
+        self.spec.times[(self.first_frame + idx).min(self.spec.num_frames() - 1)]
+    }
+
+    fn freqs(&self) -> &[f64] {
+        &self.spec.freqs
+    }
+}
+
+// ------------------------------------------------------------
+// 噪聲基底
+// ------------------------------------------------------------
+
+/// 各 10 kHz 頻率區域的噪聲基底
+///
+/// 每個區域取功率 (向下取整到 1 dB) 的眾數，平手時取較低值，再減 2 dB。
+struct ZonalNoiseMap {
+    floors: Vec<f32>,
+}
+
+impl ZonalNoiseMap {
+    fn compute(spec: &PowerSpectrogram, start_frame: usize, end_frame: usize) -> ZonalNoiseMap {
+        let floors = zonal_noise_floors(&spec.power_db, &spec.freqs, start_frame, end_frame, NOISE_ZONE_WIDTH_HZ, NoiseStatistic::Mode)
+            .into_iter()
+            .map(|mode_db| mode_db + NOISE_FLOOR_OFFSET_DB)
+            .collect();
+        ZonalNoiseMap { floors }
+    }
+
+    /// 頻率 (kHz) 所在區域的噪聲基底；超出範圍時為 -100 dB
+    fn floor_at(&self, freq_khz: f64) -> f32 {
+        let zone = (freq_khz * 1000.0 / NOISE_ZONE_WIDTH_HZ).floor();
+        if zone >= 0.0 && (zone as usize) < self.floors.len() {
+            self.floors[zone as usize]
+        } else {
+            MIN_NOISE_FLOOR_DB
+        }
+    }
+}
+
+// ------------------------------------------------------------
+// 閾值搜索
+// ------------------------------------------------------------
+
+/// 頻率箱 b 高於閾值而 b + 1 低於閾值時，線性插值出上邊緣頻率
+fn upper_edge_hz(frame: &[f32], freqs: &[f64], b: usize, threshold_db: f32) -> f64 {
+    if b + 1 < frame.len() && frame[b + 1] < threshold_db && frame[b] > threshold_db {
+        let ratio = ((frame[b] - threshold_db) / (frame[b] - frame[b + 1])) as f64;
+        freqs[b] + ratio * (freqs[b + 1] - freqs[b])
+    } else {
+        freqs[b]
+    }
+}
+
+/// 頻率箱 b 高於閾值而 b - 1 低於閾值時，線性插值出下邊緣頻率
+fn lower_edge_hz(frame: &[f32], freqs: &[f64], b: usize, threshold_db: f32) -> f64 {
+    if b > 0 && frame[b - 1] < threshold_db && frame[b] > threshold_db {
+        let ratio = ((frame[b] - threshold_db) / (frame[b] - frame[b - 1])) as f64;
+        freqs[b] - ratio * (freqs[b] - freqs[b - 1])
+    } else {
+        freqs[b]
+    }
+}
+
+/// [lo, hi] 範圍內的最大功率頻率箱
+#[allow(clippy::needless_range_loop)]
+fn peak_bin_in(frame: &[f32], lo: usize, hi: usize) -> (usize, f32) {
+    let mut best = (lo, f32::NEG_INFINITY);
+    for b in lo..=hi {
+        if frame[b] > best.1 {
+            best = (b, frame[b]);
+        }
+    }
+    best
+}
+
+/// 某個測試閾值下的 High Frequency 測量
+#[derive(Clone, Copy)]
+struct HighMeasurement {
+    threshold: i32,
+    freq_hz: f64,
+    bin: usize,
+    frame: usize,
+    power_db: f32,
+}
+
+/// High Frequency 閾值搜索結果
+struct HighFreqSearch {
+    /// 實際使用的閾值 (dB，相對峰值)
+    threshold: i32,
+    freq_hz: Option<f64>,
+    bin: usize,
+    frame: usize,
+    /// 收斂後的搜索上限幀
+    search_limit_frame: usize,
+    /// 連續 10 個閾值的頻率變化都在 0.05 kHz 以內（CF 叫聲）
+    cf_stable: bool,
+    /// 搜索觸及 -100 dB，已改用安全閾值
+    warning: bool,
+}
+
+/// 搜索最佳 High Frequency 閾值
+///
+/// 從 -1 dB 到 -100 dB 逐步降低閾值，由峰值幀向前逐幀尋找最高頻率，
+/// 頻率跳變超過 1.5 kHz 且功率不高於該區域噪聲基底時停止（CF 叫聲為 1 kHz），
+/// 並取停止前的最後一個有效測量。
+fn find_high_frequency(view: &CallView, peak_power_db: f32, peak_frame: usize, noise: &ZonalNoiseMap) -> HighFreqSearch {
+    let num_bins = view.spec.num_bins;
+    let freqs = view.freqs();
+    let mut search_limit_frame = peak_frame.min(view.num_frames - 1);
+    let mut search_min_bin = 0;
+
+    let mut stable_count = 0;
+    let mut cf_stable = false;
+    let mut last_freq_khz: Option<f64> = None;
+    let mut measurements: Vec<HighMeasurement> = Vec::new();
+    let mut stopped_at: Option<HighMeasurement> = None;
+
+    for test_threshold in (MIN_TEST_THRESHOLD_DB..=-1).rev() {
+        let threshold_db = peak_power_db + test_threshold as f32;
+        let reference_khz = measurements.last().map(|m| m.freq_hz / 1000.0);
+
+        // 由峰值幀向前掃描，允許 1 幀斷層
+        let mut found: Option<(f64, usize, usize)> = None;
+        let mut silence = 0;
+        for f in (0..=search_limit_frame).rev() {
+            let frame = view.frame(f);
+            let mut found_in_frame = false;
+            for b in (search_min_bin..num_bins).rev() {
+                if frame[b] > threshold_db {
+                    let candidate_hz = upper_edge_hz(frame, freqs, b, threshold_db);
+                    // 諧波抑制：比上一個測量高出 10 kHz 以上的候選略過
+                    if reference_khz.is_some_and(|r| candidate_hz / 1000.0 - r > 10.0) {
+                        continue;
+                    }
+                    if found.is_none_or(|(hz, _, _)| candidate_hz > hz) {
+                        found = Some((candidate_hz, b, f));
+                    }
+                    found_in_frame = true;
+                    break;
+                }
+            }
+            if found_in_frame {
+                silence = 0;
+            } else {
+                silence += 1;
+                if silence > MAX_GAP_FRAMES {
+                    break;
+                }
+            }
+        }
+
+        let Some((freq_hz, bin, frame)) = found else {
+            stable_count = 0;
+            continue;
+        };
+
+        // CF 穩定模式
+        let freq_khz = freq_hz / 1000.0;
+        if let Some(last) = last_freq_khz {
+            let diff = (freq_khz - last).abs();
+            if diff > 0.0 && diff <= 0.05 {
+                stable_count += 1;
+            } else if diff != 0.0 {
+                stable_count = 0;
+            }
+            if stable_count >= 10 {
+                cf_stable = true;
+            }
+        }
+        last_freq_khz = Some(freq_khz);
+
+        let power_db = view.frame(frame)[bin];
+        if let Some(&last) = measurements.last() {
+            let time_gap_ms = (view.time(frame) - view.time(last.frame)).abs() * 1000.0;
+            let jump_khz = (freq_khz - last.freq_hz / 1000.0).abs();
+            let stop = if power_db < -100.0 && time_gap_ms > 0.15 {
+                // 微弱信號在時間上跳離
+                true
+            } else if cf_stable && jump_khz > 1.0 {
+                true
+            } else if jump_khz > 1.5 {
+                power_db <= noise.floor_at(freq_khz).max(-115.0)
+            } else {
+                false
+            };
+            if stop {
+                stopped_at = Some(last);
+                break;
+            }
+        }
+
+        measurements.push(HighMeasurement {
+            threshold: test_threshold,
+            freq_hz,
+            bin,
+            frame,
+            power_db,
+        });
+
+        // 收斂：後續閾值只在更早的幀、更高的頻率箱中搜索
+        search_limit_frame = search_limit_frame.min(frame);
+        search_min_bin = search_min_bin.max(bin);
+    }
+
+    if measurements.is_empty() {
+        return HighFreqSearch {
+            threshold: -24,
+            freq_hz: None,
+            bin: 0,
+            frame: 0,
+            search_limit_frame,
+            cf_stable: false,
+            warning: false,
+        };
+    }
+
+    let optimal = match stopped_at {
+        Some(m) => m,
+        None => {
+            // 後處理：跳變 > 4 kHz 停止；> 2.5 kHz 且不高於噪聲為異常，其後 3 個正常測量可恢復
+            let mut last_valid = measurements[0];
+            let mut anomaly: Option<(usize, HighMeasurement)> = None;
+            for i in 1..measurements.len() {
+                let prev = measurements[i - 1];
+                let curr = measurements[i];
+                let diff = (curr.freq_hz - prev.freq_hz).abs() / 1000.0;
+                if diff > 4.0 {
+                    break;
+                }
+                let is_anomaly =
+                    diff > 2.5 && curr.power_db <= noise.floor_at(curr.freq_hz / 1000.0).max(-115.0);
+                if is_anomaly {
+                    if anomaly.is_none() {
+                        anomaly = Some((i, prev));
+                        last_valid = prev;
+                    }
+                } else {
+                    if let Some((first, _)) = anomaly {
+                        let check_start = first + 1;
+                        let check_end = (first + 3).min(measurements.len() - 1);
+                        let stable = (check_start..=check_end)
+                            .all(|k| (measurements[k].freq_hz - measurements[k - 1].freq_hz).abs() / 1000.0 <= 2.5);
+                        if stable && check_end + 1 >= check_start + 3 {
+                            anomaly = None;
+                        }
+                    }
+                    last_valid = curr;
+                }
+            }
+            match anomaly {
+                Some((_, m)) => HighMeasurement { threshold: m.threshold, ..last_valid },
+                None => last_valid,
+            }
+        }
+    };
+
+    let final_threshold = optimal.threshold.clamp(MIN_TEST_THRESHOLD_DB, -22);
+    let warning = final_threshold <= MIN_TEST_THRESHOLD_DB;
+    let mut result = HighFreqSearch {
+        threshold: if warning { -30 } else { final_threshold },
+        freq_hz: Some(optimal.freq_hz),
+        bin: optimal.bin,
+        frame: optimal.frame,
+        search_limit_frame,
+        cf_stable,
+        warning,
+    };
+
+    if warning {
+        // 以安全閾值重新掃描
+        let threshold_db = peak_power_db + result.threshold as f32;
+        let mut best: Option<(f64, usize, usize)> = None;
+        for f in 0..=search_limit_frame {
+            let frame = view.frame(f);
+            if let Some(b) = (0..num_bins).rev().find(|&b| frame[b] > threshold_db) {
+                let hz = upper_edge_hz(frame, freqs, b, threshold_db);
+                if best.is_none_or(|(best_hz, _, _)| hz > best_hz) {
+                    best = Some((hz, b, f));
+                }
+            }
+        }
+        if let Some((hz, b, f)) = best {
+            result.freq_hz = Some(hz);
+            result.bin = b;
+            result.frame = f;
+        }
+    }
+    result
+}
+
+/// 某個測試閾值下的 Low Frequency 測量
+#[derive(Clone, Copy)]
+struct LowMeasurement {
+    threshold: i32,
+    freq_hz: f64,
+    frame: usize,
+    bin: usize,
+}
+
+/// Low Frequency 閾值搜索結果
+struct LowFreqSearch {
+    /// 實際使用的閾值 (dB，相對峰值)
+    threshold: i32,
+    freq_hz: f64,
+    frame: usize,
+    bin: usize,
+    /// 搜索觸及 -100 dB，已改用安全閾值
+    warning: bool,
+}
+
+/// 搜索最佳 Low Frequency 閾值
+///
+/// 從 -1 dB 到 -100 dB 逐步降低閾值，由峰值幀向後找到最後一個有信號的幀，
+/// 取該幀的最低頻率。跳變超過 15 kHz（次諧波）或 8 kHz 時停止，
+/// 超過 1.5 kHz 且功率不高於噪聲基底時也停止。
+///
+/// # Returns
+/// 低頻觸及 10 kHz 或找不到信號時返回 None，表示應廢棄該叫聲
+fn find_low_frequency(view: &CallView, peak_power_db: f32, peak_frame: usize, noise: &ZonalNoiseMap) -> Option<LowFreqSearch> {
+    let freqs = view.freqs();
+    let search_end = view.num_frames - 1;
+    let peak_frame = peak_frame.min(search_end);
+    let mut search_start = peak_frame;
+    let mut search_max_bin = view.spec.num_bins - 1;
+    let mut measurements: Vec<LowMeasurement> = Vec::new();
+    let mut hit_noise_floor = false;
+
+    for test_threshold in (MIN_TEST_THRESHOLD_DB..=-1).rev() {
+        let threshold_db = peak_power_db + test_threshold as f32;
+        let reference_hz = measurements.last().map(|m| m.freq_hz);
+
+        // 向後掃描，允許 1 幀斷層；最低頻率低於參考值時鎖定
+        let mut active_end = search_start;
+        let mut silence = 0;
+        for f in search_start..=search_end {
+            match view.frame(f)[..=search_max_bin].iter().position(|&p| p > threshold_db) {
+                Some(b) => {
+                    active_end = f;
+                    silence = 0;
+                    if reference_hz.is_some_and(|r| freqs[b] < r) {
+                        break;
+                    }
+                }
+                None => {
+                    silence += 1;
+                    if silence > MAX_GAP_FRAMES {
+                        break;
+                    }
+                }
+            }
+        }
+        search_start = active_end;
+
+        let frame = view.frame(active_end);
+        let Some(bin) = frame[..=search_max_bin].iter().position(|&p| p > threshold_db) else {
+            continue;
+        };
+        let freq_hz = lower_edge_hz(frame, freqs, bin, threshold_db);
+        if let Some(r) = reference_hz {
+            if (freq_hz - r).abs() / 1000.0 > 15.0 {
+                hit_noise_floor = true;
+                break;
+            }
+        }
+        if freq_hz <= MIN_LOW_FREQ_HZ {
+            return None;
+        }
+        if let Some(r) = reference_hz {
+            let jump_khz = (freq_hz - r).abs() / 1000.0;
+            if jump_khz > 8.0 || (jump_khz > 1.5 && frame[bin] <= noise.floor_at(freq_hz / 1000.0)) {
+                hit_noise_floor = true;
+                break;
+            }
+        }
+
+        measurements.push(LowMeasurement {
+            threshold: test_threshold,
+            freq_hz,
+            frame: active_end,
+            bin,
+        });
+        search_max_bin = search_max_bin.min(bin);
+    }
+
+    let first = *measurements.first()?;
+    let optimal = if hit_noise_floor {
+        *measurements.last()?
+    } else {
+        // 後處理：跳變 > 1.5 kHz 為異常，其後 3 個穩定測量可恢復
+        let mut last_valid = first;
+        let mut anomaly: Option<(usize, LowMeasurement)> = None;
+        for i in 1..measurements.len() {
+            let prev = measurements[i - 1];
+            let curr = measurements[i];
+            if (curr.freq_hz - prev.freq_hz).abs() / 1000.0 > 1.5 {
+                if anomaly.is_none() {
+                    anomaly = Some((i, prev));
+                    last_valid = prev;
+                }
+            } else {
+                if let Some((first_anomaly, _)) = anomaly {
+                    let check_start = first_anomaly + 1;
+                    let check_end = (first_anomaly + 3).min(measurements.len() - 1);
+                    let stable = (check_start..=check_end)
+                        .all(|k| (measurements[k].freq_hz - measurements[k - 1].freq_hz).abs() / 1000.0 <= 1.5);
+                    if stable && check_end + 1 >= check_start + 3 {
+                        anomaly = None;
+                    }
+                }
+                last_valid = curr;
+            }
+        }
+        match anomaly {
+            Some((_, m)) => LowMeasurement { threshold: m.threshold, ..last_valid },
+            None => last_valid,
+        }
+    };
+
+    let final_threshold = optimal.threshold.clamp(MIN_TEST_THRESHOLD_DB, -1);
+    let warning = final_threshold <= MIN_TEST_THRESHOLD_DB;
+    let mut result = LowFreqSearch {
+        threshold: if warning { -30 } else { final_threshold },
+        freq_hz: optimal.freq_hz,
+        frame: optimal.frame,
+        bin: optimal.bin,
+        warning,
+    };
+
+    if warning {
+        // 以安全閾值重新掃描
+        let threshold_db = peak_power_db + result.threshold as f32;
+        let active_end = (peak_frame..=search_end)
+            .rev()
+            .find(|&f| view.frame(f).iter().any(|&p| p > threshold_db))
+            .unwrap_or(peak_frame);
+        let frame = view.frame(active_end);
+        if let Some(b) = frame.iter().position(|&p| p > threshold_db) {
+            result.freq_hz = lower_edge_hz(frame, freqs, b, threshold_db);
+        }
+    }
+    Some(result)
+}
+
+// ------------------------------------------------------------
+// 參數測量
+// ------------------------------------------------------------
+
+/// Savitzky-Golay 平滑 (窗長 5、二次多項式)
+fn savitzky_golay5(data: &[f64]) -> Vec<f64> {
+    const COEFFS: [f64; 5] = [-3.0, 12.0, 17.0, 12.0, -3.0];
+    if data.len() < COEFFS.len() {
+        return data.to_vec();
+    }
+    (0..data.len())
+        .map(|i| {
+            let sum: f64 = COEFFS
+                .iter()
+                .enumerate()
+                .filter_map(|(k, c)| (i + k).checked_sub(2).and_then(|idx| data.get(idx)).map(|v| v * c))
+                .sum();
+            sum / 35.0
+        })
+        .collect()
+}
+
+/// 在頻率輪廓上尋找膝點與踵點
+///
+/// 輪廓先做 Savitzky-Golay 平滑，再計算一階、二階導數 (kHz/ms) 與曲率。
+/// 膝點：入射斜率 ≤ -0.5 kHz/ms 且出射斜率明顯變緩處曲率最大者；
+/// 踵點：膝點之後，入射平緩 (≥ -0.5) 而出射陡降 (≤ -0.5 且至少為入射的 1.5 倍) 處曲率最大者。
+///
+/// # Arguments
+/// * `contour_khz` - 每幀峰值頻率 (kHz)
+/// * `times` - 每幀時間 (秒)
+///
+/// # Returns
+/// (膝點索引, 踵點索引)
+fn find_knee_and_heel(contour_khz: &[f64], times: &[f64]) -> (Option<usize>, Option<usize>) {
+    let smoothed = savitzky_golay5(contour_khz);
+    let first: Vec<f64> = (0..smoothed.len() - 1)
+        .map(|i| {
+            let dt_ms = (times[i + 1] - times[i]) * 1000.0;
+            (smoothed[i + 1] - smoothed[i]) / if dt_ms > 0.0 { dt_ms } else { 0.001 }
+        })
+        .collect();
+    let second: Vec<f64> = (0..first.len() - 1)
+        .map(|i| {
+            let dt_ms = (times[i + 2] - times[i]) * 1000.0 / 2.0;
+            (first[i + 1] - first[i]) / if dt_ms > 0.0 { dt_ms } else { 0.001 }
+        })
+        .collect();
+    let curvature = |i: usize| second[i].abs() / ((1.0 + first[i] * first[i]).powf(1.5) + 1e-10);
+
+    // 二階導數索引 i 對應輪廓點 i + 1，入射斜率 first[i - 1]、出射斜率 first[i]
+    let is_knee = |i: usize| {
+        if i == 0 || i >= first.len() {
+            return false;
+        }
+        let incoming = first[i - 1];
+        let outgoing = first[i];
+        incoming <= -0.5 && outgoing.abs() < incoming.abs() * 0.8
+    };
+    let is_heel = |i: usize| {
+        if i == 0 || i >= first.len() {
+            return false;
+        }
+        let incoming = first[i - 1];
+        let outgoing = first[i];
+        incoming >= -0.5 && outgoing <= -0.5 && outgoing.abs() >= incoming.abs() * 1.5
+    };
+
+    let mut knee: Option<usize> = None;
+    let mut max_curvature = -1.0;
+    for i in 0..second.len() {
+        let c = curvature(i);
+        if c > max_curvature && is_knee(i) {
+            max_curvature = c;
+            knee = Some(i + 1);
+        }
+    }
+    if knee.is_none() || max_curvature < 0.01 {
+        // 退回到二階導數絕對值最大、且入射為 FM 的點
+        let mut max_change = -1.0;
+        for i in 0..second.len() {
+            if second[i].abs() > max_change && first[i] < -0.5 {
+                max_change = second[i].abs();
+                knee = Some(i + 1);
+            }
+        }
+    }
+
+    let mut heel: Option<usize> = None;
+    let mut max_heel_curvature = -1.0;
+    for i in 0..second.len() {
+        if knee.is_some_and(|k| i <= k) {
+            continue;
+        }
+        let c = curvature(i);
+        if c > max_heel_curvature && is_heel(i) {
+            max_heel_curvature = c;
+            heel = Some(i + 1);
+        }
+    }
+    (knee, heel)
+}
+
+/// 測量一個叫聲的頻率參數
+///
+/// # Arguments
+/// * `view` - 叫聲所在的幀（含前後保留區）
+/// * `fhigh_khz` - 偵測頻帶上限，找不到 High Frequency 時使用
+/// * `noise` - 噪聲基底
+///
+/// # Returns
+/// 寬頻噪聲或低頻觸及 10 kHz 時返回 None
+fn measure_call_view(view: &CallView, fhigh_khz: f64, noise: &ZonalNoiseMap) -> Option<BatCall> {
+    let n = view.num_frames;
+    let num_bins = view.spec.num_bins;
+    let freqs = view.freqs();
+    let freq_resolution = view.spec.freq_resolution;
+
+    // 1. 峰值頻率（dB 拋物線插值）
+    let (peak_power, peak_frame, peak_bin) = view.spec.global_peak(view.first_frame, view.first_frame + n - 1);
+    let peak_frame = peak_frame - view.first_frame;
+    let mut peak_hz = freqs[peak_bin];
+    if peak_bin > 0 && peak_bin + 1 < num_bins {
+        let frame = view.frame(peak_frame);
+        let db0 = frame[peak_bin - 1] as f64;
+        let db1 = frame[peak_bin] as f64;
+        let db2 = frame[peak_bin + 1] as f64;
+        let a = (db2 - 2.0 * db1 + db0) / 2.0;
+        if a.abs() > 1e-10 {
+            peak_hz = freqs[peak_bin] + (db0 - db2) / (4.0 * a) * freq_resolution;
+        }
+    }
+
+    // 2. 峰值幀的瞬時頻寬過大時視為寬頻噪聲
+    let frame = view.frame(peak_frame);
+    let inst_threshold = peak_power - 12.0;
+    if let (Some(lo), Some(hi)) = (
+        frame.iter().position(|&p| p > inst_threshold),
+        frame.iter().rposition(|&p| p > inst_threshold),
+    ) {
+        if (hi - lo) as f64 * freq_resolution / 1000.0 > MAX_INST_BANDWIDTH_KHZ {
+            return None;
+        }
+    }
+
+    // 3. Low / High Frequency 閾值搜索
+    let low = find_low_frequency(view, peak_power, peak_frame, noise)?;
+    let high = find_high_frequency(view, peak_power, peak_frame, noise);
+    let mut high_threshold = high.threshold;
+    let (mut high_hz, mut high_bin, mut high_frame) = (high.freq_hz, high.bin, high.frame);
+
+    if high.freq_hz.is_some_and(|hz| hz < peak_hz) {
+        // High Frequency 低於峰值：改用最大值頻譜從 -24 dB 往下掃描
+        let mut max_spectrum = vec![f32::NEG_INFINITY; num_bins];
+        let mut frame_for_bin = vec![0usize; num_bins];
+        for f in 0..=high.search_limit_frame {
+            for (b, &p) in view.frame(f).iter().enumerate() {
+                if p > max_spectrum[b] {
+                    max_spectrum[b] = p;
+                    frame_for_bin[b] = f;
+                }
+            }
+        }
+        for test_threshold in (MIN_TEST_THRESHOLD_DB..=-24).rev() {
+            let threshold_db = peak_power + test_threshold as f32;
+            if let Some(b) = (0..num_bins).rev().find(|&b| max_spectrum[b] > threshold_db) {
+                let hz = upper_edge_hz(&max_spectrum, freqs, b, threshold_db);
+                if hz >= peak_hz {
+                    high_hz = Some(hz);
+                    high_bin = b;
+                    high_frame = frame_for_bin[b];
+                    high_threshold = test_threshold;
+                    break;
+                }
+            }
+        }
+    }
+    let (high_hz, high_frame, high_bin) = match high_hz {
+        Some(hz) => (hz, high_frame, high_bin),
+        None => (fhigh_khz * 1000.0, 0, 0),
+    };
+
+    // 4. 第一個高於 High 閾值的幀
+    let high_threshold_db = peak_power + high_threshold as f32;
+    let new_start = (0..n)
+        .find(|&f| view.frame(f).iter().any(|&p| p > high_threshold_db))
+        .unwrap_or(0);
+
+    // 5. 由 High Frequency 所在幀向前追蹤 Start Frequency
+    let max_jump_bins = (TRACE_MAX_JUMP_HZ / freq_resolution).ceil() as usize;
+    let (trace_start, start_threshold_db) = if high.cf_stable {
+        (true, peak_power - 35.0)
+    } else {
+        let p = view.frame(high_frame)[high_bin];
+        (p >= peak_power - 30.0 && p >= -80.0, high_threshold_db)
+    };
+    let mut start_hz = high_hz;
+    let mut start_frame = high_frame;
+    if trace_start {
+        let mut track = high_bin;
+        for f in (0..high_frame).rev() {
+            let frame = view.frame(f);
+            let (b, power) = peak_bin_in(frame, track.saturating_sub(max_jump_bins), (track + max_jump_bins).min(num_bins - 1));
+            if power <= start_threshold_db {
+                break;
+            }
+            track = b;
+            start_frame = f;
+            start_hz = freqs[b];
+            if b > 0 && b + 1 < num_bins && power > frame[b - 1] && power > frame[b + 1] {
+                let (prev, next) = (frame[b - 1], frame[b + 1]);
+                let ratio = ((power - start_threshold_db) / (power - prev.min(next))) as f64;
+                let direction = if prev < next { 1.0 } else { -1.0 };
+                start_hz = freqs[b] + ratio * (freqs[b + 1] - freqs[b]) * direction;
+            }
+        }
+    }
+
+    // 6. 由 Low Frequency 所在幀向後追蹤 End Frequency
+    let low_threshold_db = peak_power + low.threshold as f32;
+    let mut end_hz = low.freq_hz;
+    let mut end_frame = low.frame;
+    let anchor_power = view.frame(low.frame)[low.bin];
+    if anchor_power >= peak_power - 50.0 && anchor_power >= -100.0 {
+        let mut track = low.bin;
+        for f in low.frame + 1..n {
+            let frame = view.frame(f);
+            let (b, power) = peak_bin_in(frame, track.saturating_sub(max_jump_bins), (track + max_jump_bins).min(num_bins - 1));
+            if power <= low_threshold_db {
+                break;
+            }
+            track = b;
+            end_frame = f;
+            end_hz = freqs[b];
+            if b > 0 && b + 1 < num_bins && power > frame[b - 1] && power > frame[b + 1] {
+                let (prev, next) = (frame[b - 1], frame[b + 1]);
+                let ratio = ((power - low_threshold_db) / (power - prev.min(next))) as f64;
+                let direction = if prev < next { 1.0 } else { -1.0 };
+                end_hz = freqs[b] + ratio * (freqs[b + 1] - freqs[b]) * direction * 0.5;
+            }
+        }
+    }
+    let low_hz = low.freq_hz.min(start_hz).min(end_hz);
+
+    // 7. 特徵頻率：最後 40% 幀中，相鄰峰值頻率斜率絕對值最小處
+    let char_end = if end_frame == 0 { n - 1 } else { end_frame };
+    let last_part_start = (new_start as f64 + (char_end as f64 - new_start as f64) * 0.6).floor();
+    let mut char_hz = peak_hz;
+    let mut char_frame = 0;
+    if last_part_start < char_end as f64 {
+        let points: Vec<(usize, f64)> = (last_part_start.max(0.0) as usize..=char_end)
+            .map(|f| (f, freqs[peak_bin_in(view.frame(f), 0, num_bins - 1).0]))
+            .collect();
+        let dt_ms = if n > 1 { (view.time(1) - view.time(0)) * 1000.0 } else { 0.0 };
+        let mut min_slope = f64::INFINITY;
+        let mut best = 0;
+        for i in 0..points.len() - 1 {
+            let slope = if dt_ms > 0.0 { (points[i + 1].1 - points[i].1) / 1000.0 / dt_ms } else { 0.0 };
+            if slope.abs() < min_slope {
+                min_slope = slope.abs();
+                best = i;
+            }
+        }
+        (char_frame, char_hz) = points[best];
+    }
+    if char_hz < low_hz {
+        char_hz = low_hz;
+    } else if char_hz > peak_hz {
+        char_hz = peak_hz;
+    }
+
+    // 8. 膝點與踵點：在 [low - 1 kHz, high + 1 kHz] 內的每幀峰值輪廓上尋找
+    let contour_start = new_start.max(start_frame);
+    let contour_end = char_end.min(n - 1);
+    let min_bin = freqs.iter().position(|&f| f >= low_hz - 1000.0).unwrap_or(0);
+    let max_bin = freqs.iter().rposition(|&f| f <= high_hz + 1000.0).unwrap_or(num_bins - 1);
+    let mut contour_frames = Vec::new();
+    let mut contour_khz = Vec::new();
+    if contour_end + 1 > contour_start + 2 && min_bin <= max_bin {
+        for f in contour_start..=contour_end {
+            contour_frames.push(f);
+            contour_khz.push(freqs[peak_bin_in(view.frame(f), min_bin, max_bin).0] / 1000.0);
+        }
+    }
+    let (knee, heel) = if contour_khz.len() >= 5 {
+        let times: Vec<f64> = contour_frames.iter().map(|&f| view.time(f)).collect();
+        find_knee_and_heel(&contour_khz, &times)
+    } else {
+        (None, None)
+    };
+
+    // 9. 信噪比：叫聲框為 [0, End Frequency 所在幀] x [low, high]，框外為噪聲；
+    //    無法計算時以峰值功率減去 25 百分位功率（不低於 -80 dB）估計
+    let snr_db = match rms_snr(view, (0, end_frame), low_hz, high_hz, None) {
+        Some(snr) if snr.snr_db.is_finite() => snr.snr_db,
+        _ => {
+            let mut powers = view.spec.power_db[view.first_frame * num_bins..(view.first_frame + n) * num_bins].to_vec();
+            powers.sort_unstable_by(|a, b| a.total_cmp(b));
+            let floor = powers.get(powers.len() / 4).copied().unwrap_or(MIN_ROBUST_NOISE_FLOOR_DB);
+            (peak_power - floor.max(MIN_ROBUST_NOISE_FLOOR_DB)) as f64
+        }
+    };
+
+    // 10. 時間以 Start Frequency 所在幀為 0
+    let t0 = view.time(start_frame);
+    let time_ms = |f: usize| (view.time(f) - t0) * 1000.0;
+    let end_time_ms = time_ms(end_frame);
+    let bandwidth_khz = (high_hz - low_hz) / 1000.0;
+    Some(BatCall {
+        start_time: t0,
+        end_time: view.time(end_frame),
+        duration_ms: end_time_ms,
+        peak_freq_khz: peak_hz / 1000.0,
+        peak_time_ms: time_ms(peak_frame),
+        peak_power_db: peak_power as f64,
+        high_freq_khz: high_hz / 1000.0,
+        high_time_ms: time_ms(high_frame),
+        start_freq_khz: start_hz / 1000.0,
+        end_freq_khz: end_hz / 1000.0,
+        low_freq_khz: low_hz / 1000.0,
+        low_time_ms: time_ms(low.frame),
+        characteristic_freq_khz: char_hz / 1000.0,
+        characteristic_time_ms: time_ms(char_frame),
+        knee_freq_khz: knee.map(|i| contour_khz[i]),
+        knee_time_ms: knee.map(|i| time_ms(contour_frames[i])),
+        heel_freq_khz: heel.map(|i| contour_khz[i]),
+        heel_time_ms: heel.map(|i| time_ms(contour_frames[i])),
+        bandwidth_khz,
+        call_type: CallType::from_bandwidth(bandwidth_khz),
+        high_freq_threshold_db: high_threshold as f64,
+        low_freq_threshold_db: low.threshold as f64,
+        high_freq_warning: high.warning,
+        low_freq_warning: low.warning,
+        snr_db,
+        quality: CallQuality::from_snr(snr_db),
+    })
+}
+
+// ------------------------------------------------------------
+// 信噪比
+// ------------------------------------------------------------
+
+/// RMS 信噪比的計算結果
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SnrResult {
+    /// 信噪比 (dB)；沒有噪聲樣本時為正無窮
+    pub snr_db: f64,
+    /// 信號平均功率 (dB)
+    pub signal_power_db: f64,
+    /// 噪聲平均功率 (dB)；沒有噪聲樣本時為負無窮
+    pub noise_power_db: f64,
+    /// 參與計算的信號頻率箱數
+    pub signal_count: usize,
+    /// 參與計算的噪聲頻率箱數
+    pub noise_count: usize,
+    /// 依 SNR 分級的品質
+    pub quality: CallQuality,
+}
+
+#[wasm_bindgen]
+impl SnrResult {
+    /// 品質名稱 ("Very Poor", "Poor", "Normal", "Good", "Excellent")
+    #[wasm_bindgen]
+    pub fn get_quality_name(&self) -> String {
+        self.quality.as_str().to_string()
+    }
+}
+
+/// 以線性功率平均計算 RMS 信噪比（對應 calculateRMSbasedSNR）
+///
+/// 信號：叫聲框內高於 (最小值 + 動態範圍 * 25%) 的頻率箱；
+/// 噪聲：noise_spec 的全部頻率箱，未提供時為 view 中叫聲框以外的頻率箱。
+///
+/// # Arguments
+/// * `view` - 叫聲所在的幀
+/// * `signal_frames` - 叫聲框的 (起始幀, 結束幀)，相對 view，皆包含在內
+/// * `low_hz` / `high_hz` - 叫聲框的頻率範圍 (Hz)
+/// * `noise_spec` - 獨立的噪聲頻譜圖
+///
+/// # Returns
+/// 叫聲框內沒有高於動態閾值的頻率箱時為 None
+fn rms_snr(
+    view: &CallView,
+    (signal_start, signal_end): (usize, usize),
+    low_hz: f64,
+    high_hz: f64,
+    noise_spec: Option<&PowerSpectrogram>,
+) -> Option<SnrResult> {
+    let freqs = view.freqs();
+    let signal_end = signal_end.min(view.num_frames - 1);
+    let in_band = |b: usize| freqs[b] >= low_hz && freqs[b] <= high_hz;
+
+    let (mut max_db, mut min_db) = (f32::NEG_INFINITY, f32::INFINITY);
+    for f in signal_start..=signal_end {
+        for (b, &p) in view.frame(f).iter().enumerate() {
+            if in_band(b) {
+                max_db = max_db.max(p);
+                min_db = min_db.min(p);
+            }
+        }
+    }
+    if max_db == f32::NEG_INFINITY {
+        return None;
+    }
+
+    let threshold_db = min_db + (max_db - min_db) * SNR_SIGNAL_THRESHOLD_RATIO;
+    let (mut signal_sum, mut signal_count) = (0.0f64, 0usize);
+    for f in signal_start..=signal_end {
+        for (b, &p) in view.frame(f).iter().enumerate() {
+            if in_band(b) && p > threshold_db {
+                signal_sum += 10f64.powf(p as f64 / 10.0);
+                signal_count += 1;
+            }
+        }
+    }
+    if signal_count == 0 {
+        return None;
+    }
+
+    let (mut noise_sum, mut noise_count) = (0.0f64, 0usize);
+    match noise_spec {
+        Some(noise) => {
+            for &p in &noise.power_db {
+                noise_sum += 10f64.powf(p as f64 / 10.0);
+                noise_count += 1;
+            }
+        }
+        None => {
+            for f in 0..view.num_frames {
+                let in_signal_time = f >= signal_start && f <= signal_end;
+                for (b, &p) in view.frame(f).iter().enumerate() {
+                    if !(in_signal_time && in_band(b)) {
+                        noise_sum += 10f64.powf(p as f64 / 10.0);
+                        noise_count += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    let signal_power_db = 10.0 * (signal_sum / signal_count as f64).max(1e-16).log10();
+    let (noise_power_db, snr_db) = if noise_count == 0 {
+        (f64::NEG_INFINITY, f64::INFINITY)
+    } else {
+        let noise_power_db = 10.0 * (noise_sum / noise_count as f64).max(1e-16).log10();
+        (noise_power_db, signal_power_db - noise_power_db)
+    };
+    Some(SnrResult {
+        snr_db,
+        signal_power_db,
+        noise_power_db,
+        signal_count,
+        noise_count,
+        quality: CallQuality::from_snr(snr_db),
+    })
+}
+
+/// 叫聲 [start_time, end_time] 對應的幀範圍（包含在內）
+///
+/// # Arguments
+/// * `factor` - 時間擴展倍數，用於撤銷 BatCall 的時間校正
+fn call_frame_range(spec: &PowerSpectrogram, call: &BatCall, factor: f64) -> (usize, usize) {
+    let (start_s, end_s) = (call.start_time * factor, call.end_time * factor);
+    let last = spec.num_frames() - 1;
+    let first_frame = spec.times.iter().position(|&t| t >= start_s).unwrap_or(last);
+    let last_frame = spec.times.iter().rposition(|&t| t <= end_s).unwrap_or(0).max(first_frame);
+    (first_frame, last_frame)
+}
+
+/// 計算一個叫聲的 RMS 信噪比
+///
+/// 以 config 的頻帶計算 audio_data 的頻譜圖，叫聲框為 call 的
+/// [start_time, end_time] x [low_freq, high_freq]（先撤銷時間擴展校正）。
+///
+/// # Arguments
+/// * `audio_data` - 叫聲時間所相對的音頻樣本
+/// * `sample_rate` - 採樣率 (Hz)
+/// * `call` - 叫聲測量結果
+/// * `config` - 偵測配置
+/// * `noise_audio` - 獨立的噪聲樣本（例如錄音最後 10 ms）；為 None 或短於一幀時以叫聲框以外的頻率箱為噪聲
+///
+/// # Returns
+/// 音頻短於一幀或叫聲框內沒有信號時為 None
+pub fn call_snr(
+    audio_data: &[f32],
+    sample_rate: f32,
+    call: &BatCall,
+    config: &DetectionConfig,
+    noise_audio: Option<&[f32]>,
+) -> Option<SnrResult> {
+    let spec = PowerSpectrogram::compute(audio_data, sample_rate, config)?;
+    let noise_spec = noise_audio.and_then(|noise| PowerSpectrogram::compute(noise, sample_rate, config));
+
+    let factor = config.time_expansion.max(1.0);
+    let (first_frame, last_frame) = call_frame_range(&spec, call, factor);
+
+    let view = CallView {
+        spec: &spec,
+        first_frame: 0,
+        num_frames: spec.num_frames(),
+    };
+    rms_snr(
+        &view,
+        (first_frame, last_frame),
+        call.low_freq_khz / factor * 1000.0,
+        call.high_freq_khz / factor * 1000.0,
+        noise_spec.as_ref(),
+    )
+}
+
+// ------------------------------------------------------------
+// 頻率輪廓
+// ------------------------------------------------------------
+
+/// 提取一個叫聲的頻率輪廓（見 ridge::extract_ridge）
+///
+/// 只在叫聲的 [low_freq - 2 kHz, high_freq + 2 kHz] 內搜索，避免跳到諧波；
+/// 時間範圍為 call 的 [start_time, end_time]。
+///
+/// # Arguments
+/// * `audio_data` - 叫聲時間所相對的音頻樣本
+/// * `sample_rate` - 採樣率 (Hz)
+/// * `call` - 叫聲測量結果
+/// * `config` - 偵測配置
+/// * `ridge` - 脊線提取配置
+///
+/// # Returns
+/// 輪廓點；時間相對於 audio_data 開頭，並與 call 一樣做時間擴展校正
+pub fn call_contour(
+    audio_data: &[f32],
+    sample_rate: f32,
+    call: &BatCall,
+    config: &DetectionConfig,
+    ridge: &RidgeConfig,
+) -> Vec<ContourPoint> {
+    let factor = config.time_expansion.max(1.0);
+    let box_config = DetectionConfig {
+        flow_khz: (call.low_freq_khz / factor - CONTOUR_BAND_MARGIN_KHZ).max(0.0),
+        fhigh_khz: call.high_freq_khz / factor + CONTOUR_BAND_MARGIN_KHZ,
+        ..config.clone()
+    };
+    let Some(spec) = PowerSpectrogram::compute(audio_data, sample_rate, &box_config) else {
+        return Vec::new();
+    };
+    let (first_frame, last_frame) = call_frame_range(&spec, call, factor);
+
+    let mut contour = extract_ridge(&spec.power_db, &spec.freqs, &spec.times, first_frame, last_frame, ridge);
+    if factor > 1.0 {
+        for point in &mut contour {
+            point.time_s /= factor;
+            point.freq_hz *= factor;
+        }
+    }
+    contour
+}
+
+/// 依頻率輪廓分類一個叫聲的類型（見 call_shape::classify_shape）
+///
+/// # Arguments
+/// * `audio_data` - 叫聲時間所相對的音頻樣本
+/// * `sample_rate` - 採樣率 (Hz)
+/// * `call` - 叫聲測量結果
+/// * `config` - 偵測配置
+/// * `shape` - 分類配置
+///
+/// # Returns
+/// 分類結果；段的時間與頻率與 call 一樣做時間擴展校正
+pub fn call_shape(
+    audio_data: &[f32],
+    sample_rate: f32,
+    call: &BatCall,
+    config: &DetectionConfig,
+    shape: &ShapeConfig,
+) -> CallShape {
+    let contour = call_contour(audio_data, sample_rate, call, config, &RidgeConfig::default());
+    let times: Vec<f64> = contour.iter().map(|p| p.time_s).collect();
+    let freqs: Vec<f64> = contour.iter().map(|p| p.freq_hz).collect();
+    classify_shape(&times, &freqs, shape)
+}
+
+/// 分析一個叫聲的諧波結構（見 harmonics::analyze_harmonics）
+///
+/// 先以預設脊線配置提取輪廓，再在整個偵測頻帶的頻譜圖上量測各諧波。
+///
+/// # Arguments
+/// * `audio_data` - 叫聲時間所相對的音頻樣本
+/// * `sample_rate` - 採樣率 (Hz)
+/// * `call` - 叫聲測量結果
+/// * `config` - 偵測配置；頻帶上限應涵蓋要分析的諧波
+/// * `harmonic` - 諧波分析配置
+///
+/// # Returns
+/// 基頻與 call 一樣做時間擴展校正；無法分析時為 None
+pub fn call_harmonics(
+    audio_data: &[f32],
+    sample_rate: f32,
+    call: &BatCall,
+    config: &DetectionConfig,
+    harmonic: &HarmonicConfig,
+) -> Option<HarmonicAnalysis> {
+    let factor = config.time_expansion.max(1.0);
+    let mut contour = call_contour(audio_data, sample_rate, call, config, &RidgeConfig::default());
+    for point in &mut contour {
+        point.freq_hz /= factor;
+    }
+    let spec = PowerSpectrogram::compute(audio_data, sample_rate, config)?;
+
+    let mut analysis = analyze_harmonics(&spec.power_db, &spec.freqs, &contour, harmonic)?;
+    analysis.fundamental_hz *= factor;
+    Some(analysis)
+}
+
+// ------------------------------------------------------------
+// 分段
+// ------------------------------------------------------------
+
+/// 找出任一頻率箱高於 (全局最大值 + call_threshold_db) 的連續幀
+///
+/// # Returns
+/// (起始幀, 結束幀) 列表，皆包含在內
+fn detect_call_segments(spec: &PowerSpectrogram, call_threshold_db: f32) -> Vec<(usize, usize)> {
+    let n = spec.num_frames();
+    if n == 0 {
+        return Vec::new();
+    }
+    let (global_max, _, _) = spec.global_peak(0, n - 1);
+    let threshold_db = global_max + call_threshold_db;
+
+    let mut segments = Vec::new();
+    let mut segment_start: Option<usize> = None;
+    for f in 0..n {
+        let active = spec.frame(f).iter().any(|&p| p > threshold_db);
+        match (active, segment_start) {
+            (true, None) => segment_start = Some(f),
+            (false, Some(start)) => {
+                segments.push((start, f - 1));
+                segment_start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(start) = segment_start {
+        segments.push((start, n - 1));
+    }
+    segments
+}
+
+/// 以時域 RMS 包絡精修叫聲結尾
+///
+/// 從包絡峰值向後掃描：最低點低於 -60 dB 時截斷；或能量從最低點反彈超過 0.5 dB
+/// 並持續 0.5 ms（最低點高於 -32 dB 時視為叫聲主體而忽略）時，截斷於最低點。
+///
+/// # Returns
+/// 精修後的結尾樣本；無法精修時返回 end_sample
+fn refine_end_using_oscillogram(audio_data: &[f32], sample_rate: f32, start_sample: usize, end_sample: usize) -> usize {
+    let sr = sample_rate as f64;
+    let safe_end = end_sample.min(audio_data.len());
+    if safe_end <= start_sample || ((safe_end - start_sample) as f64) < sr * 0.0005 {
+        return end_sample;
+    }
+
+    let window_size = (sr * 0.0001).floor() as usize;
+    let hop_size = window_size / 2;
+    if hop_size == 0 {
+        return end_sample;
+    }
+    let sustained_steps = ((sr * 0.0005).floor() / hop_size as f64).ceil() as usize;
+
+    // RMS 包絡 (dB)
+    let mut db_values = Vec::new();
+    let mut sample_indices = Vec::new();
+    let mut i = start_sample;
+    while i + window_size < safe_end {
+        let sum_sq: f64 = audio_data[i..i + window_size].iter().map(|&v| v as f64 * v as f64).sum();
+        let rms = (sum_sq / window_size as f64).sqrt();
+        db_values.push(20.0 * (rms + 1e-9).log10());
+        sample_indices.push(i + window_size / 2);
+        i += hop_size;
+    }
+    let Some(peak_idx) = (0..db_values.len()).reduce(|best, k| if db_values[k] > db_values[best] { k } else { best }) else {
+        return end_sample;
+    };
+
+    let mut min_db = db_values[peak_idx];
+    let mut min_idx = peak_idx;
+    for i in peak_idx + 1..db_values.len() {
+        let current = db_values[i];
+        if current < min_db {
+            min_db = current;
+            min_idx = i;
+        }
+        if min_db < -60.0 && current < -58.0 {
+            return sample_indices[min_idx];
+        }
+        if current - min_db > 0.5 && min_db <= -32.0 {
+            let look_ahead = db_values.len().min(i + sustained_steps);
+            if (i + 1..look_ahead).all(|k| db_values[k] >= min_db + 0.5) {
+                return sample_indices[min_idx];
+            }
+        }
+    }
+    end_sample
+}
+
+/// 依峰值頻率 (kHz) 選擇自動高通濾波器的截止頻率 (kHz)，0 表示不濾波
+fn auto_highpass_cutoff_khz(peak_freq_khz: f64) -> f64 {
+    if peak_freq_khz >= 40.0 {
+        30.0
+    } else if peak_freq_khz >= 35.0 {
+        25.0
+    } else if peak_freq_khz >= 30.0 {
+        20.0
+    } else {
+        0.0
+    }
+}
+
+/// CallDetector: 蝙蝠叫聲偵測器
+///
+/// 對選取範圍 (detect_calls) 或整個文件 (process_full_file) 執行完整的偵測流程，
+/// 結果以 BatCall 列表保存。
+#[wasm_bindgen]
+pub struct CallDetector {
+    config: DetectionConfig,
+    calls: Vec<BatCall>,
+}
+
+impl Default for CallDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[wasm_bindgen]
+impl CallDetector {
+    /// 創建偵測器（預設配置同 DEFAULT_DETECTION_CONFIG）
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> CallDetector {
+        CallDetector::with_config(DetectionConfig::default())
+    }
+
+    /// 設置叫聲能量閾值 (dB，相對最大值，典型值 -18 ~ -24)
+    #[wasm_bindgen]
+    pub fn set_call_threshold(&mut self, threshold_db: f32) {
+        self.config.call_threshold_db = -threshold_db.abs();
+    }
+
+    /// 設置 FFT 大小與跳步百分比
+    ///
+    /// # Arguments
+    /// * `fft_size` - FFT 大小
+    /// * `hop_percent` - 跳步佔 FFT 大小的百分比
+    #[wasm_bindgen]
+    pub fn set_resolution(&mut self, fft_size: usize, hop_percent: f64) {
+        self.config.fft_size = fft_size.max(2);
+        self.config.hop_percent = hop_percent.clamp(0.0, 100.0);
+    }
+
+    /// 設置窗函數
+    #[wasm_bindgen]
+    pub fn set_window(&mut self, window_type: String) {
+        self.config.window_type = window_type;
+    }
+
+    /// 設置偵測頻帶
+    ///
+    /// # Arguments
+    /// * `flow_khz` - 下限 (kHz)
+    /// * `fhigh_khz` - 上限 (kHz)
+    #[wasm_bindgen]
+    pub fn set_band(&mut self, flow_khz: f64, fhigh_khz: f64) {
+        self.config.flow_khz = flow_khz.max(0.0);
+        self.config.fhigh_khz = fhigh_khz.max(self.config.flow_khz);
+    }
+
+    /// 設置最短叫聲時長 (毫秒)
+    #[wasm_bindgen]
+    pub fn set_min_call_duration(&mut self, duration_ms: f64) {
+        self.config.min_call_duration_ms = duration_ms.max(0.0);
+    }
+
+    /// 設置時間擴展倍數（1 表示實時錄音）
+    #[wasm_bindgen]
+    pub fn set_time_expansion(&mut self, factor: f64) {
+        self.config.time_expansion = factor.max(1.0);
+    }
+
+    /// 設置全文件掃描橋接的最大斷層 (毫秒)
+    #[wasm_bindgen]
+    pub fn set_gap_bridge(&mut self, max_gap_ms: f64) {
+        self.config.max_gap_bridge_ms = max_gap_ms.max(0.0);
+    }
+
+    /// 設置全文件掃描的遲滯 (dB)
+    #[wasm_bindgen]
+    pub fn set_scan_hysteresis(&mut self, hysteresis_db: f32) {
+        self.config.scan_hysteresis_db = hysteresis_db.max(0.0);
+    }
+
+    /// 偵測選取範圍內的叫聲
+    ///
+    /// # Arguments
+    /// * `audio_data` - 音頻數據 (Float32Array)
+    /// * `sample_rate` - 採樣率 (Hz)
+    ///
+    /// # Returns
+    /// 叫聲數量；時間相對於選取範圍開頭
+    #[wasm_bindgen]
+    pub fn detect_calls(&mut self, audio_data: &[f32], sample_rate: f32) -> usize {
+        self.calls = self.detect(audio_data, sample_rate);
+        self.calls.len()
+    }
+
+    /// 偵測整個文件的叫聲
+    ///
+    /// # Arguments
+    /// * `audio_data` - 音頻數據 (Float32Array)
+    /// * `sample_rate` - 採樣率 (Hz)
+    /// * `threshold_db` - 快速掃描的頻帶能量閾值 (dB，典型值 -60)
+    /// * `padding_ms` - 掃描範圍前後的保留時間 (毫秒，典型值 5)
+    ///
+    /// # Returns
+    /// 叫聲數量；時間相對於文件開頭
+    #[wasm_bindgen]
+    pub fn process_full_file(&mut self, audio_data: &[f32], sample_rate: f32, threshold_db: f32, padding_ms: f64) -> usize {
+        self.calls = self.detect_full_file(audio_data, sample_rate, threshold_db, padding_ms);
+        self.calls.len()
+    }
+
+    /// 測量選取範圍內最主要的叫聲（見 measure_call）
+    ///
+    /// # Arguments
+    /// * `audio_data` - 選取範圍的音頻數據 (Float32Array)
+    /// * `sample_rate` - 採樣率 (Hz)
+    /// * `flow_khz` - 頻率下限 (kHz)
+    /// * `fhigh_khz` - 頻率上限 (kHz)
+    ///
+    /// # Returns
+    /// 測量結果，不改變 get_call 的內容；範圍太短時為 None
+    #[wasm_bindgen]
+    pub fn measure_selection(&self, audio_data: &[f32], sample_rate: f32, flow_khz: f64, fhigh_khz: f64) -> Option<BatCall> {
+        measure_call(audio_data, sample_rate, flow_khz, fhigh_khz, &self.config)
+    }
+
+    /// 計算叫聲的 RMS 信噪比（見 call_snr）
+    ///
+    /// # Arguments
+    /// * `audio_data` - 叫聲時間所相對的音頻數據 (Float32Array)
+    /// * `sample_rate` - 採樣率 (Hz)
+    /// * `call` - 叫聲測量結果
+    /// * `noise_data` - 獨立的噪聲樣本 (Float32Array)；空數組表示以叫聲框以外的頻率箱為噪聲
+    ///
+    /// # Returns
+    /// 無法計算時為 None
+    #[wasm_bindgen]
+    pub fn measure_snr(&self, audio_data: &[f32], sample_rate: f32, call: &BatCall, noise_data: &[f32]) -> Option<SnrResult> {
+        let noise_audio = if noise_data.is_empty() { None } else { Some(noise_data) };
+        call_snr(audio_data, sample_rate, call, &self.config, noise_audio)
+    }
+
+    /// 提取叫聲的頻率輪廓（見 call_contour）
+    ///
+    /// # Arguments
+    /// * `audio_data` - 叫聲時間所相對的音頻數據 (Float32Array)
+    /// * `sample_rate` - 採樣率 (Hz)
+    /// * `call` - 叫聲測量結果
+    /// * `jump_penalty_db_per_khz` - 頻率跳變懲罰 (dB / kHz，典型值 1)
+    /// * `max_gap_frames` - 以插值填補的最長斷層 (幀)，0 表示不填補
+    ///
+    /// # Returns
+    /// 扁平的 Float32Array，每點 3 個值：[時間 (秒), 頻率 (Hz), 功率 (dB)]
+    #[wasm_bindgen]
+    pub fn extract_contour(
+        &self,
+        audio_data: &[f32],
+        sample_rate: f32,
+        call: &BatCall,
+        jump_penalty_db_per_khz: f32,
+        max_gap_frames: usize,
+    ) -> Vec<f32> {
+        let ridge = RidgeConfig {
+            jump_penalty_db_per_khz,
+            max_gap_frames,
+            ..RidgeConfig::default()
+        };
+        call_contour(audio_data, sample_rate, call, &self.config, &ridge)
+            .into_iter()
+            .flat_map(|p| [p.time_s as f32, p.freq_hz as f32, p.power_db])
+            .collect()
+    }
+
+    /// 依頻率輪廓分類叫聲類型（見 call_shape）
+    ///
+    /// # Arguments
+    /// * `audio_data` - 叫聲時間所相對的音頻數據 (Float32Array)
+    /// * `sample_rate` - 採樣率 (Hz)
+    /// * `call` - 叫聲測量結果
+    ///
+    /// # Returns
+    /// 分類結果，包含複合類型 (例如 "FM-CF-FM") 與各段的邊界和時長
+    #[wasm_bindgen]
+    pub fn classify_call(&self, audio_data: &[f32], sample_rate: f32, call: &BatCall) -> CallShape {
+        call_shape(audio_data, sample_rate, call, &self.config, &ShapeConfig::default())
+    }
+
+    /// 分析叫聲的諧波結構（見 call_harmonics）
+    ///
+    /// # Arguments
+    /// * `audio_data` - 叫聲時間所相對的音頻數據 (Float32Array)
+    /// * `sample_rate` - 採樣率 (Hz)
+    /// * `call` - 叫聲測量結果
+    /// * `max_harmonics` - 分析的諧波數（包含基頻，典型值 4）
+    ///
+    /// # Returns
+    /// 扁平的 Float32Array：[基頻 (Hz), 輪廓所在諧波, 最強諧波, 存在的諧波數,
+    /// 諧波 0 相對強度 (dB), ..., 諧波 max_harmonics - 1 相對強度 (dB)]；
+    /// 諧波索引 0 表示基頻，不存在的諧波強度為 -Infinity；無法分析時為空數組
+    #[wasm_bindgen]
+    pub fn analyze_harmonics(&self, audio_data: &[f32], sample_rate: f32, call: &BatCall, max_harmonics: usize) -> Vec<f32> {
+        let harmonic = HarmonicConfig {
+            max_harmonics,
+            ..HarmonicConfig::default()
+        };
+        let Some(analysis) = call_harmonics(audio_data, sample_rate, call, &self.config, &harmonic) else {
+            return Vec::new();
+        };
+        let mut result = vec![
+            analysis.fundamental_hz as f32,
+            analysis.contour_harmonic as f32,
+            analysis.dominant_harmonic as f32,
+            analysis.num_present as f32,
+        ];
+        result.extend_from_slice(&analysis.relative_levels_db);
+        result
+    }
+
+    /// 叫聲數量
+    #[wasm_bindgen]
+    pub fn get_num_calls(&self) -> usize {
+        self.calls.len()
+    }
+
+    /// 獲取一個叫聲
+    ///
+    /// # Arguments
+    /// * `call_idx` - 叫聲索引
+    ///
+    /// # Returns
+    /// 索引無效時為 None
+    #[wasm_bindgen]
+    pub fn get_call(&self, call_idx: usize) -> Option<BatCall> {
+        self.calls.get(call_idx).copied()
+    }
+
+    /// 釋放偵測結果
+    #[wasm_bindgen]
+    pub fn release_memory(&mut self) {
+        self.calls = Vec::new();
+    }
+}
+
+impl CallDetector {
+    /// 以指定配置創建偵測器
+    pub fn with_config(config: DetectionConfig) -> CallDetector {
+        CallDetector {
+            config,
+            calls: Vec::new(),
+        }
+    }
+
+    /// 當前配置
+    pub fn config(&self) -> &DetectionConfig {
+        &self.config
+    }
+
+    /// 上一次偵測的結果
+    pub fn calls(&self) -> &[BatCall] {
+        &self.calls
+    }
+
+    /// 偵測選取範圍內的叫聲
+    ///
+    /// 噪聲基底取自頻譜圖的前 5 幀（通常是選取範圍開頭的靜音區）。
+    ///
+    /// # Arguments
+    /// * `audio_data` - 音頻樣本
+    /// * `sample_rate` - 採樣率 (Hz)
+    ///
+    /// # Returns
+    /// 按時間排序的叫聲；時間相對於 audio_data 開頭
+    pub fn detect(&self, audio_data: &[f32], sample_rate: f32) -> Vec<BatCall> {
+        let Some(spec) = PowerSpectrogram::compute(audio_data, sample_rate, &self.config) else {
+            return Vec::new();
+        };
+        let noise = ZonalNoiseMap::compute(&spec, 0, NOISE_MAP_FRAMES.min(spec.num_frames() - 1));
+        let frame_duration_ms = self.config.fft_size as f64 / sample_rate as f64 * 1000.0;
+
+        detect_call_segments(&spec, self.config.call_threshold_db)
+            .into_iter()
+            .filter(|&(start, end)| (end - start + 1) as f64 * frame_duration_ms >= self.config.min_call_duration_ms)
+            .filter_map(|segment| self.measure_segment(audio_data, sample_rate, &spec, segment, DETECT_PADDING_MS, &noise))
+            .map(|mut call| {
+                call.apply_time_expansion(self.config.time_expansion);
+                call
+            })
+            .collect()
+    }
+
+    /// 偵測整個文件的叫聲
+    ///
+    /// 先以快速能量掃描找出候選範圍，再對每個範圍計算高解析度頻譜圖：
+    /// 依峰值頻率自動高通濾波，分段後把峰值相距 30 ms 以內的較弱段視為回聲捨棄，
+    /// 最後逐段測量參數。
+    ///
+    /// # Arguments
+    /// * `audio_data` - 音頻樣本
+    /// * `sample_rate` - 採樣率 (Hz)
+    /// * `threshold_db` - 快速掃描的頻帶能量閾值 (dB)
+    /// * `padding_ms` - 掃描範圍前後的保留時間 (毫秒)
+    ///
+    /// # Returns
+    /// 按時間排序的叫聲；時間相對於文件開頭
+    pub fn detect_full_file(&self, audio_data: &[f32], sample_rate: f32, threshold_db: f32, padding_ms: f64) -> Vec<BatCall> {
+        let scan_config = SegmentScanConfig {
+            low_hz: (self.config.flow_khz * 1000.0) as f32,
+            high_hz: (self.config.fhigh_khz * 1000.0) as f32,
+            fft_size: self.config.fft_size,
+            threshold_db,
+            hysteresis_db: self.config.scan_hysteresis_db,
+            min_duration_ms: self.config.min_call_duration_ms as f32,
+            max_gap_ms: self.config.max_gap_bridge_ms as f32,
+            padding_ms: padding_ms as f32,
+        };
+        let regions = scan_segments(audio_data, sample_rate, &scan_config);
+
+        let mut calls = Vec::new();
+        for (region_start, region_end) in regions {
+            let mut region = audio_data[region_start..region_end].to_vec();
+            let Some(mut spec) = PowerSpectrogram::compute(&region, sample_rate, &self.config) else {
+                continue;
+            };
+            let noise = ZonalNoiseMap::compute(&spec, 0, NOISE_MAP_FRAMES.min(spec.num_frames() - 1));
+
+            // 依峰值頻率自動高通濾波，去除低頻噪聲
+            let (_, _, peak_bin) = spec.global_peak(0, spec.num_frames() - 1);
+            let cutoff_khz = auto_highpass_cutoff_khz(spec.freqs[peak_bin] / 1000.0);
+            if cutoff_khz > 0.0 {
+                if let Some(sos) = design_sos(
+                    FilterDesign::Butterworth,
+                    FilterBand::Highpass,
+                    HIGHPASS_ORDER,
+                    sample_rate,
+                    (cutoff_khz * 1000.0) as f32,
+                    0.0,
+                    0.0,
+                    0.0,
+                ) {
+                    region = sosfilt(&sos, &region);
+                    if let Some(filtered) = PowerSpectrogram::compute(&region, sample_rate, &self.config) {
+                        spec = filtered;
+                    }
+                }
+            }
+
+            // 候選段按峰值功率由強到弱排序，峰值相距過近的較弱段視為回聲
+            let min_duration_s = self.config.min_call_duration_ms / 1000.0;
+            let mut candidates: Vec<(usize, usize, f32, f64)> = detect_call_segments(&spec, self.config.call_threshold_db)
+                .into_iter()
+                .filter(|&(start, end)| spec.times[end] - spec.times[start] >= min_duration_s)
+                .map(|(start, end)| {
+                    let (power, frame, _) = spec.global_peak(start, end);
+                    (start, end, power, spec.times[frame])
+                })
+                .collect();
+            candidates.sort_by(|a, b| b.2.total_cmp(&a.2));
+            let mut kept: Vec<(usize, usize, f32, f64)> = Vec::new();
+            for candidate in candidates {
+                if kept.iter().all(|k| (candidate.3 - k.3).abs() >= ECHO_MIN_GAP_S) {
+                    kept.push(candidate);
+                }
+            }
+            kept.sort_by_key(|k| k.0);
+
+            let offset_s = region_start as f64 / sample_rate as f64;
+            for (start, end, _, _) in kept {
+                let Some(mut call) =
+                    self.measure_segment(&region, sample_rate, &spec, (start, end), FULL_FILE_CALL_PADDING_MS, &noise)
+                else {
+                    continue;
+                };
+                if call.duration_ms <= 1.0 {
+                    continue;
+                }
+                call.start_time += offset_s;
+                call.end_time += offset_s;
+                call.apply_time_expansion(self.config.time_expansion);
+                calls.push(call);
+            }
+        }
+        calls
+    }
+
+    /// 加上保留幀、以波形精修結尾後測量一個叫聲段
+    fn measure_segment(
+        &self,
+        audio_data: &[f32],
+        sample_rate: f32,
+        spec: &PowerSpectrogram,
+        (segment_start, segment_end): (usize, usize),
+        padding_ms: f64,
+        noise: &ZonalNoiseMap,
+    ) -> Option<BatCall> {
+        let n = spec.num_frames();
+        let sr = sample_rate as f64;
+        let padding_frames = (padding_ms / 1000.0 / (spec.hop_size as f64 / sr)).ceil() as usize;
+        let first_frame = segment_start.saturating_sub(padding_frames);
+        let mut last_frame = (segment_end + padding_frames).min(n - 1);
+
+        let start_sample = (spec.times[first_frame] * sr).floor() as usize;
+        let end_sample = (spec.times[last_frame] * sr).floor() as usize;
+        let refined = refine_end_using_oscillogram(audio_data, sample_rate, start_sample, end_sample);
+        if refined < end_sample {
+            let refined_time = refined as f64 / sr;
+            let mut new_end = last_frame;
+            while new_end > first_frame && spec.times[new_end] > refined_time {
+                new_end -= 1;
+            }
+            last_frame = (new_end + 1).min(n - 1);
+        }
+
+        let duration_ms = (spec.times[(last_frame + 1).min(n - 1)] - spec.times[first_frame]) * 1000.0;
+        if duration_ms <= 1.0 || duration_ms < self.config.min_call_duration_ms {
+            return None;
+        }
+
+        let view = CallView {
+            spec,
+            first_frame,
+            num_frames: last_frame - first_frame + 1,
+        };
+        measure_call_view(&view, self.config.fhigh_khz, noise)
+    }
+}
+
+/// 測量選取範圍內的叫聲參數（對應 measureSelectionParameters）
+///
+/// 以 [flow_khz, fhigh_khz] 為偵測頻帶執行選取範圍偵測，返回時長最長的叫聲；
+/// 偵測不到叫聲時，把整個選取範圍視為一個叫聲直接測量。
+/// 結果包含 High / Low Frequency 實際使用的閾值與警告標記。
+///
+/// # Arguments
+/// * `audio_data` - 選取範圍的音頻樣本
+/// * `sample_rate` - 採樣率 (Hz)
+/// * `flow_khz` - 頻率下限 (kHz)
+/// * `fhigh_khz` - 頻率上限 (kHz)
+/// * `config` - 偵測配置（頻帶以參數為準）
+///
+/// # Returns
+/// 時間相對於 audio_data 開頭；範圍短於一幀或直接測量被判為噪聲時為 None
+pub fn measure_call(audio_data: &[f32], sample_rate: f32, flow_khz: f64, fhigh_khz: f64, config: &DetectionConfig) -> Option<BatCall> {
+    let flow_khz = flow_khz.max(0.0);
+    let detector = CallDetector::with_config(DetectionConfig {
+        flow_khz,
+        fhigh_khz: fhigh_khz.max(flow_khz),
+        ..config.clone()
+    });
+
+    let longest = detector.detect(audio_data, sample_rate).into_iter().reduce(|best, call| {
+        if call.end_time - call.start_time > best.end_time - best.start_time {
+            call
+        } else {
+            best
+        }
+    });
+    if longest.is_some() {
+        return longest;
+    }
+
+    // 直接測量整個選取範圍
+    let spec = PowerSpectrogram::compute(audio_data, sample_rate, &detector.config)?;
+    let noise = ZonalNoiseMap::compute(&spec, 0, NOISE_MAP_FRAMES.min(spec.num_frames() - 1));
+    let view = CallView {
+        spec: &spec,
+        first_frame: 0,
+        num_frames: spec.num_frames(),
+    };
+    let mut call = measure_call_view(&view, detector.config.fhigh_khz, &noise)?;
+    call.apply_time_expansion(detector.config.time_expansion);
+    Some(call)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 384_000.0;
+
+    /// 可重現的均勻白噪聲
+    fn noise(len: usize, amplitude: f32, seed: u32) -> Vec<f32> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                (state >> 8) as f32 / (1u32 << 24) as f32 * 2.0 * amplitude - amplitude
+            })
+            .collect()
+    }
+
+    /// 在噪聲中合成一個線性下掃的 FM 叫聲，兩端以 0.5 ms 餘弦斜坡淡入淡出
+    ///
+    /// # Returns
+    /// (音頻, 叫聲開始時間 s)
+    fn fm_sweep(start_hz: f64, end_hz: f64, duration_ms: f64, noise_amplitude: f32) -> (Vec<f32>, f64) {
+        let lead_s = 0.010;
+        let total = (SAMPLE_RATE as f64 * (lead_s * 2.0 + duration_ms / 1000.0)) as usize;
+        let mut audio = noise(total, noise_amplitude, 7);
+        let start = (lead_s * SAMPLE_RATE as f64) as usize;
+        let len = (duration_ms / 1000.0 * SAMPLE_RATE as f64) as usize;
+        let ramp = (0.0005 * SAMPLE_RATE as f64) as usize;
+        let duration_s = duration_ms / 1000.0;
+        for i in 0..len {
+            let t = i as f64 / SAMPLE_RATE as f64;
+            let phase = 2.0 * std::f64::consts::PI * (start_hz * t + (end_hz - start_hz) * t * t / (2.0 * duration_s));
+            let edge = i.min(len - 1 - i);
+            let gain = if edge < ramp { 0.5 - 0.5 * (std::f64::consts::PI * edge as f64 / ramp as f64).cos() } else { 1.0 };
+            audio[start + i] += (0.5 * gain * phase.sin()) as f32;
+        }
+        (audio, lead_s)
+    }
+
+    fn cf_tone(freq_hz: f64, duration_ms: f64, noise_amplitude: f32) -> Vec<f32> {
+        fm_sweep(freq_hz, freq_hz, duration_ms, noise_amplitude).0
+    }
+
+    /// 單幀合成頻譜圖：以 peak_hz 為中心、兩側每 250 Hz 下降 slope_db，並以 floor_db 為下限
+    fn triangle_spectrogram(peak_hz: f64, slope_db: f32, floor_db: f32) -> PowerSpectrogram {
+        let freqs: Vec<f64> = (20..=600).map(|b| b as f64 * 250.0).collect();
+        let power_db = freqs
+            .iter()
+            .map(|&f| (-((f - peak_hz).abs() / 250.0) as f32 * slope_db).max(floor_db))
+            .collect();
+        PowerSpectrogram {
+            power_db,
+            num_bins: freqs.len(),
+            times: vec![0.0],
+            freqs,
+            freq_resolution: 250.0,
+            hop_size: 1,
+        }
+    }
+
+    #[test]
+    fn detects_fm_sweep() {
+        let (audio, start_s) = fm_sweep(60_000.0, 30_000.0, 8.0, 3e-2);
+        let calls = CallDetector::new().detect(&audio, SAMPLE_RATE);
+        assert_eq!(calls.len(), 1);
+        let call = &calls[0];
+
+        assert!((call.start_time - start_s).abs() < 0.5e-3, "start {}", call.start_time);
+        assert!((60.0..70.0).contains(&call.high_freq_khz), "high {}", call.high_freq_khz);
+        assert!((27.0..31.0).contains(&call.low_freq_khz), "low {}", call.low_freq_khz);
+        assert!((30.0..60.0).contains(&call.peak_freq_khz), "peak {}", call.peak_freq_khz);
+        assert!((8.0..9.5).contains(&call.duration_ms), "duration {}", call.duration_ms);
+        assert_eq!(call.call_type, CallType::Fm);
+        assert!(!call.high_freq_warning);
+        assert!(!call.low_freq_warning);
+        assert!(call.high_freq_threshold_db > MIN_TEST_THRESHOLD_DB as f64);
+        assert!(call.low_freq_threshold_db > MIN_TEST_THRESHOLD_DB as f64);
+    }
+
+    #[test]
+    fn detects_cf_tone() {
+        let audio = cf_tone(40_000.0, 10.0, 0.0);
+        let calls = CallDetector::new().detect(&audio, SAMPLE_RATE);
+        assert_eq!(calls.len(), 1);
+        let call = &calls[0];
+
+        assert!((call.peak_freq_khz - 40.0).abs() < 0.1, "peak {}", call.peak_freq_khz);
+        assert!(call.low_freq_khz < 40.0 && call.high_freq_khz > 40.0);
+        assert!((10.0..12.0).contains(&call.duration_ms), "duration {}", call.duration_ms);
+    }
+
+    #[test]
+    fn silence_has_no_calls() {
+        let audio = vec![0.0f32; 38_400];
+        assert!(CallDetector::new().detect(&audio, SAMPLE_RATE).is_empty());
+        assert!(CallDetector::new().detect(&[], SAMPLE_RATE).is_empty());
+    }
+
+    #[test]
+    fn threshold_search_stops_at_noise_floor() {
+        // 兩側每 250 Hz 下降 3 dB，-60 dB 為平坦噪聲
+        let spec = triangle_spectrogram(60_000.0, 3.0, -60.0);
+        let view = CallView { spec: &spec, first_frame: 0, num_frames: 1 };
+        let noise = ZonalNoiseMap { floors: vec![-55.0; 16] };
+
+        let high = find_high_frequency(&view, 0.0, 0, &noise);
+        assert!(!high.warning);
+        assert!((-60..=-22).contains(&high.threshold), "threshold {}", high.threshold);
+        let high_hz = high.freq_hz.unwrap();
+        assert!((64_000.0..65_500.0).contains(&high_hz), "high {}", high_hz);
+
+        let low = find_low_frequency(&view, 0.0, 0, &noise).unwrap();
+        assert!(!low.warning);
+        assert!(low.threshold > MIN_TEST_THRESHOLD_DB);
+        assert!((54_500.0..60_000.0).contains(&low.freq_hz), "low {}", low.freq_hz);
+    }
+
+    #[test]
+    fn threshold_search_without_noise_floor_warns() {
+        // 每 1 dB 邊緣只移動 250 Hz，直到 -100 dB 都不會停止
+        let spec = triangle_spectrogram(60_000.0, 1.0, -200.0);
+        let view = CallView { spec: &spec, first_frame: 0, num_frames: 1 };
+        let noise = ZonalNoiseMap { floors: vec![-200.0; 16] };
+
+        // 改用 -30 dB 安全閾值：邊緣在峰值 ±7.5 kHz 附近
+        let high = find_high_frequency(&view, 0.0, 0, &noise);
+        assert!(high.warning);
+        assert_eq!(high.threshold, -30);
+        let high_hz = high.freq_hz.unwrap();
+        assert!((67_000.0..=67_500.0).contains(&high_hz), "high {}", high_hz);
+
+        let low = find_low_frequency(&view, 0.0, 0, &noise).unwrap();
+        assert!(low.warning);
+        assert_eq!(low.threshold, -30);
+        assert!((52_500.0..=53_000.0).contains(&low.freq_hz), "low {}", low.freq_hz);
+    }
+
+    #[test]
+    fn low_frequency_at_band_edge_discards_call() {
+        // 信號延伸到 10 kHz 以下時廢棄
+        let spec = triangle_spectrogram(25_000.0, 0.5, -200.0);
+        let view = CallView { spec: &spec, first_frame: 0, num_frames: 1 };
+        let noise = ZonalNoiseMap { floors: vec![-200.0; 16] };
+        assert!(find_low_frequency(&view, 0.0, 0, &noise).is_none());
+    }
+
+    #[test]
+    fn measure_call_matches_detection() {
+        let (audio, _) = fm_sweep(60_000.0, 30_000.0, 8.0, 3e-2);
+        let config = DetectionConfig::default();
+        let detected = CallDetector::new().detect(&audio, SAMPLE_RATE).remove(0);
+        let measured = measure_call(&audio, SAMPLE_RATE, config.flow_khz, config.fhigh_khz, &config).unwrap();
+
+        assert_eq!(measured.high_freq_khz, detected.high_freq_khz);
+        assert_eq!(measured.low_freq_khz, detected.low_freq_khz);
+        assert_eq!(measured.peak_freq_khz, detected.peak_freq_khz);
+        assert_eq!(measured.duration_ms, detected.duration_ms);
+        assert_eq!(measured.high_freq_threshold_db, detected.high_freq_threshold_db);
+        assert_eq!(measured.low_freq_threshold_db, detected.low_freq_threshold_db);
+        assert!(!measured.high_freq_warning && !measured.low_freq_warning);
+    }
+
+    #[test]
+    fn measure_call_falls_back_to_whole_selection() {
+        // 最短時長大於叫聲，偵測不到時直接測量整個選取範圍
+        let (audio, _) = fm_sweep(60_000.0, 30_000.0, 8.0, 3e-2);
+        let config = DetectionConfig {
+            min_call_duration_ms: 50.0,
+            ..DetectionConfig::default()
+        };
+        assert!(CallDetector::with_config(config.clone()).detect(&audio, SAMPLE_RATE).is_empty());
+
+        let call = measure_call(&audio, SAMPLE_RATE, 10.0, 150.0, &config).unwrap();
+        assert!((55.0..75.0).contains(&call.high_freq_khz), "high {}", call.high_freq_khz);
+        assert!((25.0..35.0).contains(&call.low_freq_khz), "low {}", call.low_freq_khz);
+        assert!((30.0..60.0).contains(&call.peak_freq_khz), "peak {}", call.peak_freq_khz);
+        assert!(call.high_freq_threshold_db <= -22.0);
+    }
+
+    #[test]
+    fn measure_call_rejects_short_selection() {
+        let config = DetectionConfig::default();
+        let audio = vec![0.1f32; config.fft_size - 1];
+        assert!(measure_call(&audio, SAMPLE_RATE, 10.0, 150.0, &config).is_none());
+    }
+
+    #[test]
+    fn call_snr_of_detected_call() {
+        let (audio, _) = fm_sweep(60_000.0, 30_000.0, 8.0, 3e-2);
+        let config = DetectionConfig::default();
+        let call = CallDetector::new().detect(&audio, SAMPLE_RATE).remove(0);
+
+        let snr = call_snr(&audio, SAMPLE_RATE, &call, &config, None).unwrap();
+        assert!(snr.signal_count > 0 && snr.noise_count > 0);
+        assert!((20.0..45.0).contains(&snr.snr_db), "snr {}", snr.snr_db);
+        assert!((snr.snr_db - (snr.signal_power_db - snr.noise_power_db)).abs() < 1e-9);
+        assert_eq!(snr.quality, CallQuality::from_snr(snr.snr_db));
+    }
+
+    #[test]
+    fn call_snr_with_separate_noise() {
+        let (audio, _) = fm_sweep(60_000.0, 30_000.0, 8.0, 3e-2);
+        let config = DetectionConfig::default();
+        let call = CallDetector::new().detect(&audio, SAMPLE_RATE).remove(0);
+
+        // 噪聲越強，SNR 越低
+        let quiet = call_snr(&audio, SAMPLE_RATE, &call, &config, Some(&noise(3840, 3e-2, 11))).unwrap();
+        let loud = call_snr(&audio, SAMPLE_RATE, &call, &config, Some(&noise(3840, 3e-1, 11))).unwrap();
+        assert!(quiet.noise_count > 0 && loud.noise_count > 0);
+        assert!((loud.noise_power_db - quiet.noise_power_db - 20.0).abs() < 1.0);
+        assert!(loud.snr_db < quiet.snr_db - 15.0);
+
+        // 噪聲短於一幀時退回叫聲框以外的頻率箱
+        let short = call_snr(&audio, SAMPLE_RATE, &call, &config, Some(&[0.1; 16])).unwrap();
+        let inline = call_snr(&audio, SAMPLE_RATE, &call, &config, None).unwrap();
+        assert_eq!(short, inline);
+
+        assert!(call_snr(&audio[..100], SAMPLE_RATE, &call, &config, None).is_none());
+    }
+
+    /// 在噪聲中依次放入 60 → 30 kHz、5 ms 的 FM 叫聲
+    fn call_train(starts_ms: &[f64], total_ms: f64) -> Vec<f32> {
+        let mut audio = noise((total_ms / 1000.0 * SAMPLE_RATE as f64) as usize, 1e-3, 3);
+        let (call, lead_s) = fm_sweep(60_000.0, 30_000.0, 5.0, 0.0);
+        let lead = (lead_s * SAMPLE_RATE as f64) as usize;
+        let call = &call[lead..call.len() - lead];
+        for &start_ms in starts_ms {
+            let start = (start_ms / 1000.0 * SAMPLE_RATE as f64) as usize;
+            for (sample, value) in audio[start..].iter_mut().zip(call) {
+                *sample += value;
+            }
+        }
+        audio
+    }
+
+    #[test]
+    fn full_file_keeps_calls_across_region_boundaries() {
+        // 首尾叫聲緊貼文件兩端；中間兩對相距 40 ms，橋接後落在同一掃描範圍的兩側
+        let starts_ms = [1.0, 60.0, 100.0, 200.0, 240.0, 394.0];
+        let audio = call_train(&starts_ms, 400.0);
+
+        for max_gap_bridge_ms in [0.0, 50.0] {
+            let config = DetectionConfig {
+                max_gap_bridge_ms,
+                ..DetectionConfig::default()
+            };
+            let mut detector = CallDetector::with_config(config);
+            let count = detector.process_full_file(&audio, SAMPLE_RATE, -60.0, 5.0);
+            assert_eq!(count, starts_ms.len(), "bridge {}", max_gap_bridge_ms);
+
+            // 每個叫聲恰好出現一次，按時間排序
+            let calls = detector.calls();
+            for (call, &start_ms) in calls.iter().zip(&starts_ms) {
+                assert!((call.start_time * 1000.0 - start_ms).abs() < 1.0, "bridge {}: {} vs {}", max_gap_bridge_ms, call.start_time, start_ms);
+                assert!((4.0..6.0).contains(&call.duration_ms), "duration {}", call.duration_ms);
+                assert!((55.0..75.0).contains(&call.high_freq_khz), "high {}", call.high_freq_khz);
+                assert!(call.low_freq_khz < 33.0, "low {}", call.low_freq_khz);
+            }
+            assert!(calls.windows(2).all(|w| w[1].start_time > w[0].end_time));
+            assert_eq!(detector.get_call(count - 1), calls.last().copied());
+        }
+    }
+
+    #[test]
+    fn full_file_matches_selection_detection() {
+        // 全文件偵測的時間偏移回文件開頭，與單獨偵測該範圍的結果一致
+        let audio = call_train(&[150.0], 300.0);
+        let calls = CallDetector::new().detect_full_file(&audio, SAMPLE_RATE, -60.0, 5.0);
+        assert_eq!(calls.len(), 1);
+        let start = (140.0 / 1000.0 * SAMPLE_RATE as f64) as usize;
+        let end = (165.0 / 1000.0 * SAMPLE_RATE as f64) as usize;
+        let selected = CallDetector::new().detect(&audio[start..end], SAMPLE_RATE);
+        assert_eq!(selected.len(), 1);
+        assert!((calls[0].start_time - 0.140 - selected[0].start_time).abs() < 0.5e-3);
+        assert!((calls[0].peak_freq_khz - selected[0].peak_freq_khz).abs() < 1.0);
+
+        assert!(CallDetector::new().detect_full_file(&noise(38_400, 1e-3, 5), SAMPLE_RATE, -60.0, 5.0).is_empty());
+    }
+
+    #[test]
+    fn contour_shape_and_harmonic_wrappers() {
+        let (audio, _) = fm_sweep(60_000.0, 30_000.0, 8.0, 3e-3);
+        let config = DetectionConfig::default();
+        let call = CallDetector::new().detect(&audio, SAMPLE_RATE).remove(0);
+
+        // 輪廓在叫聲框內由高頻掃向低頻
+        let contour = call_contour(&audio, SAMPLE_RATE, &call, &config, &RidgeConfig::default());
+        assert!(contour.len() > 10);
+        assert!(contour.iter().all(|p| p.time_s >= call.start_time - 1e-3 && p.time_s <= call.end_time + 1e-3));
+        assert!(contour.iter().all(|p| p.freq_hz >= call.low_freq_khz * 1000.0 - 2000.0 && p.freq_hz <= call.high_freq_khz * 1000.0 + 2000.0));
+        assert!(contour[0].freq_hz > 50_000.0 && contour[contour.len() - 1].freq_hz < 40_000.0);
+
+        // 分類結果與直接分類輪廓相同；線性掃頻的高頻段倍頻程斜率較小，結尾為 FM
+        let shape = call_shape(&audio, SAMPLE_RATE, &call, &config, &ShapeConfig::default());
+        let times: Vec<f64> = contour.iter().map(|p| p.time_s).collect();
+        let freqs: Vec<f64> = contour.iter().map(|p| p.freq_hz).collect();
+        assert_eq!(shape, classify_shape(&times, &freqs, &ShapeConfig::default()));
+        assert!(shape.get_call_type().ends_with("FM"), "{}", shape.get_call_type());
+
+        // 單一 FM 叫聲：輪廓即基頻，沒有其他諧波
+        let analysis = call_harmonics(&audio, SAMPLE_RATE, &call, &config, &HarmonicConfig::default()).unwrap();
+        assert_eq!(analysis.contour_harmonic, 0);
+        assert_eq!(analysis.dominant_harmonic, 0);
+        assert_eq!(analysis.num_present, 1);
+        assert!((30_000.0..60_000.0).contains(&analysis.fundamental_hz), "{}", analysis.fundamental_hz);
+
+        // 同一段樣本視為時間擴展 10 倍的錄音：輪廓換算回實時
+        let expanded = DetectionConfig {
+            time_expansion: 10.0,
+            ..config.clone()
+        };
+        let slow_call = CallDetector::with_config(expanded.clone()).detect(&audio, SAMPLE_RATE / 10.0).remove(0);
+        let slow_contour = call_contour(&audio, SAMPLE_RATE / 10.0, &slow_call, &expanded, &RidgeConfig::default());
+        assert!(!slow_contour.is_empty());
+        assert!(slow_contour[0].freq_hz > 50_000.0, "{}", slow_contour[0].freq_hz);
+        assert!(slow_contour.iter().all(|p| p.time_s <= slow_call.end_time + 1e-3));
+    }
+
+    #[test]
+    fn quality_rating_boundaries() {
+        assert_eq!(CallQuality::from_snr(9.99), CallQuality::VeryPoor);
+        assert_eq!(CallQuality::from_snr(10.0), CallQuality::Poor);
+        assert_eq!(CallQuality::from_snr(15.0), CallQuality::Normal);
+        assert_eq!(CallQuality::from_snr(20.0), CallQuality::Good);
+        assert_eq!(CallQuality::from_snr(30.0), CallQuality::Excellent);
+        assert_eq!(CallQuality::from_snr(f64::INFINITY), CallQuality::Excellent);
+    }
+}
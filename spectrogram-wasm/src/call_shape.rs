@@ -0,0 +1,426 @@
+// ============================================================
+// 叫聲類型分類
+// 依頻率輪廓的局部斜率把叫聲分成 CF / QCF / FM 段，
+// 得到複合類型 (FM-CF-FM、CF-FM、FM-QCF、QCF ...) 與各段邊界、時長，
+// 類型名稱與 autoid_HK.js 規則中的 callType 一致
+// ============================================================
+
+use wasm_bindgen::prelude::*;
+
+/// 分類配置
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShapeConfig {
+    /// |斜率| 低於此值 (kHz/ms) 為 CF
+    pub cf_max_slope: f64,
+    /// |斜率| 低於此值 (kHz/ms) 為 QCF，否則為 FM
+    pub qcf_max_slope: f64,
+    /// 估計局部斜率的時間窗 (毫秒)
+    pub slope_window_ms: f64,
+    /// 短於此時長 (毫秒) 的段併入相鄰段
+    pub min_segment_ms: f64,
+}
+
+impl Default for ShapeConfig {
+    fn default() -> Self {
+        ShapeConfig {
+            cf_max_slope: 0.1,
+            qcf_max_slope: 1.0,
+            slope_window_ms: 0.5,
+            min_segment_ms: 0.5,
+        }
+    }
+}
+
+/// 輪廓段的類型
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SegmentKind {
+    /// 恆頻
+    Cf,
+    /// 準恆頻
+    Qcf,
+    /// 調頻
+    Fm,
+}
+
+impl SegmentKind {
+    /// 依 |斜率| (kHz/ms) 分類
+    fn from_slope(slope: f64, config: &ShapeConfig) -> SegmentKind {
+        let slope = slope.abs();
+        if slope < config.cf_max_slope {
+            SegmentKind::Cf
+        } else if slope < config.qcf_max_slope {
+            SegmentKind::Qcf
+        } else {
+            SegmentKind::Fm
+        }
+    }
+
+    /// 類型名稱 ("CF", "QCF", "FM")
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SegmentKind::Cf => "CF",
+            SegmentKind::Qcf => "QCF",
+            SegmentKind::Fm => "FM",
+        }
+    }
+}
+
+/// 輪廓的一段
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ContourSegment {
+    /// 類型
+    pub kind: SegmentKind,
+    /// 開始時間 (秒)
+    pub start_time: f64,
+    /// 結束時間 (秒)
+    pub end_time: f64,
+    /// 時長 (毫秒)
+    pub duration_ms: f64,
+    /// 開始頻率 (kHz)
+    pub start_freq_khz: f64,
+    /// 結束頻率 (kHz)
+    pub end_freq_khz: f64,
+    /// 平均斜率 (kHz/ms，負值表示下降)
+    pub slope_khz_per_ms: f64,
+}
+
+#[wasm_bindgen]
+impl ContourSegment {
+    /// 類型名稱 ("CF", "QCF", "FM")
+    #[wasm_bindgen]
+    pub fn get_kind_name(&self) -> String {
+        self.kind.as_str().to_string()
+    }
+}
+
+/// 叫聲形狀分類結果
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CallShape {
+    segments: Vec<ContourSegment>,
+}
+
+#[wasm_bindgen]
+impl CallShape {
+    /// 複合類型名稱，例如 "FM-CF-FM"；輪廓為空時為空字串
+    #[wasm_bindgen]
+    pub fn get_call_type(&self) -> String {
+        self.call_type()
+    }
+
+    /// 段數
+    #[wasm_bindgen]
+    pub fn get_num_segments(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// 獲取一段
+    ///
+    /// # Arguments
+    /// * `segment_idx` - 段索引（按時間排序）
+    ///
+    /// # Returns
+    /// 索引無效時為 None
+    #[wasm_bindgen]
+    pub fn get_segment(&self, segment_idx: usize) -> Option<ContourSegment> {
+        self.segments.get(segment_idx).copied()
+    }
+}
+
+impl CallShape {
+    /// 按時間排序的段
+    pub fn segments(&self) -> &[ContourSegment] {
+        &self.segments
+    }
+
+    /// 複合類型名稱，例如 "FM-CF-FM"
+    pub fn call_type(&self) -> String {
+        self.segments.iter().map(|s| s.kind.as_str()).collect::<Vec<_>>().join("-")
+    }
+}
+
+/// 在 [lo, hi] 的點上做最小二乘直線擬合
+///
+/// # Returns
+/// 斜率 (kHz/ms)
+fn regression_slope(times_s: &[f64], freqs_hz: &[f64], lo: usize, hi: usize) -> f64 {
+    let n = (hi - lo + 1) as f64;
+    if n < 2.0 {
+        return 0.0;
+    }
+    let mean_t = times_s[lo..=hi].iter().sum::<f64>() / n;
+    let mean_f = freqs_hz[lo..=hi].iter().sum::<f64>() / n;
+    let (mut cov, mut var) = (0.0, 0.0);
+    for i in lo..=hi {
+        let dt = times_s[i] - mean_t;
+        cov += dt * (freqs_hz[i] - mean_f);
+        var += dt * dt;
+    }
+    if var <= 0.0 {
+        return 0.0;
+    }
+    // Hz/s -> kHz/ms
+    cov / var / 1e6
+}
+
+/// 合併類型相同的相鄰段
+fn merge_same_kind(runs: &mut Vec<(usize, usize, SegmentKind)>) {
+    let mut k = 0;
+    while k + 1 < runs.len() {
+        if runs[k].2 == runs[k + 1].2 {
+            runs[k].1 = runs[k + 1].1;
+            runs.remove(k + 1);
+        } else {
+            k += 1;
+        }
+    }
+}
+
+/// 依頻率輪廓分類叫聲類型
+///
+/// 每點以 slope_window_ms 內的直線擬合估計局部斜率並分類，相同類型的連續點成為一段；
+/// 短於 min_segment_ms 的段反覆併入平均局部斜率較接近的相鄰段，併入後以整段擬合重新分類；
+/// 最後合併整體擬合後仍屬較長段類型的相鄰 CF / QCF 段。
+///
+/// # Arguments
+/// * `times_s` - 輪廓各點的時間 (秒)，遞增
+/// * `freqs_hz` - 輪廓各點的頻率 (Hz)
+/// * `config` - 分類配置
+///
+/// # Returns
+/// 分類結果；少於兩點時沒有任何段
+pub fn classify_shape(times_s: &[f64], freqs_hz: &[f64], config: &ShapeConfig) -> CallShape {
+    let n = times_s.len().min(freqs_hz.len());
+    if n < 2 {
+        return CallShape::default();
+    }
+
+    // 1. 局部斜率
+    let half_window_s = config.slope_window_ms.max(0.0) / 2000.0;
+    let slopes: Vec<f64> = (0..n)
+        .map(|i| {
+            let mut lo = i;
+            while lo > 0 && times_s[i] - times_s[lo - 1] <= half_window_s {
+                lo -= 1;
+            }
+            let mut hi = i;
+            while hi + 1 < n && times_s[hi + 1] - times_s[i] <= half_window_s {
+                hi += 1;
+            }
+            // 至少包含左右各一點
+            regression_slope(times_s, freqs_hz, lo.min(i.saturating_sub(1)), hi.max((i + 1).min(n - 1)))
+        })
+        .collect();
+
+    // 2. 相同類型的連續點成段：(起點, 終點, 類型)
+    let mut runs: Vec<(usize, usize, SegmentKind)> = Vec::new();
+    for (i, &slope) in slopes.iter().enumerate() {
+        let kind = SegmentKind::from_slope(slope, config);
+        match runs.last_mut() {
+            Some(run) if run.2 == kind => run.1 = i,
+            _ => runs.push((i, i, kind)),
+        }
+    }
+
+    // 段邊界取相鄰兩點的中點
+    let boundary = |i: usize| -> f64 {
+        if i == 0 {
+            times_s[0]
+        } else {
+            (times_s[i - 1] + times_s[i]) / 2.0
+        }
+    };
+    let run_duration_ms = |run: &(usize, usize, SegmentKind)| -> f64 {
+        let end = if run.1 + 1 < n { boundary(run.1 + 1) } else { times_s[n - 1] };
+        (end - boundary(run.0)) * 1000.0
+    };
+
+    // 3. 反覆把最短的過短段併入平均局部斜率較接近的相鄰段
+    while runs.len() > 1 {
+        let (shortest, duration) = runs
+            .iter()
+            .enumerate()
+            .map(|(k, run)| (k, run_duration_ms(run)))
+            .fold((0, f64::INFINITY), |best, item| if item.1 < best.1 { item } else { best });
+        if duration >= config.min_segment_ms {
+            break;
+        }
+        let mean_slope = |run: (usize, usize, SegmentKind)| {
+            slopes[run.0..=run.1].iter().map(|s| s.abs()).sum::<f64>() / (run.1 - run.0 + 1) as f64
+        };
+        let own_slope = mean_slope(runs[shortest]);
+        let neighbour_diff = |k: usize| (mean_slope(runs[k]) - own_slope).abs();
+        let target = if shortest == 0 {
+            1
+        } else if shortest + 1 == runs.len() || neighbour_diff(shortest - 1) <= neighbour_diff(shortest + 1) {
+            shortest - 1
+        } else {
+            shortest + 1
+        };
+
+        let (lo, hi) = (shortest.min(target), shortest.max(target));
+        let (start, end) = (runs[lo].0, runs[hi].1);
+        let kind = SegmentKind::from_slope(regression_slope(times_s, freqs_hz, start, end), config);
+        runs[lo] = (start, end, kind);
+        runs.remove(hi);
+
+        // 重新分類後可能與相鄰段類型相同
+        merge_same_kind(&mut runs);
+    }
+
+    // 4. 相鄰的 CF 與 QCF 段整體擬合後仍屬較長段的類型時合併（斜率噪聲造成的交替）
+    let mut k = 0;
+    while k + 1 < runs.len() {
+        let (a, b) = (runs[k], runs[k + 1]);
+        if a.2 != SegmentKind::Fm && b.2 != SegmentKind::Fm {
+            let longer = if run_duration_ms(&a) >= run_duration_ms(&b) { a.2 } else { b.2 };
+            if SegmentKind::from_slope(regression_slope(times_s, freqs_hz, a.0, b.1), config) == longer {
+                runs[k] = (a.0, b.1, longer);
+                runs.remove(k + 1);
+                merge_same_kind(&mut runs);
+                k = k.saturating_sub(1);
+                continue;
+            }
+        }
+        k += 1;
+    }
+
+    let segments = runs
+        .iter()
+        .map(|run| {
+            let start_time = boundary(run.0);
+            let end_time = if run.1 + 1 < n { boundary(run.1 + 1) } else { times_s[n - 1] };
+            ContourSegment {
+                kind: run.2,
+                start_time,
+                end_time,
+                duration_ms: (end_time - start_time) * 1000.0,
+                start_freq_khz: freqs_hz[run.0] / 1000.0,
+                end_freq_khz: freqs_hz[run.1] / 1000.0,
+                slope_khz_per_ms: regression_slope(times_s, freqs_hz, run.0, run.1),
+            }
+        })
+        .collect();
+    CallShape { segments }
+}
+
+/// 依頻率輪廓分類叫聲類型（預設配置：CF < 0.1 kHz/ms ≤ QCF < 1 kHz/ms ≤ FM）
+///
+/// # Arguments
+/// * `times_s` - 輪廓各點的時間 (秒，Float64Array)
+/// * `freqs_hz` - 輪廓各點的頻率 (Hz，Float64Array)
+///
+/// # Returns
+/// 分類結果
+#[wasm_bindgen]
+pub fn classify_call_contour(times_s: &[f64], freqs_hz: &[f64]) -> CallShape {
+    classify_shape(times_s, freqs_hz, &ShapeConfig::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 每 0.05 ms 一點的分段線性輪廓：[(時長 ms, 起始 kHz, 結束 kHz)]
+    fn contour(pieces: &[(f64, f64, f64)]) -> (Vec<f64>, Vec<f64>) {
+        let step_ms = 0.05;
+        let (mut times, mut freqs) = (Vec::new(), Vec::new());
+        let mut offset_ms = 0.0;
+        for &(duration_ms, start_khz, end_khz) in pieces {
+            let steps = (duration_ms / step_ms).round() as usize;
+            // 第一段包含起點，之後各段從下一點開始
+            let first = if times.is_empty() { 0 } else { 1 };
+            for i in first..=steps {
+                let ratio = i as f64 / steps as f64;
+                times.push((offset_ms + ratio * duration_ms) / 1000.0);
+                freqs.push((start_khz + (end_khz - start_khz) * ratio) * 1000.0);
+            }
+            offset_ms += duration_ms;
+        }
+        (times, freqs)
+    }
+
+    fn classify(pieces: &[(f64, f64, f64)]) -> CallShape {
+        let (times, freqs) = contour(pieces);
+        classify_shape(&times, &freqs, &ShapeConfig::default())
+    }
+
+    #[test]
+    fn single_segment_calls() {
+        let cf = classify(&[(10.0, 83.0, 83.0)]);
+        assert_eq!(cf.call_type(), "CF");
+        let segment = cf.segments()[0];
+        assert_eq!((segment.start_time, segment.end_time), (0.0, 0.010));
+        assert!((segment.duration_ms - 10.0).abs() < 1e-9);
+        assert_eq!((segment.start_freq_khz, segment.end_freq_khz), (83.0, 83.0));
+        assert!(segment.slope_khz_per_ms.abs() < 1e-9);
+
+        // 0.375 kHz/ms
+        let qcf = classify(&[(8.0, 45.0, 42.0)]);
+        assert_eq!(qcf.call_type(), "QCF");
+        assert!((qcf.segments()[0].slope_khz_per_ms + 0.375).abs() < 1e-6);
+
+        // 16.7 kHz/ms
+        let fm = classify(&[(3.0, 80.0, 30.0)]);
+        assert_eq!(fm.call_type(), "FM");
+        assert_eq!((fm.segments()[0].start_freq_khz, fm.segments()[0].end_freq_khz), (80.0, 30.0));
+    }
+
+    #[test]
+    fn hockey_stick_fm_cf_fm() {
+        // 菊頭蝠式叫聲：上升 FM 1 ms、83 kHz CF 20 ms、下降 FM 2 ms
+        let shape = classify(&[(1.0, 70.0, 83.0), (20.0, 83.0, 83.0), (2.0, 83.0, 65.0)]);
+        assert_eq!(shape.call_type(), "FM-CF-FM");
+        assert_eq!(shape.get_num_segments(), 3);
+
+        // 段邊界落在轉折點附近（局部斜率窗 0.5 ms 的一半以內），且首尾相接
+        let segments = shape.segments();
+        let boundaries_ms = [0.0, 1.0, 21.0, 23.0];
+        for (k, segment) in segments.iter().enumerate() {
+            assert!((segment.start_time * 1000.0 - boundaries_ms[k]).abs() <= 0.25, "{:?}", segment);
+            assert!((segment.end_time * 1000.0 - boundaries_ms[k + 1]).abs() <= 0.25, "{:?}", segment);
+        }
+        assert!(segments.windows(2).all(|w| w[0].end_time == w[1].start_time));
+        assert!(segments[0].slope_khz_per_ms > 1.0 && segments[2].slope_khz_per_ms < -1.0);
+        assert!((segments[1].start_freq_khz - 83.0).abs() < 0.5 && (segments[1].end_freq_khz - 83.0).abs() < 0.5);
+        assert_eq!(segments[2].end_freq_khz, 65.0);
+        assert_eq!(shape.get_segment(1).map(|s| s.get_kind_name()), Some("CF".to_string()));
+        assert!(shape.get_segment(3).is_none());
+    }
+
+    #[test]
+    fn fm_then_qcf() {
+        // 伏翼式叫聲：FM 2 ms 後接 QCF 尾部
+        let shape = classify(&[(2.0, 80.0, 48.0), (4.0, 48.0, 45.0)]);
+        assert_eq!(shape.call_type(), "FM-QCF");
+        let boundary_ms = shape.segments()[1].start_time * 1000.0;
+        assert!((boundary_ms - 2.0).abs() <= 0.25, "{}", boundary_ms);
+    }
+
+    #[test]
+    fn short_glitch_is_absorbed() {
+        // CF 中 0.1 ms 內跳升 2 kHz，FM 段短於 min_segment_ms，併入 CF
+        let glitch = [(5.0, 40.0, 40.0), (0.1, 40.0, 42.0), (5.0, 42.0, 42.0)];
+        let shape = classify(&glitch);
+        assert_eq!(shape.call_type(), "CF");
+        assert!((shape.segments()[0].duration_ms - 10.1).abs() < 1e-9);
+
+        // min_segment_ms 為 0 時保留為獨立的 FM 段
+        let (times, freqs) = contour(&glitch);
+        let config = ShapeConfig {
+            min_segment_ms: 0.0,
+            ..ShapeConfig::default()
+        };
+        assert_eq!(classify_shape(&times, &freqs, &config).call_type(), "CF-FM-CF");
+    }
+
+    #[test]
+    fn too_short_contour() {
+        assert_eq!(classify_call_contour(&[], &[]).get_call_type(), "");
+        assert_eq!(classify_call_contour(&[0.0], &[40_000.0]).get_num_segments(), 0);
+        let (times, freqs) = contour(&[(3.0, 80.0, 30.0)]);
+        assert_eq!(classify_call_contour(&times, &freqs), classify_shape(&times, &freqs, &ShapeConfig::default()));
+    }
+}
@@ -0,0 +1,417 @@
+// ============================================================
+// 頻譜減法 / 頻譜門限降噪
+// 從純噪聲區段（或自動估計）學習噪聲輪廓，在 STFT 域中抑制噪聲後重新合成音頻
+// ============================================================
+
+use num_complex::Complex;
+use wasm_bindgen::prelude::*;
+
+use crate::stft::StftProcessor;
+
+/// 自動估計噪聲時使用的默認最安靜幀比例
+const DEFAULT_NOISE_PERCENTILE: f32 = 0.2;
+
+/// 降噪方法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DenoiseMethod {
+    /// 功率譜減法
+    Subtract,
+    /// 頻譜門限
+    Gate,
+}
+
+impl DenoiseMethod {
+    /// 依名稱選擇降噪方法 ("subtract", "gate")，其他名稱為 None
+    fn from_name(name: &str) -> Option<DenoiseMethod> {
+        match name {
+            "subtract" => Some(DenoiseMethod::Subtract),
+            "gate" => Some(DenoiseMethod::Gate),
+            _ => None,
+        }
+    }
+}
+
+/// 每個頻率箱的噪聲統計
+#[derive(Clone, Default)]
+struct NoiseProfile {
+    /// 平均功率 (|X|^2)
+    power: Vec<f32>,
+    /// dB 平均值
+    db_mean: Vec<f32>,
+    /// dB 標準差
+    db_std: Vec<f32>,
+}
+
+impl NoiseProfile {
+    fn is_empty(&self) -> bool {
+        self.power.is_empty()
+    }
+
+    /// 從頻譜幀估計噪聲統計
+    ///
+    /// `percentile` 為 1.0 時使用全部幀（整段為純噪聲）；
+    /// 小於 1.0 時只取總能量最低的那部分幀，
+    /// 用於在含有叫聲的錄音中自動估計持續性噪聲。
+    fn estimate(frames: &[Vec<Complex<f32>>], num_bins: usize, percentile: f32) -> NoiseProfile {
+        let mut profile = NoiseProfile {
+            power: vec![0.0; num_bins],
+            db_mean: vec![-120.0; num_bins],
+            db_std: vec![0.0; num_bins],
+        };
+
+        if frames.is_empty() {
+            return profile;
+        }
+
+        // 按幀能量排序，選出最安靜的幀
+        let percentile = percentile.clamp(0.01, 1.0);
+        let keep = ((frames.len() as f32 * percentile).ceil() as usize).clamp(1, frames.len());
+        let mut order: Vec<(f32, usize)> = frames
+            .iter()
+            .enumerate()
+            .map(|(idx, frame)| (frame.iter().map(|c| c.norm_sqr()).sum::<f32>(), idx))
+            .collect();
+        order.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let selected: Vec<&Vec<Complex<f32>>> = order[..keep].iter().map(|&(_, idx)| &frames[idx]).collect();
+
+        for bin in 0..num_bins {
+            let mut power_sum = 0.0f32;
+            let mut db_sum = 0.0f32;
+            let mut db_sq_sum = 0.0f32;
+            for frame in &selected {
+                let power = frame[bin].norm_sqr();
+                let db = 10.0 * power.max(1e-20).log10();
+                power_sum += power;
+                db_sum += db;
+                db_sq_sum += db * db;
+            }
+            let db_mean = db_sum / keep as f32;
+            let db_var = (db_sq_sum / keep as f32 - db_mean * db_mean).max(0.0);
+
+            profile.power[bin] = power_sum / keep as f32;
+            profile.db_mean[bin] = db_mean;
+            profile.db_std[bin] = db_var.sqrt();
+        }
+
+        profile
+    }
+}
+
+/// NoiseReducer: 頻譜減法 / 頻譜門限降噪器
+///
+/// 使用方式：
+/// 1. `learn_noise_profile` 從使用者選擇的純噪聲區段學習噪聲輪廓
+///    （或 `learn_noise_profile_auto` 從整段錄音自動估計）
+/// 2. `reduce_noise` 對音頻進行降噪並返回重新合成的樣本
+#[wasm_bindgen]
+pub struct NoiseReducer {
+    stft: StftProcessor,
+    profile: NoiseProfile,
+}
+
+#[wasm_bindgen]
+impl NoiseReducer {
+    /// 創建新的 NoiseReducer 實例
+    ///
+    /// # Arguments
+    /// * `fft_size` - FFT 大小（典型值: 1024）
+    /// * `hop_size` - 幀間步長（0 表示使用 fft_size / 4）
+    /// * `window_func` - 窗函數名稱（建議 hann）
+    #[wasm_bindgen(constructor)]
+    pub fn new(fft_size: usize, hop_size: usize, window_func: String) -> NoiseReducer {
+        NoiseReducer {
+            stft: StftProcessor::new(fft_size, hop_size, &window_func),
+            profile: NoiseProfile::default(),
+        }
+    }
+
+    /// 從純噪聲區段學習噪聲輪廓
+    ///
+    /// # Arguments
+    /// * `audio_data` - 音頻數據 (Float32Array)
+    /// * `start_sample` - 噪聲區段起始樣本
+    /// * `end_sample` - 噪聲區段結束樣本（不包含）
+    ///
+    /// # Returns
+    /// 是否成功學習（區段為空時返回 false）
+    #[wasm_bindgen]
+    pub fn learn_noise_profile(&mut self, audio_data: &[f32], start_sample: usize, end_sample: usize) -> bool {
+        let end_sample = end_sample.min(audio_data.len());
+        if start_sample >= end_sample {
+            return false;
+        }
+
+        let frames = self.stft.analyze(&audio_data[start_sample..end_sample]);
+        if frames.is_empty() {
+            return false;
+        }
+
+        self.profile = NoiseProfile::estimate(&frames, self.stft.num_bins(), 1.0);
+        true
+    }
+
+    /// 自動估計噪聲輪廓（不需要純噪聲區段）
+    ///
+    /// 只使用總能量最低的一部分幀，適合持續性的蟬鳴或溪流噪聲。
+    ///
+    /// # Arguments
+    /// * `audio_data` - 音頻數據 (Float32Array)
+    /// * `percentile` - 用於估計的最安靜幀比例 (0.01-1.0, 典型值: 0.2)
+    #[wasm_bindgen]
+    pub fn learn_noise_profile_auto(&mut self, audio_data: &[f32], percentile: f32) -> bool {
+        let frames = self.stft.analyze(audio_data);
+        if frames.is_empty() {
+            return false;
+        }
+
+        self.profile = NoiseProfile::estimate(&frames, self.stft.num_bins(), percentile);
+        true
+    }
+
+    /// 是否已有噪聲輪廓
+    #[wasm_bindgen]
+    pub fn has_noise_profile(&self) -> bool {
+        !self.profile.is_empty()
+    }
+
+    /// 獲取噪聲輪廓（每個頻率箱的平均 dB 值，長度 fft_size / 2 + 1）
+    #[wasm_bindgen]
+    pub fn get_noise_profile(&self) -> Vec<f32> {
+        self.profile.db_mean.clone()
+    }
+
+    /// 清除噪聲輪廓
+    #[wasm_bindgen]
+    pub fn clear_noise_profile(&mut self) {
+        self.profile = NoiseProfile::default();
+    }
+
+    /// 對音頻進行降噪
+    ///
+    /// 若尚未學習噪聲輪廓，會以默認百分位數從輸入本身自動估計（不保存）。
+    ///
+    /// # Arguments
+    /// * `audio_data` - 音頻數據 (Float32Array)
+    /// * `method` - "subtract"（頻譜減法）或 "gate"（頻譜門限）
+    /// * `strength` - subtract: 過減因子 (典型值 1.0-2.0)；gate: 門限高於噪聲平均值的標準差倍數 (典型值 1.5)
+    /// * `reduction_db` - 最大衰減量 (dB)，同時作為頻譜下限防止音樂噪聲 (典型值 24)
+    /// * `freq_smoothing` - 增益遮罩在頻率方向的平滑半徑（頻率箱數）
+    /// * `time_smoothing` - 增益遮罩在時間方向的平滑半徑（幀數）
+    ///
+    /// # Returns
+    /// 降噪後的音頻（長度與輸入相同）；method 無法識別時原樣返回輸入
+    #[wasm_bindgen]
+    pub fn reduce_noise(
+        &mut self,
+        audio_data: &[f32],
+        method: &str,
+        strength: f32,
+        reduction_db: f32,
+        freq_smoothing: usize,
+        time_smoothing: usize,
+    ) -> Vec<f32> {
+        if audio_data.is_empty() {
+            return Vec::new();
+        }
+        let Some(method) = DenoiseMethod::from_name(method) else {
+            return audio_data.to_vec();
+        };
+
+        let mut frames = self.stft.analyze(audio_data);
+        let num_bins = self.stft.num_bins();
+
+        let auto_profile;
+        let profile = if self.profile.is_empty() {
+            auto_profile = NoiseProfile::estimate(&frames, num_bins, DEFAULT_NOISE_PERCENTILE);
+            &auto_profile
+        } else {
+            &self.profile
+        };
+
+        let floor_gain = 10.0f32.powf(-reduction_db.abs() / 20.0);
+
+        // 計算每個時頻點的增益遮罩
+        let mut mask: Vec<Vec<f32>> = frames
+            .iter()
+            .map(|frame| {
+                (0..num_bins)
+                    .map(|bin| {
+                        let power = frame[bin].norm_sqr();
+                        match method {
+                            DenoiseMethod::Gate => {
+                                let db = 10.0 * power.max(1e-20).log10();
+                                let threshold = profile.db_mean[bin] + strength * profile.db_std[bin];
+                                if db > threshold { 1.0 } else { floor_gain }
+                            }
+                            DenoiseMethod::Subtract => {
+                                // 功率譜減法: G = sqrt(max(1 - a * N / |X|^2, floor^2))
+                                if power <= 0.0 {
+                                    floor_gain
+                                } else {
+                                    let ratio = 1.0 - strength * profile.power[bin] / power;
+                                    ratio.max(floor_gain * floor_gain).sqrt()
+                                }
+                            }
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        // 平滑增益遮罩以減少音樂噪聲
+        if freq_smoothing > 0 || time_smoothing > 0 {
+            mask = smooth_mask(&mask, freq_smoothing, time_smoothing);
+        }
+
+        for (frame, gains) in frames.iter_mut().zip(mask.iter()) {
+            for (value, &gain) in frame.iter_mut().zip(gains.iter()) {
+                *value *= gain;
+            }
+        }
+
+        self.stft.synthesize(&frames, audio_data.len())
+    }
+
+    /// 釋放 WASM 記憶體而不銷毀實例
+    #[wasm_bindgen]
+    pub fn release_memory(&mut self) {
+        self.profile = NoiseProfile::default();
+    }
+}
+
+/// 以可分離的三角形核平滑二維增益遮罩
+#[allow(clippy::needless_range_loop)]
+fn smooth_mask(mask: &[Vec<f32>], freq_radius: usize, time_radius: usize) -> Vec<Vec<f32>> {
+    let num_frames = mask.len();
+    if num_frames == 0 {
+        return Vec::new();
+    }
+    let num_bins = mask[0].len();
+
+    // 頻率方向
+    let mut freq_smoothed = vec![vec![0.0f32; num_bins]; num_frames];
+    for t in 0..num_frames {
+        for f in 0..num_bins {
+            let lo = f.saturating_sub(freq_radius);
+            let hi = (f + freq_radius).min(num_bins - 1);
+            let mut sum = 0.0f32;
+            let mut weight_sum = 0.0f32;
+            for k in lo..=hi {
+                let w = (freq_radius + 1 - k.abs_diff(f)) as f32;
+                sum += mask[t][k] * w;
+                weight_sum += w;
+            }
+            freq_smoothed[t][f] = sum / weight_sum;
+        }
+    }
+
+    // 時間方向
+    let mut result = vec![vec![0.0f32; num_bins]; num_frames];
+    for t in 0..num_frames {
+        let lo = t.saturating_sub(time_radius);
+        let hi = (t + time_radius).min(num_frames - 1);
+        for f in 0..num_bins {
+            let mut sum = 0.0f32;
+            let mut weight_sum = 0.0f32;
+            for k in lo..=hi {
+                let w = (time_radius + 1 - k.abs_diff(t)) as f32;
+                sum += freq_smoothed[k][f] * w;
+                weight_sum += w;
+            }
+            result[t][f] = sum / weight_sum;
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 48_000.0;
+    const TONE_HZ: f32 = 6000.0;
+
+    /// 前半段只有白噪聲，後半段為 6 kHz 正弦 (振幅 0.5) 加白噪聲
+    fn tone_in_noise() -> Vec<f32> {
+        let mut state = 1u32;
+        (0..48_000)
+            .map(|i| {
+                state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                let noise = ((state >> 8) as f32 / (1u32 << 24) as f32 - 0.5) * 0.1;
+                let tone = if i >= 24_000 { 0.5 * (2.0 * std::f32::consts::PI * TONE_HZ * i as f32 / SAMPLE_RATE).sin() } else { 0.0 };
+                noise + tone
+            })
+            .collect()
+    }
+
+    fn rms(data: &[f32]) -> f32 {
+        (data.iter().map(|&x| x * x).sum::<f32>() / data.len() as f32).sqrt()
+    }
+
+    /// 與 6 kHz 正弦相關得到的振幅
+    fn tone_amplitude(data: &[f32], offset: usize) -> f32 {
+        let (mut re, mut im) = (0.0f32, 0.0f32);
+        for (i, &x) in data.iter().enumerate() {
+            let phase = 2.0 * std::f32::consts::PI * TONE_HZ * (offset + i) as f32 / SAMPLE_RATE;
+            re += x * phase.cos();
+            im += x * phase.sin();
+        }
+        2.0 * (re * re + im * im).sqrt() / data.len() as f32
+    }
+
+    #[test]
+    fn lowers_noise_floor_and_keeps_tone() {
+        let audio = tone_in_noise();
+        let (noise_only, with_tone) = (8000..16_000, 32_000..40_000);
+        // 不平滑遮罩，使正弦所在頻率箱的增益保持為 1
+
+        for (method, strength) in [("subtract", 2.0), ("gate", 1.5)] {
+            let mut reducer = NoiseReducer::new(1024, 256, "hann".to_string());
+            assert!(reducer.learn_noise_profile(&audio, 0, 24_000));
+            let output = reducer.reduce_noise(&audio, method, strength, 24.0, 0, 0);
+            assert_eq!(output.len(), audio.len());
+
+            let reduction_db = 20.0 * (rms(&output[noise_only.clone()]) / rms(&audio[noise_only.clone()])).log10();
+            assert!(reduction_db < -9.0, "{}: {}", method, reduction_db);
+
+            let amplitude = tone_amplitude(&output[with_tone.clone()], with_tone.start);
+            assert!((amplitude - 0.5).abs() < 0.005, "{}: {}", method, amplitude);
+        }
+    }
+
+    #[test]
+    fn estimates_profile_automatically() {
+        // 未學習輪廓時從輸入本身估計，不保存
+        let audio = tone_in_noise();
+        let mut reducer = NoiseReducer::new(1024, 0, "hann".to_string());
+        let output = reducer.reduce_noise(&audio, "subtract", 1.5, 24.0, 1, 1);
+        assert!(!reducer.has_noise_profile());
+        assert!(rms(&output[8000..16_000]) < rms(&audio[8000..16_000]) * 0.4);
+
+        assert!(reducer.learn_noise_profile_auto(&audio, 0.2));
+        assert!(reducer.has_noise_profile());
+        assert_eq!(reducer.get_noise_profile().len(), 513);
+        reducer.clear_noise_profile();
+        assert!(!reducer.has_noise_profile());
+    }
+
+    #[test]
+    fn unknown_method_returns_input() {
+        let audio = tone_in_noise();
+        let mut reducer = NoiseReducer::new(1024, 256, "hann".to_string());
+        assert!(reducer.learn_noise_profile(&audio, 0, 24_000));
+        assert_eq!(reducer.reduce_noise(&audio, "wiener", 1.5, 24.0, 1, 1), audio);
+        assert_eq!(reducer.reduce_noise(&audio, "", 1.5, 24.0, 1, 1), audio);
+        assert!(reducer.reduce_noise(&[], "subtract", 1.5, 24.0, 1, 1).is_empty());
+    }
+
+    #[test]
+    fn rejects_empty_noise_region() {
+        let audio = tone_in_noise();
+        let mut reducer = NoiseReducer::new(1024, 256, "hann".to_string());
+        assert!(!reducer.learn_noise_profile(&audio, 100, 100));
+        assert!(!reducer.learn_noise_profile(&audio, 60_000, 70_000));
+        assert!(!reducer.has_noise_profile());
+    }
+}
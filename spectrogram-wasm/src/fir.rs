@@ -0,0 +1,741 @@
+// ============================================================
+// FIR 濾波器設計與快速卷積
+// 線性相位 FIR（窗函數法 / Parks-McClellan 等波紋法）
+// 以及基於 FFT 的 overlap-save 長信號卷積
+// ============================================================
+
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use num_complex::Complex;
+use rustfft::{Fft, FftPlanner};
+use wasm_bindgen::prelude::*;
+
+use crate::create_window;
+use crate::iir::FilterBand;
+
+/// Remez 演算法的頻率網格密度
+const REMEZ_GRID_DENSITY: usize = 16;
+/// Remez 演算法的最大迭代次數
+const REMEZ_MAX_ITERATIONS: usize = 40;
+
+/// 零階修正 Bessel 函數 I0（Kaiser 窗使用）
+pub fn bessel_i0(x: f64) -> f64 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let half = x / 2.0;
+    for k in 1..64 {
+        term *= (half / k as f64) * (half / k as f64);
+        sum += term;
+        if term < 1e-16 * sum {
+            break;
+        }
+    }
+    sum
+}
+
+/// Kaiser 窗
+pub fn kaiser_window(size: usize, beta: f64) -> Vec<f64> {
+    if size <= 1 {
+        return vec![1.0; size];
+    }
+    let denom = bessel_i0(beta);
+    let m = (size - 1) as f64;
+    (0..size)
+        .map(|i| {
+            let r = 2.0 * i as f64 / m - 1.0;
+            bessel_i0(beta * (1.0 - r * r).max(0.0).sqrt()) / denom
+        })
+        .collect()
+}
+
+/// 根據所需阻帶衰減 (dB) 計算 Kaiser 窗的 beta
+pub fn kaiser_beta(atten_db: f64) -> f64 {
+    if atten_db > 50.0 {
+        0.1102 * (atten_db - 8.7)
+    } else if atten_db > 21.0 {
+        0.5842 * (atten_db - 21.0).powf(0.4) + 0.07886 * (atten_db - 21.0)
+    } else {
+        0.0
+    }
+}
+
+/// 根據阻帶衰減與過渡帶寬（歸一化，1.0 = Nyquist）估計 Kaiser FIR 的抽頭數
+pub fn kaiser_num_taps(atten_db: f64, transition_width: f64) -> usize {
+    let n = (atten_db - 7.95) / (2.285 * PI * transition_width.max(1e-6)) + 1.0;
+    n.ceil().max(1.0) as usize
+}
+
+/// 理想低通的截斷 sinc 響應，`cutoff` 為歸一化頻率 (1.0 = Nyquist)
+fn sinc_lowpass(num_taps: usize, cutoff: f64) -> Vec<f64> {
+    let center = (num_taps as f64 - 1.0) / 2.0;
+    (0..num_taps)
+        .map(|i| {
+            let x = i as f64 - center;
+            if x.abs() < 1e-12 {
+                cutoff
+            } else {
+                (PI * cutoff * x).sin() / (PI * x)
+            }
+        })
+        .collect()
+}
+
+/// 以窗函數法設計線性相位 FIR
+///
+/// # Arguments
+/// * `num_taps` - 抽頭數，至少 2（偶數會自動加 1，使群延遲為整數樣本）
+/// * `band` - 頻帶類型
+/// * `f1` - 截止頻率（歸一化，1.0 = Nyquist）；帶通 / 帶阻時為下邊緣
+/// * `f2` - 帶通 / 帶阻時的上邊緣（歸一化）
+/// * `window` - 長度由 `num_taps` 決定的窗函數產生器
+///
+/// 係數經過歸一化，使通帶中心的增益為 1。
+pub fn firwin<W: Fn(usize) -> Vec<f64>>(
+    num_taps: usize,
+    band: FilterBand,
+    f1: f64,
+    f2: f64,
+    window: W,
+) -> Vec<f64> {
+    if num_taps == 0 {
+        return Vec::new();
+    }
+
+    // 只有奇數長度 (Type I) 的群延遲 (N - 1) / 2 為整數，FirFilter::filter 才能精確對齊；
+    // 高通與帶阻在 Nyquist 處需要非零增益，也只有奇數長度可以實現
+    let num_taps = num_taps | 1;
+
+    let ideal: Vec<f64> = match band {
+        FilterBand::Lowpass => sinc_lowpass(num_taps, f1),
+        FilterBand::Highpass => {
+            let lp = sinc_lowpass(num_taps, f1);
+            let all = sinc_lowpass(num_taps, 1.0);
+            all.iter().zip(lp.iter()).map(|(a, l)| a - l).collect()
+        }
+        FilterBand::Bandpass => {
+            let hi = sinc_lowpass(num_taps, f2);
+            let lo = sinc_lowpass(num_taps, f1);
+            hi.iter().zip(lo.iter()).map(|(h, l)| h - l).collect()
+        }
+        FilterBand::Bandstop => {
+            let all = sinc_lowpass(num_taps, 1.0);
+            let hi = sinc_lowpass(num_taps, f2);
+            let lo = sinc_lowpass(num_taps, f1);
+            (0..num_taps).map(|i| all[i] - hi[i] + lo[i]).collect()
+        }
+    };
+
+    let w = window(num_taps);
+    let mut taps: Vec<f64> = ideal.iter().zip(w.iter()).map(|(h, w)| h * w).collect();
+
+    // 歸一化：低通 / 帶阻以 DC、高通以 Nyquist、帶通以中心頻率為參考
+    let reference = match band {
+        FilterBand::Lowpass | FilterBand::Bandstop => 0.0,
+        FilterBand::Highpass => 1.0,
+        FilterBand::Bandpass => (f1 + f2) / 2.0,
+    };
+    let center = (num_taps as f64 - 1.0) / 2.0;
+    let gain: f64 = taps
+        .iter()
+        .enumerate()
+        .map(|(i, &h)| h * (PI * reference * (i as f64 - center)).cos())
+        .sum();
+    if gain.abs() > 1e-12 {
+        for h in &mut taps {
+            *h /= gain;
+        }
+    }
+
+    taps
+}
+
+// ------------------------------------------------------------
+// Parks-McClellan (Remez 交換演算法)
+// ------------------------------------------------------------
+
+/// Remez 迭代中的插值狀態
+struct RemezState {
+    x: Vec<f64>,
+    y: Vec<f64>,
+    ad: Vec<f64>,
+}
+
+impl RemezState {
+    /// 以重心拉格朗日插值計算頻率 `freq` (0-0.5) 處的響應
+    fn evaluate(&self, freq: f64) -> f64 {
+        let xc = (2.0 * PI * freq).cos();
+        let mut numer = 0.0;
+        let mut denom = 0.0;
+        for i in 0..self.x.len() {
+            let diff = xc - self.x[i];
+            if diff.abs() < 1e-7 {
+                return self.y[i];
+            }
+            let c = self.ad[i] / diff;
+            denom += c;
+            numer += c * self.y[i];
+        }
+        numer / denom
+    }
+}
+
+/// 根據目前的極值點計算插值參數與偏差
+fn remez_parameters(ext: &[usize], grid: &[f64], desired: &[f64], weight: &[f64]) -> RemezState {
+    let r = ext.len();
+    let x: Vec<f64> = ext.iter().map(|&e| (2.0 * PI * grid[e]).cos()).collect();
+
+    // 分段相乘以避免數值溢出
+    let ld = (r.saturating_sub(2)) / 15 + 1;
+    let ad: Vec<f64> = (0..r)
+        .map(|i| {
+            let mut denom = 1.0;
+            for j in 0..ld {
+                let mut k = j;
+                while k < r {
+                    if k != i {
+                        denom *= 2.0 * (x[i] - x[k]);
+                    }
+                    k += ld;
+                }
+            }
+            if denom.abs() < 1e-5 {
+                denom = 1e-5;
+            }
+            1.0 / denom
+        })
+        .collect();
+
+    let mut numer = 0.0;
+    let mut denom = 0.0;
+    let mut sign = 1.0;
+    for i in 0..r {
+        numer += ad[i] * desired[ext[i]];
+        denom += sign * ad[i] / weight[ext[i]];
+        sign = -sign;
+    }
+    let delta = numer / denom;
+
+    let mut sign = 1.0;
+    let y = (0..r)
+        .map(|i| {
+            let value = desired[ext[i]] - sign * delta / weight[ext[i]];
+            sign = -sign;
+            value
+        })
+        .collect();
+
+    RemezState { x, y, ad }
+}
+
+/// 在誤差函數上尋找新的交錯極值點集合
+fn remez_search(error: &[f64], count: usize) -> Option<Vec<usize>> {
+    let n = error.len();
+    if n < 2 {
+        return None;
+    }
+
+    // 所有局部極值（包括兩端）
+    let mut found: Vec<usize> = Vec::new();
+    for i in 0..n {
+        let e = error[i];
+        let prev = if i > 0 { error[i - 1] } else { f64::NAN };
+        let next = if i + 1 < n { error[i + 1] } else { f64::NAN };
+        let is_max = e > 0.0 && (i == 0 || e >= prev) && (i + 1 == n || e > next);
+        let is_min = e < 0.0 && (i == 0 || e <= prev) && (i + 1 == n || e < next);
+        if is_max || is_min {
+            found.push(i);
+        }
+    }
+
+    // 連續同號的極值只保留幅度最大者
+    let mut alternating: Vec<usize> = Vec::with_capacity(found.len());
+    for idx in found {
+        match alternating.last() {
+            Some(&last) if (error[last] > 0.0) == (error[idx] > 0.0) => {
+                if error[idx].abs() > error[last].abs() {
+                    *alternating.last_mut().unwrap() = idx;
+                }
+            }
+            _ => alternating.push(idx),
+        }
+    }
+
+    // 多餘的極值從兩端移除較小者
+    while alternating.len() > count {
+        let first = error[alternating[0]].abs();
+        let last = error[*alternating.last().unwrap()].abs();
+        if first < last {
+            alternating.remove(0);
+        } else {
+            alternating.pop();
+        }
+    }
+
+    if alternating.len() == count {
+        Some(alternating)
+    } else {
+        None
+    }
+}
+
+/// 以 Parks-McClellan 演算法設計等波紋線性相位 FIR（對稱脈衝響應）
+///
+/// # Arguments
+/// * `num_taps` - 抽頭數（偶數長度時 Nyquist 處增益必為 0，不適用於高通）
+/// * `bands` - 頻帶邊緣（歸一化，0.5 = Nyquist），成對出現且遞增
+/// * `desired` - 每個頻帶的目標增益
+/// * `weights` - 每個頻帶的誤差權重
+///
+/// # Returns
+/// 參數無效時返回 None
+#[allow(clippy::needless_range_loop)]
+pub fn remez(num_taps: usize, bands: &[f64], desired: &[f64], weights: &[f64]) -> Option<Vec<f64>> {
+    let num_bands = bands.len() / 2;
+    if num_taps < 3
+        || !bands.len().is_multiple_of(2)
+        || num_bands == 0
+        || desired.len() != num_bands
+        || weights.len() != num_bands
+        || bands.windows(2).any(|w| w[1] < w[0])
+        || bands[0] < 0.0
+        || bands[bands.len() - 1] > 0.5
+        || weights.iter().any(|&w| w <= 0.0)
+    {
+        return None;
+    }
+
+    let odd = num_taps % 2 == 1;
+    let r = num_taps.div_ceil(2);
+
+    // 建立稠密頻率網格
+    let delf = 0.5 / (REMEZ_GRID_DENSITY * r) as f64;
+    let mut grid = Vec::new();
+    let mut grid_desired = Vec::new();
+    let mut grid_weight = Vec::new();
+    for band in 0..num_bands {
+        let low = bands[2 * band];
+        let high = bands[2 * band + 1];
+        let k = (((high - low) / delf + 0.5) as usize).max(1);
+        for i in 0..k {
+            grid.push(if i + 1 == k { high } else { low + i as f64 * delf });
+            grid_desired.push(desired[band]);
+            grid_weight.push(weights[band]);
+        }
+    }
+
+    // 偶數長度：H(f) = cos(pi f) * P(f)，將目標與權重轉換為 P 的問題
+    if !odd {
+        if let Some(last) = grid.last_mut() {
+            if *last > 0.5 - delf {
+                *last = 0.5 - delf;
+            }
+        }
+        for i in 0..grid.len() {
+            let c = (PI * grid[i]).cos();
+            grid_desired[i] /= c;
+            grid_weight[i] *= c;
+        }
+    }
+
+    let grid_size = grid.len();
+    if grid_size < r + 1 {
+        return None;
+    }
+
+    // 初始極值點均勻分佈
+    let mut ext: Vec<usize> = (0..=r).map(|i| i * (grid_size - 1) / r).collect();
+    let mut state = remez_parameters(&ext, &grid, &grid_desired, &grid_weight);
+
+    for _ in 0..REMEZ_MAX_ITERATIONS {
+        let error: Vec<f64> = (0..grid_size)
+            .map(|i| grid_weight[i] * (grid_desired[i] - state.evaluate(grid[i])))
+            .collect();
+
+        match remez_search(&error, r + 1) {
+            Some(new_ext) => ext = new_ext,
+            None => break,
+        }
+
+        let max_err = ext.iter().map(|&e| error[e].abs()).fold(0.0, f64::max);
+        let min_err = ext.iter().map(|&e| error[e].abs()).fold(f64::INFINITY, f64::min);
+
+        state = remez_parameters(&ext, &grid, &grid_desired, &grid_weight);
+
+        if max_err > 0.0 && (max_err - min_err) / max_err < 1e-4 {
+            break;
+        }
+    }
+
+    // 在等間距頻率上採樣響應，再以頻率採樣法求脈衝響應
+    let n = num_taps as f64;
+    let amplitude: Vec<f64> = (0..=num_taps / 2)
+        .map(|k| {
+            let freq = k as f64 / n;
+            let correction = if odd { 1.0 } else { (PI * freq).cos() };
+            state.evaluate(freq) * correction
+        })
+        .collect();
+
+    let center = (n - 1.0) / 2.0;
+    let last_k = if odd { (num_taps - 1) / 2 } else { num_taps / 2 - 1 };
+    let taps = (0..num_taps)
+        .map(|i| {
+            let x = 2.0 * PI * (i as f64 - center) / n;
+            let mut value = amplitude[0];
+            for k in 1..=last_k {
+                value += 2.0 * amplitude[k] * (x * k as f64).cos();
+            }
+            value / n
+        })
+        .collect();
+
+    Some(taps)
+}
+
+// ------------------------------------------------------------
+// Overlap-save 快速卷積
+// ------------------------------------------------------------
+
+/// FirFilter: 以 FFT overlap-save 實現的 FIR 濾波器
+///
+/// 濾波器頻率響應只計算一次，適合對長音頻重複濾波。
+#[wasm_bindgen]
+pub struct FirFilter {
+    taps: Vec<f32>,
+    block_size: usize,
+    spectrum: Vec<Complex<f32>>,
+    forward: Arc<dyn Fft<f32>>,
+    inverse: Arc<dyn Fft<f32>>,
+}
+
+#[wasm_bindgen]
+impl FirFilter {
+    /// 以抽頭係數創建 FIR 濾波器
+    ///
+    /// # Arguments
+    /// * `taps` - FIR 係數 (Float32Array，例如 `design_fir_window` 的輸出)
+    #[wasm_bindgen(constructor)]
+    pub fn new(taps: &[f32]) -> FirFilter {
+        let taps = if taps.is_empty() { vec![1.0] } else { taps.to_vec() };
+
+        // 區塊大小取濾波器長度的數倍，使每塊的有效輸出足夠長
+        let block_size = (taps.len() * 4).max(4096).next_power_of_two();
+
+        let mut planner = FftPlanner::new();
+        let forward = planner.plan_fft_forward(block_size);
+        let inverse = planner.plan_fft_inverse(block_size);
+
+        let mut spectrum = vec![Complex::default(); block_size];
+        for (i, &h) in taps.iter().enumerate() {
+            spectrum[i] = Complex::new(h, 0.0);
+        }
+        forward.process(&mut spectrum);
+
+        FirFilter {
+            taps,
+            block_size,
+            spectrum,
+            forward,
+            inverse,
+        }
+    }
+
+    /// 獲取抽頭數
+    #[wasm_bindgen]
+    pub fn get_num_taps(&self) -> usize {
+        self.taps.len()
+    }
+
+    /// 獲取群延遲（樣本數，線性相位時為 (N - 1) / 2）
+    #[wasm_bindgen]
+    pub fn get_group_delay(&self) -> f32 {
+        (self.taps.len() as f32 - 1.0) / 2.0
+    }
+
+    /// 因果濾波：輸出長度與輸入相同，包含 (N - 1) / 2 樣本的延遲
+    #[wasm_bindgen]
+    pub fn filter_causal(&self, audio_data: &[f32]) -> Vec<f32> {
+        self.convolve_range(audio_data, 0, audio_data.len())
+    }
+
+    /// 延遲補償濾波：輸出與輸入在時間上對齊
+    ///
+    /// 線性相位 FIR 的延遲是固定的，補償後叫聲的起止時間不會偏移，
+    /// 適合需要精確測量持續時間的帶通處理。
+    /// 補償量為 (N - 1) / 2 取整：偶數長度的濾波器輸出仍晚 0.5 個樣本，
+    /// `design_fir_window` 與 `design_fir_remez` 總是返回奇數長度。
+    #[wasm_bindgen]
+    pub fn filter(&self, audio_data: &[f32]) -> Vec<f32> {
+        let delay = (self.taps.len() - 1) / 2;
+        self.convolve_range(audio_data, delay, audio_data.len())
+    }
+}
+
+impl FirFilter {
+    /// 計算完整線性卷積 y[n] = sum h[k] x[n - k] 中從 `offset` 開始的 `len` 個樣本
+    fn convolve_range(&self, signal: &[f32], offset: usize, len: usize) -> Vec<f32> {
+        let mut output = vec![0.0f32; len];
+        if signal.is_empty() || len == 0 {
+            return output;
+        }
+
+        let overlap = self.taps.len() - 1;
+        let step = self.block_size - overlap;
+        let norm = 1.0 / self.block_size as f32;
+        let mut buffer = vec![Complex::default(); self.block_size];
+
+        let mut out_pos = 0;
+        while out_pos < len {
+            // 此塊輸出對應的卷積索引 n0..n0+step，需要輸入 x[n0 - overlap .. n0 + step)
+            let n0 = (offset + out_pos) as isize;
+            let input_start = n0 - overlap as isize;
+            for (i, value) in buffer.iter_mut().enumerate() {
+                let idx = input_start + i as isize;
+                let sample = if idx >= 0 && (idx as usize) < signal.len() {
+                    signal[idx as usize]
+                } else {
+                    0.0
+                };
+                *value = Complex::new(sample, 0.0);
+            }
+
+            self.forward.process(&mut buffer);
+            for (value, h) in buffer.iter_mut().zip(self.spectrum.iter()) {
+                *value *= h;
+            }
+            self.inverse.process(&mut buffer);
+
+            // 前 overlap 個樣本受循環卷積混疊影響，丟棄
+            let count = step.min(len - out_pos);
+            for i in 0..count {
+                output[out_pos + i] = buffer[overlap + i].re * norm;
+            }
+            out_pos += count;
+        }
+
+        output
+    }
+}
+
+/// 以窗函數法設計線性相位 FIR
+///
+/// # Arguments
+/// * `num_taps` - 抽頭數（偶數會自動加 1，使延遲補償濾波精確對齊）
+/// * `band` - 頻帶類型 ("lowpass", "highpass", "bandpass", "bandstop")
+/// * `sample_rate` - 採樣率 (Hz)
+/// * `freq1` - 截止頻率 (Hz)；帶通 / 帶阻時為下邊緣
+/// * `freq2` - 帶通 / 帶阻時的上邊緣 (Hz)
+/// * `window_func` - 窗函數名稱（與 SpectrogramEngine 相同，另支持 "kaiser"）
+/// * `window_param` - blackman 的 alpha 或 kaiser 的 beta（可選）
+///
+/// # Returns
+/// FIR 係數 (Float32Array)，參數無效時返回空數組
+#[wasm_bindgen]
+pub fn design_fir_window(
+    num_taps: usize,
+    band: &str,
+    sample_rate: f32,
+    freq1: f32,
+    freq2: f32,
+    window_func: &str,
+    window_param: Option<f32>,
+) -> Vec<f32> {
+    let nyquist = sample_rate as f64 / 2.0;
+    let band = match FilterBand::from_name(band) {
+        Some(band) => band,
+        None => return Vec::new(),
+    };
+    let f1 = freq1 as f64 / nyquist;
+    let f2 = freq2 as f64 / nyquist;
+    let is_band = matches!(band, FilterBand::Bandpass | FilterBand::Bandstop);
+    // 長度 1 的窗函數無法定義（create_window 會除以 n - 1）
+    if num_taps < 2 || !(f1 > 0.0 && f1 < 1.0) || (is_band && !(f2 > f1 && f2 < 1.0)) {
+        return Vec::new();
+    }
+
+    let taps = if window_func == "kaiser" {
+        let beta = window_param.unwrap_or(8.6) as f64;
+        firwin(num_taps, band, f1, f2, |n| kaiser_window(n, beta))
+    } else {
+        let alpha = window_param.unwrap_or(0.16);
+        firwin(num_taps, band, f1, f2, |n| {
+            create_window(window_func, n, alpha).into_iter().map(|w| w as f64).collect()
+        })
+    };
+
+    taps.into_iter().map(|h| h as f32).collect()
+}
+
+/// 以 Parks-McClellan 演算法設計等波紋線性相位 FIR
+///
+/// # Arguments
+/// * `num_taps` - 抽頭數（偶數會自動加 1，使延遲補償濾波精確對齊）
+/// * `band_edges_hz` - 頻帶邊緣 (Hz)，成對出現，例如 [0, 15000, 20000, 96000]
+/// * `desired` - 每個頻帶的目標增益，例如 [0, 1]
+/// * `weights` - 每個頻帶的誤差權重，例如 [10, 1]
+/// * `sample_rate` - 採樣率 (Hz)
+///
+/// # Returns
+/// FIR 係數 (Float32Array)，參數無效時返回空數組
+#[wasm_bindgen]
+pub fn design_fir_remez(
+    num_taps: usize,
+    band_edges_hz: &[f32],
+    desired: &[f32],
+    weights: &[f32],
+    sample_rate: f32,
+) -> Vec<f32> {
+    if sample_rate <= 0.0 {
+        return Vec::new();
+    }
+    let bands: Vec<f64> = band_edges_hz.iter().map(|&f| f as f64 / sample_rate as f64).collect();
+    let desired: Vec<f64> = desired.iter().map(|&d| d as f64).collect();
+    let weights: Vec<f64> = weights.iter().map(|&w| w as f64).collect();
+
+    remez(num_taps | 1, &bands, &desired, &weights)
+        .map(|taps| taps.into_iter().map(|h| h as f32).collect())
+        .unwrap_or_default()
+}
+
+/// 一次性延遲補償 FIR 濾波（FFT overlap-save）
+///
+/// # Arguments
+/// * `audio_data` - 音頻數據 (Float32Array)
+/// * `taps` - FIR 係數
+///
+/// # Returns
+/// 與輸入等長且時間對齊的濾波結果
+#[wasm_bindgen]
+pub fn fir_filter(audio_data: &[f32], taps: &[f32]) -> Vec<f32> {
+    FirFilter::new(taps).filter(audio_data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 幅度響應，`freq` 以採樣率歸一化 (0.5 = Nyquist)
+    fn magnitude(taps: &[f32], freq: f64) -> f64 {
+        let (re, im) = taps.iter().enumerate().fold((0.0, 0.0), |(re, im), (n, &h)| {
+            let phase = 2.0 * PI * freq * n as f64;
+            (re + h as f64 * phase.cos(), im - h as f64 * phase.sin())
+        });
+        (re * re + im * im).sqrt()
+    }
+
+    fn max_magnitude(taps: &[f32], lo: f64, hi: f64) -> f64 {
+        (0..=200).map(|i| magnitude(taps, lo + (hi - lo) * i as f64 / 200.0)).fold(0.0, f64::max)
+    }
+
+    fn min_magnitude(taps: &[f32], lo: f64, hi: f64) -> f64 {
+        (0..=200).map(|i| magnitude(taps, lo + (hi - lo) * i as f64 / 200.0)).fold(f64::INFINITY, f64::min)
+    }
+
+    fn is_symmetric(taps: &[f32]) -> bool {
+        (0..taps.len() / 2).all(|i| (taps[i] - taps[taps.len() - 1 - i]).abs() < 1e-7)
+    }
+
+    /// 以 |y| 為權重的時間重心（樣本）
+    fn centroid(data: &[f32]) -> f64 {
+        let weight: f64 = data.iter().map(|&x| x.abs() as f64).sum();
+        data.iter().enumerate().map(|(i, &x)| i as f64 * x.abs() as f64).sum::<f64>() / weight
+    }
+
+    #[test]
+    fn window_design_cutoff_response() {
+        // 窗函數法在截止頻率處增益為 0.5 (-6 dB)
+        let low = design_fir_window(201, "lowpass", 48_000.0, 6000.0, 0.0, "kaiser", None);
+        assert_eq!(low.len(), 201);
+        assert!((magnitude(&low, 6000.0 / 48_000.0) - 0.5).abs() < 0.01);
+        assert!((magnitude(&low, 0.0) - 1.0).abs() < 1e-4);
+        assert!(max_magnitude(&low, 0.0, 4500.0 / 48_000.0) < 1.001);
+        assert!(max_magnitude(&low, 7500.0 / 48_000.0, 0.5) < 1e-4);
+
+        let high = design_fir_window(200, "highpass", 48_000.0, 6000.0, 0.0, "hamming", None);
+        assert_eq!(high.len(), 201);
+        assert!((magnitude(&high, 6000.0 / 48_000.0) - 0.5).abs() < 0.01);
+        assert!((magnitude(&high, 0.5) - 1.0).abs() < 1e-4);
+        assert!(max_magnitude(&high, 0.0, 4500.0 / 48_000.0) < 0.01);
+
+        let band = design_fir_window(301, "bandpass", 48_000.0, 6000.0, 12_000.0, "kaiser", None);
+        assert!((magnitude(&band, 9000.0 / 48_000.0) - 1.0).abs() < 1e-4);
+        assert!((magnitude(&band, 6000.0 / 48_000.0) - 0.5).abs() < 0.01);
+        assert!((magnitude(&band, 12_000.0 / 48_000.0) - 0.5).abs() < 0.01);
+
+        let stop = design_fir_window(301, "bandstop", 48_000.0, 6000.0, 12_000.0, "kaiser", None);
+        assert!((magnitude(&stop, 0.0) - 1.0).abs() < 1e-4);
+        assert!(magnitude(&stop, 9000.0 / 48_000.0) < 1e-4);
+    }
+
+    #[test]
+    fn remez_band_edges_are_equiripple() {
+        // 通帶 [0, 6] kHz、阻帶 [9, 24] kHz，權重相同時兩帶的最大誤差相等
+        let taps = design_fir_remez(61, &[0.0, 6000.0, 9000.0, 24_000.0], &[1.0, 0.0], &[1.0, 1.0], 48_000.0);
+        assert_eq!(taps.len(), 61);
+        let pass_error = (max_magnitude(&taps, 0.0, 0.125) - 1.0).max(1.0 - min_magnitude(&taps, 0.0, 0.125));
+        let stop_error = max_magnitude(&taps, 0.1875, 0.5);
+        assert!(pass_error < 0.01 && stop_error < 0.01, "{} {}", pass_error, stop_error);
+        assert!((pass_error - stop_error).abs() < 0.1 * stop_error, "{} {}", pass_error, stop_error);
+
+        // 阻帶權重 10 倍：阻帶誤差縮小為約 1/10
+        let weighted = design_fir_remez(61, &[0.0, 6000.0, 9000.0, 24_000.0], &[1.0, 0.0], &[1.0, 10.0], 48_000.0);
+        let weighted_pass = (max_magnitude(&weighted, 0.0, 0.125) - 1.0).max(1.0 - min_magnitude(&weighted, 0.0, 0.125));
+        let weighted_stop = max_magnitude(&weighted, 0.1875, 0.5);
+        assert!((weighted_pass / weighted_stop - 10.0).abs() < 1.0, "{} {}", weighted_pass, weighted_stop);
+    }
+
+    #[test]
+    fn designs_are_linear_phase() {
+        let designs = [
+            design_fir_window(64, "lowpass", 48_000.0, 6000.0, 0.0, "hann", None),
+            design_fir_window(65, "bandstop", 48_000.0, 6000.0, 9000.0, "blackman", None),
+            design_fir_remez(40, &[0.0, 6000.0, 9000.0, 24_000.0], &[1.0, 0.0], &[1.0, 1.0], 48_000.0),
+            design_fir_remez(41, &[0.0, 6000.0, 9000.0, 24_000.0], &[0.0, 1.0], &[1.0, 1.0], 48_000.0),
+        ];
+        for taps in &designs {
+            assert!(taps.len() % 2 == 1, "{}", taps.len());
+            assert!(is_symmetric(taps));
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_window_designs() {
+        assert!(design_fir_window(1, "lowpass", 48_000.0, 6000.0, 0.0, "hann", None).is_empty());
+        assert!(design_fir_window(64, "lowpass", 48_000.0, 24_000.0, 0.0, "hann", None).is_empty());
+        assert!(design_fir_window(64, "bandpass", 48_000.0, 9000.0, 6000.0, "hann", None).is_empty());
+        assert!(design_fir_window(64, "comb", 48_000.0, 6000.0, 0.0, "hann", None).is_empty());
+        assert!(design_fir_remez(41, &[0.0, 6000.0, 9000.0], &[1.0, 0.0], &[1.0, 1.0], 48_000.0).is_empty());
+    }
+
+    #[test]
+    fn filter_compensates_group_delay() {
+        let mut impulse = vec![0.0f32; 256];
+        impulse[100] = 1.0;
+
+        // 奇數長度：補償後重心恰在原位置，因果濾波晚 (N - 1) / 2
+        let taps = design_fir_window(31, "lowpass", 48_000.0, 6000.0, 0.0, "hann", None);
+        let filter = FirFilter::new(&taps);
+        assert_eq!(filter.get_group_delay(), 15.0);
+        let aligned = filter.filter(&impulse);
+        assert_eq!(aligned.len(), impulse.len());
+        assert!((centroid(&aligned) - 100.0).abs() < 1e-4, "{}", centroid(&aligned));
+        assert!((centroid(&filter.filter_causal(&impulse)) - 115.0).abs() < 1e-4);
+
+        // 偶數長度：群延遲為半樣本，補償後仍晚 0.5 個樣本
+        let even = FirFilter::new(&[0.1, 0.2, 0.3, 0.3, 0.2, 0.1]);
+        assert!((centroid(&even.filter(&impulse)) - 100.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn overlap_save_matches_direct_convolution() {
+        // 輸入長於一個 FFT 區塊
+        let taps = design_fir_window(101, "bandpass", 48_000.0, 3000.0, 9000.0, "hann", None);
+        let signal: Vec<f32> = (0..10_000).map(|i| ((i * 7919) % 1000) as f32 / 500.0 - 1.0).collect();
+        let output = FirFilter::new(&taps).filter_causal(&signal);
+        for n in [0, 50, 4095, 4096, 5000, 9999] {
+            let direct: f32 = (0..taps.len()).filter(|&k| k <= n).map(|k| taps[k] * signal[n - k]).sum();
+            assert!((output[n] - direct).abs() < 1e-4, "{}: {} != {}", n, output[n], direct);
+        }
+    }
+}
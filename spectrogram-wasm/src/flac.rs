@@ -0,0 +1,960 @@
+// ============================================================
+// FLAC 解碼
+// 純 Rust 實現：STREAMINFO / VORBIS_COMMENT / APPLICATION("riff") 元數據、
+// CONSTANT / VERBATIM / FIXED / LPC 子幀、Rice 殘差與立體聲去相關。
+// 支援最高 32 位、任意採樣率（STREAMINFO 的 20 位欄位，涵蓋 768 kHz），
+// 輸出與 WAV 解碼相同的 DecodedAudio
+// ============================================================
+
+use std::fmt;
+
+use wasm_bindgen::prelude::*;
+
+use crate::guano::GuanoMetadata;
+use crate::wav::DecodedAudio;
+use crate::wav_writer::RiffChunk;
+
+// 元數據塊類型
+const BLOCK_STREAMINFO: u8 = 0;
+const BLOCK_APPLICATION: u8 = 2;
+const BLOCK_VORBIS_COMMENT: u8 = 4;
+
+/// flac --keep-foreign-metadata 保存 RIFF 塊時使用的 APPLICATION ID
+const RIFF_APPLICATION_ID: &[u8; 4] = b"riff";
+
+/// FLAC 解析錯誤
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FlacError {
+    /// 文件不以 "fLaC" 開頭（允許前置 ID3v2 標籤）
+    NotFlac,
+    /// 缺少或無法解析 STREAMINFO 塊
+    MissingStreamInfo,
+    /// STREAMINFO 中的參數無效
+    InvalidStreamInfo,
+}
+
+impl fmt::Display for FlacError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FlacError::NotFlac => write!(f, "not a FLAC stream"),
+            FlacError::MissingStreamInfo => write!(f, "missing or malformed STREAMINFO block"),
+            FlacError::InvalidStreamInfo => write!(f, "invalid STREAMINFO parameters"),
+        }
+    }
+}
+
+impl std::error::Error for FlacError {}
+
+/// STREAMINFO 塊
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FlacStreamInfo {
+    pub min_block_size: u16,
+    pub max_block_size: u16,
+    pub min_frame_size: u32,
+    pub max_frame_size: u32,
+    pub sample_rate: u32,
+    pub num_channels: u8,
+    pub bits_per_sample: u8,
+    /// 每個通道的總樣本數（0 表示未知）
+    pub total_samples: u64,
+    pub md5: [u8; 16],
+}
+
+/// 解碼後的 FLAC 文件
+#[derive(Debug, Clone, Default)]
+pub struct FlacFile {
+    pub stream_info: FlacStreamInfo,
+    pub vendor: String,
+    /// Vorbis 註釋 (鍵, 值)，按文件順序
+    pub comments: Vec<(String, String)>,
+    /// 以 APPLICATION("riff") 保存的原 WAV 塊（guan、wamd、LIST 等）
+    pub riff_chunks: Vec<RiffChunk>,
+    pub audio: DecodedAudio,
+    /// 因 CRC 錯誤或無法解析而以靜音替代的幀數
+    pub corrupt_frames: usize,
+}
+
+// ============================================================
+// CRC
+// ============================================================
+
+const fn crc8_table() -> [u8; 256] {
+    let mut table = [0u8; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u8;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 0x80 != 0 { (crc << 1) ^ 0x07 } else { crc << 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+const fn crc16_table() -> [u16; 256] {
+    let mut table = [0u16; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = (i as u16) << 8;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 0x8000 != 0 { (crc << 1) ^ 0x8005 } else { crc << 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+static CRC8_TABLE: [u8; 256] = crc8_table();
+static CRC16_TABLE: [u16; 256] = crc16_table();
+
+fn crc8(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |crc, &b| CRC8_TABLE[(crc ^ b) as usize])
+}
+
+fn crc16(data: &[u8]) -> u16 {
+    data.iter()
+        .fold(0u16, |crc, &b| (crc << 8) ^ CRC16_TABLE[((crc >> 8) as u8 ^ b) as usize])
+}
+
+// ============================================================
+// 位元讀取
+// ============================================================
+
+/// 大端位元讀取器；越界時返回 None
+struct BitReader<'a> {
+    data: &'a [u8],
+    /// 目前的位元位置
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> BitReader<'a> {
+        BitReader { data, pos: 0 }
+    }
+
+    fn byte_pos(&self) -> usize {
+        self.pos / 8
+    }
+
+    fn align(&mut self) {
+        self.pos = self.pos.div_ceil(8) * 8;
+    }
+
+    /// 讀取 n 位 (n <= 57) 無符號整數
+    fn read(&mut self, n: u32) -> Option<u64> {
+        if n == 0 {
+            return Some(0);
+        }
+        if self.pos + n as usize > self.data.len() * 8 {
+            return None;
+        }
+        let mut value = 0u64;
+        let mut remaining = n;
+        while remaining > 0 {
+            let byte = self.data[self.pos / 8];
+            let available = 8 - (self.pos % 8) as u32;
+            let take = available.min(remaining);
+            let bits = (byte as u64 >> (available - take)) & ((1u64 << take) - 1);
+            value = (value << take) | bits;
+            remaining -= take;
+            self.pos += take as usize;
+        }
+        Some(value)
+    }
+
+    /// 讀取 n 位 (1..=57) 有符號整數（二補數）
+    fn read_signed(&mut self, n: u32) -> Option<i64> {
+        if n == 0 {
+            return Some(0);
+        }
+        let value = self.read(n)?;
+        let shift = 64 - n;
+        Some(((value << shift) as i64) >> shift)
+    }
+
+    fn read_bit(&mut self) -> Option<bool> {
+        self.read(1).map(|b| b == 1)
+    }
+
+    /// 讀取一元編碼：連續 0 的個數，並跳過結尾的 1
+    fn read_unary(&mut self) -> Option<u32> {
+        let mut count = 0u32;
+        loop {
+            let idx = self.pos / 8;
+            if idx >= self.data.len() {
+                return None;
+            }
+            let offset = (self.pos % 8) as u32;
+            let byte = self.data[idx] << offset;
+            if byte == 0 {
+                count += 8 - offset;
+                self.pos += (8 - offset) as usize;
+            } else {
+                let zeros = byte.leading_zeros();
+                count += zeros;
+                self.pos += zeros as usize + 1;
+                return Some(count);
+            }
+        }
+    }
+
+    /// 讀取 Rice 編碼的有符號殘差
+    fn read_rice(&mut self, param: u32) -> Option<i64> {
+        let quotient = self.read_unary()? as u64;
+        let remainder = self.read(param)?;
+        let folded = (quotient << param) | remainder;
+        Some((folded >> 1) as i64 ^ -((folded & 1) as i64))
+    }
+
+    /// 讀取 FLAC 的 UTF-8 式變長整數（幀號或樣本號）
+    fn read_utf8_number(&mut self) -> Option<u64> {
+        let first = self.read(8)?;
+        let extra = match first {
+            0x00..=0x7F => return Some(first),
+            0xC0..=0xDF => 1,
+            0xE0..=0xEF => 2,
+            0xF0..=0xF7 => 3,
+            0xF8..=0xFB => 4,
+            0xFC..=0xFD => 5,
+            0xFE => 6,
+            _ => return None,
+        };
+        let mut value = first & (0x3F >> extra);
+        for _ in 0..extra {
+            let byte = self.read(8)?;
+            if byte & 0xC0 != 0x80 {
+                return None;
+            }
+            value = (value << 6) | (byte & 0x3F);
+        }
+        Some(value)
+    }
+}
+
+// ============================================================
+// 元數據
+// ============================================================
+
+fn parse_stream_info(content: &[u8]) -> Result<FlacStreamInfo, FlacError> {
+    if content.len() < 34 {
+        return Err(FlacError::MissingStreamInfo);
+    }
+    let mut reader = BitReader::new(content);
+    let mut read = |n| reader.read(n).ok_or(FlacError::MissingStreamInfo);
+    let mut info = FlacStreamInfo {
+        min_block_size: read(16)? as u16,
+        max_block_size: read(16)? as u16,
+        min_frame_size: read(24)? as u32,
+        max_frame_size: read(24)? as u32,
+        sample_rate: read(20)? as u32,
+        num_channels: read(3)? as u8 + 1,
+        bits_per_sample: read(5)? as u8 + 1,
+        total_samples: read(36)?,
+        md5: [0; 16],
+    };
+    info.md5.copy_from_slice(&content[18..34]);
+
+    if info.sample_rate == 0 || info.bits_per_sample < 4 {
+        return Err(FlacError::InvalidStreamInfo);
+    }
+    Ok(info)
+}
+
+/// 解析 VORBIS_COMMENT 塊（長度為小端）
+fn parse_vorbis_comment(content: &[u8]) -> (String, Vec<(String, String)>) {
+    let read_u32 = |pos: usize| -> Option<usize> {
+        content
+            .get(pos..pos + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+    };
+    let mut comments = Vec::new();
+
+    let Some(vendor_len) = read_u32(0) else {
+        return (String::new(), comments);
+    };
+    // 長度先截斷到剩餘字節再相加，避免 wasm32 上 usize 溢出
+    let vendor_end = 4 + vendor_len.min(content.len() - 4);
+    let vendor = String::from_utf8_lossy(&content[4..vendor_end]).into_owned();
+
+    let mut pos = vendor_end;
+    let count = read_u32(pos).unwrap_or(0);
+    pos += 4;
+    for _ in 0..count {
+        let Some(len) = read_u32(pos) else { break };
+        pos += 4;
+        let end = pos + len.min(content.len() - pos);
+        let entry = String::from_utf8_lossy(&content[pos..end]);
+        if let Some((key, value)) = entry.split_once('=') {
+            comments.push((key.to_string(), value.to_string()));
+        }
+        pos = end;
+    }
+
+    (vendor, comments)
+}
+
+/// 解析 APPLICATION("riff") 的內容：RIFF 頭或一個或多個完整的 RIFF 塊
+fn parse_riff_application(payload: &[u8], chunks: &mut Vec<RiffChunk>) {
+    let mut pos = if payload.starts_with(b"RIFF") || payload.starts_with(b"RF64") { 12 } else { 0 };
+    while pos + 8 <= payload.len() {
+        let mut id = [0u8; 4];
+        id.copy_from_slice(&payload[pos..pos + 4]);
+        let size = u32::from_le_bytes([payload[pos + 4], payload[pos + 5], payload[pos + 6], payload[pos + 7]]) as usize;
+        let start = pos + 8;
+        let end = start + size.min(payload.len() - start);
+        // data 塊只保存塊頭，樣本在 FLAC 幀中
+        if &id != b"data" && &id != b"ds64" {
+            chunks.push(RiffChunk::new(&id, payload[start..end].to_vec()));
+        }
+        pos = end + size % 2;
+    }
+}
+
+// ============================================================
+// 幀解碼
+// ============================================================
+
+/// 幀頭
+struct FrameHeader {
+    block_size: usize,
+    /// 0..=7 為獨立通道 (數量 = n + 1)，8 左/側，9 側/右，10 中/側
+    channel_assignment: u8,
+    bits_per_sample: u32,
+}
+
+impl FrameHeader {
+    fn num_channels(&self) -> usize {
+        if self.channel_assignment < 8 {
+            self.channel_assignment as usize + 1
+        } else {
+            2
+        }
+    }
+}
+
+fn read_frame_header(reader: &mut BitReader, frame_start: usize, info: &FlacStreamInfo) -> Option<FrameHeader> {
+    if reader.read(14)? != 0x3FFE {
+        return None;
+    }
+    // 保留位必須為 0；blocking strategy 不影響解碼
+    if reader.read_bit()? {
+        return None;
+    }
+    reader.read(1)?;
+
+    let block_size_code = reader.read(4)?;
+    let sample_rate_code = reader.read(4)?;
+    let channel_assignment = reader.read(4)? as u8;
+    let sample_size_code = reader.read(3)?;
+    if reader.read_bit()? || channel_assignment > 10 {
+        return None;
+    }
+    reader.read_utf8_number()?;
+
+    let block_size = match block_size_code {
+        0 => return None,
+        1 => 192,
+        2..=5 => 576 << (block_size_code - 2),
+        6 => reader.read(8)? as usize + 1,
+        7 => reader.read(16)? as usize + 1,
+        _ => 256 << (block_size_code - 8),
+    };
+
+    // 幀頭中的採樣率只需讀過並驗證，輸出一律使用 STREAMINFO 的採樣率
+    match sample_rate_code {
+        12 => {
+            reader.read(8)?;
+        }
+        13 | 14 => {
+            reader.read(16)?;
+        }
+        15 => return None,
+        _ => {}
+    }
+
+    let bits_per_sample = match sample_size_code {
+        0 => info.bits_per_sample as u32,
+        1 => 8,
+        2 => 12,
+        4 => 16,
+        5 => 20,
+        6 => 24,
+        7 => 32,
+        _ => return None,
+    };
+
+    let header_end = reader.byte_pos();
+    let crc = reader.read(8)? as u8;
+    if crc8(&reader.data[frame_start..header_end]) != crc {
+        return None;
+    }
+
+    Some(FrameHeader {
+        block_size,
+        channel_assignment,
+        bits_per_sample,
+    })
+}
+
+/// 解碼殘差並寫入 output[order..]
+fn read_residual(reader: &mut BitReader, order: usize, output: &mut [i64]) -> Option<()> {
+    let block_size = output.len();
+    let (param_bits, escape) = match reader.read(2)? {
+        0 => (4, 0xF),
+        1 => (5, 0x1F),
+        _ => return None,
+    };
+    let partition_order = reader.read(4)? as u32;
+    let partitions = 1usize << partition_order;
+    let partition_len = block_size >> partition_order;
+    if partition_len * partitions != block_size || partition_len < order {
+        return None;
+    }
+
+    let mut idx = order;
+    for p in 0..partitions {
+        let count = if p == 0 { partition_len - order } else { partition_len };
+        let param = reader.read(param_bits)? as u32;
+        if param == escape {
+            let raw_bits = reader.read(5)? as u32;
+            for sample in &mut output[idx..idx + count] {
+                *sample = reader.read_signed(raw_bits)?;
+            }
+        } else {
+            for sample in &mut output[idx..idx + count] {
+                *sample = reader.read_rice(param)?;
+            }
+        }
+        idx += count;
+    }
+    Some(())
+}
+
+/// 固定預測器係數（階數 0..=4）
+const FIXED_COEFFICIENTS: [&[i64]; 5] = [&[], &[1], &[2, -1], &[3, -3, 1], &[4, -6, 4, -1]];
+
+/// 以 history 的最後 coefficients.len() 個樣本計算預測值
+///
+/// 損壞或惡意構造的殘差可能使乘積或總和溢出 i64，此時返回 None
+fn predict(coefficients: &[i64], history: &[i64]) -> Option<i64> {
+    coefficients
+        .iter()
+        .zip(history.iter().rev())
+        .try_fold(0i64, |sum, (&c, &sample)| sum.checked_add(c.checked_mul(sample)?))
+}
+
+/// 解碼一個子幀到 output（長度為 block_size）
+fn read_subframe(reader: &mut BitReader, bits_per_sample: u32, output: &mut [i64]) -> Option<()> {
+    if reader.read_bit()? {
+        return None;
+    }
+    let subframe_type = reader.read(6)? as usize;
+    let wasted = if reader.read_bit()? { reader.read_unary()? + 1 } else { 0 };
+    if wasted >= bits_per_sample {
+        return None;
+    }
+    let bits = bits_per_sample - wasted;
+    let block_size = output.len();
+
+    match subframe_type {
+        0 => {
+            let value = reader.read_signed(bits)?;
+            output.fill(value);
+        }
+        1 => {
+            for sample in output.iter_mut() {
+                *sample = reader.read_signed(bits)?;
+            }
+        }
+        8..=12 => {
+            let order = subframe_type - 8;
+            if order > block_size {
+                return None;
+            }
+            for sample in &mut output[..order] {
+                *sample = reader.read_signed(bits)?;
+            }
+            read_residual(reader, order, output)?;
+            let coefficients = FIXED_COEFFICIENTS[order];
+            for i in order..block_size {
+                let prediction = predict(coefficients, &output[..i])?;
+                output[i] = output[i].checked_add(prediction)?;
+            }
+        }
+        32..=63 => {
+            let order = subframe_type - 31;
+            if order > block_size {
+                return None;
+            }
+            for sample in &mut output[..order] {
+                *sample = reader.read_signed(bits)?;
+            }
+            let precision = reader.read(4)? as u32 + 1;
+            if precision == 16 {
+                return None;
+            }
+            let shift = reader.read_signed(5)?;
+            if shift < 0 {
+                return None;
+            }
+            let mut coefficients = Vec::with_capacity(order);
+            for _ in 0..order {
+                coefficients.push(reader.read_signed(precision)?);
+            }
+            read_residual(reader, order, output)?;
+            for i in order..block_size {
+                let prediction = predict(&coefficients, &output[..i])?;
+                output[i] = output[i].checked_add(prediction >> shift)?;
+            }
+        }
+        _ => return None,
+    }
+
+    if wasted > 0 {
+        for sample in output.iter_mut() {
+            *sample <<= wasted;
+        }
+    }
+    Some(())
+}
+
+/// 解碼幀頭之後的子幀與 CRC-16，返回 (每通道樣本, 幀結束的字節位置)
+fn decode_frame_body(
+    reader: &mut BitReader,
+    frame_start: usize,
+    header: &FrameHeader,
+) -> Option<(Vec<Vec<i64>>, usize)> {
+    let mut channels = vec![vec![0i64; header.block_size]; header.num_channels()];
+    for (ch, output) in channels.iter_mut().enumerate() {
+        // 側聲道多一位
+        let side = matches!((header.channel_assignment, ch), (8, 1) | (9, 0) | (10, 1));
+        let bits = header.bits_per_sample + side as u32;
+        read_subframe(reader, bits, output)?;
+    }
+
+    reader.align();
+    let crc_pos = reader.byte_pos();
+    let crc = reader.read(16)? as u16;
+    if crc16(&reader.data[frame_start..crc_pos]) != crc {
+        return None;
+    }
+
+    // 立體聲去相關
+    if header.channel_assignment >= 8 {
+        let (first, second) = channels.split_at_mut(1);
+        let (a, b) = (&mut first[0], &mut second[0]);
+        // 數值超出範圍的幀視為損壞
+        for i in 0..header.block_size {
+            match header.channel_assignment {
+                // 左 / 側：右 = 左 - 側
+                8 => b[i] = a[i].checked_sub(b[i])?,
+                // 側 / 右：左 = 側 + 右
+                9 => a[i] = a[i].checked_add(b[i])?,
+                // 中 / 側
+                _ => {
+                    let side = b[i];
+                    let mid = a[i].checked_mul(2)? | (side & 1);
+                    a[i] = mid.checked_add(side)? >> 1;
+                    b[i] = mid.checked_sub(side)? >> 1;
+                }
+            }
+        }
+    }
+
+    Some((channels, reader.byte_pos()))
+}
+
+/// 從 `pos` 開始尋找下一個可能的幀同步碼
+fn find_sync(data: &[u8], pos: usize) -> Option<usize> {
+    (pos..data.len().saturating_sub(1)).find(|&i| data[i] == 0xFF && data[i + 1] & 0xFE == 0xF8)
+}
+
+/// 解碼 FLAC 文件
+///
+/// 幀頭有效但內容損壞（CRC 錯誤或無法解析）的幀以同長度的靜音替代以保持時間軸，
+/// 並在下一個同步碼處恢復解碼。
+///
+/// # Arguments
+/// * `bytes` - 完整的文件內容
+///
+/// # Returns
+/// 解碼結果，或解析錯誤
+pub fn decode_flac(bytes: &[u8]) -> Result<FlacFile, FlacError> {
+    // 跳過前置的 ID3v2 標籤
+    let mut pos = 0;
+    if bytes.len() >= 10 && &bytes[0..3] == b"ID3" {
+        let size = bytes[6..10].iter().fold(0usize, |acc, &b| (acc << 7) | (b & 0x7F) as usize);
+        pos = 10 + size + if bytes[5] & 0x10 != 0 { 10 } else { 0 };
+    }
+    if bytes.len() < pos + 4 || &bytes[pos..pos + 4] != b"fLaC" {
+        return Err(FlacError::NotFlac);
+    }
+    pos += 4;
+
+    let mut file = FlacFile::default();
+    let mut stream_info = None;
+    loop {
+        if pos + 4 > bytes.len() {
+            break;
+        }
+        let is_last = bytes[pos] & 0x80 != 0;
+        let block_type = bytes[pos] & 0x7F;
+        let len = u32::from_be_bytes([0, bytes[pos + 1], bytes[pos + 2], bytes[pos + 3]]) as usize;
+        let start = pos + 4;
+        let end = start + len.min(bytes.len() - start);
+        let content = &bytes[start..end];
+
+        match block_type {
+            BLOCK_STREAMINFO => stream_info = Some(parse_stream_info(content)?),
+            BLOCK_VORBIS_COMMENT => {
+                let (vendor, comments) = parse_vorbis_comment(content);
+                file.vendor = vendor;
+                file.comments.extend(comments);
+            }
+            BLOCK_APPLICATION if content.len() >= 4 && &content[0..4] == RIFF_APPLICATION_ID => {
+                parse_riff_application(&content[4..], &mut file.riff_chunks);
+            }
+            _ => {}
+        }
+
+        pos = end;
+        if is_last {
+            break;
+        }
+    }
+
+    let info = stream_info.ok_or(FlacError::MissingStreamInfo)?;
+    let num_channels = info.num_channels as usize;
+
+    let capacity = (info.total_samples as usize).min(bytes.len() * 2);
+    let mut channels: Vec<Vec<f32>> = vec![Vec::with_capacity(capacity); num_channels];
+
+    while let Some(frame_start) = find_sync(bytes, pos) {
+        let mut reader = BitReader::new(bytes);
+        reader.pos = frame_start * 8;
+        // 幀頭無效（多為樣本資料中的假同步碼）：繼續往後搜尋
+        let header = match read_frame_header(&mut reader, frame_start, &info) {
+            Some(header) if header.num_channels() == num_channels => header,
+            _ => {
+                pos = frame_start + 1;
+                continue;
+            }
+        };
+
+        match decode_frame_body(&mut reader, frame_start, &header) {
+            Some((samples, frame_end)) => {
+                let scale = 1.0 / (1u64 << (header.bits_per_sample - 1)) as f32;
+                for (output, frame) in channels.iter_mut().zip(samples.iter()) {
+                    output.extend(frame.iter().map(|&s| s as f32 * scale));
+                }
+                pos = frame_end;
+            }
+            None => {
+                // 幀頭有效但內容損壞：以靜音替代以保持時間軸
+                file.corrupt_frames += 1;
+                for output in channels.iter_mut() {
+                    output.resize(output.len() + header.block_size, 0.0);
+                }
+                pos = frame_start + 1;
+            }
+        }
+    }
+
+    // 以 STREAMINFO 的總樣本數截斷尾部的靜音填充
+    if info.total_samples > 0 {
+        let total = info.total_samples as usize;
+        for output in channels.iter_mut() {
+            output.truncate(total);
+        }
+    }
+
+    file.audio = DecodedAudio {
+        sample_rate: info.sample_rate,
+        bits_per_sample: info.bits_per_sample as u16,
+        channels,
+    };
+    file.stream_info = info;
+    Ok(file)
+}
+
+impl FlacFile {
+    /// 查找 Vorbis 註釋（鍵不分大小寫）
+    pub fn comment(&self, key: &str) -> Option<&str> {
+        self.comments
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// 文件的 GUANO 元數據
+    ///
+    /// 來源優先順序：
+    /// 1. APPLICATION("riff") 中保存的原 WAV 塊（guan → wamd → AudioMoth 註釋）
+    /// 2. Vorbis 註釋中的 "GUANO" 欄位（整段 GUANO 文本）
+    /// 3. 以 GUANO 鍵名（例如 "GUANO|Version"、"Timestamp"）直接存放的 Vorbis 註釋
+    pub fn metadata(&self) -> GuanoMetadata {
+        let sample_rate = self.stream_info.sample_rate;
+        let chunks: Vec<([u8; 4], &[u8])> = self.riff_chunks.iter().map(|c| (c.id, c.data.as_slice())).collect();
+
+        GuanoMetadata::from_riff_chunks(&chunks, sample_rate)
+            .or_else(|| self.comment("GUANO").and_then(GuanoMetadata::parse_text))
+            .or_else(|| {
+                self.comment("GUANO|Version")?;
+                let text: Vec<String> = self.comments.iter().map(|(k, v)| format!("{}:{}", k, v)).collect();
+                GuanoMetadata::parse_text(&text.join("\n"))
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// FlacDecoder: 在 Rust 端解碼 FLAC 文件
+///
+/// 與 WavDecoder 介面一致，解碼後的通道可直接傳給 WaveformEngine::load_channel。
+#[wasm_bindgen]
+pub struct FlacDecoder {
+    file: FlacFile,
+    error: Option<FlacError>,
+}
+
+#[wasm_bindgen]
+impl FlacDecoder {
+    /// 解碼 FLAC 文件
+    ///
+    /// # Arguments
+    /// * `bytes` - 文件內容 (Uint8Array)
+    #[wasm_bindgen(constructor)]
+    pub fn new(bytes: &[u8]) -> FlacDecoder {
+        match decode_flac(bytes) {
+            Ok(file) => FlacDecoder { file, error: None },
+            Err(err) => FlacDecoder {
+                file: FlacFile::default(),
+                error: Some(err),
+            },
+        }
+    }
+
+    /// 是否成功解碼
+    #[wasm_bindgen]
+    pub fn is_valid(&self) -> bool {
+        self.error.is_none()
+    }
+
+    /// 解碼失敗的原因（成功時為空字串）
+    #[wasm_bindgen]
+    pub fn get_error(&self) -> String {
+        self.error.as_ref().map(|e| e.to_string()).unwrap_or_default()
+    }
+
+    /// 獲取採樣率 (Hz)
+    #[wasm_bindgen]
+    pub fn get_sample_rate(&self) -> u32 {
+        self.file.audio.sample_rate
+    }
+
+    /// 獲取位深
+    #[wasm_bindgen]
+    pub fn get_bits_per_sample(&self) -> u16 {
+        self.file.audio.bits_per_sample
+    }
+
+    /// 獲取通道數量
+    #[wasm_bindgen]
+    pub fn get_num_channels(&self) -> usize {
+        self.file.audio.channels.len()
+    }
+
+    /// 獲取每個通道的樣本數
+    #[wasm_bindgen]
+    pub fn get_num_frames(&self) -> usize {
+        self.file.audio.num_frames()
+    }
+
+    /// 以靜音替代的損壞幀數量
+    #[wasm_bindgen]
+    pub fn get_corrupt_frames(&self) -> usize {
+        self.file.corrupt_frames
+    }
+
+    /// 讀取 Vorbis 註釋（鍵不分大小寫）
+    #[wasm_bindgen]
+    pub fn get_comment(&self, key: &str) -> Option<String> {
+        self.file.comment(key).map(|v| v.to_string())
+    }
+
+    /// 文件的 GUANO 元數據（來自保存的 WAV 塊或 Vorbis 註釋）
+    #[wasm_bindgen]
+    pub fn get_metadata(&self) -> GuanoMetadata {
+        self.file.metadata()
+    }
+
+    /// 獲取單個通道的樣本，可直接傳給 WaveformEngine::load_channel
+    ///
+    /// # Arguments
+    /// * `channel_idx` - 通道索引
+    ///
+    /// # Returns
+    /// Float32Array（索引無效時為空）
+    #[wasm_bindgen]
+    pub fn get_channel(&self, channel_idx: usize) -> Vec<f32> {
+        self.file.audio.channels.get(channel_idx).cloned().unwrap_or_default()
+    }
+
+    /// 釋放解碼後的樣本
+    #[wasm_bindgen]
+    pub fn release_memory(&mut self) {
+        self.file.audio.channels = Vec::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 測試文件由 tests/fixtures/flac/make_fixtures.py 產生：.flac 與對應的 .pcm（交錯的小端 i32 樣本）
+    const STEREO16: &[u8] = include_bytes!("../tests/fixtures/flac/stereo16.flac");
+    const STEREO16_PCM: &[u8] = include_bytes!("../tests/fixtures/flac/stereo16.pcm");
+    const STEREO24: &[u8] = include_bytes!("../tests/fixtures/flac/stereo24.flac");
+    const STEREO24_PCM: &[u8] = include_bytes!("../tests/fixtures/flac/stereo24.pcm");
+    const STEREO32: &[u8] = include_bytes!("../tests/fixtures/flac/stereo32.flac");
+    const STEREO32_PCM: &[u8] = include_bytes!("../tests/fixtures/flac/stereo32.pcm");
+    const CORRUPT_FRAME: &[u8] = include_bytes!("../tests/fixtures/flac/corrupt_frame.flac");
+    const CORRUPT_FRAME_PCM: &[u8] = include_bytes!("../tests/fixtures/flac/corrupt_frame.pcm");
+    const OVERFLOW_RESIDUAL: &[u8] = include_bytes!("../tests/fixtures/flac/overflow_residual.flac");
+    const OVERFLOW_RESIDUAL_PCM: &[u8] = include_bytes!("../tests/fixtures/flac/overflow_residual.pcm");
+
+    /// 解交錯 .pcm 並按位深換算為 f32（與解碼器相同的縮放）
+    fn expected_channels(pcm: &[u8], num_channels: usize, bits: u32) -> Vec<Vec<f32>> {
+        let scale = 1.0 / (1u64 << (bits - 1)) as f32;
+        let samples: Vec<i32> = pcm
+            .chunks_exact(4)
+            .map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+        (0..num_channels)
+            .map(|ch| samples.iter().skip(ch).step_by(num_channels).map(|&s| s as f32 * scale).collect())
+            .collect()
+    }
+
+    fn assert_decodes_to(bytes: &[u8], pcm: &[u8], num_channels: usize, bits: u32) -> FlacFile {
+        let file = decode_flac(bytes).unwrap();
+        let expected = expected_channels(pcm, num_channels, bits);
+        assert_eq!(file.audio.channels.len(), num_channels);
+        for (ch, (decoded, expected)) in file.audio.channels.iter().zip(&expected).enumerate() {
+            assert_eq!(decoded.len(), expected.len(), "channel {}", ch);
+            if let Some(i) = (0..decoded.len()).find(|&i| decoded[i] != expected[i]) {
+                panic!("channel {} sample {}: {} != {}", ch, i, decoded[i], expected[i]);
+            }
+        }
+        file
+    }
+
+    #[test]
+    fn decodes_16bit_stereo() {
+        // 幀依次為 側/右、左/側、獨立（常數 + wasted bits）、中/側 (LPC)、中/側（不完整的末幀）
+        let file = assert_decodes_to(STEREO16, STEREO16_PCM, 2, 16);
+        assert_eq!(file.corrupt_frames, 0);
+        assert_eq!(file.stream_info.sample_rate, 48000);
+        assert_eq!(file.stream_info.total_samples, 1124);
+        assert_eq!(file.audio.bits_per_sample, 16);
+        assert!(file.audio.channels[0][512..768].iter().all(|&s| s == -1234.0 / 32768.0));
+    }
+
+    #[test]
+    fn decodes_24bit_stereo() {
+        let file = assert_decodes_to(STEREO24, STEREO24_PCM, 2, 24);
+        assert_eq!(file.corrupt_frames, 0);
+        assert_eq!(file.stream_info.sample_rate, 384000);
+        assert_eq!(file.audio.bits_per_sample, 24);
+    }
+
+    #[test]
+    fn decodes_32bit_stereo_with_33bit_side_channel() {
+        let file = assert_decodes_to(STEREO32, STEREO32_PCM, 2, 32);
+        assert_eq!(file.corrupt_frames, 0);
+        assert_eq!(file.stream_info.sample_rate, 250000);
+        // 左右聲道相差約 4e9，側聲道需要 33 位
+        assert!(file.audio.channels[0].iter().all(|&s| s > 0.8));
+        assert!(file.audio.channels[1].iter().all(|&s| s < -0.8));
+    }
+
+    #[test]
+    fn corrupt_frame_becomes_silence() {
+        // 第 2 幀的 CRC-16 錯誤：以 256 個靜音樣本替代，之後的幀正常解碼
+        let file = assert_decodes_to(CORRUPT_FRAME, CORRUPT_FRAME_PCM, 1, 16);
+        assert_eq!(file.corrupt_frames, 1);
+        assert!(file.audio.channels[0][256..512].iter().all(|&s| s == 0.0));
+        assert!(file.audio.channels[0][512..].iter().any(|&s| s != 0.0));
+    }
+
+    #[test]
+    fn overflowing_residual_becomes_silence() {
+        // 第 2、3 幀的 CRC 正確，但 FIXED / LPC 預測會溢出 i64：視為損壞而非 panic
+        let file = assert_decodes_to(OVERFLOW_RESIDUAL, OVERFLOW_RESIDUAL_PCM, 1, 32);
+        assert_eq!(file.corrupt_frames, 2);
+        assert!(file.audio.channels[0][4096..12288].iter().all(|&s| s == 0.0));
+    }
+
+    #[test]
+    fn reads_vorbis_comments() {
+        let file = decode_flac(STEREO16).unwrap();
+        assert_eq!(file.vendor, "fixture encoder");
+        assert_eq!(file.comments.len(), 2);
+        assert_eq!(file.comment("title"), Some("Sixteen"));
+        assert_eq!(file.comment("ARTIST"), Some("Someone"));
+        assert_eq!(file.comment("ALBUM"), None);
+    }
+
+    #[test]
+    fn reads_riff_application_chunks() {
+        let file = decode_flac(STEREO16).unwrap();
+        let ids: Vec<&[u8; 4]> = file.riff_chunks.iter().map(|c| &c.id).collect();
+        assert_eq!(ids, [b"fmt ", b"guan", b"LIST"]);
+
+        let guano = file.metadata();
+        assert_eq!(guano.get("Make").as_deref(), Some("Test"));
+        assert_eq!(guano.get("Species Auto ID").as_deref(), Some("Pipkuh"));
+        assert_eq!(guano.get_timestamp(), "2024-05-01T21:00:00");
+    }
+
+    #[test]
+    fn reads_guano_from_vorbis_comments() {
+        let file = decode_flac(STEREO24).unwrap();
+        assert!(file.riff_chunks.is_empty());
+        let guano = file.metadata();
+        assert_eq!(guano.get_version(), "1.0");
+        assert_eq!(guano.get("Make").as_deref(), Some("Test"));
+        assert_eq!(guano.get_te(), 10.0);
+    }
+
+    #[test]
+    fn oversized_metadata_lengths_do_not_panic() {
+        let mut vorbis = Vec::new();
+        vorbis.extend_from_slice(&u32::MAX.to_le_bytes());
+        vorbis.extend_from_slice(b"vendor");
+        assert_eq!(parse_vorbis_comment(&vorbis).0, "vendor");
+
+        let mut vorbis = 0u32.to_le_bytes().to_vec();
+        vorbis.extend_from_slice(&1u32.to_le_bytes());
+        vorbis.extend_from_slice(&u32::MAX.to_le_bytes());
+        vorbis.extend_from_slice(b"KEY=value");
+        assert_eq!(parse_vorbis_comment(&vorbis).1, [("KEY".to_string(), "value".to_string())]);
+
+        let mut riff = b"guan".to_vec();
+        riff.extend_from_slice(&u32::MAX.to_le_bytes());
+        riff.extend_from_slice(b"abc");
+        let mut chunks = Vec::new();
+        parse_riff_application(&riff, &mut chunks);
+        assert_eq!(chunks, [RiffChunk::new(b"guan", b"abc".to_vec())]);
+    }
+
+    #[test]
+    fn rejects_non_flac() {
+        assert_eq!(decode_flac(b"RIFF\0\0\0\0WAVE").unwrap_err(), FlacError::NotFlac);
+        assert_eq!(decode_flac(b"fLaC").unwrap_err(), FlacError::MissingStreamInfo);
+    }
+}
@@ -0,0 +1,134 @@
+// ============================================================
+// 分頻 (frequency division) 音頻渲染
+// 模擬傳統分頻式蝙蝠偵測器：施密特觸發器計算零交叉，
+// 每 N 個週期輸出一個方波週期，並以包絡保留幅度資訊
+// ============================================================
+
+use wasm_bindgen::prelude::*;
+
+/// 包絡跟隨器的起音時間 (秒)
+const ENVELOPE_ATTACK_SEC: f32 = 0.0001;
+/// 包絡跟隨器的釋放時間 (秒)
+const ENVELOPE_RELEASE_SEC: f32 = 0.002;
+
+/// 分頻渲染核心
+///
+/// # Arguments
+/// * `audio_data` - 音頻樣本
+/// * `sample_rate` - 採樣率 (Hz)
+/// * `division` - 分頻比 N（輸出頻率 = 輸入頻率 / N）
+/// * `hysteresis` - 施密特觸發器的門限（絕對幅度），低於此值的信號不觸發
+///
+/// # Returns
+/// 與輸入等長的方波渲染（幅度跟隨輸入包絡）
+pub fn frequency_division(audio_data: &[f32], sample_rate: f32, division: usize, hysteresis: f32) -> Vec<f32> {
+    if audio_data.is_empty() || sample_rate <= 0.0 {
+        return Vec::new();
+    }
+
+    let division = division.max(1);
+    let hysteresis = hysteresis.abs();
+    let attack = 1.0 - (-1.0 / (ENVELOPE_ATTACK_SEC * sample_rate)).exp();
+    let release = 1.0 - (-1.0 / (ENVELOPE_RELEASE_SEC * sample_rate)).exp();
+
+    // 施密特觸發器狀態：true = 上次越過正門限
+    let mut positive = false;
+    // 半週期計數，每 N 個半週期翻轉一次輸出 (週期 = N 個輸入週期)
+    let mut half_cycles = 0usize;
+    let mut level = 1.0f32;
+    let mut envelope = 0.0f32;
+
+    let mut output = Vec::with_capacity(audio_data.len());
+    for &x in audio_data {
+        let crossed = if !positive && x > hysteresis {
+            positive = true;
+            true
+        } else if positive && x < -hysteresis {
+            positive = false;
+            true
+        } else {
+            false
+        };
+
+        if crossed {
+            half_cycles += 1;
+            if half_cycles >= division {
+                half_cycles = 0;
+                level = -level;
+            }
+        }
+
+        let rectified = x.abs();
+        let coeff = if rectified > envelope { attack } else { release };
+        envelope += coeff * (rectified - envelope);
+
+        output.push(level * envelope);
+    }
+
+    output
+}
+
+/// 渲染分頻偵測器的聲音
+///
+/// # Arguments
+/// * `audio_data` - 音頻數據 (Float32Array)
+/// * `sample_rate` - 採樣率 (Hz)
+/// * `division` - 分頻比（典型值: 10）
+/// * `hysteresis` - 觸發門限（絕對幅度，典型值: 0.01）
+///
+/// # Returns
+/// 與輸入等長、相同採樣率的方波渲染 (Float32Array)
+#[wasm_bindgen]
+pub fn render_frequency_division(audio_data: &[f32], sample_rate: f32, division: usize, hysteresis: f32) -> Vec<f32> {
+    frequency_division(audio_data, sample_rate, division, hysteresis)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: f32 = 256_000.0;
+
+    fn tone(frequency: f32, amplitude: f32, len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|i| amplitude * (2.0 * std::f32::consts::PI * frequency * i as f32 / RATE).sin())
+            .collect()
+    }
+
+    /// 以輸出方波的翻轉次數估計頻率
+    fn square_freq(output: &[f32]) -> f32 {
+        let flips = output.windows(2).filter(|w| (w[0] < 0.0) != (w[1] < 0.0)).count();
+        flips as f32 / 2.0 / (output.len() as f32 / RATE)
+    }
+
+    #[test]
+    fn divides_tone_frequency() {
+        let input = tone(40_000.0, 0.5, 25_600);
+        for division in [1, 4, 10, 16] {
+            let output = frequency_division(&input, RATE, division, 0.01);
+            assert_eq!(output.len(), input.len());
+            let expected = 40_000.0 / division as f32;
+            assert!((square_freq(&output) - expected).abs() < expected * 0.02, "N={} f={}", division, square_freq(&output));
+            // 幅度跟隨包絡，不超過輸入峰值
+            assert!(output.iter().all(|x| x.abs() <= 0.5));
+        }
+        // 分頻比 0 視為 1
+        assert_eq!(frequency_division(&input, RATE, 0, 0.01), frequency_division(&input, RATE, 1, 0.01));
+    }
+
+    #[test]
+    fn hysteresis_ignores_weak_signal() {
+        // 幅度低於門限時不觸發翻轉，輸出只有包絡
+        let output = frequency_division(&tone(40_000.0, 0.005, 2_560), RATE, 10, 0.01);
+        assert_eq!(output.len(), 2_560);
+        assert!(output.iter().all(|&x| x >= 0.0));
+        assert!(output.iter().all(|&x| x <= 0.005));
+    }
+
+    #[test]
+    fn invalid_input() {
+        assert!(frequency_division(&[], RATE, 10, 0.01).is_empty());
+        assert!(frequency_division(&[0.1; 16], 0.0, 10, 0.01).is_empty());
+        assert!(render_frequency_division(&[], RATE, 10, 0.01).is_empty());
+    }
+}
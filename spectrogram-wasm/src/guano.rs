@@ -0,0 +1,613 @@
+// ============================================================
+// GUANO v1.0 元數據解析與寫入
+// 支援命名空間鍵、常用欄位的類型化存取，以及寫回 WAV 文件的 `guan` 塊
+// ============================================================
+
+use wasm_bindgen::prelude::*;
+
+use crate::audiomoth::AudioMothMetadata;
+use crate::timestamp::Timestamp;
+use crate::wamd::{WamdMetadata, WAMD_CHUNK_ID};
+use crate::wav::{parse_info_list, parse_wav, WavError};
+use crate::wav_writer::{write_riff, RiffChunk};
+
+/// GUANO 塊的 RIFF ID
+pub const GUANO_CHUNK_ID: [u8; 4] = *b"guan";
+/// 本實現寫出的 GUANO 版本
+pub const GUANO_VERSION: &str = "1.0";
+/// 預設命名空間（無前綴的鍵）
+const DEFAULT_NAMESPACE: &str = "";
+/// GUANO 自身的命名空間（僅用於 Version）
+const GUANO_NAMESPACE: &str = "GUANO";
+
+/// 單個 GUANO 欄位
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GuanoField {
+    /// 命名空間（預設命名空間為空字串）
+    pub namespace: String,
+    pub key: String,
+    pub value: String,
+}
+
+impl GuanoField {
+    /// 完整鍵名（例如 "WA|Serial" 或 "Make"）
+    pub fn full_key(&self) -> String {
+        if self.namespace.is_empty() {
+            self.key.clone()
+        } else {
+            format!("{}|{}", self.namespace, self.key)
+        }
+    }
+}
+
+/// 將完整鍵名拆分為 (命名空間, 鍵)
+fn split_key(full_key: &str) -> (&str, &str) {
+    match full_key.split_once('|') {
+        Some((namespace, key)) => (namespace.trim(), key.trim()),
+        None => (DEFAULT_NAMESPACE, full_key.trim()),
+    }
+}
+
+/// 逗號分隔的列表欄位（Species Auto ID、Species Manual ID、Tags）
+fn parse_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string())
+        .collect()
+}
+
+/// GuanoMetadata: GUANO 元數據
+///
+/// 欄位按文件中的順序保存，未知命名空間與鍵原樣保留，
+/// 因此解析後再序列化不會丟失任何資訊。
+#[wasm_bindgen]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GuanoMetadata {
+    version: String,
+    fields: Vec<GuanoField>,
+}
+
+#[wasm_bindgen]
+impl GuanoMetadata {
+    /// 創建空的 GUANO 元數據（版本 1.0）
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> GuanoMetadata {
+        GuanoMetadata {
+            version: GUANO_VERSION.to_string(),
+            fields: Vec::new(),
+        }
+    }
+
+    /// 從 WAV 文件中讀取 GUANO 元數據
+    ///
+    /// 沒有 GUANO 塊時，依次改用 Wildlife Acoustics 的 `wamd` 塊、
+    /// AudioMoth 的 ICMT 註釋轉換得到的欄位。
+    ///
+    /// # Arguments
+    /// * `bytes` - WAV 文件內容 (Uint8Array)
+    ///
+    /// # Returns
+    /// 元數據；以上皆無時返回空的元數據（`has_metadata()` 為 false）
+    #[wasm_bindgen]
+    pub fn from_wav(bytes: &[u8]) -> GuanoMetadata {
+        let Ok(info) = parse_wav(bytes) else {
+            return GuanoMetadata::default();
+        };
+        let chunks: Vec<([u8; 4], &[u8])> = info
+            .chunks
+            .iter()
+            .filter(|c| &c.id != b"data")
+            .map(|c| (c.id, info.chunk_data(bytes, c)))
+            .collect();
+        Self::from_riff_chunks(&chunks, info.format.sample_rate).unwrap_or_default()
+    }
+
+    /// 從 GUANO 文本解析
+    ///
+    /// # Arguments
+    /// * `text` - GUANO 塊的文本內容
+    #[wasm_bindgen]
+    pub fn parse(text: &str) -> GuanoMetadata {
+        Self::parse_text(text).unwrap_or_default()
+    }
+
+    /// 是否包含 GUANO 元數據
+    #[wasm_bindgen]
+    pub fn has_metadata(&self) -> bool {
+        !self.version.is_empty()
+    }
+
+    /// GUANO 版本
+    #[wasm_bindgen]
+    pub fn get_version(&self) -> String {
+        self.version.clone()
+    }
+
+    /// 讀取欄位值
+    ///
+    /// # Arguments
+    /// * `key` - 完整鍵名（例如 "Make"、"WA|Serial"）
+    #[wasm_bindgen]
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.field(key).map(|f| f.value.clone())
+    }
+
+    /// 設置欄位值（已存在時原地更新，否則追加到末尾）
+    ///
+    /// # Arguments
+    /// * `key` - 完整鍵名
+    /// * `value` - 值（換行會在序列化時轉義）
+    #[wasm_bindgen]
+    pub fn set(&mut self, key: &str, value: &str) {
+        let (namespace, name) = split_key(key);
+        if name.is_empty() {
+            return;
+        }
+        if namespace == GUANO_NAMESPACE && name == "Version" {
+            self.version = value.trim().to_string();
+            return;
+        }
+        if self.version.is_empty() {
+            self.version = GUANO_VERSION.to_string();
+        }
+        match self.fields.iter_mut().find(|f| f.namespace == namespace && f.key == name) {
+            Some(field) => field.value = value.to_string(),
+            None => self.fields.push(GuanoField {
+                namespace: namespace.to_string(),
+                key: name.to_string(),
+                value: value.to_string(),
+            }),
+        }
+    }
+
+    /// 刪除欄位
+    ///
+    /// # Returns
+    /// 欄位是否存在
+    #[wasm_bindgen]
+    pub fn remove(&mut self, key: &str) -> bool {
+        let (namespace, name) = split_key(key);
+        let before = self.fields.len();
+        self.fields.retain(|f| !(f.namespace == namespace && f.key == name));
+        self.fields.len() != before
+    }
+
+    /// 所有欄位的完整鍵名，以換行分隔（按文件順序，不含 GUANO|Version）
+    #[wasm_bindgen]
+    pub fn get_keys(&self) -> String {
+        self.fields.iter().map(|f| f.full_key()).collect::<Vec<_>>().join("\n")
+    }
+
+    /// 序列化為 GUANO 文本
+    #[wasm_bindgen]
+    pub fn to_text(&self) -> String {
+        let version = if self.version.is_empty() { GUANO_VERSION } else { &self.version };
+        let mut text = format!("{}|Version:{}", GUANO_NAMESPACE, version);
+        for field in &self.fields {
+            // 多行值（例如 Note）以字面 "\n" 轉義
+            let value = field.value.replace("\r\n", "\\n").replace('\n', "\\n");
+            text.push('\n');
+            text.push_str(&field.full_key());
+            text.push(':');
+            text.push_str(&value);
+        }
+        text
+    }
+
+    /// 錄音時間的 ISO 8601 字串（無或無效時為空字串）
+    #[wasm_bindgen]
+    pub fn get_timestamp(&self) -> String {
+        self.timestamp().map(|ts| ts.to_string()).unwrap_or_default()
+    }
+
+    /// 錄音時間的 Unix 時間 (秒)；無時區資訊時返回 None
+    #[wasm_bindgen]
+    pub fn get_timestamp_unix(&self) -> Option<f64> {
+        self.timestamp().and_then(|ts| ts.unix_seconds())
+    }
+
+    /// 錄音時間的 UTC 偏移 (分鐘)；無時區資訊時返回 None
+    #[wasm_bindgen]
+    pub fn get_utc_offset_minutes(&self) -> Option<i32> {
+        self.timestamp().and_then(|ts| ts.utc_offset_minutes)
+    }
+
+    /// 設置錄音時間
+    ///
+    /// # Arguments
+    /// * `iso` - ISO 8601 字串
+    ///
+    /// # Returns
+    /// 字串是否有效（無效時不修改）
+    #[wasm_bindgen]
+    pub fn set_timestamp(&mut self, iso: &str) -> bool {
+        match Timestamp::parse(iso) {
+            Some(ts) => {
+                self.set_timestamp_value(&ts);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 緯度 (度)
+    #[wasm_bindgen]
+    pub fn get_latitude(&self) -> Option<f64> {
+        self.loc_position().map(|p| p.0)
+    }
+
+    /// 經度 (度)
+    #[wasm_bindgen]
+    pub fn get_longitude(&self) -> Option<f64> {
+        self.loc_position().map(|p| p.1)
+    }
+
+    /// 設置錄音位置 (Loc Position)
+    #[wasm_bindgen]
+    pub fn set_location(&mut self, latitude: f64, longitude: f64) {
+        self.set("Loc Position", &format!("{} {}", latitude, longitude));
+    }
+
+    /// 採樣率 (Hz)
+    #[wasm_bindgen]
+    pub fn get_samplerate(&self) -> Option<u32> {
+        self.get_trimmed("Samplerate").and_then(|v| v.parse::<f64>().ok()).map(|v| v.round() as u32)
+    }
+
+    /// 時間擴展因子（未記錄時為 1）
+    #[wasm_bindgen]
+    pub fn get_te(&self) -> f64 {
+        self.get_trimmed("TE")
+            .and_then(|v| v.parse::<f64>().ok())
+            .filter(|te| *te > 0.0)
+            .unwrap_or(1.0)
+    }
+
+    /// 自動識別的物種代碼，以逗號分隔
+    #[wasm_bindgen]
+    pub fn get_species_auto_id(&self) -> String {
+        self.species_auto_id().join(",")
+    }
+
+    /// 人工識別的物種代碼，以逗號分隔
+    #[wasm_bindgen]
+    pub fn get_species_manual_id(&self) -> String {
+        self.species_manual_id().join(",")
+    }
+
+    /// 設置人工識別的物種代碼
+    ///
+    /// # Arguments
+    /// * `species` - 以逗號分隔的物種代碼；空字串表示刪除此欄位
+    #[wasm_bindgen]
+    pub fn set_species_manual_id(&mut self, species: &str) {
+        let list = parse_list(species);
+        if list.is_empty() {
+            self.remove("Species Manual ID");
+        } else {
+            self.set("Species Manual ID", &list.join(", "));
+        }
+    }
+
+    /// 將元數據寫入 WAV 文件
+    ///
+    /// # Arguments
+    /// * `bytes` - 原始 WAV 文件內容 (Uint8Array)
+    ///
+    /// # Returns
+    /// 新的 WAV 文件 (Uint8Array)；原文件無效時返回空數組
+    #[wasm_bindgen]
+    pub fn write_to_wav(&self, bytes: &[u8]) -> Vec<u8> {
+        self.write_wav(bytes).unwrap_or_default()
+    }
+}
+
+impl GuanoMetadata {
+    /// 解析 GUANO 文本
+    ///
+    /// 容忍 CRLF 換行、塊尾部的 NUL / 空白填充，以及冒號周圍的空白。
+    /// 文本不以 GUANO 版本行開頭時返回 None。
+    pub fn parse_text(text: &str) -> Option<GuanoMetadata> {
+        let text = text.trim_end_matches(|c: char| c == '\0' || c.is_whitespace());
+        let mut metadata = GuanoMetadata::default();
+        let mut found_version = false;
+
+        for line in text.split('\n') {
+            let line = line.trim_end_matches('\r');
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let (namespace, name) = split_key(key);
+            if name.is_empty() {
+                continue;
+            }
+            let value = value.trim().replace("\\n", "\n");
+
+            if namespace == GUANO_NAMESPACE && name == "Version" {
+                metadata.version = value;
+                found_version = true;
+                continue;
+            }
+            metadata.fields.push(GuanoField {
+                namespace: namespace.to_string(),
+                key: name.to_string(),
+                value,
+            });
+        }
+
+        if found_version {
+            Some(metadata)
+        } else {
+            None
+        }
+    }
+
+    /// 從 WAV 文件中讀取 GUANO 元數據（只讀取 GUANO，不轉換其他格式）
+    pub fn read_from_wav(bytes: &[u8]) -> Option<GuanoMetadata> {
+        let info = parse_wav(bytes).ok()?;
+        let chunks: Vec<([u8; 4], &[u8])> = info
+            .chunks
+            .iter()
+            .filter(|c| &c.id != b"data")
+            .map(|c| (c.id, info.chunk_data(bytes, c)))
+            .collect();
+        Self::find_guano_chunk(&chunks)
+    }
+
+    /// 在 RIFF 塊中尋找 GUANO
+    ///
+    /// 優先使用 `guan` 塊；找不到時再掃描其他以 "GUANO|Version" 開頭的塊。
+    fn find_guano_chunk(chunks: &[([u8; 4], &[u8])]) -> Option<GuanoMetadata> {
+        chunks
+            .iter()
+            .filter(|(id, _)| *id == GUANO_CHUNK_ID)
+            .chain(chunks.iter().filter(|(id, _)| *id != GUANO_CHUNK_ID))
+            .filter(|(_, content)| content.starts_with(b"GUANO|Version"))
+            .find_map(|(_, content)| Self::parse_text(&String::from_utf8_lossy(content)))
+    }
+
+    /// 從 RIFF 塊（WAV 文件或 FLAC 保存的原 WAV 塊）中取得元數據
+    ///
+    /// 依次嘗試 GUANO、Wildlife Acoustics `wamd`、AudioMoth 的 LIST/INFO 註釋。
+    ///
+    /// # Arguments
+    /// * `chunks` - (塊 ID, 內容) 列表
+    /// * `sample_rate` - 文件的採樣率 (Hz)，用於轉換後的 Samplerate 欄位
+    pub fn from_riff_chunks(chunks: &[([u8; 4], &[u8])], sample_rate: u32) -> Option<GuanoMetadata> {
+        Self::find_guano_chunk(chunks)
+            .or_else(|| {
+                chunks
+                    .iter()
+                    .find(|(id, _)| *id == WAMD_CHUNK_ID)
+                    .map(|(_, content)| WamdMetadata::parse(content).to_guano(sample_rate))
+            })
+            .or_else(|| {
+                let entries: Vec<([u8; 4], String)> = chunks
+                    .iter()
+                    .filter(|(id, _)| id == b"LIST")
+                    .flat_map(|(_, content)| parse_info_list(content))
+                    .collect();
+                AudioMothMetadata::from_info_entries(&entries).map(|moth| moth.to_guano(sample_rate))
+            })
+    }
+
+    /// 將元數據寫入 WAV 文件：替換原有的 `guan` 塊，沒有時追加到文件末尾
+    pub fn write_wav(&self, bytes: &[u8]) -> Result<Vec<u8>, WavError> {
+        let info = parse_wav(bytes)?;
+        let mut chunks: Vec<RiffChunk> = Vec::with_capacity(info.chunks.len() + 1);
+        let mut replaced = false;
+
+        for chunk in &info.chunks {
+            if chunk.id == GUANO_CHUNK_ID {
+                // 只保留一個 GUANO 塊
+                if !replaced {
+                    chunks.push(self.to_chunk());
+                    replaced = true;
+                }
+            } else {
+                chunks.push(RiffChunk {
+                    id: chunk.id,
+                    data: info.chunk_data(bytes, chunk).to_vec(),
+                });
+            }
+        }
+        if !replaced {
+            chunks.push(self.to_chunk());
+        }
+
+        Ok(write_riff(&chunks))
+    }
+
+    /// 序列化為 `guan` 塊
+    pub fn to_chunk(&self) -> RiffChunk {
+        RiffChunk::new(&GUANO_CHUNK_ID, self.to_text().into_bytes())
+    }
+
+    /// 所有欄位（不含 GUANO|Version）
+    pub fn fields(&self) -> &[GuanoField] {
+        &self.fields
+    }
+
+    /// 按完整鍵名查找欄位
+    pub fn field(&self, key: &str) -> Option<&GuanoField> {
+        let (namespace, name) = split_key(key);
+        self.fields.iter().find(|f| f.namespace == namespace && f.key == name)
+    }
+
+    /// 指定命名空間的所有欄位
+    pub fn namespace_fields<'a>(&'a self, namespace: &'a str) -> impl Iterator<Item = &'a GuanoField> + 'a {
+        self.fields.iter().filter(move |f| f.namespace == namespace)
+    }
+
+    fn get_trimmed(&self, key: &str) -> Option<&str> {
+        self.field(key).map(|f| f.value.trim()).filter(|v| !v.is_empty())
+    }
+
+    /// 錄音開始時間
+    pub fn timestamp(&self) -> Option<Timestamp> {
+        self.get_trimmed("Timestamp").and_then(Timestamp::parse)
+    }
+
+    /// 設置錄音開始時間
+    pub fn set_timestamp_value(&mut self, timestamp: &Timestamp) {
+        self.set("Timestamp", &timestamp.to_string());
+    }
+
+    /// 將錄音時間平移指定秒數（用於裁剪後的片段）
+    ///
+    /// # Returns
+    /// 是否有可平移的時間戳
+    pub fn shift_timestamp(&mut self, offset_seconds: f64) -> bool {
+        match self.timestamp() {
+            Some(ts) => {
+                self.set_timestamp_value(&ts.add_seconds(offset_seconds));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 錄音位置 (緯度, 經度)
+    pub fn loc_position(&self) -> Option<(f64, f64)> {
+        let value = self.get_trimmed("Loc Position")?;
+        let mut parts = value.split(|c: char| c.is_whitespace() || c == ',').filter(|s| !s.is_empty());
+        let latitude = parts.next()?.parse::<f64>().ok()?;
+        let longitude = parts.next()?.parse::<f64>().ok()?;
+        if latitude.abs() > 90.0 || longitude.abs() > 180.0 {
+            return None;
+        }
+        Some((latitude, longitude))
+    }
+
+    /// 自動識別的物種代碼
+    pub fn species_auto_id(&self) -> Vec<String> {
+        self.get_trimmed("Species Auto ID").map(parse_list).unwrap_or_default()
+    }
+
+    /// 人工識別的物種代碼
+    pub fn species_manual_id(&self) -> Vec<String> {
+        self.get_trimmed("Species Manual ID").map(parse_list).unwrap_or_default()
+    }
+
+    /// 標籤
+    pub fn tags(&self) -> Vec<String> {
+        self.get_trimmed("Tags").map(parse_list).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "GUANO|Version:1.0\r\n\
+        Make: Pettersson\n\
+        Note:first line\\nsecond line\n\
+        XYZ|Custom Key:kept as is\n\
+        Timestamp:2024-05-01T21:00:00+08:00\n\
+        Species Auto ID:Myodau, Pippip ,\n\
+        not a field\n\0\0 ";
+
+    fn wamd_subchunk(id: u16, data: &[u8]) -> Vec<u8> {
+        let mut bytes = id.to_le_bytes().to_vec();
+        bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(data);
+        bytes
+    }
+
+    fn info_list(comment: &str) -> Vec<u8> {
+        let mut bytes = b"INFOICMT".to_vec();
+        bytes.extend_from_slice(&(comment.len() as u32 + 1).to_le_bytes());
+        bytes.extend_from_slice(comment.as_bytes());
+        bytes.push(0);
+        if bytes.len() % 2 == 1 {
+            bytes.push(0);
+        }
+        bytes
+    }
+
+    #[test]
+    fn parses_text() {
+        let metadata = GuanoMetadata::parse_text(TEXT).unwrap();
+        assert_eq!(metadata.get_version(), "1.0");
+        assert_eq!(metadata.get_keys(), "Make\nNote\nXYZ|Custom Key\nTimestamp\nSpecies Auto ID");
+        assert_eq!(metadata.get("Make").as_deref(), Some("Pettersson"));
+        assert_eq!(metadata.get("Note").as_deref(), Some("first line\nsecond line"));
+        assert_eq!(metadata.namespace_fields("XYZ").count(), 1);
+        assert_eq!(metadata.get("XYZ | Custom Key").as_deref(), Some("kept as is"));
+        assert_eq!(metadata.species_auto_id(), vec!["Myodau", "Pippip"]);
+        assert_eq!(metadata.get_utc_offset_minutes(), Some(480));
+
+        // 沒有版本行時不是 GUANO
+        assert!(GuanoMetadata::parse_text("Make:Pettersson").is_none());
+        assert!(!GuanoMetadata::parse("").has_metadata());
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let metadata = GuanoMetadata::parse_text(TEXT).unwrap();
+        let text = metadata.to_text();
+        assert!(text.starts_with("GUANO|Version:1.0\n"));
+        assert!(text.contains("\nNote:first line\\nsecond line\n"));
+        assert!(text.contains("\nXYZ|Custom Key:kept as is\n"));
+        assert_eq!(GuanoMetadata::parse_text(&text), Some(metadata));
+
+        // CRLF 換行的值也轉義為單個 \n
+        let mut metadata = GuanoMetadata::new();
+        metadata.set("Note", "a\r\nb\nc");
+        let parsed = GuanoMetadata::parse_text(&metadata.to_text()).unwrap();
+        assert_eq!(parsed.get("Note").as_deref(), Some("a\nb\nc"));
+    }
+
+    #[test]
+    fn set_and_remove_fields() {
+        let mut metadata = GuanoMetadata::parse_text(TEXT).unwrap();
+        metadata.set("Make", "Wildlife Acoustics, Inc.");
+        metadata.set("WA|Serial", "S4U12345");
+        metadata.set("GUANO|Version", "1.1");
+        assert_eq!(metadata.get_keys().lines().next(), Some("Make"));
+        assert_eq!(metadata.get_keys().lines().last(), Some("WA|Serial"));
+        assert_eq!(metadata.get_version(), "1.1");
+        assert!(metadata.remove("XYZ|Custom Key"));
+        assert!(!metadata.remove("XYZ|Custom Key"));
+
+        assert!(metadata.shift_timestamp(3.5));
+        assert_eq!(metadata.get_timestamp(), "2024-05-01T21:00:03.5+08:00");
+        assert!(!metadata.set_timestamp("yesterday"));
+    }
+
+    #[test]
+    fn riff_chunk_fallback_order() {
+        let guano = GuanoMetadata::parse_text(TEXT).unwrap().to_text().into_bytes();
+        let wamd = wamd_subchunk(0x01, b"SM4BAT-FS\0");
+        let info = info_list("Recorded at 21:00:00 01/05/2024 (UTC+8) by AudioMoth 24F3190360FFAB12 at medium gain.");
+
+        // GUANO 優先於 wamd 與 AudioMoth
+        let chunks: Vec<([u8; 4], &[u8])> = vec![(*b"LIST", &info), (WAMD_CHUNK_ID, &wamd), (GUANO_CHUNK_ID, &guano)];
+        let metadata = GuanoMetadata::from_riff_chunks(&chunks, 384_000).unwrap();
+        assert_eq!(metadata.get("Make").as_deref(), Some("Pettersson"));
+        assert_eq!(metadata.get("Samplerate"), None);
+
+        // 存放在其他塊 ID 中的 GUANO 文本也能找到
+        let chunks: Vec<([u8; 4], &[u8])> = vec![(WAMD_CHUNK_ID, &wamd), (*b"note", &guano)];
+        let metadata = GuanoMetadata::from_riff_chunks(&chunks, 384_000).unwrap();
+        assert_eq!(metadata.get("Make").as_deref(), Some("Pettersson"));
+
+        // 其次是 wamd
+        let chunks: Vec<([u8; 4], &[u8])> = vec![(*b"LIST", &info), (WAMD_CHUNK_ID, &wamd)];
+        let metadata = GuanoMetadata::from_riff_chunks(&chunks, 384_000).unwrap();
+        assert_eq!(metadata.get("Make").as_deref(), Some("Wildlife Acoustics, Inc."));
+        assert_eq!(metadata.get("Model").as_deref(), Some("SM4BAT-FS"));
+        assert_eq!(metadata.get_samplerate(), Some(384_000));
+
+        // 最後是 AudioMoth 註釋
+        let chunks: Vec<([u8; 4], &[u8])> = vec![(*b"LIST", &info)];
+        let metadata = GuanoMetadata::from_riff_chunks(&chunks, 250_000).unwrap();
+        assert_eq!(metadata.get("Make").as_deref(), Some("Open Acoustic Devices"));
+        assert_eq!(metadata.get_timestamp(), "2024-05-01T21:00:00+08:00");
+
+        let chunks: Vec<([u8; 4], &[u8])> = vec![(*b"LIST", b"INFO")];
+        assert!(GuanoMetadata::from_riff_chunks(&chunks, 250_000).is_none());
+    }
+}
@@ -0,0 +1,265 @@
+// ============================================================
+// 諧波結構分析
+// 沿叫聲輪廓在各整數倍頻率處量測功率，找出基頻、能量最強的諧波
+// 與各諧波的相對強度，供自動辨識 (autoid) 的 harmonic 條件使用
+// ============================================================
+
+use crate::ridge::ContourPoint;
+
+/// 諧波分析配置
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HarmonicConfig {
+    /// 分析的諧波數（包含基頻）
+    pub max_harmonics: usize,
+    /// 在 k * f0 附近搜索的相對寬度（例如 0.03 表示 ±3%，至少一個頻率箱）
+    pub tolerance_ratio: f64,
+    /// 諧波平均功率高出同幀中位數功率至少此值 (dB) 才視為存在
+    pub min_snr_db: f32,
+}
+
+impl Default for HarmonicConfig {
+    fn default() -> Self {
+        HarmonicConfig {
+            max_harmonics: 4,
+            tolerance_ratio: 0.03,
+            min_snr_db: 10.0,
+        }
+    }
+}
+
+/// 諧波分析結果
+#[derive(Debug, Clone, PartialEq)]
+pub struct HarmonicAnalysis {
+    /// 基頻 (Hz)，取輪廓各點推算基頻的中位數
+    pub fundamental_hz: f64,
+    /// 輪廓所在的諧波（0 表示輪廓即基頻，1 表示第二諧波，依此類推）
+    pub contour_harmonic: usize,
+    /// 能量最強的諧波（0 表示基頻，與 autoid 的 harmonic 欄位相同）
+    pub dominant_harmonic: usize,
+    /// 各諧波相對最強諧波的平均功率 (dB)；超出頻譜範圍或不存在時為 -Infinity
+    pub relative_levels_db: Vec<f32>,
+    /// 存在的諧波數
+    pub num_present: usize,
+}
+
+/// 沿輪廓量測 ratio * 輪廓頻率 處的平均功率與平均中位數功率
+///
+/// # Returns
+/// (平均功率 dB, 平均中位數功率 dB)；所有點都超出頻譜範圍時為 None
+fn level_along_contour(
+    power_db: &[f32],
+    freqs_hz: &[f64],
+    contour: &[ContourPoint],
+    medians_db: &[f32],
+    ratio: f64,
+    tolerance_ratio: f64,
+) -> Option<(f32, f32)> {
+    let num_bins = freqs_hz.len();
+    let freq_resolution = if num_bins > 1 { freqs_hz[1] - freqs_hz[0] } else { 1.0 };
+    let (min_hz, max_hz) = (freqs_hz[0], freqs_hz[num_bins - 1]);
+
+    let (mut level_sum, mut median_sum, mut count) = (0.0f64, 0.0f64, 0usize);
+    for (point, &median) in contour.iter().zip(medians_db) {
+        let target = point.freq_hz * ratio;
+        let half_width = (target * tolerance_ratio).max(freq_resolution);
+        if target - half_width < min_hz || target + half_width > max_hz {
+            continue;
+        }
+        let lo = ((target - half_width - min_hz) / freq_resolution).ceil() as usize;
+        let hi = (((target + half_width - min_hz) / freq_resolution).floor() as usize).min(num_bins - 1);
+        let frame = &power_db[point.frame * num_bins..(point.frame + 1) * num_bins];
+        let peak = frame[lo..=hi].iter().fold(f32::NEG_INFINITY, |acc, &p| acc.max(p));
+        level_sum += 10f64.powf(peak as f64 / 10.0);
+        median_sum += 10f64.powf(median as f64 / 10.0);
+        count += 1;
+    }
+    if count == 0 {
+        return None;
+    }
+    let to_db = |sum: f64| (10.0 * (sum / count as f64).max(1e-16).log10()) as f32;
+    Some((to_db(level_sum), to_db(median_sum)))
+}
+
+/// 分析叫聲的諧波結構
+///
+/// 輪廓通常追蹤能量最強的分量，未必是基頻：依次假設輪廓為第 m 諧波
+/// (m = max_harmonics .. 1)，取輪廓頻率 / m 處存在信號的最大 m 作為基頻假設，
+/// 再量測 k * f0 (k = 1 .. max_harmonics) 的平均功率。
+///
+/// # Arguments
+/// * `power_db` - 功率 (dB)，幀優先排列，每幀 freqs_hz.len() 個頻率箱；頻率範圍應涵蓋各諧波
+/// * `freqs_hz` - 每個頻率箱的頻率 (Hz)，須等間距遞增
+/// * `contour` - 叫聲輪廓，frame 為 power_db 的幀索引
+/// * `config` - 分析配置
+///
+/// # Returns
+/// 輪廓為空或輪廓本身不高於噪聲時為 None
+pub fn analyze_harmonics(
+    power_db: &[f32],
+    freqs_hz: &[f64],
+    contour: &[ContourPoint],
+    config: &HarmonicConfig,
+) -> Option<HarmonicAnalysis> {
+    let num_bins = freqs_hz.len();
+    let num_frames = power_db.len().checked_div(num_bins).unwrap_or(0);
+    let contour: Vec<ContourPoint> = contour
+        .iter()
+        .copied()
+        .filter(|p| p.frame < num_frames && p.freq_hz > 0.0)
+        .collect();
+    if contour.is_empty() || config.max_harmonics == 0 {
+        return None;
+    }
+
+    // 每幀中位數功率，作為該幀的噪聲參考
+    let medians_db: Vec<f32> = contour
+        .iter()
+        .map(|p| {
+            let mut frame = power_db[p.frame * num_bins..(p.frame + 1) * num_bins].to_vec();
+            frame.sort_unstable_by(|a, b| a.total_cmp(b));
+            frame[frame.len() / 2]
+        })
+        .collect();
+    let is_present = |ratio: f64| {
+        level_along_contour(power_db, freqs_hz, &contour, &medians_db, ratio, config.tolerance_ratio)
+            .filter(|&(level, median)| level - median >= config.min_snr_db)
+    };
+    is_present(1.0)?;
+
+    // 輪廓為第 m 諧波時，頻率 / m 處應有基頻
+    let contour_order = (2..=config.max_harmonics)
+        .rev()
+        .find(|&m| is_present(1.0 / m as f64).is_some())
+        .unwrap_or(1);
+
+    let levels: Vec<Option<f32>> = (1..=config.max_harmonics)
+        .map(|k| is_present(k as f64 / contour_order as f64).map(|(level, _)| level))
+        .collect();
+    let (dominant_harmonic, dominant_db) = levels
+        .iter()
+        .enumerate()
+        .filter_map(|(k, level)| level.map(|db| (k, db)))
+        .fold((contour_order - 1, f32::NEG_INFINITY), |best, (k, db)| if db > best.1 { (k, db) } else { best });
+
+    let mut fundamentals: Vec<f64> = contour.iter().map(|p| p.freq_hz / contour_order as f64).collect();
+    fundamentals.sort_unstable_by(|a, b| a.total_cmp(b));
+
+    Some(HarmonicAnalysis {
+        fundamental_hz: fundamentals[fundamentals.len() / 2],
+        contour_harmonic: contour_order - 1,
+        dominant_harmonic,
+        relative_levels_db: levels
+            .iter()
+            .map(|level| level.map_or(f32::NEG_INFINITY, |db| db - dominant_db))
+            .collect(),
+        num_present: levels.iter().filter(|level| level.is_some()).count(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RESOLUTION: f64 = 250.0;
+    const NUM_FRAMES: usize = 21;
+
+    /// 基頻由 30 kHz 每幀下降 250 Hz
+    fn f0(frame: usize) -> f64 {
+        30_000.0 - RESOLUTION * frame as f64
+    }
+
+    /// 0 到 max_hz 的頻譜圖：-80 dB 底上各諧波的 dB 拋物線峰，levels[k] 為第 k + 1 諧波的峰值
+    fn spectrogram(max_hz: f64, levels: &[f32]) -> (Vec<f32>, Vec<f64>) {
+        let freqs: Vec<f64> = (0..=(max_hz / RESOLUTION) as usize).map(|b| b as f64 * RESOLUTION).collect();
+        let mut power = Vec::with_capacity(NUM_FRAMES * freqs.len());
+        for frame in 0..NUM_FRAMES {
+            power.extend(freqs.iter().map(|&f| {
+                levels
+                    .iter()
+                    .enumerate()
+                    .map(|(k, &level)| level - 2.0 * ((f - (k + 1) as f64 * f0(frame)) / RESOLUTION).powi(2) as f32)
+                    .fold(-80.0, f32::max)
+            }));
+        }
+        (power, freqs)
+    }
+
+    fn contour(harmonic: usize) -> Vec<ContourPoint> {
+        (0..NUM_FRAMES)
+            .map(|frame| ContourPoint {
+                frame,
+                time_s: frame as f64 * 0.001,
+                freq_hz: (harmonic + 1) as f64 * f0(frame),
+                power_db: 0.0,
+                interpolated: false,
+            })
+            .collect()
+    }
+
+    fn assert_levels(actual: &[f32], expected: &[f32]) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected) {
+            assert!(a == e || (a - e).abs() < 1e-3, "{:?} vs {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn fundamental_dominant_stack() {
+        let (power, freqs) = spectrogram(150_000.0, &[-20.0, -30.0, -40.0]);
+        let analysis = analyze_harmonics(&power, &freqs, &contour(0), &HarmonicConfig::default()).unwrap();
+        assert_eq!(analysis.contour_harmonic, 0);
+        assert_eq!(analysis.dominant_harmonic, 0);
+        assert_eq!(analysis.num_present, 3);
+        assert_eq!(analysis.fundamental_hz, f0(NUM_FRAMES / 2));
+        assert_levels(&analysis.relative_levels_db, &[0.0, -10.0, -20.0, f32::NEG_INFINITY]);
+    }
+
+    #[test]
+    fn contour_on_stronger_second_harmonic() {
+        // 第二諧波比基頻強 15 dB，輪廓追蹤第二諧波
+        let (power, freqs) = spectrogram(150_000.0, &[-30.0, -15.0, -35.0]);
+        let analysis = analyze_harmonics(&power, &freqs, &contour(1), &HarmonicConfig::default()).unwrap();
+        assert_eq!(analysis.contour_harmonic, 1);
+        assert_eq!(analysis.dominant_harmonic, 1);
+        assert_eq!(analysis.num_present, 3);
+        assert_eq!(analysis.fundamental_hz, f0(NUM_FRAMES / 2));
+        assert_levels(&analysis.relative_levels_db, &[-15.0, 0.0, -20.0, f32::NEG_INFINITY]);
+
+        // 輪廓在基頻上時，最強諧波仍是第二諧波
+        let analysis = analyze_harmonics(&power, &freqs, &contour(0), &HarmonicConfig::default()).unwrap();
+        assert_eq!((analysis.contour_harmonic, analysis.dominant_harmonic), (0, 1));
+        assert_levels(&analysis.relative_levels_db, &[-15.0, 0.0, -20.0, f32::NEG_INFINITY]);
+    }
+
+    #[test]
+    fn weak_or_out_of_range_harmonics() {
+        // 第三諧波超出頻譜範圍；低於 min_snr_db 的第二諧波視為不存在
+        let (power, freqs) = spectrogram(70_000.0, &[-20.0, -75.0, -30.0]);
+        let config = HarmonicConfig {
+            max_harmonics: 3,
+            ..HarmonicConfig::default()
+        };
+        let analysis = analyze_harmonics(&power, &freqs, &contour(0), &config).unwrap();
+        assert_eq!(analysis.num_present, 1);
+        assert_levels(&analysis.relative_levels_db, &[0.0, f32::NEG_INFINITY, f32::NEG_INFINITY]);
+    }
+
+    #[test]
+    fn rejects_empty_or_silent_contour() {
+        let (power, freqs) = spectrogram(150_000.0, &[-20.0]);
+        let config = HarmonicConfig::default();
+        assert!(analyze_harmonics(&power, &freqs, &[], &config).is_none());
+        // 輪廓所在頻率沒有信號
+        let (silent, _) = spectrogram(150_000.0, &[]);
+        assert!(analyze_harmonics(&silent, &freqs, &contour(0), &config).is_none());
+        // 幀超出範圍或頻率為 0 的點被忽略
+        let mut outside = contour(0);
+        outside.iter_mut().for_each(|p| p.frame += NUM_FRAMES);
+        assert!(analyze_harmonics(&power, &freqs, &outside, &config).is_none());
+        let zero = HarmonicConfig {
+            max_harmonics: 0,
+            ..config
+        };
+        assert!(analyze_harmonics(&power, &freqs, &contour(0), &zero).is_none());
+    }
+}
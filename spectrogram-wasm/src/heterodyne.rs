@@ -0,0 +1,248 @@
+// ============================================================
+// 外差 (heterodyne) 音頻渲染
+// 模擬外差式蝙蝠偵測器：與本地振盪器混頻、低通濾波後降採樣到可聽採樣率
+// ============================================================
+
+use std::f64::consts::PI;
+
+use wasm_bindgen::prelude::*;
+
+use crate::iir::{design_sos, sosfiltfilt, FilterBand, FilterDesign};
+use crate::resample::Resampler;
+use crate::stft::StftProcessor;
+
+/// 追蹤模式的 FFT 大小
+const TRACK_FFT_SIZE: usize = 512;
+/// 低於全局峰值多少 dB 的幀視為無叫聲，保持上一個振盪器頻率
+const TRACK_THRESHOLD_DB: f32 = 40.0;
+/// 振盪器頻率平滑的時間常數 (秒)
+const TRACK_SMOOTHING_SEC: f32 = 0.003;
+/// 混頻後低通濾波器的階數
+const LOWPASS_ORDER: usize = 6;
+
+/// 計算每幀的振盪器頻率（追蹤頻帶內的峰值頻率減去偏移）
+///
+/// # Returns
+/// (每幀的振盪器頻率, 幀步長)
+#[allow(clippy::needless_range_loop)]
+fn track_oscillator(
+    audio_data: &[f32],
+    sample_rate: f32,
+    initial_freq: f32,
+    offset_hz: f32,
+    flow_hz: f32,
+    fhigh_hz: f32,
+) -> (Vec<f32>, usize) {
+    let mut stft = StftProcessor::new(TRACK_FFT_SIZE, 0, "hann");
+    let hop = stft.hop_size();
+    let frames = stft.analyze(audio_data);
+    let bin_width = sample_rate / TRACK_FFT_SIZE as f32;
+    let num_bins = stft.num_bins();
+
+    let min_bin = ((flow_hz / bin_width).floor().max(1.0) as usize).min(num_bins - 1);
+    let max_bin = ((fhigh_hz / bin_width).ceil() as usize).clamp(min_bin, num_bins - 1);
+
+    // 每幀峰值 (bin, dB)
+    let peaks: Vec<(usize, f32)> = frames
+        .iter()
+        .map(|frame| {
+            let mut best = min_bin;
+            let mut best_power = 0.0f32;
+            for bin in min_bin..=max_bin {
+                let power = frame[bin].norm_sqr();
+                if power > best_power {
+                    best_power = power;
+                    best = bin;
+                }
+            }
+            (best, 10.0 * best_power.max(1e-20).log10())
+        })
+        .collect();
+
+    let global_max_db = peaks.iter().map(|p| p.1).fold(f32::NEG_INFINITY, f32::max);
+    let alpha = 1.0 - (-(hop as f32) / (TRACK_SMOOTHING_SEC * sample_rate)).exp();
+
+    let mut current = initial_freq;
+    let lo_freqs = peaks
+        .iter()
+        .map(|&(bin, db)| {
+            if db >= global_max_db - TRACK_THRESHOLD_DB {
+                let target = (bin as f32 * bin_width - offset_hz).max(0.0);
+                current += alpha * (target - current);
+            }
+            current
+        })
+        .collect();
+
+    (lo_freqs, hop)
+}
+
+/// 渲染外差偵測器的聲音
+///
+/// 將音頻與本地振盪器混頻 (x · cos φ)，以零相位 Butterworth 低通濾除和頻分量，
+/// 再降採樣到可聽採樣率。輸出可直接作為播放緩衝區。
+///
+/// # Arguments
+/// * `audio_data` - 音頻數據 (Float32Array)
+/// * `sample_rate` - 輸入採樣率 (Hz)
+/// * `lo_freq_hz` - 本地振盪器頻率 (Hz)，即偵測器的調諧頻率；追蹤模式下作為初始值
+/// * `output_rate` - 輸出採樣率 (Hz，典型值: 48000)
+/// * `bandwidth_hz` - 低通帶寬 (Hz，典型值: 5000)，只有 |f - f_LO| 小於此值的成分可聽
+/// * `track_offset_hz` - 若提供，振盪器跟隨追蹤頻帶內的峰值頻率，
+///   並保持低於峰值此偏移量（偏移即為聽到的拍頻音高）；null 時使用固定頻率
+/// * `flow_hz` - 追蹤頻帶下限 (Hz)
+/// * `fhigh_hz` - 追蹤頻帶上限 (Hz)
+///
+/// # Returns
+/// 輸出採樣率下的可聽音頻（Float32Array）
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn render_heterodyne(
+    audio_data: &[f32],
+    sample_rate: f32,
+    lo_freq_hz: f32,
+    output_rate: f32,
+    bandwidth_hz: f32,
+    track_offset_hz: Option<f32>,
+    flow_hz: f32,
+    fhigh_hz: f32,
+) -> Vec<f32> {
+    if audio_data.is_empty() || sample_rate <= 0.0 || output_rate <= 0.0 {
+        return Vec::new();
+    }
+
+    // 每個樣本的振盪器頻率
+    let tracking = track_offset_hz.map(|offset| {
+        track_oscillator(audio_data, sample_rate, lo_freq_hz, offset, flow_hz, fhigh_hz)
+    });
+    let lo_at = |n: usize| -> f32 {
+        match &tracking {
+            None => lo_freq_hz,
+            Some((freqs, hop)) => {
+                // 幀中心位於 k * hop，在相鄰幀之間線性插值
+                let pos = n as f32 / *hop as f32;
+                let k = pos.floor() as usize;
+                let frac = pos - k as f32;
+                let a = freqs.get(k).copied().unwrap_or(lo_freq_hz);
+                let b = freqs.get(k + 1).copied().unwrap_or(a);
+                a + (b - a) * frac
+            }
+        }
+    };
+
+    // 混頻（相位累加，頻率變化時保持連續）
+    let mut phase = 0.0f64;
+    let mut mixed: Vec<f32> = Vec::with_capacity(audio_data.len());
+    for (n, &x) in audio_data.iter().enumerate() {
+        // 乘以 2 補償混頻造成的一半幅度損失
+        mixed.push(2.0 * x * phase.cos() as f32);
+        phase += 2.0 * PI * lo_at(n) as f64 / sample_rate as f64;
+        if phase > 2.0 * PI {
+            phase -= 2.0 * PI;
+        }
+    }
+
+    // 低通濾除和頻分量，帶寬不超過輸出 Nyquist
+    let cutoff = bandwidth_hz.min(0.45 * output_rate).min(0.45 * sample_rate);
+    if cutoff > 0.0 {
+        if let Some(sos) = design_sos(
+            FilterDesign::Butterworth,
+            FilterBand::Lowpass,
+            LOWPASS_ORDER,
+            sample_rate,
+            cutoff,
+            0.0,
+            0.0,
+            0.0,
+        ) {
+            mixed = sosfiltfilt(&sos, &mixed);
+        }
+    }
+
+    Resampler::new(sample_rate as f64, output_rate as f64, 0).process(&mixed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: f32 = 384_000.0;
+
+    /// 線性掃頻（f0 == f1 時為純音）
+    fn sweep(f0: f64, f1: f64, duration_s: f64) -> Vec<f32> {
+        let n = (duration_s * RATE as f64) as usize;
+        let rate = (f1 - f0) / duration_s;
+        (0..n)
+            .map(|i| {
+                let t = i as f64 / RATE as f64;
+                (0.5 * (2.0 * PI * (f0 * t + 0.5 * rate * t * t)).sin()) as f32
+            })
+            .collect()
+    }
+
+    /// 以過零點數估計中段頻率
+    fn zero_crossing_freq(signal: &[f32], rate: f32) -> f32 {
+        let middle = &signal[signal.len() / 4..signal.len() * 3 / 4];
+        let crossings = middle.windows(2).filter(|w| (w[0] < 0.0) != (w[1] < 0.0)).count();
+        crossings as f32 / 2.0 / (middle.len() as f32 / rate)
+    }
+
+    fn rms(signal: &[f32]) -> f32 {
+        let middle = &signal[signal.len() / 4..signal.len() * 3 / 4];
+        (middle.iter().map(|x| x * x).sum::<f32>() / middle.len() as f32).sqrt()
+    }
+
+    #[test]
+    fn fixed_oscillator_gives_difference_tone() {
+        // 45 kHz 音與 40 kHz 振盪器混頻得到 5 kHz 拍頻
+        let tone = sweep(45_000.0, 45_000.0, 0.05);
+        let output = render_heterodyne(&tone, RATE, 40_000.0, 48_000.0, 10_000.0, None, 0.0, 0.0);
+        assert_eq!(output.len(), 2400);
+        assert!((zero_crossing_freq(&output, 48_000.0) - 5_000.0).abs() < 50.0);
+        // 混頻後乘 2，保持原幅度 (0.5 / √2)
+        assert!((rms(&output) - 0.354).abs() < 0.02, "{}", rms(&output));
+
+        // 超出帶寬的成分被濾除
+        let far = sweep(60_000.0, 60_000.0, 0.05);
+        let output = render_heterodyne(&far, RATE, 40_000.0, 48_000.0, 5_000.0, None, 0.0, 0.0);
+        assert!(rms(&output) < 0.01, "{}", rms(&output));
+    }
+
+    #[test]
+    fn oscillator_tracks_sweep() {
+        // 100 ms 內由 60 kHz 線性下降到 40 kHz
+        let audio = sweep(60_000.0, 40_000.0, 0.1);
+        let (lo_freqs, hop) = track_oscillator(&audio, RATE, 50_000.0, 3_000.0, 20_000.0, 100_000.0);
+        assert_eq!(hop, TRACK_FFT_SIZE / 4);
+        for (k, &lo) in lo_freqs.iter().enumerate() {
+            let t = (k * hop) as f32 / RATE;
+            // 跳過平滑起步的 10 ms 與邊緣幀
+            if !(0.01..0.095).contains(&t) {
+                continue;
+            }
+            let expected = 60_000.0 - 200_000.0 * t - 3_000.0;
+            assert!((lo - expected).abs() < 1_500.0, "t={} lo={} expected={}", t, lo, expected);
+        }
+
+        // 追蹤模式下聽到的音高約為偏移量；下降掃頻時平滑延遲 (0.2 kHz/ms × 3 ms) 使拍頻低約 600 Hz
+        let output = render_heterodyne(&audio, RATE, 50_000.0, 48_000.0, 10_000.0, Some(3_000.0), 20_000.0, 100_000.0);
+        let pitch = zero_crossing_freq(&output, 48_000.0);
+        assert!((pitch - 2_400.0).abs() < 300.0, "{}", pitch);
+    }
+
+    #[test]
+    fn quiet_frames_hold_oscillator() {
+        // 叫聲之後的靜音保持最後的振盪器頻率
+        let mut audio = sweep(50_000.0, 50_000.0, 0.02);
+        audio.extend(vec![0.0; 7680]);
+        let (lo_freqs, _) = track_oscillator(&audio, RATE, 30_000.0, 2_000.0, 20_000.0, 100_000.0);
+        let last = *lo_freqs.last().unwrap();
+        assert!((last - 48_000.0).abs() < 750.0, "{}", last);
+    }
+
+    #[test]
+    fn invalid_input() {
+        assert!(render_heterodyne(&[], RATE, 40_000.0, 48_000.0, 5_000.0, None, 0.0, 0.0).is_empty());
+        assert!(render_heterodyne(&[0.1; 16], 0.0, 40_000.0, 48_000.0, 5_000.0, None, 0.0, 0.0).is_empty());
+    }
+}
@@ -0,0 +1,903 @@
+// ============================================================
+// IIR 濾波器設計與零相位濾波
+// Butterworth / Chebyshev I / Chebyshev II / 橢圓濾波器，
+// 以二階節 (biquad) 級聯實現，並提供前向-後向零相位濾波
+// ============================================================
+
+use std::f64::consts::PI;
+
+use num_complex::Complex;
+use wasm_bindgen::prelude::*;
+
+type C64 = Complex<f64>;
+
+/// 二階節係數 [b0, b1, b2, a0, a1, a2]（a0 恆為 1）
+pub type Sos = [f64; 6];
+
+/// 濾波器原型
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FilterDesign {
+    Butterworth,
+    Chebyshev1,
+    Chebyshev2,
+    Elliptic,
+}
+
+impl FilterDesign {
+    /// 從名稱解析濾波器原型
+    pub fn from_name(name: &str) -> Option<FilterDesign> {
+        match name {
+            "butterworth" | "butter" => Some(FilterDesign::Butterworth),
+            "chebyshev1" | "cheby1" | "chebyshev" => Some(FilterDesign::Chebyshev1),
+            "chebyshev2" | "cheby2" => Some(FilterDesign::Chebyshev2),
+            "elliptic" | "ellip" => Some(FilterDesign::Elliptic),
+            _ => None,
+        }
+    }
+}
+
+/// 濾波器頻帶類型
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FilterBand {
+    Lowpass,
+    Highpass,
+    Bandpass,
+    Bandstop,
+}
+
+impl FilterBand {
+    /// 從名稱解析頻帶類型
+    pub fn from_name(name: &str) -> Option<FilterBand> {
+        match name {
+            "lowpass" | "low" => Some(FilterBand::Lowpass),
+            "highpass" | "high" => Some(FilterBand::Highpass),
+            "bandpass" | "band" => Some(FilterBand::Bandpass),
+            "bandstop" | "notch" => Some(FilterBand::Bandstop),
+            _ => None,
+        }
+    }
+}
+
+/// 零點、極點與增益表示
+struct Zpk {
+    zeros: Vec<C64>,
+    poles: Vec<C64>,
+    gain: f64,
+}
+
+/// 設計 IIR 濾波器並返回二階節級聯
+///
+/// # Arguments
+/// * `design` - 濾波器原型
+/// * `band` - 頻帶類型
+/// * `order` - 原型階數（帶通 / 帶阻的實際階數為 2 * order）
+/// * `sample_rate` - 採樣率 (Hz)
+/// * `freq1` - 截止頻率 (Hz)；帶通 / 帶阻時為下邊緣
+/// * `freq2` - 帶通 / 帶阻時的上邊緣 (Hz)，其他類型忽略
+/// * `passband_ripple_db` - 通帶紋波 (Chebyshev I / 橢圓)
+/// * `stopband_atten_db` - 阻帶衰減 (Chebyshev II / 橢圓)
+///
+/// # Returns
+/// 參數無效時返回 None
+#[allow(clippy::too_many_arguments)]
+pub fn design_sos(
+    design: FilterDesign,
+    band: FilterBand,
+    order: usize,
+    sample_rate: f32,
+    freq1: f32,
+    freq2: f32,
+    passband_ripple_db: f32,
+    stopband_atten_db: f32,
+) -> Option<Vec<Sos>> {
+    let nyquist = sample_rate as f64 / 2.0;
+    if order == 0 || order > 32 || nyquist <= 0.0 {
+        return None;
+    }
+
+    // 歸一化頻率 (1.0 = Nyquist)
+    let w1 = freq1 as f64 / nyquist;
+    let w2 = freq2 as f64 / nyquist;
+    let is_band = matches!(band, FilterBand::Bandpass | FilterBand::Bandstop);
+    if !(w1 > 0.0 && w1 < 1.0) || (is_band && !(w2 > w1 && w2 < 1.0)) {
+        return None;
+    }
+
+    let rp = passband_ripple_db as f64;
+    let rs = stopband_atten_db as f64;
+    let prototype = match design {
+        FilterDesign::Butterworth => butterworth_prototype(order),
+        FilterDesign::Chebyshev1 => {
+            if rp <= 0.0 {
+                return None;
+            }
+            chebyshev1_prototype(order, rp)
+        }
+        FilterDesign::Chebyshev2 => {
+            if rs <= 0.0 {
+                return None;
+            }
+            chebyshev2_prototype(order, rs)
+        }
+        FilterDesign::Elliptic => {
+            if rp <= 0.0 || rs <= rp {
+                return None;
+            }
+            elliptic_prototype(order, rp, rs)?
+        }
+    };
+
+    // 雙線性變換前的頻率預扭曲 (fs = 2)
+    let fs = 2.0;
+    let warped1 = 2.0 * fs * (PI * w1 / fs).tan();
+    let warped2 = 2.0 * fs * (PI * w2 / fs).tan();
+
+    let analog = match band {
+        FilterBand::Lowpass => lp_to_lp(prototype, warped1),
+        FilterBand::Highpass => lp_to_hp(prototype, warped1),
+        FilterBand::Bandpass => lp_to_bp(prototype, (warped1 * warped2).sqrt(), warped2 - warped1),
+        FilterBand::Bandstop => lp_to_bs(prototype, (warped1 * warped2).sqrt(), warped2 - warped1),
+    };
+
+    let digital = bilinear(analog, fs);
+    Some(zpk_to_sos(digital))
+}
+
+// ------------------------------------------------------------
+// 類比原型 (截止頻率 1 rad/s)
+// ------------------------------------------------------------
+
+fn butterworth_prototype(order: usize) -> Zpk {
+    let n = order as f64;
+    let poles = (0..order)
+        .map(|k| {
+            let theta = PI * (2.0 * k as f64 + n + 1.0) / (2.0 * n);
+            C64::new(theta.cos(), theta.sin())
+        })
+        .collect();
+
+    Zpk { zeros: Vec::new(), poles, gain: 1.0 }
+}
+
+fn chebyshev1_prototype(order: usize, rp: f64) -> Zpk {
+    let n = order as f64;
+    let eps = (10f64.powf(0.1 * rp) - 1.0).sqrt();
+    let mu = (1.0 / eps).asinh() / n;
+
+    let poles: Vec<C64> = (0..order)
+        .map(|k| {
+            let theta = PI * (2.0 * k as f64 + 1.0) / (2.0 * n);
+            C64::new(-mu.sinh() * theta.sin(), mu.cosh() * theta.cos())
+        })
+        .collect();
+
+    let mut gain = product(&poles, |p| -p).re;
+    if order.is_multiple_of(2) {
+        gain /= (1.0 + eps * eps).sqrt();
+    }
+
+    Zpk { zeros: Vec::new(), poles, gain }
+}
+
+fn chebyshev2_prototype(order: usize, rs: f64) -> Zpk {
+    let n = order as f64;
+    let de = 1.0 / (10f64.powf(0.1 * rs) - 1.0).sqrt();
+    let mu = (1.0 / de).asinh() / n;
+
+    // 奇數階時跳過位於無窮遠的零點
+    let zeros: Vec<C64> = (0..order)
+        .filter(|&k| !(order % 2 == 1 && 2 * k + 1 == order))
+        .map(|k| {
+            let theta = PI * (2.0 * k as f64 + 1.0) / (2.0 * n);
+            C64::new(0.0, 1.0 / theta.cos())
+        })
+        .collect();
+
+    let poles: Vec<C64> = (0..order)
+        .map(|k| {
+            let theta = PI * (2.0 * k as f64 + 1.0) / (2.0 * n);
+            let p = C64::new(-mu.sinh() * theta.sin(), mu.cosh() * theta.cos());
+            C64::new(1.0, 0.0) / p
+        })
+        .collect();
+
+    let gain = (product(&poles, |p| -p) / product(&zeros, |z| -z)).re;
+
+    Zpk { zeros, poles, gain }
+}
+
+fn elliptic_prototype(order: usize, rp: f64, rs: f64) -> Option<Zpk> {
+    let eps_sq = 10f64.powf(0.1 * rp) - 1.0;
+    let eps = eps_sq.sqrt();
+
+    if order == 1 {
+        let p = -(1.0 / eps_sq).sqrt();
+        return Some(Zpk {
+            zeros: Vec::new(),
+            poles: vec![C64::new(p, 0.0)],
+            gain: -p,
+        });
+    }
+
+    // 選擇性參數與模數
+    let ck1_sq = eps_sq / (10f64.powf(0.1 * rs) - 1.0);
+    let m = elliptic_degree(order, ck1_sq);
+    if !(m > 0.0 && m < 1.0) {
+        return None;
+    }
+    let capk = ellipk(m);
+
+    let mut zeros = Vec::new();
+    let mut upper_poles = Vec::new();
+
+    // v0 由 sc 的反函數給出: sc(v0 * N * K1 / K, m1') = 1 / eps
+    let r = incomplete_ellipf((1.0 / eps).atan(), 1.0 - ck1_sq);
+    let v0 = capk * r / (order as f64 * ellipk(ck1_sq));
+    let (sv, cv, dv) = ellipj(v0, 1.0 - m);
+
+    let mut j = 1 - order % 2;
+    while j < order {
+        let (s, c, d) = ellipj(j as f64 * capk / order as f64, m);
+        if s.abs() > 1e-12 {
+            zeros.push(C64::new(0.0, 1.0 / (m.sqrt() * s)));
+        }
+        let denom = 1.0 - (d * sv) * (d * sv);
+        upper_poles.push(C64::new(-c * d * sv * cv / denom, -s * dv / denom));
+        j += 2;
+    }
+
+    let mut poles = upper_poles.clone();
+    for p in &upper_poles {
+        // 實極點只保留一次
+        if p.im.abs() > 1e-12 * p.norm() {
+            poles.push(p.conj());
+        }
+    }
+    let conj_zeros: Vec<C64> = zeros.iter().map(|z| z.conj()).collect();
+    zeros.extend(conj_zeros);
+
+    let mut gain = (product(&poles, |p| -p) / product(&zeros, |z| -z)).re;
+    if order.is_multiple_of(2) {
+        gain /= (1.0 + eps_sq).sqrt();
+    }
+
+    Some(Zpk { zeros, poles, gain })
+}
+
+/// 由選擇性參數求解橢圓濾波器的模數 (degree equation)
+fn elliptic_degree(order: usize, m1: f64) -> f64 {
+    let k1 = ellipk(m1);
+    let k1p = ellipk(1.0 - m1);
+    let q1 = (-PI * k1p / k1).exp();
+    let q = q1.powf(1.0 / order as f64);
+
+    let num: f64 = (0..=7).map(|i| q.powi(i * (i + 1))).sum();
+    let den: f64 = 1.0 + 2.0 * (1..=8).map(|i| q.powi(i * i)).sum::<f64>();
+
+    16.0 * q * (num / den).powi(4)
+}
+
+/// Carlson 對稱橢圓積分 R_F(x, y, z)
+fn carlson_rf(mut x: f64, mut y: f64, mut z: f64) -> f64 {
+    for _ in 0..100 {
+        let lambda = (x * y).sqrt() + (y * z).sqrt() + (z * x).sqrt();
+        x = (x + lambda) / 4.0;
+        y = (y + lambda) / 4.0;
+        z = (z + lambda) / 4.0;
+        let mean = (x + y + z) / 3.0;
+        let tolerance = (x - mean).abs().max((y - mean).abs()).max((z - mean).abs());
+        if tolerance < 1e-12 * mean {
+            break;
+        }
+    }
+    let mean = (x + y + z) / 3.0;
+    let dx = 1.0 - x / mean;
+    let dy = 1.0 - y / mean;
+    let dz = 1.0 - z / mean;
+    let e2 = dx * dy - dz * dz;
+    let e3 = dx * dy * dz;
+    (1.0 - e2 / 10.0 + e3 / 14.0 + e2 * e2 / 24.0 - 3.0 * e2 * e3 / 44.0) / mean.sqrt()
+}
+
+/// 第一類完全橢圓積分 K(m)
+fn ellipk(m: f64) -> f64 {
+    carlson_rf(0.0, 1.0 - m, 1.0)
+}
+
+/// 第一類不完全橢圓積分 F(phi | m)
+fn incomplete_ellipf(phi: f64, m: f64) -> f64 {
+    let s = phi.sin();
+    let c = phi.cos();
+    s * carlson_rf(c * c, 1.0 - m * s * s, 1.0)
+}
+
+/// Jacobi 橢圓函數 (sn, cn, dn)，使用 AGM 下降法
+fn ellipj(u: f64, m: f64) -> (f64, f64, f64) {
+    if m < 1e-12 {
+        return (u.sin(), u.cos(), 1.0);
+    }
+    if m > 1.0 - 1e-12 {
+        let t = u.tanh();
+        let sech = 1.0 / u.cosh();
+        return (t, sech, sech);
+    }
+
+    let mut a = vec![1.0f64];
+    let mut c = vec![m.sqrt()];
+    let mut b = (1.0 - m).sqrt();
+    while c.last().unwrap().abs() > 1e-15 && a.len() < 16 {
+        let a_prev = *a.last().unwrap();
+        a.push((a_prev + b) / 2.0);
+        c.push((a_prev - b) / 2.0);
+        b = (a_prev * b).sqrt();
+    }
+
+    let n = a.len() - 1;
+    let mut phi = 2f64.powi(n as i32) * a[n] * u;
+    for i in (1..=n).rev() {
+        phi = (phi + (c[i] / a[i] * phi.sin()).asin()) / 2.0;
+    }
+
+    let sn = phi.sin();
+    let cn = phi.cos();
+    let dn = (1.0 - m * sn * sn).sqrt();
+    (sn, cn, dn)
+}
+
+fn product<F: Fn(C64) -> C64>(values: &[C64], f: F) -> C64 {
+    values.iter().fold(C64::new(1.0, 0.0), |acc, &v| acc * f(v))
+}
+
+// ------------------------------------------------------------
+// 頻率變換與雙線性變換
+// ------------------------------------------------------------
+
+fn lp_to_lp(zpk: Zpk, wo: f64) -> Zpk {
+    let degree = zpk.poles.len() as i32 - zpk.zeros.len() as i32;
+    Zpk {
+        zeros: zpk.zeros.iter().map(|z| z * wo).collect(),
+        poles: zpk.poles.iter().map(|p| p * wo).collect(),
+        gain: zpk.gain * wo.powi(degree),
+    }
+}
+
+fn lp_to_hp(zpk: Zpk, wo: f64) -> Zpk {
+    let degree = zpk.poles.len() - zpk.zeros.len();
+    let gain = zpk.gain * (product(&zpk.zeros, |z| -z) / product(&zpk.poles, |p| -p)).re;
+
+    let mut zeros: Vec<C64> = zpk.zeros.iter().map(|&z| C64::new(wo, 0.0) / z).collect();
+    zeros.extend(std::iter::repeat_n(C64::new(0.0, 0.0), degree));
+
+    Zpk {
+        zeros,
+        poles: zpk.poles.iter().map(|&p| C64::new(wo, 0.0) / p).collect(),
+        gain,
+    }
+}
+
+fn lp_to_bp(zpk: Zpk, wo: f64, bw: f64) -> Zpk {
+    let degree = zpk.poles.len() - zpk.zeros.len();
+    let transform = |roots: &[C64]| -> Vec<C64> {
+        let scaled: Vec<C64> = roots.iter().map(|r| r * (bw / 2.0)).collect();
+        let mut result = Vec::with_capacity(roots.len() * 2);
+        for &r in &scaled {
+            result.push(r + (r * r - wo * wo).sqrt());
+        }
+        for &r in &scaled {
+            result.push(r - (r * r - wo * wo).sqrt());
+        }
+        result
+    };
+
+    let mut zeros = transform(&zpk.zeros);
+    zeros.extend(std::iter::repeat_n(C64::new(0.0, 0.0), degree));
+
+    Zpk {
+        zeros,
+        poles: transform(&zpk.poles),
+        gain: zpk.gain * bw.powi(degree as i32),
+    }
+}
+
+fn lp_to_bs(zpk: Zpk, wo: f64, bw: f64) -> Zpk {
+    let degree = zpk.poles.len() - zpk.zeros.len();
+    let gain = zpk.gain * (product(&zpk.zeros, |z| -z) / product(&zpk.poles, |p| -p)).re;
+    let transform = |roots: &[C64]| -> Vec<C64> {
+        let inverted: Vec<C64> = roots.iter().map(|&r| C64::new(bw / 2.0, 0.0) / r).collect();
+        let mut result = Vec::with_capacity(roots.len() * 2);
+        for &r in &inverted {
+            result.push(r + (r * r - wo * wo).sqrt());
+        }
+        for &r in &inverted {
+            result.push(r - (r * r - wo * wo).sqrt());
+        }
+        result
+    };
+
+    let mut zeros = transform(&zpk.zeros);
+    zeros.extend(std::iter::repeat_n(C64::new(0.0, wo), degree));
+    zeros.extend(std::iter::repeat_n(C64::new(0.0, -wo), degree));
+
+    Zpk {
+        zeros,
+        poles: transform(&zpk.poles),
+        gain,
+    }
+}
+
+fn bilinear(zpk: Zpk, fs: f64) -> Zpk {
+    let degree = zpk.poles.len() - zpk.zeros.len();
+    let fs2 = C64::new(2.0 * fs, 0.0);
+
+    let mut zeros: Vec<C64> = zpk.zeros.iter().map(|&z| (fs2 + z) / (fs2 - z)).collect();
+    zeros.extend(std::iter::repeat_n(C64::new(-1.0, 0.0), degree));
+    let poles = zpk.poles.iter().map(|&p| (fs2 + p) / (fs2 - p)).collect();
+    let gain = zpk.gain * (product(&zpk.zeros, |z| fs2 - z) / product(&zpk.poles, |p| fs2 - p)).re;
+
+    Zpk { zeros, poles, gain }
+}
+
+// ------------------------------------------------------------
+// 零極點配對為二階節
+// ------------------------------------------------------------
+
+/// 將根分組為實係數的一階或二階因子 (共軛對 / 兩個實根)
+fn group_roots(roots: &[C64]) -> Vec<Vec<C64>> {
+    let tolerance = 1e-9;
+    let mut complex: Vec<C64> = roots.iter().copied().filter(|r| r.im > tolerance).collect();
+    let mut real: Vec<f64> = roots.iter().filter(|r| r.im.abs() <= tolerance).map(|r| r.re).collect();
+
+    // 最接近單位圓的因子排在後面
+    complex.sort_by(|a, b| a.norm().total_cmp(&b.norm()));
+    real.sort_by(|a, b| a.abs().total_cmp(&b.abs()));
+
+    let mut groups: Vec<Vec<C64>> = Vec::new();
+    let mut idx = 0;
+    if real.len() % 2 == 1 {
+        groups.push(vec![C64::new(real[0], 0.0)]);
+        idx = 1;
+    }
+    while idx + 1 < real.len() {
+        groups.push(vec![C64::new(real[idx], 0.0), C64::new(real[idx + 1], 0.0)]);
+        idx += 2;
+    }
+    for c in complex {
+        groups.push(vec![c, c.conj()]);
+    }
+
+    groups.sort_by(|a, b| {
+        let na = a.iter().map(|r| r.norm()).fold(0.0, f64::max);
+        let nb = b.iter().map(|r| r.norm()).fold(0.0, f64::max);
+        na.total_cmp(&nb)
+    });
+    groups
+}
+
+/// 由根計算多項式係數 [1, c1, c2]
+fn quadratic_from_roots(roots: &[C64]) -> [f64; 3] {
+    match roots.len() {
+        0 => [1.0, 0.0, 0.0],
+        1 => [1.0, -roots[0].re, 0.0],
+        _ => {
+            let sum = roots[0] + roots[1];
+            let prod = roots[0] * roots[1];
+            [1.0, -sum.re, prod.re]
+        }
+    }
+}
+
+/// 為一組極點選擇最近的零點組
+///
+/// 優先選擇與極點數相同的零點組，避免二階極點只配到一個零點、
+/// 導致剩下的零點無處安放。
+fn nearest_zero_group(zero_groups: &[Vec<C64>], poles: &[C64]) -> Option<usize> {
+    let anchor = poles[0];
+    let distance = |group: &Vec<C64>| group.iter().map(|z| (z - anchor).norm()).fold(f64::INFINITY, f64::min);
+
+    let pick = |same_len: bool| {
+        zero_groups
+            .iter()
+            .enumerate()
+            .filter(|(_, group)| if same_len { group.len() == poles.len() } else { group.len() < poles.len() })
+            .min_by(|a, b| distance(a.1).total_cmp(&distance(b.1)))
+            .map(|(idx, _)| idx)
+    };
+
+    pick(true).or_else(|| pick(false))
+}
+
+fn zpk_to_sos(zpk: Zpk) -> Vec<Sos> {
+    let pole_groups = group_roots(&zpk.poles);
+    let mut zero_groups = group_roots(&zpk.zeros);
+
+    let mut sos = Vec::with_capacity(pole_groups.len());
+    // 從最接近單位圓的極點開始，為每組極點選擇最近的零點組
+    for poles in pole_groups.iter().rev() {
+        let zeros = match nearest_zero_group(&zero_groups, poles) {
+            Some(idx) => zero_groups.remove(idx),
+            None => Vec::new(),
+        };
+
+        let b = quadratic_from_roots(&zeros);
+        let a = quadratic_from_roots(poles);
+        sos.push([b[0], b[1], b[2], a[0], a[1], a[2]]);
+    }
+
+    // 最接近單位圓的節放在最後，以降低內部溢出
+    sos.reverse();
+    if let Some(first) = sos.first_mut() {
+        first[0] *= zpk.gain;
+        first[1] *= zpk.gain;
+        first[2] *= zpk.gain;
+    }
+    sos
+}
+
+// ------------------------------------------------------------
+// 濾波
+// ------------------------------------------------------------
+
+/// 單節的穩態初始狀態 (相當於 lfilter_zi)
+fn section_zi(section: &Sos) -> [f64; 2] {
+    let [b0, b1, b2, _, a1, a2] = *section;
+    let denom = 1.0 + a1 + a2;
+    if denom.abs() < 1e-12 {
+        return [0.0, 0.0];
+    }
+    let zi0 = (b1 - a1 * b0 + b2 - a2 * b0) / denom;
+    let zi1 = b2 - a2 * b0 - a2 * zi0;
+    [zi0, zi1]
+}
+
+/// 級聯的穩態初始狀態 (相當於 sosfilt_zi)
+fn sos_zi(sos: &[Sos]) -> Vec<[f64; 2]> {
+    let mut scale = 1.0;
+    sos.iter()
+        .map(|section| {
+            let zi = section_zi(section);
+            let result = [zi[0] * scale, zi[1] * scale];
+            let a_sum = section[3] + section[4] + section[5];
+            if a_sum.abs() > 1e-12 {
+                scale *= (section[0] + section[1] + section[2]) / a_sum;
+            }
+            result
+        })
+        .collect()
+}
+
+/// 轉置直接 II 型級聯濾波（就地處理）
+fn sos_filter_in_place(sos: &[Sos], data: &mut [f64], state: &mut [[f64; 2]]) {
+    for (section, z) in sos.iter().zip(state.iter_mut()) {
+        let [b0, b1, b2, _, a1, a2] = *section;
+        let (mut z0, mut z1) = (z[0], z[1]);
+        for x in data.iter_mut() {
+            let input = *x;
+            let y = b0 * input + z0;
+            z0 = b1 * input - a1 * y + z1;
+            z1 = b2 * input - a2 * y;
+            *x = y;
+        }
+        z[0] = z0;
+        z[1] = z1;
+    }
+}
+
+/// 因果濾波（單向，有相位延遲）
+pub fn sosfilt(sos: &[Sos], audio_data: &[f32]) -> Vec<f32> {
+    let mut data: Vec<f64> = audio_data.iter().map(|&x| x as f64).collect();
+    let mut state = vec![[0.0f64; 2]; sos.len()];
+    sos_filter_in_place(sos, &mut data, &mut state);
+    data.into_iter().map(|x| x as f32).collect()
+}
+
+/// 零相位前向-後向濾波
+///
+/// 兩端以奇對稱延拓填充，並以穩態初始條件啟動，減少邊緣瞬態
+/// （行為與 scipy.signal.sosfiltfilt 相同）。
+pub fn sosfiltfilt(sos: &[Sos], audio_data: &[f32]) -> Vec<f32> {
+    let len = audio_data.len();
+    if sos.is_empty() || len == 0 {
+        return audio_data.to_vec();
+    }
+
+    let trailing_zeros = sos.iter().filter(|s| s[2] == 0.0).count().min(sos.iter().filter(|s| s[5] == 0.0).count());
+    let pad_len = (3 * (2 * sos.len() + 1 - trailing_zeros)).min(len - 1);
+
+    // 奇對稱延拓
+    let first = audio_data[0] as f64;
+    let last = audio_data[len - 1] as f64;
+    let mut extended = Vec::with_capacity(len + 2 * pad_len);
+    for i in (1..=pad_len).rev() {
+        extended.push(2.0 * first - audio_data[i] as f64);
+    }
+    extended.extend(audio_data.iter().map(|&x| x as f64));
+    for i in 1..=pad_len {
+        extended.push(2.0 * last - audio_data[len - 1 - i] as f64);
+    }
+
+    let zi = sos_zi(sos);
+
+    // 前向
+    let x0 = extended[0];
+    let mut state: Vec<[f64; 2]> = zi.iter().map(|z| [z[0] * x0, z[1] * x0]).collect();
+    sos_filter_in_place(sos, &mut extended, &mut state);
+
+    // 後向
+    extended.reverse();
+    let y0 = extended[0];
+    let mut state: Vec<[f64; 2]> = zi.iter().map(|z| [z[0] * y0, z[1] * y0]).collect();
+    sos_filter_in_place(sos, &mut extended, &mut state);
+    extended.reverse();
+
+    extended[pad_len..pad_len + len].iter().map(|&x| x as f32).collect()
+}
+
+/// 計算級聯在指定頻率的幅度響應 (dB)
+pub fn sos_magnitude_db(sos: &[Sos], sample_rate: f32, freq_hz: f32) -> f32 {
+    let w = 2.0 * PI * freq_hz as f64 / sample_rate as f64;
+    let z1 = C64::new(w.cos(), -w.sin());
+    let z2 = z1 * z1;
+    let mut response = C64::new(1.0, 0.0);
+    for s in sos {
+        let num = s[0] + z1 * s[1] + z2 * s[2];
+        let den = s[3] + z1 * s[4] + z2 * s[5];
+        response *= num / den;
+    }
+    (20.0 * response.norm().max(1e-30).log10()) as f32
+}
+
+/// IirFilter: 以二階節級聯實現的 IIR 濾波器
+///
+/// 可在任何 SpectrogramEngine 調用之前直接對音頻濾波，
+/// 濾波結果可直接送入 STFT，無需回到 JavaScript 處理。
+#[wasm_bindgen]
+pub struct IirFilter {
+    sos: Vec<Sos>,
+    sample_rate: f32,
+}
+
+#[wasm_bindgen]
+impl IirFilter {
+    /// 設計新的 IIR 濾波器
+    ///
+    /// # Arguments
+    /// * `design` - 濾波器原型 ("butterworth", "chebyshev1", "chebyshev2", "elliptic")
+    /// * `band` - 頻帶類型 ("lowpass", "highpass", "bandpass", "bandstop")
+    /// * `order` - 原型階數（帶通 / 帶阻的實際階數為 2 * order）
+    /// * `sample_rate` - 採樣率 (Hz)
+    /// * `freq1` - 截止頻率 (Hz)；帶通 / 帶阻時為下邊緣
+    /// * `freq2` - 帶通 / 帶阻時的上邊緣 (Hz)
+    /// * `passband_ripple_db` - 通帶紋波 dB（Chebyshev I / 橢圓，典型值: 1）
+    /// * `stopband_atten_db` - 阻帶衰減 dB（Chebyshev II / 橢圓，典型值: 60）
+    ///
+    /// 參數無效時返回的濾波器 `is_valid()` 為 false，濾波時原樣返回輸入。
+    #[wasm_bindgen(constructor)]
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        design: &str,
+        band: &str,
+        order: usize,
+        sample_rate: f32,
+        freq1: f32,
+        freq2: f32,
+        passband_ripple_db: f32,
+        stopband_atten_db: f32,
+    ) -> IirFilter {
+        let sos = match (FilterDesign::from_name(design), FilterBand::from_name(band)) {
+            (Some(design), Some(band)) => design_sos(
+                design,
+                band,
+                order,
+                sample_rate,
+                freq1,
+                freq2,
+                passband_ripple_db,
+                stopband_atten_db,
+            )
+            .unwrap_or_default(),
+            _ => Vec::new(),
+        };
+
+        IirFilter { sos, sample_rate }
+    }
+
+    /// 濾波器是否設計成功
+    #[wasm_bindgen]
+    pub fn is_valid(&self) -> bool {
+        !self.sos.is_empty()
+    }
+
+    /// 獲取二階節數量
+    #[wasm_bindgen]
+    pub fn get_num_sections(&self) -> usize {
+        self.sos.len()
+    }
+
+    /// 獲取二階節係數
+    ///
+    /// # Returns
+    /// Float64Array，每節 6 個值 [b0, b1, b2, a0, a1, a2]
+    #[wasm_bindgen]
+    pub fn get_sos(&self) -> Vec<f64> {
+        self.sos.iter().flat_map(|s| s.iter().copied()).collect()
+    }
+
+    /// 因果濾波（與類比濾波器相同，有相位延遲）
+    #[wasm_bindgen]
+    pub fn filter(&self, audio_data: &[f32]) -> Vec<f32> {
+        sosfilt(&self.sos, audio_data)
+    }
+
+    /// 零相位濾波（前向-後向，幅度響應為單向的平方）
+    #[wasm_bindgen]
+    pub fn filtfilt(&self, audio_data: &[f32]) -> Vec<f32> {
+        sosfiltfilt(&self.sos, audio_data)
+    }
+
+    /// 計算指定頻率的幅度響應 (dB，單向)
+    ///
+    /// # Arguments
+    /// * `freqs_hz` - 頻率列表 (Hz)
+    #[wasm_bindgen]
+    pub fn magnitude_response(&self, freqs_hz: &[f32]) -> Vec<f32> {
+        freqs_hz
+            .iter()
+            .map(|&f| sos_magnitude_db(&self.sos, self.sample_rate, f))
+            .collect()
+    }
+}
+
+/// 一次性零相位 IIR 濾波
+///
+/// 參數同 `IirFilter::new`；設計失敗時原樣返回輸入。
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn iir_filtfilt(
+    audio_data: &[f32],
+    sample_rate: f32,
+    design: &str,
+    band: &str,
+    order: usize,
+    freq1: f32,
+    freq2: f32,
+    passband_ripple_db: f32,
+    stopband_atten_db: f32,
+) -> Vec<f32> {
+    let filter = IirFilter::new(
+        design,
+        band,
+        order,
+        sample_rate,
+        freq1,
+        freq2,
+        passband_ripple_db,
+        stopband_atten_db,
+    );
+    filter.filtfilt(audio_data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FS: f32 = 48_000.0;
+
+    fn design(design: FilterDesign, band: FilterBand, order: usize, f1: f32, f2: f32, rp: f32, rs: f32) -> Vec<Sos> {
+        design_sos(design, band, order, FS, f1, f2, rp, rs).unwrap()
+    }
+
+    /// [lo, hi] 內等間隔取點的幅度響應 (dB)
+    fn response(sos: &[Sos], lo: f32, hi: f32) -> Vec<f32> {
+        (0..=400).map(|i| sos_magnitude_db(sos, FS, lo + (hi - lo) * i as f32 / 400.0)).collect()
+    }
+
+    fn max(values: &[f32]) -> f32 {
+        values.iter().copied().fold(f32::NEG_INFINITY, f32::max)
+    }
+
+    fn min(values: &[f32]) -> f32 {
+        values.iter().copied().fold(f32::INFINITY, f32::min)
+    }
+
+    /// 以 |x| 為權重的時間重心（樣本）
+    fn centroid(data: &[f32]) -> f64 {
+        let weight: f64 = data.iter().map(|&x| x.abs() as f64).sum();
+        data.iter().enumerate().map(|(i, &x)| i as f64 * x.abs() as f64).sum::<f64>() / weight
+    }
+
+    #[test]
+    fn butterworth_is_minus_3db_at_cutoff() {
+        let low = design(FilterDesign::Butterworth, FilterBand::Lowpass, 4, 5000.0, 0.0, 0.0, 0.0);
+        assert_eq!(low.len(), 2);
+        assert!((sos_magnitude_db(&low, FS, 5000.0) + 3.01).abs() < 0.02);
+        assert!(sos_magnitude_db(&low, FS, 10.0).abs() < 1e-3);
+        // 單調下降
+        let mags = response(&low, 0.0, 23_900.0);
+        assert!(mags.windows(2).all(|w| w[1] <= w[0] + 1e-4));
+
+        let high = design(FilterDesign::Butterworth, FilterBand::Highpass, 5, 5000.0, 0.0, 0.0, 0.0);
+        assert_eq!(high.len(), 3);
+        assert!((sos_magnitude_db(&high, FS, 5000.0) + 3.01).abs() < 0.02);
+        assert!(sos_magnitude_db(&high, FS, 20_000.0).abs() < 1e-3);
+
+        let band = design(FilterDesign::Butterworth, FilterBand::Bandpass, 3, 4000.0, 8000.0, 0.0, 0.0);
+        assert!((sos_magnitude_db(&band, FS, 4000.0) + 3.01).abs() < 0.02);
+        assert!((sos_magnitude_db(&band, FS, 8000.0) + 3.01).abs() < 0.02);
+        assert!(sos_magnitude_db(&band, FS, (4000.0f32 * 8000.0).sqrt()).abs() < 1e-3);
+
+        let stop = design(FilterDesign::Butterworth, FilterBand::Bandstop, 3, 4000.0, 8000.0, 0.0, 0.0);
+        assert!((sos_magnitude_db(&stop, FS, 4000.0) + 3.01).abs() < 0.02);
+        assert!(sos_magnitude_db(&stop, FS, 100.0).abs() < 1e-3);
+        assert!(min(&response(&stop, 4000.0, 8000.0)) < -60.0);
+    }
+
+    #[test]
+    fn chebyshev1_passband_ripple() {
+        for order in [4, 5] {
+            let sos = design(FilterDesign::Chebyshev1, FilterBand::Lowpass, order, 6000.0, 0.0, 1.0, 0.0);
+            let passband = response(&sos, 0.0, 6000.0);
+            // 通帶在 [-rp, 0] 內等紋波，截止頻率處為 -rp
+            assert!(max(&passband) < 1e-3 && max(&passband) > -1e-2, "order {}: {}", order, max(&passband));
+            assert!(min(&passband) > -1.0 - 1e-3 && min(&passband) < -0.99, "order {}: {}", order, min(&passband));
+            assert!((sos_magnitude_db(&sos, FS, 6000.0) + 1.0).abs() < 1e-2);
+            assert!(sos_magnitude_db(&sos, FS, 12_000.0) < -30.0);
+        }
+    }
+
+    #[test]
+    fn chebyshev2_stopband_floor() {
+        let sos = design(FilterDesign::Chebyshev2, FilterBand::Lowpass, 5, 6000.0, 0.0, 0.0, 40.0);
+        // 截止頻率為阻帶邊緣：其上全部低於 -rs，並在等紋波處觸及 -rs
+        let stopband = response(&sos, 6000.0, 24_000.0);
+        assert!(max(&stopband) < -40.0 + 1e-2, "{}", max(&stopband));
+        assert!(max(&stopband) > -40.5);
+        assert!(sos_magnitude_db(&sos, FS, 10.0).abs() < 1e-3);
+
+        let high = design(FilterDesign::Chebyshev2, FilterBand::Highpass, 4, 6000.0, 0.0, 0.0, 50.0);
+        assert!(max(&response(&high, 0.0, 6000.0)) < -50.0 + 1e-2);
+        assert!(sos_magnitude_db(&high, FS, 20_000.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn elliptic_ripple_and_stopband_floor() {
+        let sos = design(FilterDesign::Elliptic, FilterBand::Lowpass, 4, 6000.0, 0.0, 0.5, 60.0);
+        let passband = response(&sos, 0.0, 6000.0);
+        assert!(max(&passband) < 1e-3);
+        assert!(min(&passband) > -0.5 - 1e-3);
+        assert!((sos_magnitude_db(&sos, FS, 6000.0) + 0.5).abs() < 1e-2);
+
+        // 過渡帶之後阻帶等紋波，最高處恰為 -rs
+        let stopband = response(&sos, 13_000.0, 24_000.0);
+        assert!(max(&stopband) < -60.0 + 1e-2, "{}", max(&stopband));
+        assert!(max(&stopband) > -61.0, "{}", max(&stopband));
+    }
+
+    #[test]
+    fn rejects_invalid_designs() {
+        let invalid = [
+            design_sos(FilterDesign::Butterworth, FilterBand::Lowpass, 0, FS, 1000.0, 0.0, 0.0, 0.0),
+            design_sos(FilterDesign::Butterworth, FilterBand::Lowpass, 4, FS, 24_000.0, 0.0, 0.0, 0.0),
+            design_sos(FilterDesign::Butterworth, FilterBand::Bandpass, 4, FS, 8000.0, 4000.0, 0.0, 0.0),
+            design_sos(FilterDesign::Chebyshev1, FilterBand::Lowpass, 4, FS, 1000.0, 0.0, 0.0, 40.0),
+            design_sos(FilterDesign::Chebyshev2, FilterBand::Lowpass, 4, FS, 1000.0, 0.0, 1.0, 0.0),
+            design_sos(FilterDesign::Elliptic, FilterBand::Lowpass, 4, FS, 1000.0, 0.0, 40.0, 20.0),
+        ];
+        assert!(invalid.iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn filtfilt_keeps_pulse_centroid() {
+        // 對稱脈衝：零相位濾波後重心不動，單向濾波則延後
+        let pulse: Vec<f32> = (0..2001).map(|i| (-((i as f32 - 1000.0) / 20.0).powi(2)).exp()).collect();
+        let sos = design(FilterDesign::Butterworth, FilterBand::Lowpass, 4, 2000.0, 0.0, 0.0, 0.0);
+
+        let zero_phase = sosfiltfilt(&sos, &pulse);
+        assert_eq!(zero_phase.len(), pulse.len());
+        assert!((centroid(&zero_phase) - 1000.0).abs() < 0.05, "{}", centroid(&zero_phase));
+        // 對稱輸入的輸出也對稱
+        assert!((0..1000).all(|i| (zero_phase[i] - zero_phase[2000 - i]).abs() < 1e-4));
+
+        let causal = sosfilt(&sos, &pulse);
+        assert!(centroid(&causal) - 1000.0 > 5.0, "{}", centroid(&causal));
+    }
+}
@@ -3,6 +3,31 @@ use rustfft::FftPlanner;
 use num_complex::Complex;
 use std::f32::consts::PI;
 
+pub mod stft;
+pub mod denoise;
+pub mod iir;
+pub mod fir;
+pub mod resample;
+pub mod heterodyne;
+pub mod freq_division;
+pub mod vocoder;
+pub mod timestamp;
+pub mod wav;
+pub mod wav_writer;
+pub mod guano;
+pub mod wamd;
+pub mod audiomoth;
+pub mod flac;
+pub mod anabat;
+pub mod zc;
+pub mod call_detector;
+pub mod segment_scan;
+pub mod noise_floor;
+pub mod peaks;
+pub mod ridge;
+pub mod harmonics;
+pub mod call_shape;
+
 /// SpectrogramEngine: 處理音頻頻譜圖計算
 /// 將 FFT、窗函數應用、濾波器組應用和 dB 轉換從 JavaScript 移到 Rust
 #[wasm_bindgen]
@@ -24,6 +49,7 @@ pub struct SpectrogramEngine {
     last_magnitude_buffer: Vec<f32>,
     last_num_frames: usize,
     last_global_max: f32,
+    last_step: usize,
     // 色彩映射：256 種顏色的 RGBA 值 (u32 packed)
     color_map: Vec<u32>,
     // 配置存儲
@@ -70,6 +96,7 @@ impl SpectrogramEngine {
             last_magnitude_buffer: Vec::new(),
             last_num_frames: 0,
             last_global_max: 0.0,
+            last_step: 0,
             color_map: Vec::new(),  // 256 * 4 bytes
             current_scale: "linear".to_string(),
             freq_min: 0.0,
@@ -299,6 +326,7 @@ impl SpectrogramEngine {
         self.last_magnitude_buffer = all_magnitudes;
         self.last_num_frames = num_frames;
         self.last_global_max = global_max;
+        self.last_step = step;
         
         result
     }
@@ -307,6 +335,7 @@ impl SpectrogramEngine {
     /// 
     /// magnitude: 線性幅度頻譜 (長度: freq_bins)
     /// 返回: 濾波後的幅度 (長度: num_filters)
+    #[allow(clippy::needless_range_loop)]
     fn apply_filter_bank(&self, magnitude: &[f32]) -> Vec<f32> {
         let mut result = vec![0.0f32; self.num_filters];
         
@@ -346,6 +375,7 @@ impl SpectrogramEngine {
     /// - 如果超過閾值: 峰值所在的頻率 bin 索引 (0 到 fft_size/2-1)
     /// - 如果未超過閾值: u16::MAX (0xFFFF，表示無效)
     #[wasm_bindgen]
+    #[allow(clippy::needless_range_loop)]
     pub fn get_peaks(&self, threshold_ratio: f32) -> Vec<u16> {
         if self.last_magnitude_buffer.is_empty() || self.last_global_max <= 0.0 {
             return Vec::new();
@@ -395,6 +425,7 @@ impl SpectrogramEngine {
     /// Float32Array，其中每個元素是對應時間幀的峰值幅度值
     /// 如果該幀沒有有效的峰值，返回 0.0
     #[wasm_bindgen]
+    #[allow(clippy::needless_range_loop)]
     pub fn get_peak_magnitudes(&self, threshold_ratio: f32) -> Vec<f32> {
         if self.last_magnitude_buffer.is_empty() || self.last_global_max <= 0.0 {
             return Vec::new();
@@ -442,6 +473,163 @@ impl SpectrogramEngine {
         self.last_global_max
     }
 
+    /// 在頻帶內追蹤每幀的峰值頻率（頻率箱以下的精度）
+    ///
+    /// 基於在最後一次 compute_spectrogram_u8 調用中計算的線性幅度值。
+    /// 與 get_peaks 不同，只在 [flow_hz, fhigh_hz] 內搜索，並以插值精修峰值。
+    ///
+    /// # Arguments
+    /// * `sample_rate` - 採樣率 (Hz)
+    /// * `flow_hz` - 最低頻率 (Hz)
+    /// * `fhigh_hz` - 最高頻率 (Hz)
+    /// * `threshold_ratio` - 相對於全局最大值的閾值比率 (0.0-1.0, 典型值: 0.4)
+    /// * `interpolation` - 插值方法 ("parabolic", "gaussian"，其他值不插值)
+    ///
+    /// # Returns
+    /// Float32Array，每個時間幀 3 個值：[頻率 (Hz), 時間 (秒，幀中心), 幅度 (dB)]
+    /// 頻帶內峰值未超過閾值的幀，頻率為 0、幅度為 -Infinity
+    #[wasm_bindgen]
+    pub fn get_peak_track(
+        &self,
+        sample_rate: f32,
+        flow_hz: f32,
+        fhigh_hz: f32,
+        threshold_ratio: f32,
+        interpolation: &str,
+    ) -> Vec<f32> {
+        if self.last_magnitude_buffer.is_empty() || self.last_global_max <= 0.0 || sample_rate <= 0.0 {
+            return Vec::new();
+        }
+
+        let freq_bins = self.fft_size / 2;
+        let freq_resolution = sample_rate / self.fft_size as f32;
+        let Some(band) = peaks::band_bins(freq_bins, freq_resolution, flow_hz, fhigh_hz) else {
+            return Vec::new();
+        };
+        let method = peaks::PeakInterpolation::from_name(interpolation);
+        let threshold = self.last_global_max * threshold_ratio;
+
+        let mut track = Vec::with_capacity(self.last_num_frames * 3);
+        for frame_idx in 0..self.last_num_frames {
+            let frame_data = &self.last_magnitude_buffer[frame_idx * freq_bins..(frame_idx + 1) * freq_bins];
+            let time = (frame_idx * self.last_step) as f32 / sample_rate + self.fft_size as f32 / 2.0 / sample_rate;
+            match peaks::find_band_peak(frame_data, freq_resolution, band, threshold, method) {
+                Some(peak) => track.extend_from_slice(&[peak.freq_hz, time, peak.power_db]),
+                None => track.extend_from_slice(&[0.0, time, f32::NEG_INFINITY]),
+            }
+        }
+        track
+    }
+
+    /// 獲取每幀的多個峰值（諧波或同時出現的多隻蝙蝠）
+    ///
+    /// 基於在最後一次 compute_spectrogram_u8 調用中計算的線性幅度值。
+    /// 在 [flow_hz, fhigh_hz] 內選取顯著度足夠、彼此相距足夠的最強峰值，並以高斯插值精修。
+    ///
+    /// # Arguments
+    /// * `sample_rate` - 採樣率 (Hz)
+    /// * `flow_hz` - 最低頻率 (Hz)
+    /// * `fhigh_hz` - 最高頻率 (Hz)
+    /// * `max_peaks` - 每幀最多峰值數
+    /// * `min_prominence_db` - 最小顯著度 (dB，典型值: 6)
+    /// * `min_separation_hz` - 峰值之間的最小間距 (Hz)
+    ///
+    /// # Returns
+    /// Float32Array，每個時間幀 max_peaks 組 [頻率 (Hz), 幅度 (dB)]，按幅度由強到弱排列；
+    /// 未使用的位置頻率為 0、幅度為 -Infinity
+    #[wasm_bindgen]
+    pub fn get_multi_peaks(
+        &self,
+        sample_rate: f32,
+        flow_hz: f32,
+        fhigh_hz: f32,
+        max_peaks: usize,
+        min_prominence_db: f32,
+        min_separation_hz: f32,
+    ) -> Vec<f32> {
+        if self.last_magnitude_buffer.is_empty() || max_peaks == 0 || sample_rate <= 0.0 {
+            return Vec::new();
+        }
+
+        let freq_bins = self.fft_size / 2;
+        let freq_resolution = sample_rate / self.fft_size as f32;
+        let Some(band) = peaks::band_bins(freq_bins, freq_resolution, flow_hz, fhigh_hz) else {
+            return Vec::new();
+        };
+
+        let stride = max_peaks * 2;
+        let mut result = vec![0.0f32; self.last_num_frames * stride];
+        for frame_idx in 0..self.last_num_frames {
+            let frame_data = &self.last_magnitude_buffer[frame_idx * freq_bins..(frame_idx + 1) * freq_bins];
+            let found = peaks::find_prominent_peaks(
+                frame_data,
+                freq_resolution,
+                band,
+                max_peaks,
+                min_prominence_db,
+                min_separation_hz,
+                peaks::PeakInterpolation::Gaussian,
+            );
+            let slots = &mut result[frame_idx * stride..(frame_idx + 1) * stride];
+            for (k, slot) in slots.chunks_exact_mut(2).enumerate() {
+                match found.get(k) {
+                    Some(p) => {
+                        slot[0] = p.peak.freq_hz;
+                        slot[1] = p.peak.power_db;
+                    }
+                    None => slot[1] = f32::NEG_INFINITY,
+                }
+            }
+        }
+        result
+    }
+
+    /// 計算分區噪聲基底
+    ///
+    /// 基於在最後一次 compute_spectrogram_u8 調用中計算的線性幅度值，
+    /// 把頻率軸按 zone_width_hz 分區（由 0 Hz 起算），取指定幀範圍內每個區域的百分位數。
+    ///
+    /// # Arguments
+    /// * `sample_rate` - 採樣率 (Hz)
+    /// * `start_frame` - 起始幀
+    /// * `end_frame` - 結束幀（包含在內，超出範圍時截斷）
+    /// * `zone_width_hz` - 區域寬度 (Hz，典型值 10000)
+    /// * `percentile` - 百分位數 (0-100，典型值 25)
+    ///
+    /// # Returns
+    /// Float32Array，每個元素是一個區域的噪聲基底 (dB，20 * log10(幅度))；
+    /// 第 k 個元素對應 [k * zone_width_hz, (k + 1) * zone_width_hz)
+    #[wasm_bindgen]
+    pub fn get_zonal_noise_floors(
+        &self,
+        sample_rate: f32,
+        start_frame: usize,
+        end_frame: usize,
+        zone_width_hz: f32,
+        percentile: f32,
+    ) -> Vec<f32> {
+        if self.last_magnitude_buffer.is_empty() || start_frame >= self.last_num_frames || start_frame > end_frame {
+            return Vec::new();
+        }
+
+        let freq_bins = self.fft_size / 2;
+        let freq_resolution = sample_rate as f64 / self.fft_size as f64;
+        let freqs: Vec<f64> = (0..freq_bins).map(|i| i as f64 * freq_resolution).collect();
+        let mut estimator = noise_floor::ZonalNoiseEstimator::new(&freqs, zone_width_hz as f64);
+
+        let mut frame_db = vec![0.0f32; freq_bins];
+        for frame_idx in start_frame..=end_frame.min(self.last_num_frames - 1) {
+            let frame_data = &self.last_magnitude_buffer[frame_idx * freq_bins..(frame_idx + 1) * freq_bins];
+            // 與 compute_spectrogram_u8 相同的 dB 轉換
+            for (db, &mag) in frame_db.iter_mut().zip(frame_data) {
+                *db = 20.0 * mag.max(1e-10).log10();
+            }
+            estimator.add_frame(&frame_db);
+        }
+
+        estimator.floors(noise_floor::NoiseStatistic::Percentile(percentile))
+    }
+
     /// 設置 256 色的色彩映射 (RGBA)
     /// 
     /// # Arguments
@@ -600,21 +788,90 @@ impl SpectrogramEngine {
                 }
 
                 // 步驟 4: 色彩化
-                let clamped_idx = (magnitude * 255.0).clamp(0.0, 255.0) as usize;
-                let rgba = self.color_map.get(clamped_idx).copied().unwrap_or(0);
-
-                // 解包 RGBA 並寫入輸出
                 let pixel_idx = (y * width + x) * 4;
-                output[pixel_idx] = (rgba >> 24) as u8;      // R
-                output[pixel_idx + 1] = ((rgba >> 16) & 0xFF) as u8;  // G
-                output[pixel_idx + 2] = ((rgba >> 8) & 0xFF) as u8;   // B
-                output[pixel_idx + 3] = (rgba & 0xFF) as u8;          // A
+                self.write_color(&mut output[pixel_idx..pixel_idx + 4], magnitude);
+            }
+        }
+
+        output
+    }
+
+    /// 將過零點序列渲染為 RGBA 圖像
+    ///
+    /// 使用與 compute_spectrogram_image 相同的色彩映射：背景為色彩映射的第 0 色，
+    /// 點為第 255 色。時間軸左到右，頻率軸線性、從上到下對應從高到低頻率。
+    /// 範圍外或非有限值 (NaN / Infinity) 的點略過。
+    ///
+    /// # Arguments
+    /// * `times` - 點的時間 (秒，Float32Array)
+    /// * `frequencies` - 點的頻率 (Hz，Float32Array，與 times 一一對應)
+    /// * `width` - 輸出圖像寬度 (時間軸)
+    /// * `height` - 輸出圖像高度 (頻率軸)
+    /// * `start_time` - 圖像左邊界時間 (秒)
+    /// * `end_time` - 圖像右邊界時間 (秒)
+    /// * `freq_min` - 圖像底部頻率 (Hz)
+    /// * `freq_max` - 圖像頂部頻率 (Hz)
+    ///
+    /// # Returns
+    /// RGBA 圖像數據 (Uint8ClampedArray) 大小：width * height * 4
+    #[wasm_bindgen]
+    #[allow(clippy::too_many_arguments)]
+    pub fn compute_dots_image(
+        &self,
+        times: &[f32],
+        frequencies: &[f32],
+        width: usize,
+        height: usize,
+        start_time: f32,
+        end_time: f32,
+        freq_min: f32,
+        freq_max: f32,
+    ) -> Vec<u8> {
+        let mut output = vec![0u8; width * height * 4];
+        if width == 0 || height == 0 || self.color_map.is_empty() {
+            return output;
+        }
+
+        // 步驟 1: 將點柵格化為 [0, 1] 強度
+        let mut intensity = vec![0.0f32; width * height];
+        let time_span = end_time - start_time;
+        let freq_span = freq_max - freq_min;
+        if time_span > 0.0 && freq_span > 0.0 {
+            for (&t, &f) in times.iter().zip(frequencies.iter()) {
+                // NaN 與任何值比較皆為 false，須先排除
+                if !t.is_finite() || !f.is_finite() {
+                    continue;
+                }
+                if t < start_time || t >= end_time || f < freq_min || f > freq_max {
+                    continue;
+                }
+                let x = (((t - start_time) / time_span) * width as f32) as usize;
+                let y_from_bottom = ((f - freq_min) / freq_span * (height - 1) as f32).round() as usize;
+                let y = height - 1 - y_from_bottom.min(height - 1);
+                intensity[y * width + x.min(width - 1)] = 1.0;
             }
         }
 
+        // 步驟 2: 色彩化
+        for (i, &value) in intensity.iter().enumerate() {
+            self.write_color(&mut output[i * 4..i * 4 + 4], value);
+        }
+
         output
     }
 
+    /// 輔助方法：將 [0, 1] 強度按色彩映射寫入一個 RGBA 像素
+    fn write_color(&self, pixel: &mut [u8], value: f32) {
+        let clamped_idx = (value * 255.0).clamp(0.0, 255.0) as usize;
+        let rgba = self.color_map.get(clamped_idx).copied().unwrap_or(0);
+
+        // 解包 RGBA 並寫入輸出
+        pixel[0] = (rgba >> 24) as u8;      // R
+        pixel[1] = ((rgba >> 16) & 0xFF) as u8;  // G
+        pixel[2] = ((rgba >> 8) & 0xFF) as u8;   // B
+        pixel[3] = (rgba & 0xFF) as u8;          // A
+    }
+
     /// 輔助方法：計算單幀的頻譜 (返回 u8 值)
     fn compute_frame_spectrum(&mut self, frame: &[f32], gain_db: f32, range_db: f32) -> Vec<u8> {
         let fft_size = self.fft_size;
@@ -690,11 +947,13 @@ impl SpectrogramEngine {
         self.use_filter_bank = false;
         self.last_num_frames = 0;
         self.last_global_max = 0.0;
+        self.last_step = 0;
     }
 }
 
 
 /// 根據名稱創建窗函數
+#[allow(clippy::needless_range_loop)]
 fn create_window(window_name: &str, size: usize, alpha: f32) -> Vec<f32> {
     let mut window = vec![0.0; size];
     
@@ -848,6 +1107,12 @@ pub struct WaveformEngine {
     channels: Vec<Vec<f32>>,
 }
 
+impl Default for WaveformEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[wasm_bindgen]
 impl WaveformEngine {
     /// 創建新的 WaveformEngine 實例
@@ -901,6 +1166,7 @@ impl WaveformEngine {
     /// 2. 對於每個像素，在對應的樣本區間內找到最大絕對值
     /// 3. 返回包含所有峰值的數組
     #[wasm_bindgen]
+    #[allow(clippy::needless_range_loop)]
     pub fn get_peaks_in_range(
         &self,
         channel_idx: usize,
@@ -970,6 +1236,46 @@ impl WaveformEngine {
         }
     }
     
+    /// 在指定範圍內渲染分頻偵測器的聲音
+    /// 
+    /// # Arguments
+    /// * `channel_idx` - 通道索引
+    /// * `start_sample` - 起始樣本索引
+    /// * `end_sample` - 結束樣本索引（不包含）
+    /// * `sample_rate` - 採樣率 (Hz)
+    /// * `division` - 分頻比（典型值: 10）
+    /// * `hysteresis` - 觸發門限（絕對幅度，典型值: 0.01）
+    /// 
+    /// # Returns
+    /// Float32Array，與範圍等長的方波渲染（幅度跟隨輸入包絡）
+    #[wasm_bindgen]
+    pub fn render_frequency_division(
+        &self,
+        channel_idx: usize,
+        start_sample: usize,
+        end_sample: usize,
+        sample_rate: f32,
+        division: usize,
+        hysteresis: f32,
+    ) -> Vec<f32> {
+        if channel_idx >= self.channels.len() {
+            return Vec::new();
+        }
+        
+        let channel_data = &self.channels[channel_idx];
+        let end_sample = end_sample.min(channel_data.len());
+        if start_sample >= end_sample {
+            return Vec::new();
+        }
+        
+        freq_division::frequency_division(
+            &channel_data[start_sample..end_sample],
+            sample_rate,
+            division,
+            hysteresis,
+        )
+    }
+    
     /// 獲取通道數量
     /// 
     /// # Returns
@@ -1016,6 +1322,7 @@ impl WaveformEngine {
 /// # Returns
 /// 頻域功率譜 (dB 值)
 #[wasm_bindgen]
+#[allow(clippy::needless_range_loop)]
 pub fn compute_power_spectrum(
     audio_data: &[f32],
     sample_rate: u32,
@@ -1129,6 +1436,7 @@ pub fn compute_power_spectrum(
 /// # Returns
 /// 峰值頻率 (Hz)，如果未找到返回 0
 #[wasm_bindgen]
+#[allow(clippy::needless_range_loop)]
 pub fn find_peak_frequency_from_spectrum(
     spectrum: &[f32],
     sample_rate: u32,
@@ -1141,7 +1449,7 @@ pub fn find_peak_frequency_from_spectrum(
     }
 
     let freq_resolution = sample_rate as f32 / fft_size as f32;
-    let min_bin = ((flow_hz / freq_resolution) as usize).max(0);
+    let min_bin = (flow_hz / freq_resolution) as usize;
     let max_bin = ((fhigh_hz / freq_resolution) as usize)
         .min(spectrum.len().saturating_sub(1));
 
@@ -1177,3 +1485,120 @@ pub fn find_peak_frequency_from_spectrum(
     // 無插值，直接返回
     peak_bin as f32 * freq_resolution
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 256_000.0;
+
+    /// 各音調之和：(頻率 Hz, 幅度, 開始樣本, 結束樣本)
+    fn tones(len: usize, parts: &[(f32, f32, usize, usize)]) -> Vec<f32> {
+        let mut audio = vec![0.0f32; len];
+        for &(frequency, amplitude, start, end) in parts {
+            for (i, sample) in audio.iter_mut().enumerate().take(end).skip(start) {
+                *sample += amplitude * (2.0 * std::f32::consts::PI * frequency * i as f32 / SAMPLE_RATE).sin();
+            }
+        }
+        audio
+    }
+
+    fn engine(audio: &[f32]) -> SpectrogramEngine {
+        let mut engine = SpectrogramEngine::new(1024, "hann".to_string(), None);
+        engine.compute_spectrogram_u8(audio, 512, 0.0, 80.0);
+        engine
+    }
+
+    #[test]
+    fn peak_track_refines_tone_between_bins() {
+        // 40.1 kHz 位於頻率箱 160 與 161 之間；頻帶外有更強的 80 kHz 音調
+        let audio = tones(10_240, &[(40_100.0, 0.6, 0, 10_240), (80_000.0, 1.0, 0, 10_240)]);
+        let engine = engine(&audio);
+        let track = engine.get_peak_track(SAMPLE_RATE, 30_000.0, 50_000.0, 0.4, "gaussian");
+        assert_eq!(track.len(), 19 * 3);
+        for (frame_idx, point) in track.chunks_exact(3).enumerate() {
+            assert!((point[0] - 40_100.0).abs() < 5.0, "{}", point[0]);
+            assert!((point[1] - (frame_idx * 512 + 512) as f32 / SAMPLE_RATE).abs() < 1e-6);
+            assert!((point[2] - 20.0 * 0.3f32.log10()).abs() < 0.5, "{}", point[2]);
+        }
+
+        // 不插值時落在頻率箱中心
+        let track = engine.get_peak_track(SAMPLE_RATE, 30_000.0, 50_000.0, 0.4, "none");
+        assert_eq!(track[0], 160.0 * 250.0);
+
+        // 頻帶包含 80 kHz 時追蹤最強音調
+        let track = engine.get_peak_track(SAMPLE_RATE, 30_000.0, 100_000.0, 0.4, "parabolic");
+        assert!(track.chunks_exact(3).all(|p| (p[0] - 80_000.0).abs() < 1.0));
+    }
+
+    #[test]
+    fn peak_track_marks_quiet_frames() {
+        // 後半段靜音：低於閾值的幀頻率為 0、幅度為 -Infinity
+        let audio = tones(10_240, &[(40_100.0, 0.5, 0, 5_120)]);
+        let engine = engine(&audio);
+        let track = engine.get_peak_track(SAMPLE_RATE, 30_000.0, 50_000.0, 0.4, "gaussian");
+        let frames: Vec<&[f32]> = track.chunks_exact(3).collect();
+        assert!(frames[..8].iter().all(|p| (p[0] - 40_100.0).abs() < 5.0));
+        assert!(frames[11..].iter().all(|p| p[0] == 0.0 && p[2] == f32::NEG_INFINITY));
+
+        assert!(engine.get_peak_track(SAMPLE_RATE, 50_000.0, 30_000.0, 0.4, "gaussian").is_empty());
+        let empty = SpectrogramEngine::new(1024, "hann".to_string(), None);
+        assert!(empty.get_peak_track(SAMPLE_RATE, 30_000.0, 50_000.0, 0.4, "gaussian").is_empty());
+    }
+
+    #[test]
+    fn multi_peaks_layout_and_selection() {
+        // 60 kHz 最強；41 kHz 比 40 kHz 強，兩者相距 1 kHz
+        let audio = tones(10_240, &[(40_000.0, 0.3, 0, 10_240), (41_000.0, 0.5, 0, 10_240), (60_000.0, 1.0, 0, 10_240)]);
+        let engine = engine(&audio);
+        let frame = |result: &[f32], max_peaks: usize| result[..max_peaks * 2].to_vec();
+
+        let result = engine.get_multi_peaks(SAMPLE_RATE, 30_000.0, 70_000.0, 3, 6.0, 500.0);
+        assert_eq!(result.len(), 19 * 6);
+        let first = frame(&result, 3);
+        for (slot, expected) in first.chunks_exact(2).zip([(60_000.0, 1.0f32), (41_000.0, 0.5), (40_000.0, 0.3)]) {
+            assert!((slot[0] - expected.0).abs() < 5.0, "{:?}", first);
+            assert!((slot[1] - 20.0 * (expected.1 / 2.0).log10()).abs() < 0.5, "{:?}", first);
+        }
+        assert!(result.chunks_exact(6).all(|f| f.iter().zip(&first).step_by(2).all(|(a, b)| (a - b).abs() < 5.0)));
+
+        // 間距 2 kHz：較弱的 40 kHz 被略過，第三個位置留給其他峰值或留空
+        let result = engine.get_multi_peaks(SAMPLE_RATE, 30_000.0, 70_000.0, 3, 6.0, 2_000.0);
+        let slots = frame(&result, 3);
+        assert!((slots[0] - 60_000.0).abs() < 5.0 && (slots[2] - 41_000.0).abs() < 5.0);
+        assert!(slots[4] == 0.0 || (slots[4] - 40_000.0).abs() > 2_000.0, "{:?}", slots);
+
+        // 數量上限 1 只保留最強峰；頻帶排除 60 kHz 後由 41 kHz 領先
+        assert!((engine.get_multi_peaks(SAMPLE_RATE, 30_000.0, 70_000.0, 1, 6.0, 0.0)[0] - 60_000.0).abs() < 5.0);
+        assert!((engine.get_multi_peaks(SAMPLE_RATE, 30_000.0, 50_000.0, 1, 6.0, 0.0)[0] - 41_000.0).abs() < 5.0);
+    }
+
+    #[test]
+    fn multi_peaks_leave_unused_slots_empty() {
+        let engine = engine(&vec![0.0f32; 4096]);
+        let result = engine.get_multi_peaks(SAMPLE_RATE, 30_000.0, 70_000.0, 2, 6.0, 0.0);
+        assert_eq!(result.len(), 7 * 4);
+        assert!(result.chunks_exact(2).all(|slot| slot[0] == 0.0 && slot[1] == f32::NEG_INFINITY));
+        assert!(engine.get_multi_peaks(SAMPLE_RATE, 30_000.0, 70_000.0, 0, 6.0, 0.0).is_empty());
+    }
+
+    #[test]
+    fn waveform_frequency_division_range() {
+        let audio = tones(25_600, &[(40_000.0, 0.5, 0, 25_600)]);
+        let mut waveform = WaveformEngine::new();
+        waveform.resize(1);
+        waveform.load_channel(0, &audio);
+
+        // 輸出與所選範圍等長，頻率為 f / N
+        let output = waveform.render_frequency_division(0, 2_560, 23_040, SAMPLE_RATE, 8, 0.01);
+        assert_eq!(output.len(), 20_480);
+        assert_eq!(output, freq_division::frequency_division(&audio[2_560..23_040], SAMPLE_RATE, 8, 0.01));
+        let flips = output.windows(2).filter(|w| (w[0] < 0.0) != (w[1] < 0.0)).count();
+        assert!((flips as f32 / 2.0 / (20_480.0 / SAMPLE_RATE) - 5_000.0).abs() < 100.0, "{}", flips);
+
+        // 結束位置截斷到通道長度；無效通道或空範圍返回空
+        assert_eq!(waveform.render_frequency_division(0, 25_000, 30_000, SAMPLE_RATE, 8, 0.01).len(), 600);
+        assert!(waveform.render_frequency_division(1, 0, 1_000, SAMPLE_RATE, 8, 0.01).is_empty());
+        assert!(waveform.render_frequency_division(0, 1_000, 1_000, SAMPLE_RATE, 8, 0.01).is_empty());
+    }
+}
@@ -0,0 +1,213 @@
+// ============================================================
+// 分區噪聲基底估計
+// 把頻率軸按固定寬度分區，在指定的幀範圍內統計每個區域的功率分佈，
+// 以百分位數或眾數作為該區域的噪聲基底，供閾值搜索與 SNR 共用
+// ============================================================
+
+/// 沒有數據的區域的噪聲基底 (dB)；眾數統計時低於此值的功率以此計
+pub const MIN_NOISE_FLOOR_DB: f32 = -100.0;
+/// 眾數直方圖的 dB 範圍
+const MODE_HIST_MIN_DB: i32 = -120;
+const MODE_HIST_MAX_DB: i32 = 20;
+
+/// 噪聲基底的統計量
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NoiseStatistic {
+    /// 百分位數 (0-100)，例如 25 表示 25% 的功率低於噪聲基底
+    Percentile(f32),
+    /// 1 dB 直方圖的眾數（平手時取較低值），與 batCallDetector.js 的 calculateZonalNoiseFloors 一致
+    Mode,
+}
+
+/// 分區噪聲基底估計器
+///
+/// 逐幀加入功率 (dB)，最後按區域計算噪聲基底。
+/// 第 k 個區域覆蓋頻率 [k * zone_width_hz, (k + 1) * zone_width_hz)，由 0 Hz 起算。
+pub struct ZonalNoiseEstimator {
+    zone_width_hz: f64,
+    /// 每個頻率箱所屬的區域
+    zone_of_bin: Vec<usize>,
+    /// 每個區域收集的功率 (dB)
+    values: Vec<Vec<f32>>,
+}
+
+impl ZonalNoiseEstimator {
+    /// 創建估計器
+    ///
+    /// # Arguments
+    /// * `freqs_hz` - 每個頻率箱的頻率 (Hz)
+    /// * `zone_width_hz` - 區域寬度 (Hz)
+    pub fn new(freqs_hz: &[f64], zone_width_hz: f64) -> ZonalNoiseEstimator {
+        let zone_width_hz = if zone_width_hz > 0.0 { zone_width_hz } else { 10000.0 };
+        let zone_of_bin: Vec<usize> = freqs_hz
+            .iter()
+            .map(|&f| (f.max(0.0) / zone_width_hz).floor() as usize)
+            .collect();
+        let num_zones = zone_of_bin.iter().max().map_or(0, |&z| z + 1);
+        ZonalNoiseEstimator {
+            zone_width_hz,
+            zone_of_bin,
+            values: vec![Vec::new(); num_zones],
+        }
+    }
+
+    /// 區域數量
+    pub fn num_zones(&self) -> usize {
+        self.values.len()
+    }
+
+    /// 區域寬度 (Hz)
+    pub fn zone_width_hz(&self) -> f64 {
+        self.zone_width_hz
+    }
+
+    /// 加入一幀的功率 (dB)，長度應與 freqs_hz 相同，多出的頻率箱會被忽略
+    pub fn add_frame(&mut self, power_db: &[f32]) {
+        for (&zone, &db) in self.zone_of_bin.iter().zip(power_db) {
+            self.values[zone].push(db);
+        }
+    }
+
+    /// 計算每個區域的噪聲基底 (dB)
+    ///
+    /// # Returns
+    /// 長度為 num_zones 的數組；沒有數據的區域為 MIN_NOISE_FLOOR_DB
+    pub fn floors(&self, statistic: NoiseStatistic) -> Vec<f32> {
+        self.values
+            .iter()
+            .map(|zone| match statistic {
+                NoiseStatistic::Percentile(p) => percentile_db(zone, p),
+                NoiseStatistic::Mode => mode_db(zone),
+            })
+            .collect()
+    }
+}
+
+/// 百分位數（取排序後 floor(len * p / 100) 處的值）
+fn percentile_db(values: &[f32], percentile: f32) -> f32 {
+    if values.is_empty() {
+        return MIN_NOISE_FLOOR_DB;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable_by(|a, b| a.total_cmp(b));
+    let idx = (sorted.len() as f32 * percentile.clamp(0.0, 100.0) / 100.0).floor() as usize;
+    sorted[idx.min(sorted.len() - 1)]
+}
+
+/// 1 dB 直方圖的眾數，平手時取較低值
+fn mode_db(values: &[f32]) -> f32 {
+    let hist_range = (MODE_HIST_MAX_DB - MODE_HIST_MIN_DB + 1) as usize;
+    let mut histogram = vec![0u32; hist_range];
+    for &power in values {
+        let db = (power.max(MIN_NOISE_FLOOR_DB).floor() as i32).clamp(MODE_HIST_MIN_DB, MODE_HIST_MAX_DB);
+        histogram[(db - MODE_HIST_MIN_DB) as usize] += 1;
+    }
+
+    let mut max_count = 0;
+    let mut mode = MIN_NOISE_FLOOR_DB;
+    for (i, &count) in histogram.iter().enumerate() {
+        if count > max_count {
+            max_count = count;
+            mode = (i as i32 + MODE_HIST_MIN_DB) as f32;
+        }
+    }
+    mode
+}
+
+/// 計算幀優先排列的功率矩陣在指定幀範圍內的分區噪聲基底
+///
+/// # Arguments
+/// * `power_db` - 功率 (dB)，幀優先排列，每幀 freqs_hz.len() 個頻率箱
+/// * `freqs_hz` - 每個頻率箱的頻率 (Hz)
+/// * `start_frame` - 起始幀
+/// * `end_frame` - 結束幀（包含在內，超出範圍時截斷）
+/// * `zone_width_hz` - 區域寬度 (Hz)
+/// * `statistic` - 統計量
+///
+/// # Returns
+/// 每個區域的噪聲基底 (dB)
+pub fn zonal_noise_floors(
+    power_db: &[f32],
+    freqs_hz: &[f64],
+    start_frame: usize,
+    end_frame: usize,
+    zone_width_hz: f64,
+    statistic: NoiseStatistic,
+) -> Vec<f32> {
+    let mut estimator = ZonalNoiseEstimator::new(freqs_hz, zone_width_hz);
+    let num_bins = freqs_hz.len();
+    if num_bins > 0 {
+        for frame in power_db.chunks_exact(num_bins).take(end_frame.saturating_add(1)).skip(start_frame) {
+            estimator.add_frame(frame);
+        }
+    }
+    estimator.floors(statistic)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentile_of_uniform_distribution() {
+        // 區域 0：-90 .. -51 dB 各一個值
+        let values: Vec<f32> = (0..40).map(|i| -90.0 + i as f32).collect();
+        assert_eq!(percentile_db(&values, 0.0), -90.0);
+        assert_eq!(percentile_db(&values, 25.0), -80.0);
+        assert_eq!(percentile_db(&values, 50.0), -70.0);
+        assert_eq!(percentile_db(&values, 100.0), -51.0);
+        // 超出範圍的百分位數被限制在 0-100
+        assert_eq!(percentile_db(&values, 150.0), -51.0);
+        assert_eq!(percentile_db(&values, -5.0), -90.0);
+        assert_eq!(percentile_db(&[], 25.0), MIN_NOISE_FLOOR_DB);
+    }
+
+    #[test]
+    fn mode_of_histogram() {
+        // -70 dB 區間 (含 -70.0 到 -69.1) 出現最多次
+        let values = [-70.0, -69.5, -69.1, -60.2, -60.9, -80.0];
+        assert_eq!(mode_db(&values), -70.0);
+        // 平手時取較低值
+        assert_eq!(mode_db(&[-60.5, -60.5, -75.5, -75.5]), -76.0);
+        // 低於 MIN_NOISE_FLOOR_DB 的功率以其計
+        assert_eq!(mode_db(&[-140.0, f32::NEG_INFINITY, -60.0]), MIN_NOISE_FLOOR_DB);
+        assert_eq!(mode_db(&[]), MIN_NOISE_FLOOR_DB);
+    }
+
+    #[test]
+    fn floors_per_zone() {
+        // 0, 5, 20, 25 kHz 四個頻率箱；10-20 kHz 區域沒有頻率箱
+        let freqs = [0.0, 5_000.0, 20_000.0, 25_000.0];
+        let mut estimator = ZonalNoiseEstimator::new(&freqs, 10_000.0);
+        assert_eq!(estimator.num_zones(), 3);
+        for i in 0..20 {
+            let low = -90.0 + i as f32;
+            estimator.add_frame(&[low, low + 0.5, -60.0, -60.0 - i as f32]);
+        }
+        // 區域 0：-90 .. -70.5 每 0.5 dB 一個值；區域 2：21 個 -60 與 -79 .. -61
+        let floors = estimator.floors(NoiseStatistic::Percentile(25.0));
+        assert_eq!(floors, vec![-85.0, MIN_NOISE_FLOOR_DB, -69.0]);
+        let floors = estimator.floors(NoiseStatistic::Mode);
+        assert_eq!(floors, vec![-90.0, MIN_NOISE_FLOOR_DB, -60.0]);
+
+        // 區域寬度無效時使用 10 kHz
+        let estimator = ZonalNoiseEstimator::new(&freqs, 0.0);
+        assert_eq!((estimator.num_zones(), estimator.zone_width_hz()), (3, 10_000.0));
+        assert!(ZonalNoiseEstimator::new(&[], 10_000.0).floors(NoiseStatistic::Mode).is_empty());
+    }
+
+    #[test]
+    fn frame_range() {
+        // 第 k 幀所有頻率箱為 -100 + 10k dB
+        let freqs = [0.0, 5_000.0];
+        let power: Vec<f32> = (0..5).flat_map(|k| [-100.0 + 10.0 * k as f32; 2]).collect();
+        let median = NoiseStatistic::Percentile(50.0);
+        assert_eq!(zonal_noise_floors(&power, &freqs, 1, 3, 10_000.0, median), vec![-80.0]);
+        // 結束幀包含在內，超出範圍時截斷
+        assert_eq!(zonal_noise_floors(&power, &freqs, 3, 3, 10_000.0, median), vec![-70.0]);
+        assert_eq!(zonal_noise_floors(&power, &freqs, 3, usize::MAX, 10_000.0, median), vec![-60.0]);
+        // 起始幀超出範圍：區域沒有數據
+        assert_eq!(zonal_noise_floors(&power, &freqs, 10, 20, 10_000.0, median), vec![MIN_NOISE_FLOOR_DB]);
+        assert!(zonal_noise_floors(&power, &[], 0, 4, 10_000.0, median).is_empty());
+    }
+}
@@ -0,0 +1,412 @@
+// ============================================================
+// 頻譜峰值追蹤與多峰值選取
+// 在指定頻帶內逐幀尋找峰值（單一最大值或依顯著度選取的多個峰值），並以插值精修到頻率箱以下的精度
+// ============================================================
+
+/// 峰值插值方法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PeakInterpolation {
+    /// 不插值，取頻率箱中心
+    None,
+    /// 對線性幅度做拋物線插值
+    Parabolic,
+    /// 對對數幅度 (dB) 做拋物線插值，等同於高斯峰形擬合
+    Gaussian,
+}
+
+impl PeakInterpolation {
+    /// 依名稱選擇插值方法 ("parabolic", "gaussian")，其他名稱不插值
+    pub fn from_name(name: &str) -> PeakInterpolation {
+        match name {
+            "parabolic" => PeakInterpolation::Parabolic,
+            "gaussian" => PeakInterpolation::Gaussian,
+            _ => PeakInterpolation::None,
+        }
+    }
+}
+
+/// 一幀的峰值
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpectralPeak {
+    /// 峰值所在的頻率箱
+    pub bin: usize,
+    /// 插值後的頻率 (Hz)
+    pub freq_hz: f32,
+    /// 插值後的幅度 (dB，20 * log10(幅度))
+    pub power_db: f32,
+}
+
+/// 線性幅度轉 dB，與 SpectrogramEngine::compute_spectrogram_u8 相同
+pub fn magnitude_to_db(magnitude: f32) -> f32 {
+    20.0 * magnitude.max(1e-10).log10()
+}
+
+/// 以相鄰兩個頻率箱精修峰值
+///
+/// # Arguments
+/// * `magnitudes` - 一幀的線性幅度
+/// * `bin` - 峰值頻率箱（須為局部最大值）
+/// * `method` - 插值方法
+///
+/// # Returns
+/// (頻率箱偏移 -0.5 ~ 0.5, 峰值 dB)；位於邊界時不插值
+pub fn refine_peak(magnitudes: &[f32], bin: usize, method: PeakInterpolation) -> (f32, f32) {
+    let peak_db = magnitude_to_db(magnitudes[bin]);
+    if bin == 0 || bin + 1 >= magnitudes.len() {
+        return (0.0, peak_db);
+    }
+
+    let (y0, y1, y2) = match method {
+        PeakInterpolation::None => return (0.0, peak_db),
+        PeakInterpolation::Parabolic => (magnitudes[bin - 1], magnitudes[bin], magnitudes[bin + 1]),
+        PeakInterpolation::Gaussian => (
+            magnitude_to_db(magnitudes[bin - 1]),
+            peak_db,
+            magnitude_to_db(magnitudes[bin + 1]),
+        ),
+    };
+    let a = (y2 - 2.0 * y1 + y0) / 2.0;
+    if a.abs() <= 1e-10 {
+        return (0.0, peak_db);
+    }
+    let delta = ((y0 - y2) / (4.0 * a)).clamp(-0.5, 0.5);
+    let peak_value = y1 - (y0 - y2) * delta / 4.0;
+    let refined_db = match method {
+        PeakInterpolation::Parabolic => magnitude_to_db(peak_value),
+        _ => peak_value,
+    };
+    (delta, refined_db.max(peak_db))
+}
+
+/// 頻率範圍 (Hz) 對應的頻率箱範圍（包含在內）
+///
+/// # Returns
+/// 範圍為空時為 None
+pub fn band_bins(num_bins: usize, freq_resolution: f32, flow_hz: f32, fhigh_hz: f32) -> Option<(usize, usize)> {
+    if num_bins == 0 || freq_resolution <= 0.0 {
+        return None;
+    }
+    let min_bin = (flow_hz.max(0.0) / freq_resolution).ceil() as usize;
+    let max_bin = ((fhigh_hz.max(0.0) / freq_resolution).floor() as usize).min(num_bins - 1);
+    if min_bin > max_bin {
+        None
+    } else {
+        Some((min_bin, max_bin))
+    }
+}
+
+/// 在一幀的指定頻帶內尋找最大峰值並插值
+///
+/// # Arguments
+/// * `magnitudes` - 一幀的線性幅度
+/// * `freq_resolution` - 頻率解析度 (Hz)
+/// * `(min_bin, max_bin)` - 搜索的頻率箱範圍（包含在內）
+/// * `min_magnitude` - 峰值幅度下限，低於此值視為沒有峰值
+/// * `method` - 插值方法
+pub fn find_band_peak(
+    magnitudes: &[f32],
+    freq_resolution: f32,
+    (min_bin, max_bin): (usize, usize),
+    min_magnitude: f32,
+    method: PeakInterpolation,
+) -> Option<SpectralPeak> {
+    let max_bin = max_bin.min(magnitudes.len().checked_sub(1)?);
+    if min_bin > max_bin {
+        return None;
+    }
+    let mut bin = min_bin;
+    for i in min_bin + 1..=max_bin {
+        if magnitudes[i] > magnitudes[bin] {
+            bin = i;
+        }
+    }
+    if magnitudes[bin] <= 0.0 || magnitudes[bin] < min_magnitude {
+        return None;
+    }
+
+    // 峰值位於搜索邊界時，只有真正的局部最大值才插值
+    let is_local_max = (bin == 0 || magnitudes[bin] >= magnitudes[bin - 1])
+        && (bin + 1 >= magnitudes.len() || magnitudes[bin] >= magnitudes[bin + 1]);
+    let (delta, power_db) = if is_local_max {
+        refine_peak(magnitudes, bin, method)
+    } else {
+        (0.0, magnitude_to_db(magnitudes[bin]))
+    };
+    Some(SpectralPeak {
+        bin,
+        freq_hz: (bin as f32 + delta) * freq_resolution,
+        power_db,
+    })
+}
+
+/// 具顯著度的頻譜峰值
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProminentPeak {
+    /// 插值後的峰值
+    pub peak: SpectralPeak,
+    /// 顯著度 (dB)：峰值高出兩側「到更高峰值或邊界前的最低點」中較高者的量
+    pub prominence_db: f32,
+}
+
+/// 在一幀的指定頻帶內尋找最多 max_peaks 個峰值
+///
+/// 先找出顯著度不低於 min_prominence_db 的局部最大值，再由強到弱選取，
+/// 與已選峰值相距小於 min_separation_hz 的峰值略過。
+///
+/// # Arguments
+/// * `magnitudes` - 一幀的線性幅度
+/// * `freq_resolution` - 頻率解析度 (Hz)
+/// * `(min_bin, max_bin)` - 搜索的頻率箱範圍（包含在內）
+/// * `max_peaks` - 最多返回的峰值數
+/// * `min_prominence_db` - 最小顯著度 (dB)
+/// * `min_separation_hz` - 峰值之間的最小間距 (Hz)
+/// * `method` - 插值方法
+///
+/// # Returns
+/// 按幅度由強到弱排序的峰值
+pub fn find_prominent_peaks(
+    magnitudes: &[f32],
+    freq_resolution: f32,
+    (min_bin, max_bin): (usize, usize),
+    max_peaks: usize,
+    min_prominence_db: f32,
+    min_separation_hz: f32,
+    method: PeakInterpolation,
+) -> Vec<ProminentPeak> {
+    let Some(last) = magnitudes.len().checked_sub(1) else {
+        return Vec::new();
+    };
+    let max_bin = max_bin.min(last);
+    if max_peaks == 0 || min_bin > max_bin {
+        return Vec::new();
+    }
+    let band_db: Vec<f32> = magnitudes[min_bin..=max_bin].iter().map(|&m| magnitude_to_db(m)).collect();
+    let n = band_db.len();
+
+    let mut candidates = Vec::new();
+    let mut i = 0;
+    while i < n {
+        // 平台視為一個峰值，取平台左端
+        let mut plateau_end = i;
+        while plateau_end + 1 < n && band_db[plateau_end + 1] == band_db[i] {
+            plateau_end += 1;
+        }
+        // 頻帶邊界以頻帶外的相鄰頻率箱判斷，避免把頻帶外峰值的山坡當作峰值
+        let rises = if i == 0 {
+            min_bin == 0 || magnitudes[min_bin - 1] < magnitudes[min_bin]
+        } else {
+            band_db[i - 1] < band_db[i]
+        };
+        let falls = if plateau_end + 1 == n {
+            max_bin == last || magnitudes[max_bin + 1] < magnitudes[max_bin]
+        } else {
+            band_db[plateau_end + 1] < band_db[i]
+        };
+        if rises && falls {
+            let height = band_db[i];
+            let left_min = band_db[..i]
+                .iter()
+                .rev()
+                .take_while(|&&v| v <= height)
+                .fold(height, |acc, &v| acc.min(v));
+            let right_min = band_db[plateau_end + 1..]
+                .iter()
+                .take_while(|&&v| v <= height)
+                .fold(height, |acc, &v| acc.min(v));
+            let prominence_db = height - left_min.max(right_min);
+            if prominence_db >= min_prominence_db {
+                let bin = min_bin + i;
+                let (delta, power_db) = refine_peak(magnitudes, bin, method);
+                candidates.push(ProminentPeak {
+                    peak: SpectralPeak {
+                        bin,
+                        freq_hz: (bin as f32 + delta) * freq_resolution,
+                        power_db,
+                    },
+                    prominence_db,
+                });
+            }
+        }
+        i = plateau_end + 1;
+    }
+
+    candidates.sort_by(|a, b| b.peak.power_db.total_cmp(&a.peak.power_db));
+    let mut selected: Vec<ProminentPeak> = Vec::with_capacity(max_peaks.min(candidates.len()));
+    for candidate in candidates {
+        if selected.len() >= max_peaks {
+            break;
+        }
+        if selected
+            .iter()
+            .all(|p| (p.peak.freq_hz - candidate.peak.freq_hz).abs() >= min_separation_hz)
+        {
+            selected.push(candidate);
+        }
+    }
+    selected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_complex::Complex;
+    use rustfft::FftPlanner;
+
+    const FFT_SIZE: usize = 1024;
+    const SAMPLE_RATE: f32 = 256_000.0;
+    const RESOLUTION: f32 = SAMPLE_RATE / FFT_SIZE as f32;
+
+    /// Hann 窗下各音調的單幀幅度譜 (|X| * 2 / N)
+    fn spectrum(tones: &[(f32, f32)]) -> Vec<f32> {
+        let window = crate::create_window("hann", FFT_SIZE, 0.16);
+        let mut buffer: Vec<Complex<f32>> = (0..FFT_SIZE)
+            .map(|i| {
+                let t = i as f32 / SAMPLE_RATE;
+                let x: f32 = tones.iter().map(|&(f, a)| a * (2.0 * std::f32::consts::PI * f * t).sin()).sum();
+                Complex::new(x * window[i], 0.0)
+            })
+            .collect();
+        FftPlanner::new().plan_fft_forward(FFT_SIZE).process(&mut buffer);
+        buffer[..FFT_SIZE / 2].iter().map(|c| c.norm() * 2.0 / FFT_SIZE as f32).collect()
+    }
+
+    #[test]
+    fn refines_tone_between_bins() {
+        // 頻率箱寬 250 Hz；高斯插值誤差 < 0.02 箱，拋物線 < 0.06 箱，不插值時 ≤ 0.5 箱
+        let band = band_bins(FFT_SIZE / 2, RESOLUTION, 10_000.0, 120_000.0).unwrap();
+        for frequency in [40_000.0, 40_062.5, 40_100.0, 40_125.0, 40_187.5, 87_654.0] {
+            let magnitudes = spectrum(&[(frequency, 1.0)]);
+            let error = |method| {
+                let peak = find_band_peak(&magnitudes, RESOLUTION, band, 0.0, method).unwrap();
+                assert_eq!(peak.bin, (frequency / RESOLUTION).round() as usize);
+                (peak.freq_hz - frequency).abs() / RESOLUTION
+            };
+            assert!(error(PeakInterpolation::Gaussian) < 0.02, "{}: {}", frequency, error(PeakInterpolation::Gaussian));
+            assert!(error(PeakInterpolation::Parabolic) < 0.06, "{}: {}", frequency, error(PeakInterpolation::Parabolic));
+            assert!(error(PeakInterpolation::None) <= 0.5 + 1e-3);
+        }
+    }
+
+    #[test]
+    fn refined_level_recovers_scalloping_loss() {
+        // 半個頻率箱處 Hann 窗的幅度損失約 1.4 dB；插值後更接近真實峰值 (-6.02 dB)
+        let magnitudes = spectrum(&[(40_125.0, 1.0)]);
+        let band = (0, FFT_SIZE / 2 - 1);
+        let level = |method| find_band_peak(&magnitudes, RESOLUTION, band, 0.0, method).unwrap().power_db;
+        let none = level(PeakInterpolation::None);
+        assert!((none + 7.45).abs() < 0.1, "{}", none);
+        for method in [PeakInterpolation::Parabolic, PeakInterpolation::Gaussian] {
+            assert!((level(method) + 6.02).abs() < (none + 6.02).abs(), "{:?}: {}", method, level(method));
+        }
+
+        // 高斯峰形（dB 為拋物線）可完全還原
+        let shape: Vec<f32> = (0..8).map(|i| 10f32.powf(-((i as f32 - 3.3).powi(2)) / 20.0)).collect();
+        let (delta, peak_db) = refine_peak(&shape, 3, PeakInterpolation::Gaussian);
+        assert!((delta - 0.3).abs() < 1e-4 && peak_db.abs() < 1e-4, "{} {}", delta, peak_db);
+    }
+
+    #[test]
+    fn ignores_bins_outside_band() {
+        // 頻帶外更強的音調不影響頻帶內的峰值
+        let magnitudes = spectrum(&[(40_100.0, 0.5), (80_000.0, 1.0)]);
+        let band = band_bins(FFT_SIZE / 2, RESOLUTION, 30_000.0, 50_000.0).unwrap();
+        assert_eq!(band, (120, 200));
+        let peak = find_band_peak(&magnitudes, RESOLUTION, band, 0.0, PeakInterpolation::Gaussian).unwrap();
+        assert!((peak.freq_hz - 40_100.0).abs() < 5.0, "{}", peak.freq_hz);
+
+        // 頻帶只包含強音調的山坡時，峰值落在邊界且不插值
+        let band = band_bins(FFT_SIZE / 2, RESOLUTION, 60_000.0, 79_000.0).unwrap();
+        let peak = find_band_peak(&magnitudes, RESOLUTION, band, 0.0, PeakInterpolation::Gaussian).unwrap();
+        assert_eq!(peak.bin, band.1);
+        assert_eq!(peak.freq_hz, band.1 as f32 * RESOLUTION);
+
+        // 低於幅度下限時沒有峰值
+        assert!(find_band_peak(&magnitudes, RESOLUTION, (120, 200), 0.5, PeakInterpolation::None).is_none());
+        assert!(find_band_peak(&[], RESOLUTION, (0, 10), 0.0, PeakInterpolation::None).is_none());
+    }
+
+    #[test]
+    fn band_bin_ranges() {
+        assert_eq!(band_bins(512, 250.0, 10_000.0, 20_000.0), Some((40, 80)));
+        assert_eq!(band_bins(512, 250.0, 10_100.0, 20_100.0), Some((41, 80)));
+        assert_eq!(band_bins(512, 250.0, 0.0, 1e9), Some((0, 511)));
+        assert_eq!(band_bins(512, 250.0, 20_100.0, 20_200.0), None);
+        assert_eq!(band_bins(0, 250.0, 0.0, 1000.0), None);
+    }
+
+    /// 合成譜：-60 dB 底上的三角峰（每箱 6 dB），bin 14 是 A 山坡上的肩部，bin 40–41 是平台峰
+    fn synthetic_spectrum() -> Vec<f32> {
+        let peaks = [(10, -10.0), (30, -20.0), (33, -25.0), (40, -30.0), (41, -30.0), (50, -40.0)];
+        let mut db: Vec<f32> = (0..60)
+            .map(|i: i32| {
+                peaks
+                    .iter()
+                    .map(|&(bin, level)| level - 6.0 * (i - bin).abs() as f32)
+                    .fold(-60.0, f32::max)
+            })
+            .collect();
+        db[14] = -27.0;
+        db.iter().map(|&d| 10f32.powf(d / 20.0)).collect()
+    }
+
+    fn bins(peaks: &[ProminentPeak]) -> Vec<usize> {
+        peaks.iter().map(|p| p.peak.bin).collect()
+    }
+
+    #[test]
+    fn prominence_threshold() {
+        let magnitudes = synthetic_spectrum();
+        let find = |min_prominence_db| {
+            find_prominent_peaks(&magnitudes, 250.0, (0, 59), 10, min_prominence_db, 0.0, PeakInterpolation::Gaussian)
+        };
+
+        // 由強到弱排序；肩部顯著度只有 1 dB
+        let peaks = find(3.0);
+        assert_eq!(bins(&peaks), vec![10, 30, 33, 40, 50]);
+        let prominences: Vec<f32> = peaks.iter().map(|p| p.prominence_db).collect();
+        for (value, expected) in prominences.iter().zip([50.0, 40.0, 6.0, 18.0, 20.0]) {
+            assert!((value - expected).abs() < 1e-3, "{:?}", prominences);
+        }
+        // 對稱峰插值後仍在頻率箱中心，平台峰取左端
+        assert!((peaks[1].peak.freq_hz - 7500.0).abs() < 1e-2);
+        assert!((peaks[1].peak.power_db + 20.0).abs() < 1e-3);
+
+        assert_eq!(bins(&find(0.5)), vec![10, 30, 33, 14, 40, 50]);
+        assert_eq!(bins(&find(10.0)), vec![10, 30, 40, 50]);
+        assert_eq!(bins(&find(60.0)), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn separation_and_max_count() {
+        let magnitudes = synthetic_spectrum();
+        let find = |max_peaks, min_separation_hz| {
+            bins(&find_prominent_peaks(&magnitudes, 250.0, (0, 59), max_peaks, 3.0, min_separation_hz, PeakInterpolation::None))
+        };
+        // bin 33 距更強的 bin 30 只有 750 Hz
+        assert_eq!(find(10, 750.0), vec![10, 30, 33, 40, 50]);
+        assert_eq!(find(10, 1000.0), vec![10, 30, 40, 50]);
+        // 間距大到只剩最強峰
+        assert_eq!(find(10, 20_000.0), vec![10]);
+        // 數量上限保留最強的峰，被略過的峰不佔名額
+        assert_eq!(find(2, 0.0), vec![10, 30]);
+        assert_eq!(find(3, 1000.0), vec![10, 30, 40]);
+        assert!(find(0, 0.0).is_empty());
+    }
+
+    #[test]
+    fn band_edges_ignore_hillsides() {
+        let magnitudes = synthetic_spectrum();
+        let find = |band| bins(&find_prominent_peaks(&magnitudes, 250.0, band, 10, 0.5, 0.0, PeakInterpolation::None));
+        // bin 12 是 A 的下坡，不是峰值；肩部仍保留
+        assert_eq!(find((12, 59)), vec![30, 33, 14, 40, 50]);
+        // bin 29 是 B 的上坡
+        assert_eq!(find((0, 29)), vec![10, 14]);
+        // 頻帶邊界上的真正峰值仍是峰值，但頻帶外一側不計入顯著度
+        let edge = find_prominent_peaks(&magnitudes, 250.0, (30, 32), 10, 0.0, 0.0, PeakInterpolation::None);
+        assert_eq!(bins(&edge), vec![30]);
+        assert_eq!(edge[0].prominence_db, 0.0);
+        assert!(find((30, 32)).is_empty());
+        assert!(find((60, 70)).is_empty());
+        assert!(find_prominent_peaks(&[], 250.0, (0, 10), 3, 0.0, 0.0, PeakInterpolation::None).is_empty());
+    }
+}
@@ -0,0 +1,416 @@
+// ============================================================
+// 多相採樣率轉換
+// 有理數比例使用多相 FIR；無法化為小整數比的比例使用
+// 高解析度 sinc 插值表（任意比例）
+// ============================================================
+
+use std::f64::consts::PI;
+
+use wasm_bindgen::prelude::*;
+
+use crate::fir::{bessel_i0, kaiser_beta};
+
+/// 有理數比例的最大上採樣因子，超過則改用任意比例插值
+const MAX_RATIONAL_FACTOR: usize = 4096;
+/// 任意比例插值表中每個輸入樣本的細分數
+const SINC_TABLE_RESOLUTION: usize = 512;
+/// 默認每側零交叉數（決定濾波器長度與過渡帶陡峭程度）
+const DEFAULT_ZERO_CROSSINGS: usize = 16;
+/// 通帶截止相對於新 Nyquist 的比例（留出過渡帶以抑制混疊）
+const ROLLOFF: f64 = 0.92;
+/// 阻帶衰減目標 (dB)
+const STOPBAND_ATTEN_DB: f64 = 90.0;
+
+fn gcd(mut a: usize, mut b: usize) -> usize {
+    while b != 0 {
+        let t = a % b;
+        a = b;
+        b = t;
+    }
+    a
+}
+
+/// 加 Kaiser 窗的 sinc 核，`t` 與 `half_len` 使用相同單位
+fn windowed_sinc(t: f64, cutoff: f64, half_len: f64, beta: f64, beta_norm: f64) -> f64 {
+    if t.abs() >= half_len {
+        return 0.0;
+    }
+    let x = 2.0 * cutoff * t;
+    let sinc = if x.abs() < 1e-12 { 1.0 } else { (PI * x).sin() / (PI * x) };
+    let r = t / half_len;
+    let window = bessel_i0(beta * (1.0 - r * r).max(0.0).sqrt()) / beta_norm;
+    2.0 * cutoff * sinc * window
+}
+
+/// 重採樣的實際實現方式
+enum Kernel {
+    /// 有理數比例 up / down，多相係數按相位排列
+    Rational {
+        up: usize,
+        down: usize,
+        /// phases[p][j] = h[p + j * up]
+        phases: Vec<Vec<f32>>,
+        /// 原型濾波器在上採樣域的延遲
+        delay: usize,
+    },
+    /// 任意比例，使用單側 sinc 查表
+    Arbitrary {
+        table: Vec<f32>,
+        half_len: f64,
+    },
+}
+
+/// Resampler: 高品質採樣率轉換器
+///
+/// 當兩個採樣率之比可化為小整數比（例如 384 kHz → 250 kHz = 125 / 192）時，
+/// 使用多相 FIR 精確實現；否則使用任意比例的 sinc 插值。
+/// 兩種方式都以 Kaiser 窗低通濾波抑制混疊，輸出與輸入在時間上對齊。
+#[wasm_bindgen]
+pub struct Resampler {
+    input_rate: f64,
+    output_rate: f64,
+    kernel: Kernel,
+}
+
+#[wasm_bindgen]
+impl Resampler {
+    /// 創建新的 Resampler 實例
+    ///
+    /// # Arguments
+    /// * `input_rate` - 輸入採樣率 (Hz)
+    /// * `output_rate` - 輸出採樣率 (Hz)
+    /// * `zero_crossings` - 濾波器每側的零交叉數（0 表示默認 16；越大越陡但越慢）
+    #[wasm_bindgen(constructor)]
+    pub fn new(input_rate: f64, output_rate: f64, zero_crossings: usize) -> Resampler {
+        let zero_crossings = if zero_crossings == 0 { DEFAULT_ZERO_CROSSINGS } else { zero_crossings };
+        let input_rate = if input_rate > 0.0 { input_rate } else { 1.0 };
+        let output_rate = if output_rate > 0.0 { output_rate } else { input_rate };
+
+        let kernel = Self::rational_factors(input_rate, output_rate)
+            .map(|(up, down)| Self::design_rational(up, down, zero_crossings))
+            .unwrap_or_else(|| Self::design_arbitrary(output_rate / input_rate, zero_crossings));
+
+        Resampler {
+            input_rate,
+            output_rate,
+            kernel,
+        }
+    }
+
+    /// 是否使用有理數多相實現
+    #[wasm_bindgen]
+    pub fn is_rational(&self) -> bool {
+        matches!(self.kernel, Kernel::Rational { .. })
+    }
+
+    /// 上採樣因子（任意比例時返回 0）
+    #[wasm_bindgen]
+    pub fn get_up_factor(&self) -> usize {
+        match self.kernel {
+            Kernel::Rational { up, .. } => up,
+            Kernel::Arbitrary { .. } => 0,
+        }
+    }
+
+    /// 下採樣因子（任意比例時返回 0）
+    #[wasm_bindgen]
+    pub fn get_down_factor(&self) -> usize {
+        match self.kernel {
+            Kernel::Rational { down, .. } => down,
+            Kernel::Arbitrary { .. } => 0,
+        }
+    }
+
+    /// 獲取輸出採樣率
+    #[wasm_bindgen]
+    pub fn get_output_rate(&self) -> f64 {
+        self.output_rate
+    }
+
+    /// 給定輸入長度時的輸出樣本數
+    #[wasm_bindgen]
+    pub fn get_output_length(&self, input_len: usize) -> usize {
+        match self.kernel {
+            // 以 u64 計算，避免 wasm32 上 input_len * up 溢出
+            Kernel::Rational { up, down, .. } => (input_len as u64 * up as u64).div_ceil(down as u64) as usize,
+            Kernel::Arbitrary { .. } => (input_len as f64 * self.output_rate / self.input_rate).ceil() as usize,
+        }
+    }
+
+    /// 對音頻進行重採樣
+    ///
+    /// # Arguments
+    /// * `audio_data` - 輸入音頻 (Float32Array)
+    ///
+    /// # Returns
+    /// 輸出採樣率下的音頻
+    #[wasm_bindgen]
+    pub fn process(&self, audio_data: &[f32]) -> Vec<f32> {
+        if audio_data.is_empty() {
+            return Vec::new();
+        }
+        let output_len = self.get_output_length(audio_data.len());
+
+        match &self.kernel {
+            Kernel::Rational { up, down, phases, delay } => {
+                Self::process_rational(audio_data, *up, *down, phases, *delay, output_len)
+            }
+            Kernel::Arbitrary { table, half_len } => {
+                let step = self.input_rate / self.output_rate;
+                Self::process_arbitrary(audio_data, step, table, *half_len, output_len)
+            }
+        }
+    }
+}
+
+impl Resampler {
+    /// 若兩個採樣率皆為整數且約分後的因子夠小，返回 (up, down)
+    fn rational_factors(input_rate: f64, output_rate: f64) -> Option<(usize, usize)> {
+        if input_rate.fract() != 0.0 || output_rate.fract() != 0.0 {
+            return None;
+        }
+        let input = input_rate as usize;
+        let output = output_rate as usize;
+        let divisor = gcd(input, output);
+        let up = output / divisor;
+        let down = input / divisor;
+        if up <= MAX_RATIONAL_FACTOR && down <= MAX_RATIONAL_FACTOR {
+            Some((up, down))
+        } else {
+            None
+        }
+    }
+
+    fn design_rational(up: usize, down: usize, zero_crossings: usize) -> Kernel {
+        if up == 1 && down == 1 {
+            return Kernel::Rational {
+                up,
+                down,
+                phases: vec![vec![1.0]],
+                delay: 0,
+            };
+        }
+
+        // 在上採樣域 (up * fs_in) 設計原型低通
+        let factor = up.max(down);
+        let cutoff = 0.5 * ROLLOFF / factor as f64;
+        let half_len = zero_crossings * factor;
+        let beta = kaiser_beta(STOPBAND_ATTEN_DB);
+        let beta_norm = bessel_i0(beta);
+
+        let len = 2 * half_len + 1;
+        let prototype: Vec<f64> = (0..len)
+            .map(|k| {
+                let t = k as f64 - half_len as f64;
+                up as f64 * windowed_sinc(t, cutoff, half_len as f64 + 1.0, beta, beta_norm)
+            })
+            .collect();
+
+        let phases = (0..up)
+            .map(|p| prototype.iter().skip(p).step_by(up).map(|&h| h as f32).collect())
+            .collect();
+
+        Kernel::Rational {
+            up,
+            down,
+            phases,
+            delay: half_len,
+        }
+    }
+
+    fn design_arbitrary(ratio: f64, zero_crossings: usize) -> Kernel {
+        // 以輸入樣本為單位的截止頻率與核長度
+        let scale = ratio.min(1.0);
+        let cutoff = 0.5 * ROLLOFF * scale;
+        let half_len = zero_crossings as f64 / scale;
+        let beta = kaiser_beta(STOPBAND_ATTEN_DB);
+        let beta_norm = bessel_i0(beta);
+
+        let table_len = (half_len * SINC_TABLE_RESOLUTION as f64).ceil() as usize + 2;
+        let table = (0..table_len)
+            .map(|i| {
+                let t = i as f64 / SINC_TABLE_RESOLUTION as f64;
+                windowed_sinc(t, cutoff, half_len, beta, beta_norm) as f32
+            })
+            .collect();
+
+        Kernel::Arbitrary { table, half_len }
+    }
+
+    fn process_rational(
+        audio_data: &[f32],
+        up: usize,
+        down: usize,
+        phases: &[Vec<f32>],
+        delay: usize,
+        output_len: usize,
+    ) -> Vec<f32> {
+        let len = audio_data.len() as isize;
+        let (up, down) = (up as u64, down as u64);
+        let mut output = Vec::with_capacity(output_len);
+
+        for n in 0..output_len {
+            // 上採樣域中的位置，加上原型延遲以對齊時間（以 u64 計算，避免 wasm32 上溢出）
+            let t = n as u64 * down + delay as u64;
+            let base = (t / up) as isize;
+            let taps = &phases[(t % up) as usize];
+
+            let mut sum = 0.0f32;
+            for (j, &h) in taps.iter().enumerate() {
+                let idx = base - j as isize;
+                if idx < 0 {
+                    break;
+                }
+                if idx < len {
+                    sum += h * audio_data[idx as usize];
+                }
+            }
+            output.push(sum);
+        }
+
+        output
+    }
+
+    fn process_arbitrary(audio_data: &[f32], step: f64, table: &[f32], half_len: f64, output_len: usize) -> Vec<f32> {
+        let len = audio_data.len() as isize;
+        let resolution = SINC_TABLE_RESOLUTION as f64;
+        let lookup = |offset: f64| -> f32 {
+            let pos = offset.abs() * resolution;
+            let idx = pos as usize;
+            if idx + 1 >= table.len() {
+                return 0.0;
+            }
+            let frac = (pos - idx as f64) as f32;
+            table[idx] * (1.0 - frac) + table[idx + 1] * frac
+        };
+
+        let mut output = Vec::with_capacity(output_len);
+        for n in 0..output_len {
+            let t = n as f64 * step;
+            let first = ((t - half_len).floor() as isize + 1).max(0);
+            let last = ((t + half_len).floor() as isize).min(len - 1);
+
+            let mut sum = 0.0f32;
+            let mut k = first;
+            while k <= last {
+                sum += audio_data[k as usize] * lookup(t - k as f64);
+                k += 1;
+            }
+            output.push(sum);
+        }
+
+        output
+    }
+}
+
+/// 一次性重採樣
+///
+/// # Arguments
+/// * `audio_data` - 輸入音頻 (Float32Array)
+/// * `input_rate` - 輸入採樣率 (Hz)
+/// * `output_rate` - 輸出採樣率 (Hz)
+///
+/// # Returns
+/// 輸出採樣率下的音頻
+#[wasm_bindgen]
+pub fn resample_audio(audio_data: &[f32], input_rate: f64, output_rate: f64) -> Vec<f32> {
+    Resampler::new(input_rate, output_rate, 0).process(audio_data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(freq_hz: f64, sample_rate: f64, len: usize) -> Vec<f32> {
+        (0..len).map(|i| (2.0 * PI * freq_hz * i as f64 / sample_rate).sin() as f32 * 0.5).collect()
+    }
+
+    /// 中段（避開兩端瞬態）與理想正弦的最大誤差
+    fn max_error_from_sine(output: &[f32], freq_hz: f64, sample_rate: f64) -> f32 {
+        let expected = sine(freq_hz, sample_rate, output.len());
+        let margin = output.len() / 8;
+        (margin..output.len() - margin)
+            .map(|i| (output[i] - expected[i]).abs())
+            .fold(0.0, f32::max)
+    }
+
+    /// 中段 RMS 相對振幅 0.5 的 dB
+    fn rms_db(output: &[f32]) -> f32 {
+        let margin = output.len() / 8;
+        let middle = &output[margin..output.len() - margin];
+        let rms = (middle.iter().map(|&x| x * x).sum::<f32>() / middle.len() as f32).sqrt();
+        20.0 * (rms / (0.5 / 2f32.sqrt())).max(1e-12).log10()
+    }
+
+    #[test]
+    fn reduces_rates_to_rational_factors() {
+        assert_eq!(Resampler::rational_factors(384_000.0, 250_000.0), Some((125, 192)));
+        assert_eq!(Resampler::rational_factors(44_100.0, 48_000.0), Some((160, 147)));
+        assert_eq!(Resampler::rational_factors(48_000.0, 48_000.0), Some((1, 1)));
+        assert_eq!(Resampler::rational_factors(44_100.5, 48_000.0), None);
+        // 約分後因子超過上限
+        assert_eq!(Resampler::rational_factors(4099.0, 4097.0), None);
+
+        let rational = Resampler::new(384_000.0, 250_000.0, 0);
+        assert!(rational.is_rational());
+        assert_eq!((rational.get_up_factor(), rational.get_down_factor()), (125, 192));
+        let arbitrary = Resampler::new(48_000.0, 44_100.5, 0);
+        assert!(!arbitrary.is_rational());
+        assert_eq!((arbitrary.get_up_factor(), arbitrary.get_down_factor()), (0, 0));
+    }
+
+    #[test]
+    fn output_length() {
+        let rational = Resampler::new(384_000.0, 250_000.0, 0);
+        assert_eq!(rational.get_output_length(0), 0);
+        assert_eq!(rational.get_output_length(192), 125);
+        assert_eq!(rational.get_output_length(1000), 652);
+        // input_len * up 超過 u32：wasm32 上曾溢出
+        assert_eq!(rational.get_output_length(40_000_000), 26_041_667);
+
+        let arbitrary = Resampler::new(48_000.0, 44_100.5, 0);
+        assert_eq!(arbitrary.get_output_length(48_000), 44_101);
+        assert_eq!(arbitrary.process(&vec![0.0; 480]).len(), arbitrary.get_output_length(480));
+        assert!(arbitrary.process(&[]).is_empty());
+    }
+
+    #[test]
+    fn rational_tone_keeps_frequency_amplitude_and_timing() {
+        // 與理想正弦逐點比較：頻率、振幅或延遲任一錯誤都會產生大誤差
+        let input = sine(20_000.0, 384_000.0, 38_400);
+        let output = resample_audio(&input, 384_000.0, 250_000.0);
+        assert_eq!(output.len(), 25_000);
+        let error = max_error_from_sine(&output, 20_000.0, 250_000.0);
+        assert!(error < 1e-3, "{}", error);
+    }
+
+    #[test]
+    fn arbitrary_tone_keeps_frequency_amplitude_and_timing() {
+        let input = sine(5_000.0, 48_000.0, 9_600);
+        let resampler = Resampler::new(48_000.0, 44_100.5, 0);
+        let output = resampler.process(&input);
+        let error = max_error_from_sine(&output, 5_000.0, 44_100.5);
+        assert!(error < 1e-3, "{}", error);
+    }
+
+    #[test]
+    fn large_rational_positions_do_not_overflow() {
+        // n * down 超過 u32 範圍的輸出位置
+        let resampler = Resampler::new(4095.0, 4096.0, 0);
+        assert_eq!((resampler.get_up_factor(), resampler.get_down_factor()), (4096, 4095));
+        let input = sine(100.0, 4095.0, 1_200_000);
+        let output = resampler.process(&input);
+        assert_eq!(output.len(), resampler.get_output_length(input.len()));
+        let error = max_error_from_sine(&output, 100.0, 4096.0);
+        assert!(error < 1e-3, "{}", error);
+    }
+
+    #[test]
+    fn attenuates_tones_above_new_nyquist() {
+        let rational = resample_audio(&sine(150_000.0, 384_000.0, 38_400), 384_000.0, 250_000.0);
+        assert!(rms_db(&rational) < -80.0, "{}", rms_db(&rational));
+
+        let arbitrary = Resampler::new(48_000.0, 22_050.5, 0).process(&sine(15_000.0, 48_000.0, 9_600));
+        assert!(rms_db(&arbitrary) < -80.0, "{}", rms_db(&arbitrary));
+    }
+}
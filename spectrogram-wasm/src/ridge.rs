@@ -0,0 +1,302 @@
+// ============================================================
+// 頻率脊線（輪廓）提取
+// 以動態規劃 (Viterbi) 在功率頻譜圖中尋找總功率最大、頻率跳變受懲罰的路徑，
+// 避免逐幀最大值在弱幀跳到噪聲或回聲；短暫的斷層可用線性插值填補
+// ============================================================
+
+/// 脊線提取配置
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RidgeConfig {
+    /// 頻率跳變懲罰 (dB / kHz)
+    pub jump_penalty_db_per_khz: f32,
+    /// 相鄰幀允許的最大頻率跳變 (Hz)
+    pub max_jump_hz: f64,
+    /// 路徑功率低於路徑最大功率減此值 (dB) 的幀視為斷層
+    pub dropout_db: f32,
+    /// 以插值填補的最長斷層 (幀)，0 表示不填補；更長的斷層從輪廓中移除
+    pub max_gap_frames: usize,
+}
+
+impl Default for RidgeConfig {
+    fn default() -> Self {
+        RidgeConfig {
+            jump_penalty_db_per_khz: 1.0,
+            max_jump_hz: 5000.0,
+            dropout_db: 30.0,
+            max_gap_frames: 2,
+        }
+    }
+}
+
+/// 輪廓上的一點
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ContourPoint {
+    /// 幀索引
+    pub frame: usize,
+    /// 時間 (秒)
+    pub time_s: f64,
+    /// 頻率 (Hz)，已做頻率箱以下的插值
+    pub freq_hz: f64,
+    /// 功率 (dB)
+    pub power_db: f32,
+    /// 此點由斷層填補插值而來
+    pub interpolated: bool,
+}
+
+/// 在 [first_frame, last_frame] 內提取頻率脊線
+///
+/// 每幀的分數 = 該頻率箱功率 + max(上一幀分數 - 跳變懲罰)，
+/// 跳變超過 max_jump_hz 的轉移不允許；回溯得到最佳路徑後，
+/// 首尾的斷層幀移除，中間不超過 max_gap_frames 的斷層以線性插值填補，更長的斷層移除。
+///
+/// # Arguments
+/// * `power_db` - 功率 (dB)，幀優先排列，每幀 freqs_hz.len() 個頻率箱
+/// * `freqs_hz` - 每個頻率箱的頻率 (Hz)，須等間距遞增
+/// * `times_s` - 每幀的時間 (秒)
+/// * `first_frame` - 起始幀
+/// * `last_frame` - 結束幀（包含在內）
+/// * `config` - 脊線提取配置
+///
+/// # Returns
+/// 按時間排序的輪廓點；範圍內功率完全平坦時為空
+#[allow(clippy::needless_range_loop)]
+pub fn extract_ridge(
+    power_db: &[f32],
+    freqs_hz: &[f64],
+    times_s: &[f64],
+    first_frame: usize,
+    last_frame: usize,
+    config: &RidgeConfig,
+) -> Vec<ContourPoint> {
+    let num_bins = freqs_hz.len();
+    let num_frames = power_db.len().checked_div(num_bins).unwrap_or(0).min(times_s.len());
+    if num_frames == 0 || first_frame > last_frame || first_frame >= num_frames {
+        return Vec::new();
+    }
+    let last_frame = last_frame.min(num_frames - 1);
+    let frame = |f: usize| &power_db[f * num_bins..(f + 1) * num_bins];
+
+    let freq_resolution = if num_bins > 1 { freqs_hz[1] - freqs_hz[0] } else { 1.0 };
+    let max_jump_bins = (config.max_jump_hz.max(0.0) / freq_resolution).floor() as usize;
+    let penalty_per_bin = config.jump_penalty_db_per_khz.max(0.0) * (freq_resolution / 1000.0) as f32;
+
+    // Viterbi 前向
+    let n = last_frame - first_frame + 1;
+    let mut score = frame(first_frame).to_vec();
+    let mut back = vec![0usize; n * num_bins];
+    let mut next = vec![0.0f32; num_bins];
+    for k in 1..n {
+        let current = frame(first_frame + k);
+        for b in 0..num_bins {
+            let lo = b.saturating_sub(max_jump_bins);
+            let hi = (b + max_jump_bins).min(num_bins - 1);
+            let mut best = (f32::NEG_INFINITY, b);
+            for prev in lo..=hi {
+                let candidate = score[prev] - penalty_per_bin * prev.abs_diff(b) as f32;
+                if candidate > best.0 {
+                    best = (candidate, prev);
+                }
+            }
+            next[b] = best.0 + current[b];
+            back[k * num_bins + b] = best.1;
+        }
+        std::mem::swap(&mut score, &mut next);
+    }
+
+    // 回溯
+    let mut path = vec![0usize; n];
+    path[n - 1] = (0..num_bins).fold(0, |best, b| if score[b] > score[best] { b } else { best });
+    for k in (1..n).rev() {
+        path[k - 1] = back[k * num_bins + path[k]];
+    }
+
+    let mut points: Vec<ContourPoint> = path
+        .iter()
+        .enumerate()
+        .map(|(k, &b)| {
+            let f = first_frame + k;
+            let (freq_hz, power) = refine_bin(frame(f), freqs_hz, b, freq_resolution);
+            ContourPoint {
+                frame: f,
+                time_s: times_s[f],
+                freq_hz,
+                power_db: power,
+                interpolated: false,
+            }
+        })
+        .collect();
+
+    // 斷層處理
+    let max_power = points.iter().fold(f32::NEG_INFINITY, |acc, p| acc.max(p.power_db));
+    // 路徑沒有高出範圍內的最低功率（例如整段靜音）時沒有脊線
+    let min_power = power_db[first_frame * num_bins..(last_frame + 1) * num_bins]
+        .iter()
+        .fold(f32::INFINITY, |acc, &v| acc.min(v));
+    if max_power <= min_power {
+        return Vec::new();
+    }
+    let dropout_threshold = max_power - config.dropout_db.max(0.0);
+    let valid: Vec<bool> = points.iter().map(|p| p.power_db >= dropout_threshold).collect();
+    let (Some(start), Some(end)) = (valid.iter().position(|&v| v), valid.iter().rposition(|&v| v)) else {
+        return Vec::new();
+    };
+
+    let mut keep = vec![true; n];
+    let mut k = start;
+    while k <= end {
+        if valid[k] {
+            k += 1;
+            continue;
+        }
+        let gap_start = k;
+        while !valid[k] {
+            k += 1;
+        }
+        // gap_start - 1 與 k 皆為有效幀
+        let gap_len = k - gap_start;
+        if gap_len <= config.max_gap_frames {
+            let (before, after) = (points[gap_start - 1], points[k]);
+            for g in gap_start..k {
+                let ratio = (g - gap_start + 1) as f64 / (gap_len + 1) as f64;
+                let freq_hz = before.freq_hz + (after.freq_hz - before.freq_hz) * ratio;
+                let bin = (((freq_hz - freqs_hz[0]) / freq_resolution).round().max(0.0) as usize).min(num_bins - 1);
+                points[g].freq_hz = freq_hz;
+                points[g].power_db = frame(first_frame + g)[bin];
+                points[g].interpolated = true;
+            }
+        } else {
+            keep[gap_start..k].iter_mut().for_each(|v| *v = false);
+        }
+    }
+
+    points
+        .into_iter()
+        .enumerate()
+        .filter(|&(k, _)| k >= start && k <= end && keep[k])
+        .map(|(_, p)| p)
+        .collect()
+}
+
+/// 頻率箱為局部最大值時以 dB 拋物線插值精修
+fn refine_bin(frame: &[f32], freqs_hz: &[f64], b: usize, freq_resolution: f64) -> (f64, f32) {
+    let power = frame[b];
+    if b == 0 || b + 1 >= frame.len() || power < frame[b - 1] || power < frame[b + 1] {
+        return (freqs_hz[b], power);
+    }
+    let (y0, y1, y2) = (frame[b - 1] as f64, power as f64, frame[b + 1] as f64);
+    let a = (y2 - 2.0 * y1 + y0) / 2.0;
+    if a.abs() <= 1e-10 {
+        return (freqs_hz[b], power);
+    }
+    let delta = ((y0 - y2) / (4.0 * a)).clamp(-0.5, 0.5);
+    (freqs_hz[b] + delta * freq_resolution, (y1 - (y0 - y2) * delta / 4.0) as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NUM_BINS: usize = 100;
+    const RESOLUTION: f64 = 500.0;
+    const FLOOR_DB: f32 = -80.0;
+
+    /// 每幀一個 dB 拋物線峰 (中心頻率箱, 峰值 dB)，偏離 d 箱下降 2d² dB；None 表示該幀只有噪聲底
+    fn spectrogram(ridge: &[Option<(f64, f32)>]) -> (Vec<f32>, Vec<f64>, Vec<f64>) {
+        let freqs: Vec<f64> = (0..NUM_BINS).map(|b| b as f64 * RESOLUTION).collect();
+        let times: Vec<f64> = (0..ridge.len()).map(|f| f as f64 * 0.001).collect();
+        let power = ridge
+            .iter()
+            .flat_map(|point| {
+                (0..NUM_BINS).map(move |b| match point {
+                    Some((centre, level)) => (level - 2.0 * (b as f64 - centre).powi(2) as f32).max(FLOOR_DB),
+                    None => FLOOR_DB,
+                })
+            })
+            .collect();
+        (power, freqs, times)
+    }
+
+    /// 由 bin 80 線性下掃到 bin 40 的 FM 輪廓
+    fn fm_bin(frame: usize) -> f64 {
+        80.0 - 40.0 * frame as f64 / 29.0
+    }
+
+    #[test]
+    fn follows_contour_past_dropouts_and_outliers() {
+        let mut ridge: Vec<Option<(f64, f32)>> = (0..30).map(|f| Some((fm_bin(f), -20.0))).collect();
+        // 開頭靜音、2 幀與 5 幀的斷層
+        for f in [0, 1, 10, 11, 20, 21, 22, 23, 24] {
+            ridge[f] = None;
+        }
+        let (mut power, freqs, times) = spectrogram(&ridge);
+        // 更強的離群點：第 15 幀遠離輪廓 (bin 10, 0 dB)，第 16 幀距輪廓 9 箱 (-15 dB)
+        power[15 * NUM_BINS + 10] = 0.0;
+        let near = (fm_bin(16).round() as usize) + 9;
+        power[16 * NUM_BINS + near] = -15.0;
+
+        let contour = extract_ridge(&power, &freqs, &times, 0, 29, &RidgeConfig::default());
+        let frames: Vec<usize> = contour.iter().map(|p| p.frame).collect();
+        let expected: Vec<usize> = (2..30).filter(|f| !(20..25).contains(f)).collect();
+        assert_eq!(frames, expected);
+
+        for point in &contour {
+            let true_hz = fm_bin(point.frame) * RESOLUTION;
+            assert!((point.freq_hz - true_hz).abs() < 0.5 * RESOLUTION, "{:?} vs {}", point, true_hz);
+            assert!((point.time_s - point.frame as f64 * 0.001).abs() < 1e-12);
+            assert_eq!(point.interpolated, point.frame == 10 || point.frame == 11);
+        }
+        // 非插值點以 dB 拋物線精修到頻率箱以下
+        let exact = contour.iter().filter(|p| !p.interpolated).all(|p| (p.freq_hz - fm_bin(p.frame) * RESOLUTION).abs() < 1.0);
+        assert!(exact);
+
+        // 不填補時 2 幀斷層也被移除
+        let no_gap = RidgeConfig {
+            max_gap_frames: 0,
+            ..RidgeConfig::default()
+        };
+        let contour = extract_ridge(&power, &freqs, &times, 0, 29, &no_gap);
+        assert!(contour.iter().all(|p| !p.interpolated && p.frame != 10 && p.frame != 11));
+    }
+
+    #[test]
+    fn jump_penalty_keeps_track_on_contour() {
+        // 輪廓旁 4 箱 (2 kHz) 處有兩幀比輪廓強 1.5 dB 的干擾：來回跳變的懲罰 4 dB 大於增益 3 dB
+        let ridge: Vec<Option<(f64, f32)>> = (0..20).map(|_| Some((50.0, -20.0))).collect();
+        let (mut power, freqs, times) = spectrogram(&ridge);
+        for f in 8..10 {
+            power[f * NUM_BINS + 54] = -18.5;
+        }
+        let contour = extract_ridge(&power, &freqs, &times, 0, 19, &RidgeConfig::default());
+        assert_eq!(contour.len(), 20);
+        assert!(contour.iter().all(|p| (p.freq_hz - 25_000.0).abs() < 1.0));
+
+        // 沒有跳變懲罰時逐幀取最大值
+        let free = RidgeConfig {
+            jump_penalty_db_per_khz: 0.0,
+            ..RidgeConfig::default()
+        };
+        let contour = extract_ridge(&power, &freqs, &times, 0, 19, &free);
+        assert!((contour[8].freq_hz - 27_000.0).abs() < 0.5 * RESOLUTION, "{}", contour[8].freq_hz);
+    }
+
+    #[test]
+    fn frame_range_is_respected() {
+        let ridge: Vec<Option<(f64, f32)>> = (0..30).map(|f| Some((fm_bin(f), -20.0))).collect();
+        let (power, freqs, times) = spectrogram(&ridge);
+        let contour = extract_ridge(&power, &freqs, &times, 5, 12, &RidgeConfig::default());
+        assert_eq!(contour.first().map(|p| p.frame), Some(5));
+        assert_eq!(contour.last().map(|p| p.frame), Some(12));
+        // 結束幀超出範圍時截斷
+        assert_eq!(extract_ridge(&power, &freqs, &times, 25, 100, &RidgeConfig::default()).len(), 5);
+    }
+
+    #[test]
+    fn empty_or_silent_input() {
+        let config = RidgeConfig::default();
+        assert!(extract_ridge(&[], &[], &[], 0, 10, &config).is_empty());
+        let (power, freqs, times) = spectrogram(&[None; 10]);
+        assert!(extract_ridge(&power, &freqs, &times, 0, 9, &config).is_empty());
+        assert!(extract_ridge(&power, &freqs, &times, 5, 4, &config).is_empty());
+        assert!(extract_ridge(&power, &freqs, &times, 10, 20, &config).is_empty());
+    }
+}
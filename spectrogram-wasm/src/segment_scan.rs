@@ -0,0 +1,345 @@
+// ============================================================
+// 基於頻帶能量的快速分段掃描
+// 以 50% 重疊的 FFT 幀逐幀計算頻帶能量包絡，配合遲滯、最短時長與斷層橋接，
+// 直接返回加上保留時間的樣本範圍，不需把完整頻譜圖傳到 JavaScript
+// ============================================================
+
+use num_complex::Complex;
+use rustfft::FftPlanner;
+use wasm_bindgen::prelude::*;
+
+use crate::create_window;
+
+/// 分段掃描配置
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SegmentScanConfig {
+    /// 頻帶下限 (Hz)
+    pub low_hz: f32,
+    /// 頻帶上限 (Hz)，不低於 Nyquist 時取到 Nyquist
+    pub high_hz: f32,
+    /// FFT 大小（跳步為一半）
+    pub fft_size: usize,
+    /// 進入活動狀態的能量閾值 (dB)
+    pub threshold_db: f32,
+    /// 遲滯 (dB)：能量降到 threshold_db - hysteresis_db 以下才離開活動狀態
+    pub hysteresis_db: f32,
+    /// 短於此時長 (毫秒) 的範圍捨棄（在橋接之後判斷）
+    pub min_duration_ms: f32,
+    /// 相距不超過此時長 (毫秒) 的範圍合併
+    pub max_gap_ms: f32,
+    /// 每個範圍前後的保留時間 (毫秒)
+    pub padding_ms: f32,
+}
+
+impl Default for SegmentScanConfig {
+    fn default() -> Self {
+        SegmentScanConfig {
+            low_hz: 10000.0,
+            high_hz: 150000.0,
+            fft_size: 1024,
+            threshold_db: -60.0,
+            hysteresis_db: 3.0,
+            min_duration_ms: 0.0,
+            max_gap_ms: 0.0,
+            padding_ms: 5.0,
+        }
+    }
+}
+
+/// 計算每幀的頻帶能量 (dB)
+///
+/// 幅度與 SpectrogramEngine::compute_spectrogram 相同 (|X| * 2 / N)，
+/// 能量 = 10 * log10(Σ 幅度² / (N / 4))，與 batCallDetector.js 的快速掃描閾值一致。
+///
+/// # Returns
+/// (每幀能量, 跳步)
+fn band_energy_envelope(audio_data: &[f32], sample_rate: f32, config: &SegmentScanConfig) -> (Vec<f32>, usize) {
+    let fft_size = config.fft_size;
+    let hop_size = fft_size / 2;
+    if fft_size < 2 || audio_data.len() < fft_size || sample_rate <= 0.0 {
+        return (Vec::new(), hop_size);
+    }
+
+    let freq_resolution = sample_rate / fft_size as f32;
+    let min_bin = (config.low_hz / freq_resolution).floor().max(0.0) as usize;
+    let max_bin = ((config.high_hz / freq_resolution).ceil().max(0.0) as usize).min(fft_size / 2 - 1);
+    if max_bin < min_bin {
+        return (Vec::new(), hop_size);
+    }
+
+    let window = create_window("hann", fft_size, 0.16);
+    let fft = FftPlanner::new().plan_fft_forward(fft_size);
+    let mut buffer = vec![Complex::default(); fft_size];
+    let scale = 2.0 / fft_size as f32;
+    let reference = fft_size as f32 * 0.25;
+
+    let num_frames = (audio_data.len() - fft_size) / hop_size + 1;
+    let mut envelope = Vec::with_capacity(num_frames);
+    for frame_idx in 0..num_frames {
+        let start = frame_idx * hop_size;
+        for i in 0..fft_size {
+            buffer[i] = Complex::new(audio_data[start + i] * window[i], 0.0);
+        }
+        fft.process(&mut buffer);
+        let energy: f32 = buffer[min_bin..=max_bin]
+            .iter()
+            .map(|c| {
+                let magnitude = c.norm() * scale;
+                magnitude * magnitude
+            })
+            .sum();
+        envelope.push(10.0 * (energy / reference).max(1e-30).log10());
+    }
+    (envelope, hop_size)
+}
+
+/// 每個範圍前後加上保留樣本，並合併重疊的範圍
+///
+/// # Arguments
+/// * `segments` - (起始樣本, 結束樣本) 列表
+/// * `total_samples` - 音頻長度
+/// * `padding` - 保留樣本數
+pub fn merge_and_pad(mut segments: Vec<(usize, usize)>, total_samples: usize, padding: usize) -> Vec<(usize, usize)> {
+    segments.sort_unstable();
+    let mut merged: Vec<(usize, usize)> = Vec::new();
+    for (start, end) in segments {
+        let start = start.saturating_sub(padding);
+        let end = (end + padding).min(total_samples);
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// 掃描頻帶能量超過閾值的範圍
+///
+/// 幀能量高於 threshold_db 時進入活動狀態，降到 threshold_db - hysteresis_db 以下時離開；
+/// 相距不超過 max_gap_ms 的範圍先合併，再捨棄短於 min_duration_ms 的範圍，
+/// 最後前後加上 padding_ms 並合併重疊部分。
+///
+/// # Arguments
+/// * `audio_data` - 音頻樣本
+/// * `sample_rate` - 採樣率 (Hz)
+/// * `config` - 掃描配置
+///
+/// # Returns
+/// 按時間排序、互不重疊的 (起始樣本, 結束樣本) 列表
+pub fn scan_segments(audio_data: &[f32], sample_rate: f32, config: &SegmentScanConfig) -> Vec<(usize, usize)> {
+    let (envelope, hop_size) = band_energy_envelope(audio_data, sample_rate, config);
+    if envelope.is_empty() {
+        return Vec::new();
+    }
+
+    // 遲滯門限
+    let on_db = config.threshold_db;
+    let off_db = config.threshold_db - config.hysteresis_db.max(0.0);
+    let mut runs: Vec<(usize, usize)> = Vec::new();
+    let mut run_start: Option<usize> = None;
+    for (frame_idx, &db) in envelope.iter().enumerate() {
+        match run_start {
+            None if db > on_db => run_start = Some(frame_idx),
+            Some(start) if db <= off_db => {
+                runs.push((start, frame_idx - 1));
+                run_start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(start) = run_start {
+        runs.push((start, envelope.len() - 1));
+    }
+
+    // 幀範圍轉為樣本範圍：覆蓋首幀開頭到末幀結尾
+    let fft_size = config.fft_size;
+    let ms_to_samples = |ms: f32| (ms.max(0.0) / 1000.0 * sample_rate).round() as usize;
+    let max_gap = ms_to_samples(config.max_gap_ms);
+    let min_duration = ms_to_samples(config.min_duration_ms);
+
+    let mut bridged: Vec<(usize, usize)> = Vec::new();
+    for (first, last) in runs {
+        let start = first * hop_size;
+        let end = (last * hop_size + fft_size).min(audio_data.len());
+        match bridged.last_mut() {
+            Some(prev) if start <= prev.1 + max_gap => prev.1 = prev.1.max(end),
+            _ => bridged.push((start, end)),
+        }
+    }
+    bridged.retain(|&(start, end)| end - start >= min_duration);
+
+    merge_and_pad(bridged, audio_data.len(), ms_to_samples(config.padding_ms))
+}
+
+/// 掃描頻帶能量超過閾值的範圍（FFT 大小 1024）
+///
+/// # Arguments
+/// * `audio_data` - 音頻數據 (Float32Array)
+/// * `sample_rate` - 採樣率 (Hz)
+/// * `low_hz` - 頻帶下限 (Hz)
+/// * `high_hz` - 頻帶上限 (Hz)
+/// * `threshold_db` - 能量閾值 (dB，典型值 -60)
+/// * `hysteresis_db` - 遲滯 (dB)
+/// * `min_duration_ms` - 最短時長 (毫秒)
+/// * `max_gap_ms` - 橋接的最大斷層 (毫秒)
+/// * `padding_ms` - 前後保留時間 (毫秒)
+///
+/// # Returns
+/// 扁平的樣本範圍 (Uint32Array)：[start0, end0, start1, end1, ...]
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn scan_energy_segments(
+    audio_data: &[f32],
+    sample_rate: f32,
+    low_hz: f32,
+    high_hz: f32,
+    threshold_db: f32,
+    hysteresis_db: f32,
+    min_duration_ms: f32,
+    max_gap_ms: f32,
+    padding_ms: f32,
+) -> Vec<u32> {
+    let config = SegmentScanConfig {
+        low_hz,
+        high_hz,
+        threshold_db,
+        hysteresis_db,
+        min_duration_ms,
+        max_gap_ms,
+        padding_ms,
+        ..SegmentScanConfig::default()
+    };
+    scan_segments(audio_data, sample_rate, &config)
+        .into_iter()
+        .flat_map(|(start, end)| [start as u32, end as u32])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 256_000.0;
+    /// 一幀 (1024 樣本) 的時長
+    const FRAME_MS: f32 = 4.0;
+
+    fn ms(samples: usize) -> f32 {
+        samples as f32 / SAMPLE_RATE * 1000.0
+    }
+
+    /// 靜音中的 40 kHz 音段：(開始 ms, 結束 ms, 幅度)
+    fn bursts(total_ms: f32, parts: &[(f32, f32, f32)]) -> Vec<f32> {
+        let to_sample = |t: f32| (t / 1000.0 * SAMPLE_RATE) as usize;
+        let mut audio = vec![0.0f32; to_sample(total_ms)];
+        for &(start, end, amplitude) in parts {
+            for (i, sample) in audio[to_sample(start)..to_sample(end)].iter_mut().enumerate() {
+                *sample = amplitude * (2.0 * std::f32::consts::PI * 40_000.0 * i as f32 / SAMPLE_RATE).sin();
+            }
+        }
+        audio
+    }
+
+    fn config() -> SegmentScanConfig {
+        SegmentScanConfig {
+            threshold_db: -38.0,
+            hysteresis_db: 0.0,
+            padding_ms: 0.0,
+            ..SegmentScanConfig::default()
+        }
+    }
+
+    /// 偵測範圍覆蓋音段，且前後最多多出一幀
+    fn assert_covers(range: (usize, usize), start_ms: f32, end_ms: f32) {
+        let (start, end) = (ms(range.0), ms(range.1));
+        assert!(start <= start_ms && start > start_ms - FRAME_MS, "{} vs {}", start, start_ms);
+        assert!(end >= end_ms && end < end_ms + FRAME_MS, "{} vs {}", end, end_ms);
+    }
+
+    #[test]
+    fn hysteresis_holds_quieter_tail() {
+        // 0.5 幅度約 -34 dB，0.2 幅度約 -42 dB
+        let audio = bursts(120.0, &[(20.0, 40.0, 0.5), (40.0, 60.0, 0.2), (90.0, 100.0, 0.2)]);
+
+        let segments = scan_segments(&audio, SAMPLE_RATE, &config());
+        assert_eq!(segments.len(), 1);
+        assert_covers(segments[0], 20.0, 40.0);
+
+        // 遲滯 6 dB：-42 dB 的尾段保持活動，但單獨的 -42 dB 音段不會觸發
+        let segments = scan_segments(&audio, SAMPLE_RATE, &SegmentScanConfig { hysteresis_db: 6.0, ..config() });
+        assert_eq!(segments.len(), 1);
+        assert_covers(segments[0], 20.0, 60.0);
+    }
+
+    #[test]
+    fn bridges_gaps() {
+        let audio = bursts(170.0, &[(20.0, 40.0, 0.5), (70.0, 90.0, 0.5), (140.0, 145.0, 0.5)]);
+
+        let segments = scan_segments(&audio, SAMPLE_RATE, &SegmentScanConfig { max_gap_ms: 10.0, ..config() });
+        assert_eq!(segments.len(), 3);
+        assert_covers(segments[0], 20.0, 40.0);
+        assert_covers(segments[1], 70.0, 90.0);
+        assert_covers(segments[2], 140.0, 145.0);
+
+        let segments = scan_segments(&audio, SAMPLE_RATE, &SegmentScanConfig { max_gap_ms: 30.0, ..config() });
+        assert_eq!(segments.len(), 2);
+        assert_covers(segments[0], 20.0, 90.0);
+        assert_covers(segments[1], 140.0, 145.0);
+    }
+
+    #[test]
+    fn drops_short_ranges_after_bridging() {
+        let audio = bursts(200.0, &[(20.0, 21.0, 0.5), (60.0, 80.0, 0.5), (120.0, 123.0, 0.5), (133.0, 136.0, 0.5)]);
+        let base = SegmentScanConfig {
+            min_duration_ms: 12.0,
+            ..config()
+        };
+
+        // 1 ms 與兩個 3 ms 的音段都短於 12 ms
+        let segments = scan_segments(&audio, SAMPLE_RATE, &base);
+        assert_eq!(segments.len(), 1);
+        assert_covers(segments[0], 60.0, 80.0);
+
+        // 兩個 3 ms 音段橋接後長於 12 ms，保留
+        let segments = scan_segments(&audio, SAMPLE_RATE, &SegmentScanConfig { max_gap_ms: 10.0, ..base });
+        assert_eq!(segments.len(), 2);
+        assert_covers(segments[1], 120.0, 136.0);
+    }
+
+    #[test]
+    fn padding_clamps_and_merges() {
+        // 音段緊貼文件兩端，中間兩段加上保留時間後重疊
+        let audio = bursts(100.0, &[(0.0, 10.0, 0.5), (40.0, 50.0, 0.5), (56.0, 66.0, 0.5), (92.0, 100.0, 0.5)]);
+        let segments = scan_segments(&audio, SAMPLE_RATE, &SegmentScanConfig { padding_ms: 5.0, ..config() });
+        assert_eq!(segments.len(), 3);
+        assert_eq!(segments[0].0, 0);
+        assert_eq!(segments[2].1, audio.len());
+        assert_covers(segments[1], 35.0, 71.0);
+
+        // 扁平輸出與 scan_segments 一致
+        let flat = scan_energy_segments(&audio, SAMPLE_RATE, 10000.0, 150000.0, -38.0, 0.0, 0.0, 0.0, 5.0);
+        let expected: Vec<u32> = segments.iter().flat_map(|&(s, e)| [s as u32, e as u32]).collect();
+        assert_eq!(flat, expected);
+    }
+
+    #[test]
+    fn merge_and_pad_ranges() {
+        assert_eq!(merge_and_pad(vec![(500, 600), (10, 20), (90, 100)], 610, 50), vec![(0, 150), (450, 610)]);
+        // 相接的範圍也合併
+        assert_eq!(merge_and_pad(vec![(0, 10), (10, 20)], 100, 0), vec![(0, 20)]);
+        assert_eq!(merge_and_pad(vec![(0, 10), (20, 30)], 100, 4), vec![(0, 14), (16, 34)]);
+        assert!(merge_and_pad(Vec::new(), 100, 10).is_empty());
+    }
+
+    #[test]
+    fn silent_or_short_input() {
+        assert!(scan_segments(&[0.0; 50_000], SAMPLE_RATE, &config()).is_empty());
+        assert!(scan_segments(&[0.5; 100], SAMPLE_RATE, &config()).is_empty());
+        // 頻帶外的音調不觸發
+        let audio = bursts(50.0, &[(10.0, 40.0, 0.5)]);
+        let band = SegmentScanConfig {
+            low_hz: 60_000.0,
+            ..config()
+        };
+        assert!(scan_segments(&audio, SAMPLE_RATE, &band).is_empty());
+    }
+}
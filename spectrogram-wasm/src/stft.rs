@@ -0,0 +1,224 @@
+// ============================================================
+// STFT / ISTFT 分析與重建
+// 供降噪、相位聲碼器等需要重新合成音頻的模塊共用
+// ============================================================
+
+use std::sync::Arc;
+
+use num_complex::Complex;
+use rustfft::{Fft, FftPlanner};
+
+use crate::create_window;
+
+/// 短時傅里葉變換處理器
+///
+/// 幀以中心對齊方式排列：第 k 幀的中心位於樣本 k * hop_size，
+/// 信號兩端以零填充 fft_size / 2 個樣本，因此邊緣的樣本也能被完整重建。
+pub struct StftProcessor {
+    fft_size: usize,
+    hop_size: usize,
+    window: Vec<f32>,
+    forward: Arc<dyn Fft<f32>>,
+    inverse: Arc<dyn Fft<f32>>,
+    buffer: Vec<Complex<f32>>,
+}
+
+impl StftProcessor {
+    /// 創建新的 STFT 處理器
+    ///
+    /// # Arguments
+    /// * `fft_size` - FFT 大小
+    /// * `hop_size` - 幀間步長（樣本數，0 時使用 fft_size / 4）
+    /// * `window_func` - 窗函數名稱（見 `create_window`）
+    pub fn new(fft_size: usize, hop_size: usize, window_func: &str) -> StftProcessor {
+        let fft_size = fft_size.max(2);
+        let hop_size = if hop_size == 0 { fft_size / 4 } else { hop_size }.clamp(1, fft_size);
+
+        let mut planner = FftPlanner::new();
+        let forward = planner.plan_fft_forward(fft_size);
+        let inverse = planner.plan_fft_inverse(fft_size);
+
+        StftProcessor {
+            fft_size,
+            hop_size,
+            window: create_window(window_func, fft_size, 0.16),
+            forward,
+            inverse,
+            buffer: vec![Complex::default(); fft_size],
+        }
+    }
+
+    /// 獲取 FFT 大小
+    pub fn fft_size(&self) -> usize {
+        self.fft_size
+    }
+
+    /// 獲取幀間步長
+    pub fn hop_size(&self) -> usize {
+        self.hop_size
+    }
+
+    /// 每幀的頻率箱數 (fft_size / 2 + 1，包含 DC 與 Nyquist)
+    pub fn num_bins(&self) -> usize {
+        self.fft_size / 2 + 1
+    }
+
+    /// 給定信號長度時的幀數
+    pub fn num_frames(&self, signal_len: usize) -> usize {
+        if signal_len == 0 {
+            0
+        } else {
+            signal_len / self.hop_size + 1
+        }
+    }
+
+    /// 分析：將信號轉換為複數頻譜幀
+    ///
+    /// # Returns
+    /// 每幀 num_bins 個複數值的幀列表
+    pub fn analyze(&mut self, signal: &[f32]) -> Vec<Vec<Complex<f32>>> {
+        let num_frames = self.num_frames(signal.len());
+        let half = (self.fft_size / 2) as isize;
+        let num_bins = self.num_bins();
+        let mut frames = Vec::with_capacity(num_frames);
+
+        for frame_idx in 0..num_frames {
+            let start = (frame_idx * self.hop_size) as isize - half;
+
+            // 應用窗函數，超出範圍的樣本視為 0
+            for i in 0..self.fft_size {
+                let pos = start + i as isize;
+                let sample = if pos >= 0 && (pos as usize) < signal.len() {
+                    signal[pos as usize]
+                } else {
+                    0.0
+                };
+                self.buffer[i] = Complex::new(sample * self.window[i], 0.0);
+            }
+
+            self.forward.process(&mut self.buffer);
+            frames.push(self.buffer[..num_bins].to_vec());
+        }
+
+        frames
+    }
+
+    /// 合成：以加權重疊相加 (WOLA) 將頻譜幀還原為時域信號
+    ///
+    /// # Arguments
+    /// * `frames` - `analyze` 產生（或經修改）的頻譜幀
+    /// * `output_len` - 輸出樣本數
+    pub fn synthesize(&mut self, frames: &[Vec<Complex<f32>>], output_len: usize) -> Vec<f32> {
+        self.synthesize_with_hop(frames, self.hop_size, output_len)
+    }
+
+    /// 以指定的合成步長進行重建（相位聲碼器的時間伸縮使用）
+    pub fn synthesize_with_hop(
+        &mut self,
+        frames: &[Vec<Complex<f32>>],
+        synthesis_hop: usize,
+        output_len: usize,
+    ) -> Vec<f32> {
+        let synthesis_hop = synthesis_hop.max(1);
+        let half = self.fft_size / 2;
+        let num_bins = self.num_bins();
+        let padded_len = output_len + self.fft_size + frames.len() * synthesis_hop;
+        let mut output = vec![0.0f32; padded_len];
+        let mut weight = vec![0.0f32; padded_len];
+        let norm = 1.0 / self.fft_size as f32;
+
+        for (frame_idx, frame) in frames.iter().enumerate() {
+            // 還原共軛對稱的完整頻譜
+            for i in 0..self.fft_size {
+                self.buffer[i] = if i < num_bins {
+                    frame.get(i).copied().unwrap_or_default()
+                } else {
+                    frame
+                        .get(self.fft_size - i)
+                        .copied()
+                        .unwrap_or_default()
+                        .conj()
+                };
+            }
+            // DC 與 Nyquist 必須為實數
+            self.buffer[0].im = 0.0;
+            if self.fft_size.is_multiple_of(2) {
+                self.buffer[half].im = 0.0;
+            }
+
+            self.inverse.process(&mut self.buffer);
+
+            let start = frame_idx * synthesis_hop;
+            for i in 0..self.fft_size {
+                let w = self.window[i];
+                output[start + i] += self.buffer[i].re * norm * w;
+                weight[start + i] += w * w;
+            }
+        }
+
+        // 去除中心填充並按窗能量歸一化
+        let mut result = vec![0.0f32; output_len];
+        for (i, value) in result.iter_mut().enumerate() {
+            let idx = i + half;
+            if idx < padded_len && weight[idx] > 1e-8 {
+                *value = output[idx] / weight[idx];
+            }
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_signal(len: usize) -> Vec<f32> {
+        let mut state = 12345u32;
+        (0..len)
+            .map(|i| {
+                state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                (state >> 8) as f32 / (1u32 << 24) as f32 - 0.5 + (i as f32 * 0.05).sin() * 0.3
+            })
+            .collect()
+    }
+
+    #[test]
+    fn frame_layout() {
+        let stft = StftProcessor::new(1024, 0, "hann");
+        assert_eq!(stft.hop_size(), 256);
+        assert_eq!(stft.num_bins(), 513);
+        assert_eq!(stft.num_frames(0), 0);
+        assert_eq!(stft.num_frames(1), 1);
+        assert_eq!(stft.num_frames(1024), 5);
+        // 步長不超過 fft_size
+        assert_eq!(StftProcessor::new(256, 1000, "hann").hop_size(), 256);
+    }
+
+    #[test]
+    fn wola_reconstructs_signal() {
+        // 包含兩端樣本在內逐點還原
+        let signal = test_signal(5000);
+        for (fft_size, hop_size, window) in [(1024, 256, "hann"), (512, 256, "hamming"), (256, 100, "blackman"), (1023, 300, "hann")] {
+            let mut stft = StftProcessor::new(fft_size, hop_size, window);
+            let frames = stft.analyze(&signal);
+            assert_eq!(frames.len(), stft.num_frames(signal.len()));
+            assert!(frames.iter().all(|f| f.len() == stft.num_bins()));
+
+            let output = stft.synthesize(&frames, signal.len());
+            let error = signal.iter().zip(&output).map(|(a, b)| (a - b).abs()).fold(0.0, f32::max);
+            assert!(error < 1e-5, "{} / {} / {}: {}", fft_size, hop_size, window, error);
+        }
+    }
+
+    #[test]
+    fn analyze_centres_frames_on_hops() {
+        // 位於第 3 幀中心的脈衝：該幀頻譜平坦，幅度為窗函數峰值
+        let mut signal = vec![0.0f32; 2048];
+        signal[3 * 256] = 1.0;
+        let mut stft = StftProcessor::new(1024, 256, "hann");
+        let frames = stft.analyze(&signal);
+        assert!(frames[3].iter().all(|c| (c.norm() - 1.0).abs() < 1e-5));
+        assert!(frames[1].iter().all(|c| c.norm() < 0.5));
+    }
+}
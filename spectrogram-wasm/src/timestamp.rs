@@ -0,0 +1,347 @@
+// ============================================================
+// ISO 8601 時間戳解析、格式化與運算
+// 用於 GUANO / WAMD / AudioMoth 等元數據中的錄音時間
+// ============================================================
+
+use std::fmt;
+
+/// 日曆日期時間，可帶 UTC 偏移
+///
+/// 格式化時保留解析時的日期時間分隔符與小數秒位數，
+/// 使修改後寫回文件的字串與原文件風格一致。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timestamp {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+    /// 秒以下的部分 (微秒)
+    pub microsecond: u32,
+    /// UTC 偏移 (分鐘)；None 表示本地時間（未指定時區）
+    pub utc_offset_minutes: Option<i32>,
+    /// 格式化時的小數秒位數 (0..=6)
+    pub fraction_digits: u8,
+    /// 日期與時間之間的分隔符（'T' 或空格）
+    pub separator: char,
+}
+
+/// 自 1970-01-01 起的天數（proleptic Gregorian）
+pub fn days_from_civil(year: i32, month: u32, day: u32) -> i64 {
+    let y = if month <= 2 { year as i64 - 1 } else { year as i64 };
+    let era = if y >= 0 { y } else { y - 399 } / 400;
+    let yoe = y - era * 400;
+    let m = month as i64;
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// days_from_civil 的逆運算，返回 (年, 月, 日)
+pub fn civil_from_days(days: i64) -> (i32, u32, u32) {
+    let z = days + 719_468;
+    let era = if z >= 0 { z } else { z - 146_096 } / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = (yoe + era * 400 + if month <= 2 { 1 } else { 0 }) as i32;
+    (year, month, day)
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if (year % 4 == 0 && year % 100 != 0) || year % 400 == 0 => 29,
+        2 => 28,
+        _ => 0,
+    }
+}
+
+/// 解析固定位數的十進制數字
+fn parse_digits(s: &str, start: usize, len: usize) -> Option<u32> {
+    let part = s.get(start..start + len)?;
+    if !part.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    part.parse().ok()
+}
+
+/// 解析時區後綴："Z"、"±HH:MM"、"±HHMM" 或 "±HH"
+pub fn parse_utc_offset(s: &str) -> Option<i32> {
+    let s = s.trim();
+    if s == "Z" || s == "z" {
+        return Some(0);
+    }
+    let sign = match s.as_bytes().first()? {
+        b'+' => 1,
+        b'-' => -1,
+        _ => return None,
+    };
+    let rest = &s[1..];
+    let (hours, minutes) = match rest.len() {
+        2 => (parse_digits(rest, 0, 2)?, 0),
+        4 => (parse_digits(rest, 0, 2)?, parse_digits(rest, 2, 2)?),
+        5 if rest.as_bytes()[2] == b':' => (parse_digits(rest, 0, 2)?, parse_digits(rest, 3, 2)?),
+        _ => return None,
+    };
+    if hours > 23 || minutes > 59 {
+        return None;
+    }
+    Some(sign * (hours * 60 + minutes) as i32)
+}
+
+impl Timestamp {
+    /// 從各欄位建立時間戳（欄位無效時返回 None）
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        year: i32,
+        month: u32,
+        day: u32,
+        hour: u32,
+        minute: u32,
+        second: u32,
+        microsecond: u32,
+        utc_offset_minutes: Option<i32>,
+    ) -> Option<Timestamp> {
+        if month == 0 || month > 12 || day == 0 || day > days_in_month(year, month) {
+            return None;
+        }
+        // 允許閏秒 60
+        if hour > 23 || minute > 59 || second > 60 || microsecond >= 1_000_000 {
+            return None;
+        }
+        Some(Timestamp {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+            microsecond,
+            utc_offset_minutes,
+            fraction_digits: if microsecond == 0 { 0 } else { 6 },
+            separator: 'T',
+        })
+    }
+
+    /// 解析 ISO 8601 時間戳
+    ///
+    /// 接受 `YYYY-MM-DDTHH:MM:SS[.ffffff][Z|±HH:MM]`，日期與時間之間也可用空格分隔，
+    /// 秒可省略；超過 6 位的小數秒會被截斷。
+    pub fn parse(s: &str) -> Option<Timestamp> {
+        let s = s.trim();
+        if s.len() < 16 {
+            return None;
+        }
+        let bytes = s.as_bytes();
+        if bytes[4] != b'-' || bytes[7] != b'-' || bytes[13] != b':' {
+            return None;
+        }
+        let separator = match bytes[10] {
+            b'T' | b't' => 'T',
+            b' ' => ' ',
+            _ => return None,
+        };
+
+        let year = parse_digits(s, 0, 4)? as i32;
+        let month = parse_digits(s, 5, 2)?;
+        let day = parse_digits(s, 8, 2)?;
+        let hour = parse_digits(s, 11, 2)?;
+        let minute = parse_digits(s, 14, 2)?;
+
+        let mut pos = 16;
+        let mut second = 0;
+        if bytes.get(pos) == Some(&b':') {
+            second = parse_digits(s, pos + 1, 2)?;
+            pos += 3;
+        }
+
+        let mut microsecond = 0u32;
+        let mut fraction_digits = 0u8;
+        if matches!(bytes.get(pos), Some(b'.') | Some(b',')) {
+            pos += 1;
+            let digits_start = pos;
+            while pos < bytes.len() && bytes[pos].is_ascii_digit() {
+                if pos - digits_start < 6 {
+                    microsecond = microsecond * 10 + (bytes[pos] - b'0') as u32;
+                }
+                pos += 1;
+            }
+            let count = pos - digits_start;
+            if count == 0 {
+                return None;
+            }
+            fraction_digits = count.min(6) as u8;
+            microsecond *= 10u32.pow(6 - fraction_digits as u32);
+        }
+
+        let suffix = &s[pos..];
+        let utc_offset_minutes = if suffix.trim().is_empty() {
+            None
+        } else {
+            Some(parse_utc_offset(suffix)?)
+        };
+
+        let mut ts = Timestamp::new(year, month, day, hour, minute, second, microsecond, utc_offset_minutes)?;
+        ts.fraction_digits = fraction_digits;
+        ts.separator = separator;
+        Some(ts)
+    }
+
+    /// 時間戳所表示的本地時間，以自 1970-01-01 00:00:00 起的秒數表示（不考慮時區）
+    pub fn local_seconds(&self) -> f64 {
+        let days = days_from_civil(self.year, self.month, self.day);
+        let secs = days * 86_400 + (self.hour * 3600 + self.minute * 60 + self.second) as i64;
+        secs as f64 + self.microsecond as f64 * 1e-6
+    }
+
+    /// Unix 時間 (秒)；未指定時區時返回 None
+    pub fn unix_seconds(&self) -> Option<f64> {
+        self.utc_offset_minutes
+            .map(|offset| self.local_seconds() - offset as f64 * 60.0)
+    }
+
+    /// 由本地秒數建立時間戳，沿用 `self` 的時區與格式
+    fn with_local_seconds(&self, local_seconds: f64) -> Timestamp {
+        let total_micros = (local_seconds * 1e6).round() as i64;
+        let secs = total_micros.div_euclid(1_000_000);
+        let microsecond = total_micros.rem_euclid(1_000_000) as u32;
+        let days = secs.div_euclid(86_400);
+        let day_secs = secs.rem_euclid(86_400) as u32;
+        let (year, month, day) = civil_from_days(days);
+
+        Timestamp {
+            year,
+            month,
+            day,
+            hour: day_secs / 3600,
+            minute: day_secs / 60 % 60,
+            second: day_secs % 60,
+            microsecond,
+            ..*self
+        }
+    }
+
+    /// 加上秒數（可為負或帶小數），時區不變
+    ///
+    /// 若結果帶有原格式無法表示的小數秒，自動增加格式化位數。
+    pub fn add_seconds(&self, seconds: f64) -> Timestamp {
+        let mut ts = self.with_local_seconds(self.local_seconds() + seconds);
+        let mut digits = ts.fraction_digits;
+        while digits < 6 && !ts.microsecond.is_multiple_of(10u32.pow(6 - digits as u32)) {
+            digits += 1;
+        }
+        ts.fraction_digits = digits;
+        ts
+    }
+
+    /// 轉換到指定的 UTC 偏移；未指定時區時僅附加偏移而不改變時間
+    pub fn to_offset(&self, utc_offset_minutes: i32) -> Timestamp {
+        let mut ts = match self.utc_offset_minutes {
+            Some(current) => self.with_local_seconds(self.local_seconds() + (utc_offset_minutes - current) as f64 * 60.0),
+            None => *self,
+        };
+        ts.utc_offset_minutes = Some(utc_offset_minutes);
+        ts
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02}{}{:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.separator, self.hour, self.minute, self.second
+        )?;
+        if self.fraction_digits > 0 {
+            let digits = self.fraction_digits.min(6) as u32;
+            let value = self.microsecond / 10u32.pow(6 - digits);
+            write!(f, ".{:0width$}", value, width = digits as usize)?;
+        }
+        match self.utc_offset_minutes {
+            None => Ok(()),
+            Some(0) => write!(f, "Z"),
+            Some(offset) => {
+                let sign = if offset < 0 { '-' } else { '+' };
+                let offset = offset.abs();
+                write!(f, "{}{:02}:{:02}", sign, offset / 60, offset % 60)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ts(s: &str) -> Timestamp {
+        Timestamp::parse(s).unwrap()
+    }
+
+    #[test]
+    fn parses_iso_8601() {
+        let t = ts("2024-05-01T21:00:07Z");
+        assert_eq!((t.year, t.month, t.day, t.hour, t.minute, t.second), (2024, 5, 1, 21, 0, 7));
+        assert_eq!(t.utc_offset_minutes, Some(0));
+        assert_eq!(t.unix_seconds(), Some(1_714_597_207.0));
+
+        assert_eq!(ts("2024-05-01T21:00:07+08:00").utc_offset_minutes, Some(480));
+        assert_eq!(ts("2024-05-01T21:00:07-0530").utc_offset_minutes, Some(-330));
+        assert_eq!(ts("2024-05-01T21:00:07+02").utc_offset_minutes, Some(120));
+        assert_eq!(ts("2024-05-01 21:00").to_string(), "2024-05-01 21:00:00");
+        assert_eq!(ts("2024-05-01t21:00:07").unix_seconds(), None);
+
+        // 小數秒保留原位數，超過 6 位截斷
+        assert_eq!(ts("2024-05-01T21:00:07.250").to_string(), "2024-05-01T21:00:07.250");
+        let t = ts("2024-05-01T21:00:07,123456789Z");
+        assert_eq!(t.microsecond, 123_456);
+        assert_eq!(t.to_string(), "2024-05-01T21:00:07.123456Z");
+
+        for invalid in ["2024-05-01", "2024/05/01T21:00:07", "2024-02-30T21:00:07", "2024-05-01T24:00:00", "2024-05-01T21:00:07.", "2024-05-01T21:00:07+8"] {
+            assert!(Timestamp::parse(invalid).is_none(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn civil_day_conversion() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 3, 1), 11_017);
+        assert_eq!(days_from_civil(1969, 12, 31), -1);
+        for days in [-800_000, -1, 0, 11_016, 19_844, 60_000] {
+            let (y, m, d) = civil_from_days(days);
+            assert_eq!(days_from_civil(y, m, d), days);
+        }
+    }
+
+    #[test]
+    fn add_seconds_crosses_boundaries() {
+        // 跨日、跨月、跨年
+        assert_eq!(ts("2024-05-31T23:59:50+08:00").add_seconds(15.0).to_string(), "2024-06-01T00:00:05+08:00");
+        assert_eq!(ts("2023-12-31T23:59:59Z").add_seconds(1.0).to_string(), "2024-01-01T00:00:00Z");
+        // 閏年與平年的 2 月
+        assert_eq!(ts("2024-02-28T12:00:00").add_seconds(86_400.0).to_string(), "2024-02-29T12:00:00");
+        assert_eq!(ts("2023-02-28T12:00:00").add_seconds(86_400.0).to_string(), "2023-03-01T12:00:00");
+        // 負數向前平移
+        assert_eq!(ts("2024-03-01T00:00:00").add_seconds(-0.25).to_string(), "2024-02-29T23:59:59.75");
+        // 原格式的小數位數不足時自動增加
+        assert_eq!(ts("2024-03-01T00:00:00.5").add_seconds(0.125).to_string(), "2024-03-01T00:00:00.625");
+        assert_eq!(ts("2024-03-01T00:00:00.500").add_seconds(1.0).to_string(), "2024-03-01T00:00:01.500");
+    }
+
+    #[test]
+    fn converts_offset() {
+        let t = ts("2024-05-01T02:30:00+08:00");
+        let utc = t.to_offset(0);
+        assert_eq!(utc.to_string(), "2024-04-30T18:30:00Z");
+        assert_eq!(utc.unix_seconds(), t.unix_seconds());
+        assert_eq!(t.to_offset(-150).to_string(), "2024-04-30T16:00:00-02:30");
+
+        // 無時區時只附加偏移
+        assert_eq!(ts("2024-05-01T02:30:00").to_offset(480).to_string(), "2024-05-01T02:30:00+08:00");
+    }
+}
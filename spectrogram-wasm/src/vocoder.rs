@@ -0,0 +1,237 @@
+// ============================================================
+// 相位聲碼器：時間伸縮與音高移動
+// 以 STFT / ISTFT 為基礎，使用 identity phase locking 保持諧波結構
+// ============================================================
+
+use std::f32::consts::PI;
+
+use num_complex::Complex;
+use wasm_bindgen::prelude::*;
+
+use crate::resample::Resampler;
+use crate::stft::StftProcessor;
+
+/// 將相位包裹到 [-PI, PI)
+fn wrap_phase(phase: f32) -> f32 {
+    let two_pi = 2.0 * PI;
+    phase - two_pi * ((phase + PI) / two_pi).floor()
+}
+
+/// 找出幅度譜中的局部峰值 (大於左右各兩個相鄰 bin)
+fn find_spectral_peaks(magnitude: &[f32]) -> Vec<usize> {
+    let n = magnitude.len();
+    (0..n)
+        .filter(|&k| {
+            let m = magnitude[k];
+            m > 0.0
+                && (k < 1 || m > magnitude[k - 1])
+                && (k < 2 || m > magnitude[k - 2])
+                && (k + 1 >= n || m >= magnitude[k + 1])
+                && (k + 2 >= n || m >= magnitude[k + 2])
+        })
+        .collect()
+}
+
+/// 相位聲碼器時間伸縮核心
+///
+/// # Arguments
+/// * `audio_data` - 音頻樣本
+/// * `stretch` - 時間伸縮因子（> 1 變慢變長，< 1 變快變短，音高不變）
+/// * `fft_size` - FFT 大小
+///
+/// # Returns
+/// 長度約為 len * stretch 的音頻
+pub fn phase_vocoder_stretch(audio_data: &[f32], stretch: f32, fft_size: usize) -> Vec<f32> {
+    if audio_data.is_empty() || !stretch.is_finite() || stretch <= 0.0 {
+        return Vec::new();
+    }
+
+    let fft_size = fft_size.max(64);
+    // 較長的一側步長取 fft_size / 8 到 fft_size / 4 之間，另一側按比例決定，
+    // 確保兩邊都有足夠重疊；在範圍內選擇使實際比例最接近目標的整數步長
+    let base_hop = fft_size / 4;
+    let (analysis_hop, synthesis_hop) = (base_hop / 2..=base_hop)
+        .map(|long_hop| {
+            if stretch >= 1.0 {
+                (((long_hop as f32 / stretch).round() as usize).max(1), long_hop)
+            } else {
+                (long_hop, ((long_hop as f32 * stretch).round() as usize).max(1))
+            }
+        })
+        .min_by(|a, b| {
+            let error_a = (a.1 as f32 / a.0 as f32 - stretch).abs();
+            let error_b = (b.1 as f32 / b.0 as f32 - stretch).abs();
+            error_a.total_cmp(&error_b)
+        })
+        .unwrap_or((base_hop, base_hop));
+    let actual_stretch = synthesis_hop as f32 / analysis_hop as f32;
+
+    let mut stft = StftProcessor::new(fft_size, analysis_hop, "hann");
+    let frames = stft.analyze(audio_data);
+    let num_bins = stft.num_bins();
+
+    // 每個 bin 在一個分析步長內的期望相位增量
+    let omega: Vec<f32> = (0..num_bins)
+        .map(|k| 2.0 * PI * k as f32 * analysis_hop as f32 / fft_size as f32)
+        .collect();
+
+    let mut prev_phase = vec![0.0f32; num_bins];
+    let mut synth_phase = vec![0.0f32; num_bins];
+    let mut output_frames: Vec<Vec<Complex<f32>>> = Vec::with_capacity(frames.len());
+
+    for (frame_idx, frame) in frames.iter().enumerate() {
+        let magnitude: Vec<f32> = frame.iter().map(|c| c.norm()).collect();
+        let phase: Vec<f32> = frame.iter().map(|c| c.arg()).collect();
+
+        if frame_idx == 0 {
+            synth_phase.copy_from_slice(&phase);
+        } else {
+            let peaks = find_spectral_peaks(&magnitude);
+
+            // 峰值 bin：以瞬時頻率推進合成相位
+            for &p in &peaks {
+                let deviation = wrap_phase(phase[p] - prev_phase[p] - omega[p]);
+                synth_phase[p] += (omega[p] + deviation) * actual_stretch;
+            }
+
+            // 非峰值 bin：鎖定到所屬峰值的相位關係 (identity phase locking)
+            if peaks.is_empty() {
+                for k in 0..num_bins {
+                    let deviation = wrap_phase(phase[k] - prev_phase[k] - omega[k]);
+                    synth_phase[k] += (omega[k] + deviation) * actual_stretch;
+                }
+            } else {
+                let mut region_start = 0;
+                for (i, &p) in peaks.iter().enumerate() {
+                    // 影響區域邊界：相鄰峰值之間的幅度最小點
+                    let region_end = match peaks.get(i + 1) {
+                        Some(&next) => {
+                            (p..next)
+                                .min_by(|&a, &b| magnitude[a].total_cmp(&magnitude[b]))
+                                .unwrap_or(p)
+                                + 1
+                        }
+                        None => num_bins,
+                    };
+                    for k in region_start..region_end {
+                        if k != p {
+                            synth_phase[k] = synth_phase[p] + phase[k] - phase[p];
+                        }
+                    }
+                    region_start = region_end;
+                }
+            }
+        }
+
+        prev_phase.copy_from_slice(&phase);
+        output_frames.push(
+            magnitude
+                .iter()
+                .zip(synth_phase.iter())
+                .map(|(&m, &ph)| Complex::from_polar(m, ph))
+                .collect(),
+        );
+    }
+
+    let output_len = (audio_data.len() as f32 * actual_stretch).round() as usize;
+    stft.synthesize_with_hop(&output_frames, synthesis_hop, output_len)
+}
+
+/// 時間伸縮（音高不變）
+///
+/// 用於以原始音高慢速播放，取代單純降低採樣率的時間擴展。
+///
+/// # Arguments
+/// * `audio_data` - 音頻數據 (Float32Array)
+/// * `stretch` - 時間伸縮因子（例如 10 表示慢 10 倍）
+/// * `fft_size` - FFT 大小（典型值: 1024；0 表示默認 1024）
+///
+/// # Returns
+/// 伸縮後的音頻 (Float32Array)
+#[wasm_bindgen]
+pub fn time_stretch(audio_data: &[f32], stretch: f32, fft_size: usize) -> Vec<f32> {
+    let fft_size = if fft_size == 0 { 1024 } else { fft_size };
+    phase_vocoder_stretch(audio_data, stretch, fft_size)
+}
+
+/// 音高移動（時長不變）
+///
+/// 先以相位聲碼器將時長伸縮為 pitch_factor 倍，再重採樣回原長度，
+/// 使超聲波叫聲以原速播放但音高落在可聽範圍。
+///
+/// # Arguments
+/// * `audio_data` - 音頻數據 (Float32Array)
+/// * `pitch_factor` - 音高倍數（例如 0.1 將 40 kHz 移到 4 kHz）
+/// * `fft_size` - FFT 大小（典型值: 1024；0 表示默認 1024）
+///
+/// # Returns
+/// 與輸入等長的音頻 (Float32Array)
+#[wasm_bindgen]
+pub fn pitch_shift(audio_data: &[f32], pitch_factor: f32, fft_size: usize) -> Vec<f32> {
+    if audio_data.is_empty() || !pitch_factor.is_finite() || pitch_factor <= 0.0 {
+        return Vec::new();
+    }
+
+    let fft_size = if fft_size == 0 { 1024 } else { fft_size };
+    let stretched = phase_vocoder_stretch(audio_data, pitch_factor, fft_size);
+    if stretched.is_empty() {
+        return vec![0.0; audio_data.len()];
+    }
+
+    // 將伸縮後的信號重採樣回原長度：音高按 pitch_factor 改變
+    let resampler = Resampler::new(stretched.len() as f64, audio_data.len() as f64, 0);
+    let mut output = resampler.process(&stretched);
+    output.resize(audio_data.len(), 0.0);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 每樣本 cycles 週期的正弦
+    fn tone(cycles: f32, len: usize) -> Vec<f32> {
+        (0..len).map(|i| 0.5 * (2.0 * PI * cycles * i as f32).sin()).collect()
+    }
+
+    /// 以中段過零點數估計每樣本週期數
+    fn cycles_per_sample(signal: &[f32]) -> f32 {
+        let middle = &signal[signal.len() / 4..signal.len() * 3 / 4];
+        let crossings = middle.windows(2).filter(|w| (w[0] < 0.0) != (w[1] < 0.0)).count();
+        crossings as f32 / 2.0 / middle.len() as f32
+    }
+
+    #[test]
+    fn stretch_scales_length_keeps_pitch() {
+        // 不落在頻率箱中心的音調
+        let input = tone(0.0731, 16_384);
+        for stretch in [0.5, 1.0, 1.5, 2.0, 4.0, 10.0] {
+            let output = time_stretch(&input, stretch, 1024);
+            let expected_len = input.len() as f32 * stretch;
+            assert!((output.len() as f32 - expected_len).abs() <= expected_len * 0.02, "k={} len={}", stretch, output.len());
+            let cycles = cycles_per_sample(&output);
+            assert!((cycles - 0.0731).abs() < 0.0731 * 0.01, "k={} cycles={}", stretch, cycles);
+        }
+    }
+
+    #[test]
+    fn pitch_shift_keeps_length() {
+        let input = tone(0.2, 16_384);
+        for factor in [0.1, 0.5] {
+            let output = pitch_shift(&input, factor, 0);
+            assert_eq!(output.len(), input.len());
+            let cycles = cycles_per_sample(&output);
+            assert!((cycles - 0.2 * factor).abs() < 0.2 * factor * 0.02, "factor={} cycles={}", factor, cycles);
+        }
+    }
+
+    #[test]
+    fn invalid_factor() {
+        let input = tone(0.1, 1024);
+        for factor in [0.0, -1.0, f32::NAN, f32::INFINITY] {
+            assert!(time_stretch(&input, factor, 0).is_empty());
+            assert!(pitch_shift(&input, factor, 0).is_empty());
+        }
+        assert!(time_stretch(&[], 2.0, 0).is_empty());
+    }
+}
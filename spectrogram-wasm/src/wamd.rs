@@ -0,0 +1,345 @@
+// ============================================================
+// Wildlife Acoustics WAMD 元數據
+// SM2BAT / SM3BAT / SM4BAT / EM3 將元數據存放在二進制 `wamd` 塊中，
+// 此處解析後映射為 GUANO 欄位，與 GUANO 文件共用同一套元數據處理
+// ============================================================
+
+use crate::guano::GuanoMetadata;
+use crate::timestamp::Timestamp;
+use crate::wav::parse_wav;
+
+/// WAMD 塊的 RIFF ID
+pub const WAMD_CHUNK_ID: [u8; 4] = *b"wamd";
+
+// 子塊 ID
+const WAMD_VERSION: u16 = 0x00;
+const WAMD_MODEL: u16 = 0x01;
+const WAMD_SERIAL: u16 = 0x02;
+const WAMD_FIRMWARE: u16 = 0x03;
+const WAMD_PREFIX: u16 = 0x04;
+const WAMD_TIMESTAMP: u16 = 0x05;
+const WAMD_GPS_FIRST: u16 = 0x06;
+const WAMD_SOFTWARE: u16 = 0x08;
+const WAMD_NOTES: u16 = 0x0A;
+const WAMD_AUTO_ID: u16 = 0x0B;
+const WAMD_MANUAL_ID: u16 = 0x0C;
+const WAMD_TIME_EXPANSION: u16 = 0x0F;
+const WAMD_PROGRAM: u16 = 0x10;
+const WAMD_MICROPHONE: u16 = 0x12;
+const WAMD_SENSITIVITY: u16 = 0x13;
+const WAMD_TEMPERATURE_INT: u16 = 0x14;
+const WAMD_TEMPERATURE_EXT: u16 = 0x15;
+
+/// GPS 位置
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GpsPosition {
+    pub latitude: f64,
+    pub longitude: f64,
+    /// 海拔 (米)
+    pub altitude: Option<f64>,
+}
+
+/// WAMD 元數據
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WamdMetadata {
+    pub version: Option<u16>,
+    pub model: Option<String>,
+    pub serial: Option<String>,
+    pub firmware: Option<String>,
+    /// 文件名前綴
+    pub prefix: Option<String>,
+    pub timestamp: Option<Timestamp>,
+    pub position: Option<GpsPosition>,
+    pub software: Option<String>,
+    pub notes: Option<String>,
+    /// 自動識別的物種代碼
+    pub auto_id: Vec<String>,
+    /// 人工識別的物種代碼
+    pub manual_id: Vec<String>,
+    /// 時間擴展因子
+    pub time_expansion: Option<u16>,
+    pub program: Option<String>,
+    pub microphone: Option<String>,
+    pub sensitivity: Option<String>,
+    /// 內部溫度 (°C)
+    pub temperature_int: Option<f64>,
+    /// 外部溫度 (°C)
+    pub temperature_ext: Option<f64>,
+}
+
+/// 子塊文本：去除 NUL 填充與首尾空白
+fn text_value(data: &[u8]) -> Option<String> {
+    let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+    let text = String::from_utf8_lossy(&data[..end]).trim().to_string();
+    if text.is_empty() {
+        None
+    } else {
+        Some(text)
+    }
+}
+
+fn u16_value(data: &[u8]) -> Option<u16> {
+    (data.len() >= 2).then(|| u16::from_le_bytes([data[0], data[1]]))
+}
+
+/// 解析開頭的數字（例如 "23.5C" → 23.5）
+fn leading_number(text: &str) -> Option<f64> {
+    let end = text
+        .char_indices()
+        .find(|&(i, c)| !(c.is_ascii_digit() || c == '.' || (i == 0 && (c == '-' || c == '+'))))
+        .map_or(text.len(), |(i, _)| i);
+    text[..end].parse().ok()
+}
+
+/// 物種代碼列表（以逗號或空白分隔）
+fn species_list(text: &str) -> Vec<String> {
+    text.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string())
+        .collect()
+}
+
+/// 解析 GPS 子塊
+///
+/// 兩種已知格式：
+/// - "WGS84,41.12345,N,81.12345,W,178.4"（帶半球字母）
+/// - "WGS84,41.12345,-81.12345,178.4"（帶符號）
+fn parse_gps(text: &str) -> Option<GpsPosition> {
+    let values: Vec<&str> = text.split(',').map(|s| s.trim()).collect();
+    let values = values.get(1..)?;
+
+    let (latitude, longitude, altitude) = if values.len() >= 4 && matches!(values[1], "N" | "S") {
+        let lat: f64 = values[0].parse().ok()?;
+        let lon: f64 = values[2].parse().ok()?;
+        let lat = if values[1] == "S" { -lat } else { lat };
+        let lon = if values[3] == "W" { -lon } else { lon };
+        (lat, lon, values.get(4).and_then(|v| v.parse().ok()))
+    } else if values.len() >= 2 {
+        (
+            values[0].parse().ok()?,
+            values[1].parse().ok()?,
+            values.get(2).and_then(|v| v.parse().ok()),
+        )
+    } else {
+        return None;
+    };
+
+    if latitude.abs() > 90.0 || longitude.abs() > 180.0 {
+        return None;
+    }
+    Some(GpsPosition {
+        latitude,
+        longitude,
+        altitude,
+    })
+}
+
+impl WamdMetadata {
+    /// 解析 `wamd` 塊內容
+    ///
+    /// 塊由一系列子塊組成：u16 ID、u32 大小、內容。
+    /// 未知 ID 略過；子塊被截斷時停止解析並保留已讀取的欄位。
+    pub fn parse(content: &[u8]) -> WamdMetadata {
+        let mut metadata = WamdMetadata::default();
+        let mut pos = 0;
+
+        while pos + 6 <= content.len() {
+            let id = u16::from_le_bytes([content[pos], content[pos + 1]]);
+            let size = u32::from_le_bytes([content[pos + 2], content[pos + 3], content[pos + 4], content[pos + 5]]) as usize;
+            pos += 6;
+            if size > content.len() - pos {
+                break;
+            }
+            let data = &content[pos..pos + size];
+            pos += size;
+
+            match id {
+                WAMD_VERSION => metadata.version = u16_value(data),
+                WAMD_MODEL => metadata.model = text_value(data),
+                WAMD_SERIAL => metadata.serial = text_value(data),
+                WAMD_FIRMWARE => metadata.firmware = text_value(data),
+                WAMD_PREFIX => metadata.prefix = text_value(data),
+                WAMD_TIMESTAMP => metadata.timestamp = text_value(data).and_then(|t| Timestamp::parse(&t)),
+                WAMD_GPS_FIRST => metadata.position = text_value(data).and_then(|t| parse_gps(&t)),
+                WAMD_SOFTWARE => metadata.software = text_value(data),
+                WAMD_NOTES => metadata.notes = text_value(data),
+                WAMD_AUTO_ID => metadata.auto_id = text_value(data).map(|t| species_list(&t)).unwrap_or_default(),
+                WAMD_MANUAL_ID => metadata.manual_id = text_value(data).map(|t| species_list(&t)).unwrap_or_default(),
+                WAMD_TIME_EXPANSION => metadata.time_expansion = u16_value(data).filter(|&te| te > 0),
+                WAMD_PROGRAM => metadata.program = text_value(data),
+                WAMD_MICROPHONE => metadata.microphone = text_value(data),
+                WAMD_SENSITIVITY => metadata.sensitivity = text_value(data),
+                WAMD_TEMPERATURE_INT => metadata.temperature_int = text_value(data).and_then(|t| leading_number(&t)),
+                WAMD_TEMPERATURE_EXT => metadata.temperature_ext = text_value(data).and_then(|t| leading_number(&t)),
+                _ => {}
+            }
+        }
+
+        metadata
+    }
+
+    /// 從 WAV 文件中讀取 WAMD 元數據；沒有 `wamd` 塊時返回 None
+    pub fn read_from_wav(bytes: &[u8]) -> Option<WamdMetadata> {
+        let info = parse_wav(bytes).ok()?;
+        let chunk = info.find_chunk(&WAMD_CHUNK_ID)?;
+        Some(WamdMetadata::parse(info.chunk_data(bytes, chunk)))
+    }
+
+    /// 映射為 GUANO 元數據
+    ///
+    /// 有對應 GUANO 標準欄位的值寫入標準欄位，其餘寫入 `WA|` 命名空間。
+    ///
+    /// # Arguments
+    /// * `sample_rate` - WAV 頭中的採樣率 (Hz)，0 表示不寫入 Samplerate
+    pub fn to_guano(&self, sample_rate: u32) -> GuanoMetadata {
+        let mut guano = GuanoMetadata::new();
+        guano.set("Make", "Wildlife Acoustics, Inc.");
+
+        let mut set = |key: &str, value: &Option<String>| {
+            if let Some(value) = value {
+                guano.set(key, value);
+            }
+        };
+        set("Model", &self.model);
+        set("Serial", &self.serial);
+        set("Firmware Version", &self.firmware);
+        set("Note", &self.notes);
+        set("WA|Prefix", &self.prefix);
+        set("WA|Software", &self.software);
+        set("WA|Program", &self.program);
+        set("WA|Microphone", &self.microphone);
+        set("WA|Sensitivity", &self.sensitivity);
+
+        if let Some(ts) = &self.timestamp {
+            guano.set_timestamp_value(ts);
+        }
+        if let Some(position) = &self.position {
+            guano.set_location(position.latitude, position.longitude);
+            if let Some(altitude) = position.altitude {
+                guano.set("Loc Elevation", &altitude.to_string());
+            }
+        }
+        if sample_rate > 0 {
+            guano.set("Samplerate", &sample_rate.to_string());
+        }
+        if let Some(te) = self.time_expansion {
+            guano.set("TE", &te.to_string());
+        }
+        if let Some(temperature) = self.temperature_int {
+            guano.set("Temperature Int", &temperature.to_string());
+        }
+        if let Some(temperature) = self.temperature_ext {
+            guano.set("Temperature Ext", &temperature.to_string());
+        }
+        if !self.auto_id.is_empty() {
+            guano.set("Species Auto ID", &self.auto_id.join(", "));
+        }
+        if !self.manual_id.is_empty() {
+            guano.set("Species Manual ID", &self.manual_id.join(", "));
+        }
+        if let Some(version) = self.version {
+            guano.set("WA|WAMD Version", &version.to_string());
+        }
+
+        guano
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wav::SampleFormat;
+    use crate::wav_writer::{encode_wav, RiffChunk};
+
+    fn subchunk(id: u16, data: &[u8]) -> Vec<u8> {
+        let mut bytes = id.to_le_bytes().to_vec();
+        bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(data);
+        bytes
+    }
+
+    fn content(subchunks: &[(u16, &[u8])]) -> Vec<u8> {
+        subchunks.iter().flat_map(|&(id, data)| subchunk(id, data)).collect()
+    }
+
+    #[test]
+    fn parses_subchunks() {
+        let bytes = content(&[
+            (WAMD_VERSION, &1u16.to_le_bytes()),
+            (WAMD_MODEL, b"SM4BAT-FS\0\0\0"),
+            (WAMD_SERIAL, b" S4U12345 "),
+            (WAMD_TIMESTAMP, b"2024-05-01 21:00:00-05:00\0"),
+            (WAMD_GPS_FIRST, b"WGS84,41.12345,N,81.12345,W,178.4"),
+            (0x7F, b"unknown"),
+            (WAMD_AUTO_ID, b"MYOLUC, EPTFUS"),
+            (WAMD_TIME_EXPANSION, &10u16.to_le_bytes()),
+            (WAMD_TEMPERATURE_INT, b"23.5C"),
+            (WAMD_TEMPERATURE_EXT, b"-4C"),
+        ]);
+        let metadata = WamdMetadata::parse(&bytes);
+        assert_eq!(metadata.version, Some(1));
+        assert_eq!(metadata.model.as_deref(), Some("SM4BAT-FS"));
+        assert_eq!(metadata.serial.as_deref(), Some("S4U12345"));
+        assert_eq!(metadata.timestamp.map(|t| t.to_string()).as_deref(), Some("2024-05-01 21:00:00-05:00"));
+        let position = metadata.position.unwrap();
+        assert_eq!((position.latitude, position.longitude, position.altitude), (41.12345, -81.12345, Some(178.4)));
+        assert_eq!(metadata.auto_id, vec!["MYOLUC", "EPTFUS"]);
+        assert_eq!(metadata.time_expansion, Some(10));
+        assert_eq!((metadata.temperature_int, metadata.temperature_ext), (Some(23.5), Some(-4.0)));
+        assert_eq!(metadata.notes, None);
+
+        let guano = metadata.to_guano(384_000);
+        assert_eq!(guano.get("Model").as_deref(), Some("SM4BAT-FS"));
+        assert_eq!(guano.get("Samplerate").as_deref(), Some("384000"));
+        assert_eq!(guano.get("TE").as_deref(), Some("10"));
+        assert_eq!(guano.get("Species Auto ID").as_deref(), Some("MYOLUC, EPTFUS"));
+    }
+
+    #[test]
+    fn odd_length_subchunks_are_not_padded() {
+        // 子塊之間沒有對齊填充：奇數長度後緊接下一個子塊
+        let bytes = content(&[(WAMD_MODEL, b"EM3"), (WAMD_SERIAL, b"12345"), (WAMD_VERSION, &[1, 0])]);
+        let metadata = WamdMetadata::parse(&bytes);
+        assert_eq!(metadata.model.as_deref(), Some("EM3"));
+        assert_eq!(metadata.serial.as_deref(), Some("12345"));
+        assert_eq!(metadata.version, Some(1));
+
+        // 奇數長度的數值子塊只讀前兩個字節；不足兩字節時略過
+        let metadata = WamdMetadata::parse(&content(&[(WAMD_TIME_EXPANSION, &[8, 0, 0]), (WAMD_VERSION, &[1])]));
+        assert_eq!((metadata.time_expansion, metadata.version), (Some(8), None));
+    }
+
+    #[test]
+    fn truncated_subchunks() {
+        let full = content(&[(WAMD_MODEL, b"SM4BAT-FS"), (WAMD_SERIAL, b"S4U12345")]);
+
+        // 內容被截斷：保留之前已讀取的欄位
+        let metadata = WamdMetadata::parse(&full[..full.len() - 1]);
+        assert_eq!(metadata.model.as_deref(), Some("SM4BAT-FS"));
+        assert_eq!(metadata.serial, None);
+
+        // 子塊頭被截斷
+        let metadata = WamdMetadata::parse(&full[..15 + 3]);
+        assert_eq!((metadata.model.as_deref(), metadata.serial), (Some("SM4BAT-FS"), None));
+
+        // 大小欄位超出剩餘內容（包括 u32 溢出邊界）
+        let mut oversized = subchunk(WAMD_MODEL, b"SM4");
+        oversized[2..6].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(WamdMetadata::parse(&oversized), WamdMetadata::default());
+        assert_eq!(WamdMetadata::parse(&[]), WamdMetadata::default());
+    }
+
+    #[test]
+    fn reads_odd_sized_chunk_from_wav() {
+        // 奇數大小的 wamd 塊後有填充字節，之後的 data 塊仍能讀取
+        let wamd = content(&[(WAMD_MODEL, b"EM3")]);
+        assert_eq!(wamd.len() % 2, 1);
+        let bytes = encode_wav(&[vec![0.0; 100]], 256_000, SampleFormat::Int, 16, &[RiffChunk::new(&WAMD_CHUNK_ID, wamd)]).unwrap();
+        let metadata = WamdMetadata::read_from_wav(&bytes).unwrap();
+        assert_eq!(metadata.model.as_deref(), Some("EM3"));
+        assert_eq!(parse_wav(&bytes).unwrap().data_chunk().size, 200);
+
+        let plain = encode_wav(&[vec![0.0; 100]], 256_000, SampleFormat::Int, 16, &[]).unwrap();
+        assert!(WamdMetadata::read_from_wav(&plain).is_none());
+    }
+}
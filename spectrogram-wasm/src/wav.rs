@@ -0,0 +1,789 @@
+// ============================================================
+// RIFF/WAVE 解碼
+// 支援 PCM 8/16/24/32 位、IEEE 浮點、WAVE_FORMAT_EXTENSIBLE、RF64/BW64，
+// 容忍缺少填充字節與被截斷的塊，輸出可直接傳給 WaveformEngine::load_channel 的 f32 通道
+// ============================================================
+
+use std::fmt;
+
+use wasm_bindgen::prelude::*;
+
+/// 格式標籤：整數 PCM
+pub const WAVE_FORMAT_PCM: u16 = 0x0001;
+/// 格式標籤：IEEE 浮點
+pub const WAVE_FORMAT_IEEE_FLOAT: u16 = 0x0003;
+/// 格式標籤：擴展格式，實際格式位於子格式 GUID 的前兩個字節
+pub const WAVE_FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+/// RF64 中表示「實際大小見 ds64 塊」的 32 位佔位值
+const RF64_SIZE_PLACEHOLDER: u32 = 0xFFFF_FFFF;
+
+/// WAV 解析錯誤
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WavError {
+    /// 文件頭不是 RIFF/RF64/BW64 + WAVE
+    NotWave,
+    /// 找不到或無法解析 fmt 塊
+    MissingFormat,
+    /// 找不到 data 塊
+    MissingData,
+    /// 不支援的格式標籤
+    UnsupportedFormat(u16),
+    /// 不支援的位深
+    UnsupportedBitDepth(u16),
+    /// fmt 塊中的參數無效（通道數、採樣率或塊對齊為 0 等）
+    InvalidFormat,
+    /// 請求的樣本範圍為空
+    EmptyRange,
+}
+
+impl fmt::Display for WavError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WavError::NotWave => write!(f, "not a RIFF/WAVE file"),
+            WavError::MissingFormat => write!(f, "missing or malformed fmt chunk"),
+            WavError::MissingData => write!(f, "missing data chunk"),
+            WavError::UnsupportedFormat(tag) => write!(f, "unsupported format tag 0x{:04X}", tag),
+            WavError::UnsupportedBitDepth(bits) => write!(f, "unsupported bit depth {}", bits),
+            WavError::InvalidFormat => write!(f, "invalid fmt chunk parameters"),
+            WavError::EmptyRange => write!(f, "empty sample range"),
+        }
+    }
+}
+
+impl std::error::Error for WavError {}
+
+/// 樣本編碼方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleFormat {
+    /// 整數 PCM（8 位為無符號，其餘為有符號小端）
+    Int,
+    /// IEEE 浮點（32 或 64 位）
+    Float,
+}
+
+/// fmt 塊描述的音頻格式
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WavFormat {
+    /// 解析 WAVE_FORMAT_EXTENSIBLE 後的實際格式標籤
+    pub format_tag: u16,
+    pub sample_format: SampleFormat,
+    pub num_channels: u16,
+    pub sample_rate: u32,
+    /// 每個樣本容器的位數
+    pub bits_per_sample: u16,
+    /// 有效位數（非擴展格式時等於 bits_per_sample）
+    pub valid_bits_per_sample: u16,
+    /// 每幀字節數
+    pub block_align: u16,
+    /// 擴展格式的揚聲器位置遮罩（非擴展格式時為 0）
+    pub channel_mask: u32,
+    /// 文件是否使用 WAVE_FORMAT_EXTENSIBLE
+    pub extensible: bool,
+}
+
+impl WavFormat {
+    /// 每個樣本容器的字節數
+    pub fn bytes_per_sample(&self) -> usize {
+        self.block_align as usize / self.num_channels as usize
+    }
+}
+
+/// 文件中的一個塊（位置與大小，不複製內容）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkInfo {
+    pub id: [u8; 4],
+    /// 塊內容在文件中的起始偏移（跳過 8 字節塊頭）
+    pub offset: usize,
+    /// 實際可讀取的內容大小（已按文件長度截斷）
+    pub size: usize,
+    /// 塊頭聲明的大小（RF64 中已由 ds64 解析）
+    pub declared_size: u64,
+}
+
+impl ChunkInfo {
+    /// 塊 ID 的字串形式
+    pub fn id_str(&self) -> String {
+        String::from_utf8_lossy(&self.id).into_owned()
+    }
+
+    /// 塊內容是否因文件截斷而不完整
+    pub fn is_truncated(&self) -> bool {
+        (self.size as u64) < self.declared_size
+    }
+}
+
+/// WAV 文件結構：格式、所有塊的位置，以及 data 塊
+#[derive(Debug, Clone)]
+pub struct WavInfo {
+    pub format: WavFormat,
+    /// 按文件順序排列的所有塊（包含 fmt、data 與 ds64）
+    pub chunks: Vec<ChunkInfo>,
+    /// data 塊在 chunks 中的索引
+    pub data_index: usize,
+    /// 是否為 RF64/BW64 文件
+    pub is_rf64: bool,
+}
+
+impl WavInfo {
+    /// data 塊
+    pub fn data_chunk(&self) -> &ChunkInfo {
+        &self.chunks[self.data_index]
+    }
+
+    /// 完整的幀數（忽略尾部不完整的幀）
+    pub fn num_frames(&self) -> usize {
+        self.data_chunk().size / self.format.block_align as usize
+    }
+
+    /// 音頻時長 (秒)
+    pub fn duration(&self) -> f64 {
+        self.num_frames() as f64 / self.format.sample_rate as f64
+    }
+
+    /// 文件是否有任何塊被截斷
+    pub fn is_truncated(&self) -> bool {
+        self.chunks.iter().any(|c| c.is_truncated())
+    }
+
+    /// 按 ID 查找第一個塊
+    pub fn find_chunk(&self, id: &[u8; 4]) -> Option<&ChunkInfo> {
+        self.chunks.iter().find(|c| &c.id == id)
+    }
+
+    /// 取得塊的內容
+    pub fn chunk_data<'a>(&self, bytes: &'a [u8], chunk: &ChunkInfo) -> &'a [u8] {
+        &bytes[chunk.offset..chunk.offset + chunk.size]
+    }
+}
+
+/// 解碼後的音頻：每個通道一個 f32 向量，值域 [-1, 1]
+#[derive(Debug, Clone, Default)]
+pub struct DecodedAudio {
+    pub sample_rate: u32,
+    pub bits_per_sample: u16,
+    pub channels: Vec<Vec<f32>>,
+}
+
+impl DecodedAudio {
+    /// 每個通道的樣本數
+    pub fn num_frames(&self) -> usize {
+        self.channels.first().map_or(0, |c| c.len())
+    }
+}
+
+fn read_u16(bytes: &[u8], pos: usize) -> u16 {
+    u16::from_le_bytes([bytes[pos], bytes[pos + 1]])
+}
+
+fn read_u32(bytes: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes([bytes[pos], bytes[pos + 1], bytes[pos + 2], bytes[pos + 3]])
+}
+
+fn read_u64(bytes: &[u8], pos: usize) -> u64 {
+    let mut buf = [0u8; 8];
+    buf.copy_from_slice(&bytes[pos..pos + 8]);
+    u64::from_le_bytes(buf)
+}
+
+/// 塊 ID 是否由可列印 ASCII 組成（用於判斷填充字節是否存在）
+fn is_plausible_id(bytes: &[u8], pos: usize) -> bool {
+    pos + 4 <= bytes.len() && bytes[pos..pos + 4].iter().all(|&b| (0x20..0x7F).contains(&b))
+}
+
+/// 解析 ds64 塊：返回 (data 大小, 其他塊的 64 位大小表)
+fn parse_ds64(content: &[u8]) -> (Option<u64>, Vec<([u8; 4], u64)>) {
+    if content.len() < 24 {
+        return (None, Vec::new());
+    }
+    let data_size = read_u64(content, 8);
+    let mut table = Vec::new();
+    if content.len() >= 28 {
+        let table_len = read_u32(content, 24) as usize;
+        for i in 0..table_len {
+            let pos = 28 + i * 12;
+            if pos + 12 > content.len() {
+                break;
+            }
+            let mut id = [0u8; 4];
+            id.copy_from_slice(&content[pos..pos + 4]);
+            table.push((id, read_u64(content, pos + 4)));
+        }
+    }
+    (Some(data_size), table)
+}
+
+/// 解析 fmt 塊
+fn parse_format(content: &[u8]) -> Result<WavFormat, WavError> {
+    if content.len() < 16 {
+        return Err(WavError::MissingFormat);
+    }
+    let raw_tag = read_u16(content, 0);
+    let num_channels = read_u16(content, 2);
+    let sample_rate = read_u32(content, 4);
+    let block_align = read_u16(content, 12);
+    let bits_per_sample = read_u16(content, 14);
+
+    let extensible = raw_tag == WAVE_FORMAT_EXTENSIBLE;
+    let (format_tag, valid_bits_per_sample, channel_mask) = if extensible {
+        // cbSize(2) + wValidBitsPerSample(2) + dwChannelMask(4) + SubFormat GUID(16)
+        if content.len() < 40 {
+            return Err(WavError::MissingFormat);
+        }
+        let valid = read_u16(content, 18);
+        let valid = if valid == 0 { bits_per_sample } else { valid };
+        (read_u16(content, 24), valid, read_u32(content, 20))
+    } else {
+        (raw_tag, bits_per_sample, 0)
+    };
+
+    if num_channels == 0 || sample_rate == 0 {
+        return Err(WavError::InvalidFormat);
+    }
+
+    let sample_format = match format_tag {
+        WAVE_FORMAT_PCM => SampleFormat::Int,
+        WAVE_FORMAT_IEEE_FLOAT => SampleFormat::Float,
+        other => return Err(WavError::UnsupportedFormat(other)),
+    };
+
+    // 部分寫入器的 block_align 錯誤，此時由位深重新計算
+    let container_bytes = (bits_per_sample as usize).div_ceil(8);
+    let expected_align = container_bytes * num_channels as usize;
+    let block_align = if block_align != 0 && (block_align as usize).is_multiple_of(num_channels as usize) {
+        block_align
+    } else if expected_align > 0 && expected_align <= u16::MAX as usize {
+        expected_align as u16
+    } else {
+        return Err(WavError::InvalidFormat);
+    };
+
+    let container_bits = (block_align as usize / num_channels as usize * 8) as u16;
+    let supported = match sample_format {
+        SampleFormat::Int => matches!(container_bits, 8 | 16 | 24 | 32),
+        SampleFormat::Float => matches!(container_bits, 32 | 64),
+    };
+    if !supported {
+        return Err(WavError::UnsupportedBitDepth(bits_per_sample));
+    }
+
+    Ok(WavFormat {
+        format_tag,
+        sample_format,
+        num_channels,
+        sample_rate,
+        bits_per_sample: container_bits,
+        valid_bits_per_sample: valid_bits_per_sample.min(container_bits),
+        block_align,
+        channel_mask,
+        extensible,
+    })
+}
+
+/// 解析 WAV 文件結構（不解碼樣本）
+///
+/// 容錯規則：
+/// - 塊大小超過文件長度時截斷到文件末尾
+/// - 奇數大小的塊缺少填充字節時，自動判斷下一個塊的實際位置
+/// - data 塊大小為 0xFFFFFFFF，或為 0 但其後不是另一個塊（串流寫入未回填）時，
+///   視為延伸到文件末尾
+///
+/// # Arguments
+/// * `bytes` - 完整的文件內容
+///
+/// # Returns
+/// 文件結構，或解析錯誤
+pub fn parse_wav(bytes: &[u8]) -> Result<WavInfo, WavError> {
+    if bytes.len() < 12 || &bytes[8..12] != b"WAVE" {
+        return Err(WavError::NotWave);
+    }
+    let is_rf64 = match &bytes[0..4] {
+        b"RIFF" => false,
+        b"RF64" | b"BW64" => true,
+        _ => return Err(WavError::NotWave),
+    };
+
+    let mut chunks: Vec<ChunkInfo> = Vec::new();
+    let mut ds64_data_size: Option<u64> = None;
+    let mut ds64_table: Vec<([u8; 4], u64)> = Vec::new();
+    let mut pos = 12;
+
+    while pos + 8 <= bytes.len() {
+        let mut id = [0u8; 4];
+        id.copy_from_slice(&bytes[pos..pos + 4]);
+        let raw_size = read_u32(bytes, pos + 4);
+        let offset = pos + 8;
+        let remaining = (bytes.len() - offset) as u64;
+
+        let declared_size = if is_rf64 && raw_size == RF64_SIZE_PLACEHOLDER {
+            if &id == b"data" {
+                ds64_data_size.unwrap_or(remaining)
+            } else {
+                ds64_table
+                    .iter()
+                    .find(|(table_id, _)| *table_id == id)
+                    .map_or(remaining, |&(_, size)| size)
+            }
+        } else if &id == b"data"
+            && (raw_size == RF64_SIZE_PLACEHOLDER || (raw_size == 0 && !is_plausible_id(bytes, offset)))
+        {
+            // 串流寫入未回填大小：data 延伸到文件末尾
+            remaining
+        } else {
+            raw_size as u64
+        };
+
+        let size = declared_size.min(remaining) as usize;
+        if &id == b"ds64" {
+            let (data_size, table) = parse_ds64(&bytes[offset..offset + size]);
+            ds64_data_size = data_size;
+            ds64_table = table;
+        }
+        chunks.push(ChunkInfo {
+            id,
+            offset,
+            size,
+            declared_size,
+        });
+
+        let next = offset + size;
+        // 填充字節按規範為 0；若該位置非 0 而恰好是合理的塊 ID，表示寫入器省略了填充字節
+        pos = if size % 2 == 1 && next < bytes.len() && bytes[next] != 0 && is_plausible_id(bytes, next) {
+            next
+        } else {
+            next + size % 2
+        };
+    }
+
+    let format_chunk = chunks.iter().find(|c| &c.id == b"fmt ").ok_or(WavError::MissingFormat)?;
+    let format = parse_format(&bytes[format_chunk.offset..format_chunk.offset + format_chunk.size])?;
+    let data_index = chunks.iter().position(|c| &c.id == b"data").ok_or(WavError::MissingData)?;
+
+    Ok(WavInfo {
+        format,
+        chunks,
+        data_index,
+        is_rf64,
+    })
+}
+
+/// 解析 `LIST` 塊中的 INFO 子塊（例如 ICMT、IART）
+///
+/// # Arguments
+/// * `content` - LIST 塊內容（以 "INFO" 開頭）
+///
+/// # Returns
+/// 按順序排列的 (子塊 ID, 文本)；不是 INFO 列表時為空
+pub fn parse_info_list(content: &[u8]) -> Vec<([u8; 4], String)> {
+    let mut entries = Vec::new();
+    if content.len() < 4 || &content[0..4] != b"INFO" {
+        return entries;
+    }
+
+    let mut pos = 4;
+    while pos + 8 <= content.len() {
+        let mut id = [0u8; 4];
+        id.copy_from_slice(&content[pos..pos + 4]);
+        let size = (read_u32(content, pos + 4) as usize).min(content.len() - pos - 8);
+        let data = &content[pos + 8..pos + 8 + size];
+        let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+        entries.push((id, String::from_utf8_lossy(&data[..end]).trim().to_string()));
+        pos += 8 + size + size % 2;
+    }
+
+    entries
+}
+
+/// 從 WAV 文件中讀取所有 LIST/INFO 條目
+pub fn read_info_entries(bytes: &[u8], info: &WavInfo) -> Vec<([u8; 4], String)> {
+    info.chunks
+        .iter()
+        .filter(|c| &c.id == b"LIST")
+        .flat_map(|c| parse_info_list(info.chunk_data(bytes, c)))
+        .collect()
+}
+
+/// 將一個樣本容器轉換為 [-1, 1] 範圍的 f32
+fn decode_sample(bytes: &[u8], format: &WavFormat) -> f32 {
+    match (format.sample_format, bytes.len()) {
+        (SampleFormat::Int, 1) => (bytes[0] as f32 - 128.0) / 128.0,
+        (SampleFormat::Int, 2) => i16::from_le_bytes([bytes[0], bytes[1]]) as f32 / 32768.0,
+        (SampleFormat::Int, 3) => {
+            // 放到 i32 高位以保留符號
+            let value = i32::from_le_bytes([0, bytes[0], bytes[1], bytes[2]]) >> 8;
+            value as f32 / 8_388_608.0
+        }
+        (SampleFormat::Int, 4) => {
+            i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f32 / 2_147_483_648.0
+        }
+        (SampleFormat::Float, 4) => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+        (SampleFormat::Float, 8) => {
+            let mut buf = [0u8; 8];
+            buf.copy_from_slice(bytes);
+            f64::from_le_bytes(buf) as f32
+        }
+        _ => 0.0,
+    }
+}
+
+/// 解碼 data 塊中的一段幀
+///
+/// # Arguments
+/// * `bytes` - 完整的文件內容
+/// * `info` - parse_wav 的結果
+/// * `start_frame` - 起始幀（包含）
+/// * `end_frame` - 結束幀（不包含，超出時截斷到文件末尾）
+///
+/// # Returns
+/// 每個通道一個向量
+pub fn decode_frames(bytes: &[u8], info: &WavInfo, start_frame: usize, end_frame: usize) -> Vec<Vec<f32>> {
+    let format = &info.format;
+    let num_channels = format.num_channels as usize;
+    let end_frame = end_frame.min(info.num_frames());
+    let start_frame = start_frame.min(end_frame);
+    let frame_count = end_frame - start_frame;
+
+    let block_align = format.block_align as usize;
+    let sample_bytes = format.bytes_per_sample();
+    let data = info.data_chunk();
+    let base = data.offset + start_frame * block_align;
+
+    let mut channels = vec![Vec::with_capacity(frame_count); num_channels];
+    for frame in 0..frame_count {
+        let frame_pos = base + frame * block_align;
+        for (ch, channel) in channels.iter_mut().enumerate() {
+            let pos = frame_pos + ch * sample_bytes;
+            channel.push(decode_sample(&bytes[pos..pos + sample_bytes], format));
+        }
+    }
+
+    channels
+}
+
+/// 解碼完整的 WAV 文件
+///
+/// # Arguments
+/// * `bytes` - 完整的文件內容
+///
+/// # Returns
+/// 解碼後的音頻，或解析錯誤
+pub fn decode_wav(bytes: &[u8]) -> Result<DecodedAudio, WavError> {
+    let info = parse_wav(bytes)?;
+    Ok(DecodedAudio {
+        sample_rate: info.format.sample_rate,
+        bits_per_sample: info.format.valid_bits_per_sample,
+        channels: decode_frames(bytes, &info, 0, usize::MAX),
+    })
+}
+
+/// WavDecoder: 在 Rust 端解碼 WAV 文件
+///
+/// 解碼失敗時不拋出異常，而是 `is_valid()` 返回 false，
+/// 並可由 `get_error()` 取得原因。
+#[wasm_bindgen]
+pub struct WavDecoder {
+    audio: DecodedAudio,
+    chunk_ids: Vec<String>,
+    is_rf64: bool,
+    truncated: bool,
+    error: Option<WavError>,
+}
+
+#[wasm_bindgen]
+impl WavDecoder {
+    /// 解碼 WAV 文件
+    ///
+    /// # Arguments
+    /// * `bytes` - 文件內容 (Uint8Array)
+    #[wasm_bindgen(constructor)]
+    pub fn new(bytes: &[u8]) -> WavDecoder {
+        let mut decoder = WavDecoder {
+            audio: DecodedAudio::default(),
+            chunk_ids: Vec::new(),
+            is_rf64: false,
+            truncated: false,
+            error: None,
+        };
+
+        match parse_wav(bytes) {
+            Ok(info) => {
+                decoder.audio = DecodedAudio {
+                    sample_rate: info.format.sample_rate,
+                    bits_per_sample: info.format.valid_bits_per_sample,
+                    channels: decode_frames(bytes, &info, 0, usize::MAX),
+                };
+                decoder.chunk_ids = info.chunks.iter().map(|c| c.id_str()).collect();
+                decoder.is_rf64 = info.is_rf64;
+                decoder.truncated = info.is_truncated();
+            }
+            Err(err) => decoder.error = Some(err),
+        }
+
+        decoder
+    }
+
+    /// 是否成功解碼
+    #[wasm_bindgen]
+    pub fn is_valid(&self) -> bool {
+        self.error.is_none()
+    }
+
+    /// 解碼失敗的原因（成功時為空字串）
+    #[wasm_bindgen]
+    pub fn get_error(&self) -> String {
+        self.error.as_ref().map(|e| e.to_string()).unwrap_or_default()
+    }
+
+    /// 獲取採樣率 (Hz)
+    #[wasm_bindgen]
+    pub fn get_sample_rate(&self) -> u32 {
+        self.audio.sample_rate
+    }
+
+    /// 獲取有效位深
+    #[wasm_bindgen]
+    pub fn get_bits_per_sample(&self) -> u16 {
+        self.audio.bits_per_sample
+    }
+
+    /// 獲取通道數量
+    #[wasm_bindgen]
+    pub fn get_num_channels(&self) -> usize {
+        self.audio.channels.len()
+    }
+
+    /// 獲取每個通道的樣本數
+    #[wasm_bindgen]
+    pub fn get_num_frames(&self) -> usize {
+        self.audio.num_frames()
+    }
+
+    /// 是否為 RF64/BW64 文件
+    #[wasm_bindgen]
+    pub fn is_rf64(&self) -> bool {
+        self.is_rf64
+    }
+
+    /// 文件是否被截斷（data 或其他塊短於聲明的大小）
+    #[wasm_bindgen]
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// 按文件順序列出所有塊 ID，以逗號分隔（例如 "fmt ,LIST,data,guan"）
+    #[wasm_bindgen]
+    pub fn get_chunk_ids(&self) -> String {
+        self.chunk_ids.join(",")
+    }
+
+    /// 獲取單個通道的樣本，可直接傳給 WaveformEngine::load_channel
+    ///
+    /// # Arguments
+    /// * `channel_idx` - 通道索引
+    ///
+    /// # Returns
+    /// Float32Array（索引無效時為空）
+    #[wasm_bindgen]
+    pub fn get_channel(&self, channel_idx: usize) -> Vec<f32> {
+        self.audio.channels.get(channel_idx).cloned().unwrap_or_default()
+    }
+
+    /// 釋放解碼後的樣本
+    #[wasm_bindgen]
+    pub fn release_memory(&mut self) {
+        self.audio.channels = Vec::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wav_writer::encode_wav;
+
+    /// 16 位 PCM 樣本值與對應的解碼結果
+    const SAMPLES: [i16; 4] = [0, 16384, -16384, -32768];
+    const DECODED: [f32; 4] = [0.0, 0.5, -0.5, -1.0];
+
+    fn chunk(id: &[u8; 4], size: u32, content: &[u8]) -> Vec<u8> {
+        let mut out = id.to_vec();
+        out.extend_from_slice(&size.to_le_bytes());
+        out.extend_from_slice(content);
+        out
+    }
+
+    fn riff(tag: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut out = tag.to_vec();
+        out.extend_from_slice(&(4 + body.len() as u32).to_le_bytes());
+        out.extend_from_slice(b"WAVE");
+        out.extend_from_slice(body);
+        out
+    }
+
+    /// 單通道 16 位 PCM 的 fmt 塊
+    fn pcm16_fmt(sample_rate: u32) -> Vec<u8> {
+        let mut fmt = Vec::new();
+        fmt.extend_from_slice(&WAVE_FORMAT_PCM.to_le_bytes());
+        fmt.extend_from_slice(&1u16.to_le_bytes());
+        fmt.extend_from_slice(&sample_rate.to_le_bytes());
+        fmt.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+        fmt.extend_from_slice(&2u16.to_le_bytes());
+        fmt.extend_from_slice(&16u16.to_le_bytes());
+        chunk(b"fmt ", 16, &fmt)
+    }
+
+    fn pcm16_data() -> Vec<u8> {
+        SAMPLES.iter().flat_map(|s| s.to_le_bytes()).collect()
+    }
+
+    fn assert_samples(channel: &[f32], expected: &[f32]) {
+        assert_eq!(channel.len(), expected.len());
+        for (a, b) in channel.iter().zip(expected) {
+            assert!((a - b).abs() < 1e-6, "{} != {}", a, b);
+        }
+    }
+
+    #[test]
+    fn rf64_uses_ds64_data_size() {
+        let data = pcm16_data();
+        let mut ds64 = Vec::new();
+        ds64.extend_from_slice(&0u64.to_le_bytes());
+        ds64.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        ds64.extend_from_slice(&0u64.to_le_bytes());
+        ds64.extend_from_slice(&0u32.to_le_bytes());
+
+        let mut body = chunk(b"ds64", 28, &ds64);
+        body.extend(pcm16_fmt(48000));
+        body.extend(chunk(b"data", u32::MAX, &data));
+        // data 之後的塊不應被當作樣本
+        body.extend(chunk(b"junk", 4, &[1, 2, 3, 4]));
+        let bytes = riff(b"RF64", &body);
+
+        let info = parse_wav(&bytes).unwrap();
+        assert!(info.is_rf64);
+        assert_eq!(info.num_frames(), SAMPLES.len());
+        assert!(info.find_chunk(b"junk").is_some());
+        assert!(!info.is_truncated());
+        assert_samples(&decode_wav(&bytes).unwrap().channels[0], &DECODED);
+    }
+
+    #[test]
+    fn extensible_format_resolves_subformat() {
+        let channels = vec![vec![0.0, 0.5, -0.5]; 3];
+        let bytes = encode_wav(&channels, 96000, SampleFormat::Int, 24, &[]).unwrap();
+
+        let info = parse_wav(&bytes).unwrap();
+        assert!(info.format.extensible);
+        assert_eq!(info.format.format_tag, WAVE_FORMAT_PCM);
+        assert_eq!(info.format.num_channels, 3);
+        assert_eq!(info.format.bits_per_sample, 24);
+        assert_eq!(info.format.block_align, 9);
+
+        let decoded = decode_wav(&bytes).unwrap();
+        assert_eq!(decoded.sample_rate, 96000);
+        for channel in &decoded.channels {
+            assert_samples(channel, &[0.0, 0.5, -0.5]);
+        }
+    }
+
+    #[test]
+    fn extensible_float_format() {
+        let bytes = encode_wav(&vec![vec![0.25, -0.75]; 4], 44100, SampleFormat::Float, 32, &[]).unwrap();
+        let info = parse_wav(&bytes).unwrap();
+        assert!(info.format.extensible);
+        assert_eq!(info.format.sample_format, SampleFormat::Float);
+        assert_samples(&decode_wav(&bytes).unwrap().channels[3], &[0.25, -0.75]);
+    }
+
+    #[test]
+    fn odd_chunk_without_pad_byte() {
+        let mut body = pcm16_fmt(48000);
+        // 奇數大小的塊，寫入器省略了填充字節
+        body.extend(chunk(b"note", 3, b"abc"));
+        body.extend(chunk(b"data", 8, &pcm16_data()));
+        let bytes = riff(b"RIFF", &body);
+
+        let info = parse_wav(&bytes).unwrap();
+        let ids: Vec<String> = info.chunks.iter().map(|c| c.id_str()).collect();
+        assert_eq!(ids, ["fmt ", "note", "data"]);
+        assert_samples(&decode_wav(&bytes).unwrap().channels[0], &DECODED);
+    }
+
+    #[test]
+    fn odd_chunk_with_pad_byte() {
+        let mut body = pcm16_fmt(48000);
+        body.extend(chunk(b"note", 3, b"abc\0"));
+        body.extend(chunk(b"data", 8, &pcm16_data()));
+        let bytes = riff(b"RIFF", &body);
+
+        let info = parse_wav(&bytes).unwrap();
+        assert_eq!(info.find_chunk(b"note").unwrap().size, 3);
+        assert_samples(&decode_wav(&bytes).unwrap().channels[0], &DECODED);
+    }
+
+    #[test]
+    fn streaming_data_size_extends_to_end_of_file() {
+        for raw_size in [0, u32::MAX] {
+            let mut body = pcm16_fmt(48000);
+            body.extend(chunk(b"data", raw_size, &pcm16_data()));
+            let bytes = riff(b"RIFF", &body);
+
+            let info = parse_wav(&bytes).unwrap();
+            assert!(!info.is_rf64);
+            assert_eq!(info.num_frames(), SAMPLES.len(), "data size {:#X}", raw_size);
+            assert_samples(&decode_wav(&bytes).unwrap().channels[0], &DECODED);
+        }
+    }
+
+    #[test]
+    fn empty_data_chunk_followed_by_chunk() {
+        let mut body = pcm16_fmt(48000);
+        body.extend(chunk(b"data", 0, &[]));
+        body.extend(chunk(b"junk", 8, &pcm16_data()));
+        let bytes = riff(b"RIFF", &body);
+
+        let info = parse_wav(&bytes).unwrap();
+        assert_eq!(info.num_frames(), 0);
+        assert!(info.find_chunk(b"junk").is_some());
+    }
+
+    #[test]
+    fn truncated_data_chunk() {
+        let mut body = pcm16_fmt(48000);
+        // 聲明 1000 字節，實際只有 3 個完整幀加半幀
+        body.extend(chunk(b"data", 1000, &pcm16_data()[..7]));
+        let bytes = riff(b"RIFF", &body);
+
+        let info = parse_wav(&bytes).unwrap();
+        assert!(info.is_truncated());
+        assert_eq!(info.data_chunk().size, 7);
+        assert_eq!(info.data_chunk().declared_size, 1000);
+        assert_eq!(info.num_frames(), 3);
+        assert_samples(&decode_wav(&bytes).unwrap().channels[0], &DECODED[..3]);
+    }
+
+    #[test]
+    fn truncated_chunk_header() {
+        let mut body = pcm16_fmt(48000);
+        body.extend(chunk(b"data", 8, &pcm16_data()));
+        // 文件末尾只剩半個塊頭
+        body.extend_from_slice(b"LIS");
+        let bytes = riff(b"RIFF", &body);
+
+        let info = parse_wav(&bytes).unwrap();
+        assert_eq!(info.chunks.len(), 2);
+        assert_samples(&decode_wav(&bytes).unwrap().channels[0], &DECODED);
+    }
+
+    #[test]
+    fn truncated_format_chunk() {
+        let mut bytes = riff(b"RIFF", &pcm16_fmt(48000));
+        bytes.truncate(bytes.len() - 4);
+        assert_eq!(parse_wav(&bytes).unwrap_err(), WavError::MissingFormat);
+    }
+
+    #[test]
+    fn rejects_non_wave() {
+        assert_eq!(parse_wav(b"RIFF\0\0\0\0AVI ").unwrap_err(), WavError::NotWave);
+        assert_eq!(parse_wav(b"RIFF").unwrap_err(), WavError::NotWave);
+    }
+}
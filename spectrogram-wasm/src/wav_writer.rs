@@ -0,0 +1,348 @@
+// ============================================================
+// RIFF/WAVE 寫入與裁剪
+// 裁剪時直接複製樣本字節（無損），保留 GUANO、WAMD、LIST/INFO 及其他未知塊，
+// 重新計算塊大小，並將 GUANO 時間戳按裁剪起點平移、時長改為片段時長
+// ============================================================
+
+use wasm_bindgen::prelude::*;
+
+use crate::guano::GuanoMetadata;
+use crate::wav::{parse_wav, SampleFormat, WavError, WAVE_FORMAT_EXTENSIBLE, WAVE_FORMAT_IEEE_FLOAT, WAVE_FORMAT_PCM};
+
+/// 一個 RIFF 塊（ID 與內容，不含 8 字節塊頭）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RiffChunk {
+    pub id: [u8; 4],
+    pub data: Vec<u8>,
+}
+
+impl RiffChunk {
+    pub fn new(id: &[u8; 4], data: Vec<u8>) -> RiffChunk {
+        RiffChunk { id: *id, data }
+    }
+}
+
+/// 將塊序列組裝成完整的 WAVE 文件
+///
+/// 塊按給定順序寫出，奇數大小的塊補一個 0 字節。
+/// 若總大小超過 32 位上限，自動改寫為 RF64 並在最前面插入 ds64 塊；
+/// 傳入的 ds64 塊一律忽略（由本函數重建）。
+///
+/// # Arguments
+/// * `chunks` - 塊序列（應包含 fmt 與 data）
+///
+/// # Returns
+/// 完整的文件內容
+pub fn write_riff(chunks: &[RiffChunk]) -> Vec<u8> {
+    let chunks: Vec<&RiffChunk> = chunks.iter().filter(|c| &c.id != b"ds64").collect();
+    let body_len: u64 = chunks.iter().map(|c| 8 + c.data.len() as u64 + c.data.len() as u64 % 2).sum();
+
+    // ds64 固定部分：riffSize(8) + dataSize(8) + sampleCount(8) + tableLength(4)
+    const DS64_LEN: u64 = 28;
+    let is_rf64 = 4 + body_len > u32::MAX as u64;
+    let riff_size = if is_rf64 { 4 + 8 + DS64_LEN + body_len } else { 4 + body_len };
+
+    let mut out = Vec::with_capacity(riff_size as usize + 8);
+    if is_rf64 {
+        out.extend_from_slice(b"RF64");
+        out.extend_from_slice(&u32::MAX.to_le_bytes());
+        out.extend_from_slice(b"WAVE");
+
+        let data_size = chunks.iter().find(|c| &c.id == b"data").map_or(0, |c| c.data.len() as u64);
+        out.extend_from_slice(b"ds64");
+        out.extend_from_slice(&(DS64_LEN as u32).to_le_bytes());
+        out.extend_from_slice(&riff_size.to_le_bytes());
+        out.extend_from_slice(&data_size.to_le_bytes());
+        // sampleCount 為可選欄位，讀取端以 data 大小與 fmt 計算
+        out.extend_from_slice(&0u64.to_le_bytes());
+        out.extend_from_slice(&0u32.to_le_bytes());
+    } else {
+        out.extend_from_slice(b"RIFF");
+        out.extend_from_slice(&(riff_size as u32).to_le_bytes());
+        out.extend_from_slice(b"WAVE");
+    }
+
+    for chunk in chunks {
+        let size = chunk.data.len() as u64;
+        let header_size = if size > u32::MAX as u64 { u32::MAX } else { size as u32 };
+        out.extend_from_slice(&chunk.id);
+        out.extend_from_slice(&header_size.to_le_bytes());
+        out.extend_from_slice(&chunk.data);
+        if size % 2 == 1 {
+            out.push(0);
+        }
+    }
+
+    out
+}
+
+/// 產生 fmt 塊內容
+///
+/// 超過兩個通道或整數位深超過 16 位時使用 WAVE_FORMAT_EXTENSIBLE。
+///
+/// # Returns
+/// 塊對齊超出 u16 或字節率超出 u32 時為 None
+fn format_chunk(
+    sample_format: SampleFormat,
+    num_channels: u16,
+    sample_rate: u32,
+    bits_per_sample: u16,
+) -> Option<Vec<u8>> {
+    let tag = match sample_format {
+        SampleFormat::Int => WAVE_FORMAT_PCM,
+        SampleFormat::Float => WAVE_FORMAT_IEEE_FLOAT,
+    };
+    let extensible = num_channels > 2 || (sample_format == SampleFormat::Int && bits_per_sample > 16);
+    let block_align = u16::try_from(num_channels as u32 * bits_per_sample as u32 / 8).ok()?;
+    let byte_rate = sample_rate.checked_mul(block_align as u32)?;
+
+    let mut fmt = Vec::with_capacity(40);
+    fmt.extend_from_slice(&(if extensible { WAVE_FORMAT_EXTENSIBLE } else { tag }).to_le_bytes());
+    fmt.extend_from_slice(&num_channels.to_le_bytes());
+    fmt.extend_from_slice(&sample_rate.to_le_bytes());
+    fmt.extend_from_slice(&byte_rate.to_le_bytes());
+    fmt.extend_from_slice(&block_align.to_le_bytes());
+    fmt.extend_from_slice(&bits_per_sample.to_le_bytes());
+
+    if extensible {
+        fmt.extend_from_slice(&22u16.to_le_bytes());
+        fmt.extend_from_slice(&bits_per_sample.to_le_bytes());
+        // 不指定揚聲器位置
+        fmt.extend_from_slice(&0u32.to_le_bytes());
+        // KSDATAFORMAT_SUBTYPE_* = {tag-0000-0010-8000-00AA00389B71}
+        fmt.extend_from_slice(&tag.to_le_bytes());
+        fmt.extend_from_slice(&[
+            0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x80, 0x00, 0x00, 0xAA, 0x00, 0x38, 0x9B, 0x71,
+        ]);
+    }
+
+    Some(fmt)
+}
+
+/// 將 f32 樣本編碼為 WAV
+///
+/// # Arguments
+/// * `channels` - 每個通道一個向量（長度不一時以最短者為準）
+/// * `sample_rate` - 採樣率 (Hz)
+/// * `sample_format` - 整數 PCM 或 IEEE 浮點
+/// * `bits_per_sample` - 整數：8/16/24/32；浮點：32/64
+/// * `extra_chunks` - 寫在 fmt 與 data 之間的其他塊（例如 guan、LIST）
+///
+/// # Returns
+/// 完整的文件內容；參數無效（包括通道數 × 位深超出 fmt 塊的塊對齊範圍）時返回 None
+pub fn encode_wav(
+    channels: &[Vec<f32>],
+    sample_rate: u32,
+    sample_format: SampleFormat,
+    bits_per_sample: u16,
+    extra_chunks: &[RiffChunk],
+) -> Option<Vec<u8>> {
+    let valid_bits = match sample_format {
+        SampleFormat::Int => matches!(bits_per_sample, 8 | 16 | 24 | 32),
+        SampleFormat::Float => matches!(bits_per_sample, 32 | 64),
+    };
+    if channels.is_empty() || channels.len() > u16::MAX as usize || sample_rate == 0 || !valid_bits {
+        return None;
+    }
+    let fmt = format_chunk(sample_format, channels.len() as u16, sample_rate, bits_per_sample)?;
+
+    let num_frames = channels.iter().map(|c| c.len()).min().unwrap_or(0);
+    let bytes_per_sample = bits_per_sample as usize / 8;
+    let mut data = Vec::with_capacity(num_frames * channels.len() * bytes_per_sample);
+
+    for frame in 0..num_frames {
+        for channel in channels {
+            let x = channel[frame];
+            match (sample_format, bits_per_sample) {
+                (SampleFormat::Float, 32) => data.extend_from_slice(&x.to_le_bytes()),
+                (SampleFormat::Float, _) => data.extend_from_slice(&(x as f64).to_le_bytes()),
+                (SampleFormat::Int, 8) => {
+                    // 無符號偏移 128，與解碼時的 (b - 128) / 128 對應
+                    data.push((x as f64 * 128.0 + 128.0).round().clamp(0.0, 255.0) as u8);
+                }
+                (SampleFormat::Int, bits) => {
+                    let scale = (1i64 << (bits - 1)) as f64;
+                    let value = (x as f64 * scale).round().clamp(-scale, scale - 1.0) as i32;
+                    data.extend_from_slice(&value.to_le_bytes()[..bytes_per_sample]);
+                }
+            }
+        }
+    }
+
+    let mut chunks = Vec::with_capacity(extra_chunks.len() + 2);
+    chunks.push(RiffChunk::new(b"fmt ", fmt));
+    chunks.extend(extra_chunks.iter().filter(|c| &c.id != b"fmt " && &c.id != b"data").cloned());
+    chunks.push(RiffChunk::new(b"data", data));
+
+    Some(write_riff(&chunks))
+}
+
+/// 按時間裁剪 WAV 文件
+///
+/// 樣本字節直接複製，不經過重新量化。其餘塊按原順序保留：
+/// - `guan`：Timestamp 加上裁剪起點的偏移，Length 改為片段時長（皆按 TE 換算為實際時間），
+///   使片段可追溯到原錄音時間
+/// - `wamd`、`LIST`、`bext` 及其他未知塊：原樣複製
+/// - `ds64`：依新的大小重建（僅在輸出仍超過 4 GB 時）
+///
+/// # Arguments
+/// * `bytes` - 原始文件內容
+/// * `start_sec` - 起始時間 (秒)
+/// * `end_sec` - 結束時間 (秒，超出文件長度時截斷)
+///
+/// # Returns
+/// 裁剪後的文件內容，或解析錯誤
+pub fn crop_wav_bytes(bytes: &[u8], start_sec: f64, end_sec: f64) -> Result<Vec<u8>, WavError> {
+    let info = parse_wav(bytes)?;
+    let sample_rate = info.format.sample_rate as f64;
+    let num_frames = info.num_frames();
+
+    let start_frame = ((start_sec.max(0.0) * sample_rate).floor() as usize).min(num_frames);
+    let end_frame = ((end_sec.max(0.0) * sample_rate).floor() as usize).min(num_frames);
+    if end_frame <= start_frame {
+        return Err(WavError::EmptyRange);
+    }
+    let offset_seconds = start_frame as f64 / sample_rate;
+    let duration_seconds = (end_frame - start_frame) as f64 / sample_rate;
+
+    let block_align = info.format.block_align as usize;
+    let chunks: Vec<RiffChunk> = info
+        .chunks
+        .iter()
+        .filter(|chunk| &chunk.id != b"ds64")
+        .map(|chunk| {
+            let content = info.chunk_data(bytes, chunk);
+            let data = match &chunk.id {
+                b"data" => content[start_frame * block_align..end_frame * block_align].to_vec(),
+                b"guan" => match GuanoMetadata::parse_text(&String::from_utf8_lossy(content)) {
+                    Some(mut guano) => {
+                        // 時間擴展錄音的文件時長是實際時長的 TE 倍
+                        let te = guano.get_te();
+                        guano.shift_timestamp(offset_seconds / te);
+                        if guano.get("Length").is_some() {
+                            guano.set("Length", &format!("{:.6}", duration_seconds / te));
+                        }
+                        guano.to_text().into_bytes()
+                    }
+                    None => content.to_vec(),
+                },
+                _ => content.to_vec(),
+            };
+            RiffChunk { id: chunk.id, data }
+        })
+        .collect();
+
+    Ok(write_riff(&chunks))
+}
+
+/// 按時間裁剪 WAV 文件，保留元數據塊
+///
+/// # Arguments
+/// * `bytes` - 原始文件內容 (Uint8Array)
+/// * `start_sec` - 起始時間 (秒)
+/// * `end_sec` - 結束時間 (秒)
+///
+/// # Returns
+/// 裁剪後的 WAV 文件 (Uint8Array)；文件無效或範圍為空時返回空數組
+#[wasm_bindgen]
+pub fn crop_wav(bytes: &[u8], start_sec: f64, end_sec: f64) -> Vec<u8> {
+    crop_wav_bytes(bytes, start_sec, end_sec).unwrap_or_default()
+}
+
+/// 將單通道 f32 音頻編碼為 WAV
+///
+/// # Arguments
+/// * `audio_data` - 音頻數據 (Float32Array)
+/// * `sample_rate` - 採樣率 (Hz)
+/// * `bits_per_sample` - 位深（8/16/24 為整數 PCM，32 為 IEEE 浮點）
+///
+/// # Returns
+/// WAV 文件 (Uint8Array)；參數無效時返回空數組
+#[wasm_bindgen]
+pub fn encode_wav_mono(audio_data: &[f32], sample_rate: u32, bits_per_sample: u16) -> Vec<u8> {
+    let sample_format = if bits_per_sample == 32 { SampleFormat::Float } else { SampleFormat::Int };
+    encode_wav(&[audio_data.to_vec()], sample_rate, sample_format, bits_per_sample, &[]).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn guano_chunk(te: &str) -> RiffChunk {
+        let mut guano = GuanoMetadata::new();
+        guano.set("Timestamp", "2024-05-01T21:00:00");
+        guano.set("Length", "10.000000");
+        guano.set("TE", te);
+        guano.to_chunk()
+    }
+
+    fn cropped_guano(bytes: &[u8], start_sec: f64, end_sec: f64) -> GuanoMetadata {
+        let cropped = crop_wav_bytes(bytes, start_sec, end_sec).unwrap();
+        GuanoMetadata::read_from_wav(&cropped).unwrap()
+    }
+
+    #[test]
+    fn crop_updates_guano_timestamp_and_length() {
+        let bytes = encode_wav(&[vec![0.0; 10_000]], 1000, SampleFormat::Int, 16, &[guano_chunk("1")]).unwrap();
+        let guano = cropped_guano(&bytes, 2.0, 3.0);
+        assert_eq!(guano.get("Length").unwrap(), "1.000000");
+        assert_eq!(guano.get_timestamp(), "2024-05-01T21:00:02");
+    }
+
+    #[test]
+    fn crop_scales_guano_length_by_te() {
+        let bytes = encode_wav(&[vec![0.0; 10_000]], 1000, SampleFormat::Int, 16, &[guano_chunk("10")]).unwrap();
+        let guano = cropped_guano(&bytes, 2.0, 3.0);
+        assert_eq!(guano.get("Length").unwrap(), "0.100000");
+        assert_eq!(guano.get_timestamp(), "2024-05-01T21:00:00.2");
+    }
+
+    #[test]
+    fn encode_many_channels_without_u16_overflow() {
+        // 1024 × 64 位超出 u16 的中間乘積，但塊對齊 8192 仍然有效
+        let bytes = encode_wav(&vec![vec![0.5f32; 2]; 1024], 1000, SampleFormat::Float, 64, &[]).unwrap();
+        let info = crate::wav::parse_wav(&bytes).unwrap();
+        assert_eq!(info.format.block_align, 8192);
+        assert_eq!(info.num_frames(), 2);
+    }
+
+    #[test]
+    fn encode_rejects_block_align_overflow() {
+        let channels = vec![vec![0.0f32; 1]; 8192];
+        assert!(encode_wav(&channels, 1000, SampleFormat::Float, 64, &[]).is_none());
+        assert!(encode_wav(&channels[..8191], 1000, SampleFormat::Float, 64, &[]).is_some());
+    }
+
+    #[test]
+    fn encode_decode_round_trip_every_depth() {
+        // 包括兩端的滿幅值與超出範圍的值（被限制到可表示範圍）
+        let samples = vec![-1.5, -1.0, -0.5, -0.25, 0.0, 0.1, 0.25, 0.5, 0.9, 1.0, 1.5];
+        let cases = [
+            (SampleFormat::Int, 8, 1.0 / 128.0),
+            (SampleFormat::Int, 16, 1.0 / 32_768.0),
+            (SampleFormat::Int, 24, 1.0 / 8_388_608.0),
+            (SampleFormat::Int, 32, 1.0 / 2_147_483_648.0),
+            (SampleFormat::Float, 32, 0.0),
+            (SampleFormat::Float, 64, 0.0),
+        ];
+        for (sample_format, bits, step) in cases {
+            let bytes = encode_wav(std::slice::from_ref(&samples), 1000, sample_format, bits, &[]).unwrap();
+            let decoded = crate::wav::decode_wav(&bytes).unwrap();
+            assert_eq!(decoded.bits_per_sample, bits);
+            let output = &decoded.channels[0];
+            assert_eq!(output.len(), samples.len());
+            for (&x, &y) in samples.iter().zip(output) {
+                let expected = match sample_format {
+                    SampleFormat::Float => x,
+                    // 整數 PCM 可表示 [-1, 1 - step]
+                    SampleFormat::Int => x.clamp(-1.0, 1.0 - step),
+                };
+                assert!((y - expected).abs() <= step / 2.0 + 1e-7, "{:?} {} bits: {} -> {}", sample_format, bits, x, y);
+            }
+        }
+
+        // 8 位：0 編碼為 128，-1 為 0，滿幅正值限制在 255
+        let bytes = encode_wav(&[vec![0.0, -1.0, 1.0, 0.5]], 1000, SampleFormat::Int, 8, &[]).unwrap();
+        assert_eq!(bytes[bytes.len() - 4..], [128, 0, 255, 192]);
+    }
+}
@@ -0,0 +1,512 @@
+// ============================================================
+// 全頻譜音頻的過零 (ZC) 分析
+// 模擬 Anabat 式 ZC 偵測器：帶通濾波、施密特觸發器、分頻計數，
+// 產生與 Anabat 文件相同的 (時間, 頻率) 點序列、AnalookW 風格的叫聲參數，
+// 並可導出為 ZC 文件，便於與 ZC 參考庫比對
+// ============================================================
+
+use wasm_bindgen::prelude::*;
+
+use crate::anabat::{encode_anabat, AnabatHeader, ZcDot};
+use crate::iir::{design_sos, sosfiltfilt, FilterBand, FilterDesign};
+use crate::timestamp::Timestamp;
+
+/// 帶通濾波器的原型階數
+const BANDPASS_ORDER: usize = 4;
+/// 未設置下限頻率時，可接受的最低點頻率 (Hz)
+const MIN_DOT_FREQ_HZ: f64 = 1000.0;
+/// body（最平坦部分）佔叫聲點數的比例
+const BODY_FRACTION: f64 = 0.4;
+/// body 的最少點數
+const MIN_BODY_DOTS: usize = 3;
+
+/// ZC 分析配置
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ZcConfig {
+    /// 帶通下限 (Hz)，0 表示不做高通
+    pub low_hz: f32,
+    /// 帶通上限 (Hz)，0 或不低於 Nyquist 表示不做低通
+    pub high_hz: f32,
+    /// 分頻比（每個點包含的週期數）
+    pub div_ratio: u8,
+    /// 施密特觸發器門限（絕對幅度）
+    pub threshold: f32,
+    /// 點間隔超過此值 (毫秒) 時分為不同叫聲
+    pub call_gap_ms: f64,
+    /// 少於此點數的叫聲不計算參數
+    pub min_call_dots: usize,
+}
+
+impl Default for ZcConfig {
+    fn default() -> Self {
+        ZcConfig {
+            low_hz: 8000.0,
+            high_hz: 0.0,
+            div_ratio: 8,
+            threshold: 0.01,
+            call_gap_ms: 5.0,
+            min_call_dots: 5,
+        }
+    }
+}
+
+/// AnalookW 風格的叫聲參數
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ZcCallParameters {
+    /// 第一個點的時間 (秒)
+    pub start_time: f64,
+    /// 最後一個點的時間 (秒)
+    pub end_time: f64,
+    /// 時長 Dur (毫秒)
+    pub duration_ms: f64,
+    /// 最高頻率 Fmax (Hz)
+    pub fmax: f64,
+    /// 最低頻率 Fmin (Hz)
+    pub fmin: f64,
+    /// 平均頻率 Fmean (Hz)，按時間加權
+    pub fmean: f64,
+    /// 特徵頻率 Fc (Hz)：body 最後一點的頻率
+    pub fc: f64,
+    /// 特徵斜率 Sc (octaves/s)：body 的斜率，下降為正
+    pub sc: f64,
+    /// 點數
+    pub num_dots: usize,
+}
+
+/// 帶通濾波（零相位 Butterworth）
+fn bandpass(audio_data: &[f32], sample_rate: f32, low_hz: f32, high_hz: f32) -> Vec<f32> {
+    let nyquist = sample_rate / 2.0;
+    let has_low = low_hz > 0.0 && low_hz < nyquist;
+    let has_high = high_hz > 0.0 && high_hz < nyquist && high_hz > low_hz;
+    let (band, freq1, freq2) = match (has_low, has_high) {
+        (true, true) => (FilterBand::Bandpass, low_hz, high_hz),
+        (true, false) => (FilterBand::Highpass, low_hz, 0.0),
+        (false, true) => (FilterBand::Lowpass, high_hz, 0.0),
+        (false, false) => return audio_data.to_vec(),
+    };
+    match design_sos(FilterDesign::Butterworth, band, BANDPASS_ORDER, sample_rate, freq1, freq2, 0.0, 0.0) {
+        Some(sos) => sosfiltfilt(&sos, audio_data),
+        None => audio_data.to_vec(),
+    }
+}
+
+/// 由全頻譜音頻產生過零點序列
+///
+/// 施密特觸發器在信號低於 -threshold 後再高於 +threshold 時計一個週期，
+/// 週期的時間取最近一次向上過零（樣本間線性插值）。每 div_ratio 個週期輸出一個點，
+/// 頻率 = div_ratio / 與上一輸出點的間隔。相鄰週期的間隔對應的頻率低於帶通下限時
+/// （信號中斷），重新開始計數而不產生點。
+///
+/// # Arguments
+/// * `audio_data` - 音頻樣本
+/// * `sample_rate` - 採樣率 (Hz)
+/// * `config` - 分析配置
+///
+/// # Returns
+/// 按時間排序的點序列
+pub fn zero_crossing_dots(audio_data: &[f32], sample_rate: f32, config: &ZcConfig) -> Vec<ZcDot> {
+    if audio_data.len() < 2 || sample_rate <= 0.0 {
+        return Vec::new();
+    }
+
+    let filtered = bandpass(audio_data, sample_rate, config.low_hz, config.high_hz);
+    let div_ratio = config.div_ratio.max(1) as f64;
+    let threshold = config.threshold.abs();
+    let min_freq = if config.low_hz > 0.0 { config.low_hz as f64 } else { MIN_DOT_FREQ_HZ };
+    let max_cycle = 1.0 / min_freq;
+    let sample_period = 1.0 / sample_rate as f64;
+
+    let mut dots = Vec::new();
+    // 施密特觸發器狀態：true = 已低於 -threshold，等待越過 +threshold
+    let mut armed = false;
+    let mut last_zero = 0.0f64;
+    let mut cycles = 0usize;
+    let mut last_cycle: Option<f64> = None;
+    let mut last_event = 0.0f64;
+
+    for n in 1..filtered.len() {
+        let (prev, x) = (filtered[n - 1], filtered[n]);
+        if prev <= 0.0 && x > 0.0 {
+            let frac = (-prev / (x - prev)) as f64;
+            last_zero = (n as f64 - 1.0 + frac) * sample_period;
+        }
+
+        if x < -threshold {
+            armed = true;
+        } else if armed && x > threshold {
+            armed = false;
+            let cycle_time = last_zero;
+
+            match last_cycle {
+                Some(previous) if cycle_time - previous <= max_cycle => {
+                    cycles += 1;
+                    if cycles >= div_ratio as usize {
+                        let interval = cycle_time - last_event;
+                        if interval > 0.0 {
+                            dots.push(ZcDot {
+                                time: cycle_time,
+                                frequency: div_ratio / interval,
+                                off: false,
+                            });
+                        }
+                        last_event = cycle_time;
+                        cycles = 0;
+                    }
+                }
+                // 第一個週期或信號中斷：從此週期重新開始計數
+                _ => {
+                    last_event = cycle_time;
+                    cycles = 0;
+                }
+            }
+            last_cycle = Some(cycle_time);
+        }
+    }
+
+    dots
+}
+
+/// 按點間隔將點序列分段為叫聲
+///
+/// # Returns
+/// 每段的 [起始索引, 結束索引)
+pub fn split_calls(dots: &[ZcDot], call_gap_ms: f64) -> Vec<(usize, usize)> {
+    let gap = call_gap_ms / 1000.0;
+    let mut calls = Vec::new();
+    let mut start = 0;
+    for i in 1..=dots.len() {
+        if i == dots.len() || dots[i].time - dots[i - 1].time > gap {
+            if i > start {
+                calls.push((start, i));
+            }
+            start = i;
+        }
+    }
+    calls
+}
+
+/// log2 頻率對時間的線性回歸斜率 (octaves/s)
+fn octave_slope(dots: &[ZcDot]) -> f64 {
+    let n = dots.len() as f64;
+    let mean_t = dots.iter().map(|d| d.time).sum::<f64>() / n;
+    let mean_f = dots.iter().map(|d| d.frequency.log2()).sum::<f64>() / n;
+    let mut cov = 0.0;
+    let mut var = 0.0;
+    for d in dots {
+        let dt = d.time - mean_t;
+        cov += dt * (d.frequency.log2() - mean_f);
+        var += dt * dt;
+    }
+    if var > 0.0 {
+        cov / var
+    } else {
+        0.0
+    }
+}
+
+/// 計算一個叫聲的參數
+///
+/// body 定義為點數佔 40%（至少 3 點）、斜率絕對值最小的連續窗口；
+/// Fc 為 body 最後一點的頻率，Sc 為 body 的斜率。
+///
+/// # Arguments
+/// * `dots` - 一個叫聲的點序列
+///
+/// # Returns
+/// 點數少於 2 時返回 None
+pub fn call_parameters(dots: &[ZcDot]) -> Option<ZcCallParameters> {
+    if dots.len() < 2 {
+        return None;
+    }
+
+    let start_time = dots[0].time;
+    let end_time = dots[dots.len() - 1].time;
+    let fmax = dots.iter().map(|d| d.frequency).fold(f64::MIN, f64::max);
+    let fmin = dots.iter().map(|d| d.frequency).fold(f64::MAX, f64::min);
+
+    // 按時間加權的平均頻率：每點代表其間隔 (div / f) 內的頻率
+    let weight_sum: f64 = dots.iter().map(|d| 1.0 / d.frequency).sum();
+    let fmean = dots.len() as f64 / weight_sum;
+
+    let body_len = ((dots.len() as f64 * BODY_FRACTION).ceil() as usize)
+        .max(MIN_BODY_DOTS)
+        .min(dots.len());
+    let (body_end, body_slope) = (body_len..=dots.len())
+        .map(|end| (end, octave_slope(&dots[end - body_len..end])))
+        .min_by(|a, b| a.1.abs().total_cmp(&b.1.abs()))?;
+
+    Some(ZcCallParameters {
+        start_time,
+        end_time,
+        duration_ms: (end_time - start_time) * 1000.0,
+        fmax,
+        fmin,
+        fmean,
+        fc: dots[body_end - 1].frequency,
+        sc: -body_slope,
+        num_dots: dots.len(),
+    })
+}
+
+/// ZcAnalyzer: 全頻譜音頻的過零分析
+///
+/// 分析結果的點可直接傳給 SpectrogramEngine::compute_dots_image，
+/// 也可導出為 Anabat ZC 文件。
+#[wasm_bindgen]
+pub struct ZcAnalyzer {
+    sample_rate: f32,
+    config: ZcConfig,
+    dots: Vec<ZcDot>,
+    calls: Vec<ZcCallParameters>,
+}
+
+#[wasm_bindgen]
+impl ZcAnalyzer {
+    /// 創建分析器（預設：8 kHz 高通、分頻比 8、門限 0.01）
+    ///
+    /// # Arguments
+    /// * `sample_rate` - 採樣率 (Hz)
+    #[wasm_bindgen(constructor)]
+    pub fn new(sample_rate: f32) -> ZcAnalyzer {
+        ZcAnalyzer {
+            sample_rate,
+            config: ZcConfig::default(),
+            dots: Vec::new(),
+            calls: Vec::new(),
+        }
+    }
+
+    /// 設置帶通範圍
+    ///
+    /// # Arguments
+    /// * `low_hz` - 下限 (Hz)，0 表示不做高通
+    /// * `high_hz` - 上限 (Hz)，0 表示不做低通
+    #[wasm_bindgen]
+    pub fn set_band(&mut self, low_hz: f32, high_hz: f32) {
+        self.config.low_hz = low_hz.max(0.0);
+        self.config.high_hz = high_hz.max(0.0);
+    }
+
+    /// 設置分頻比（典型值: 8 或 16）
+    #[wasm_bindgen]
+    pub fn set_div_ratio(&mut self, div_ratio: u8) {
+        self.config.div_ratio = div_ratio.max(1);
+    }
+
+    /// 設置施密特觸發器門限（絕對幅度）
+    #[wasm_bindgen]
+    pub fn set_threshold(&mut self, threshold: f32) {
+        self.config.threshold = threshold.abs();
+    }
+
+    /// 設置叫聲分段
+    ///
+    /// # Arguments
+    /// * `call_gap_ms` - 點間隔超過此值 (毫秒) 時分為不同叫聲
+    /// * `min_call_dots` - 少於此點數的叫聲不計算參數
+    #[wasm_bindgen]
+    pub fn set_call_segmentation(&mut self, call_gap_ms: f64, min_call_dots: usize) {
+        self.config.call_gap_ms = call_gap_ms.max(0.0);
+        self.config.min_call_dots = min_call_dots;
+    }
+
+    /// 分析音頻，產生點序列與叫聲參數
+    ///
+    /// # Arguments
+    /// * `audio_data` - 音頻數據 (Float32Array)
+    ///
+    /// # Returns
+    /// 點數量
+    #[wasm_bindgen]
+    pub fn analyze(&mut self, audio_data: &[f32]) -> usize {
+        self.dots = zero_crossing_dots(audio_data, self.sample_rate, &self.config);
+        let min_dots = self.config.min_call_dots.max(2);
+        self.calls = split_calls(&self.dots, self.config.call_gap_ms)
+            .into_iter()
+            .filter(|(start, end)| end - start >= min_dots)
+            .filter_map(|(start, end)| call_parameters(&self.dots[start..end]))
+            .collect();
+        self.dots.len()
+    }
+
+    /// 點的時間 (秒，Float32Array)
+    #[wasm_bindgen]
+    pub fn get_times(&self) -> Vec<f32> {
+        self.dots.iter().map(|d| d.time as f32).collect()
+    }
+
+    /// 點的頻率 (Hz，Float32Array)
+    #[wasm_bindgen]
+    pub fn get_frequencies(&self) -> Vec<f32> {
+        self.dots.iter().map(|d| d.frequency as f32).collect()
+    }
+
+    /// 叫聲數量
+    #[wasm_bindgen]
+    pub fn get_num_calls(&self) -> usize {
+        self.calls.len()
+    }
+
+    /// 獲取一個叫聲的參數
+    ///
+    /// # Arguments
+    /// * `call_idx` - 叫聲索引
+    ///
+    /// # Returns
+    /// [start_time (s), end_time (s), Dur (ms), Fmax, Fmin, Fmean, Fc (Hz), Sc (OPS), 點數]；
+    /// 索引無效時為空
+    #[wasm_bindgen]
+    pub fn get_call_parameters(&self, call_idx: usize) -> Vec<f64> {
+        self.calls
+            .get(call_idx)
+            .map(|c| {
+                vec![
+                    c.start_time,
+                    c.end_time,
+                    c.duration_ms,
+                    c.fmax,
+                    c.fmin,
+                    c.fmean,
+                    c.fc,
+                    c.sc,
+                    c.num_dots as f64,
+                ]
+            })
+            .unwrap_or_default()
+    }
+
+    /// 導出為 Anabat ZC 文件 (132 版)
+    ///
+    /// # Arguments
+    /// * `timestamp` - 錄音開始時間 (ISO 8601，可選)
+    /// * `species` - 物種欄位
+    /// * `notes` - 註記
+    ///
+    /// # Returns
+    /// 文件內容 (Uint8Array)
+    #[wasm_bindgen]
+    pub fn export_zc(&self, timestamp: Option<String>, species: &str, notes: &str) -> Vec<u8> {
+        let header = AnabatHeader {
+            species: species.to_string(),
+            note1: notes.to_string(),
+            div_ratio: self.config.div_ratio,
+            timestamp: timestamp.as_deref().and_then(Timestamp::parse),
+            ..AnabatHeader::default()
+        };
+        encode_anabat(&header, &self.dots)
+    }
+
+    /// 釋放點序列與叫聲參數
+    #[wasm_bindgen]
+    pub fn release_memory(&mut self) {
+        self.dots = Vec::new();
+        self.calls = Vec::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::anabat::parse_anabat;
+    use std::f64::consts::PI;
+
+    const SAMPLE_RATE: f32 = 384_000.0;
+
+    /// 前後各有 silence 秒靜音的線性掃頻 f0 → f1
+    fn sweep(f0: f64, f1: f64, duration: f64, silence: f64) -> Vec<f32> {
+        let fs = SAMPLE_RATE as f64;
+        let lead = (silence * fs) as usize;
+        let len = (duration * fs) as usize;
+        let mut signal = vec![0.0f32; lead];
+        signal.extend((0..len).map(|i| {
+            let t = i as f64 / fs;
+            let phase = 2.0 * PI * (f0 * t + (f1 - f0) * t * t / (2.0 * duration));
+            (0.5 * phase.sin()) as f32
+        }));
+        signal.extend(vec![0.0f32; lead]);
+        signal
+    }
+
+    #[test]
+    fn pure_tone_dots_match_frequency() {
+        for (frequency, div_ratio) in [(40_000.0, 8), (23_456.0, 16), (90_000.0, 16)] {
+            let config = ZcConfig {
+                div_ratio,
+                ..ZcConfig::default()
+            };
+            let dots = zero_crossing_dots(&sweep(frequency, frequency, 0.05, 0.0), SAMPLE_RATE, &config);
+            let expected = (0.05 * frequency / div_ratio as f64) as usize;
+            assert!(dots.len() + 2 >= expected && dots.len() <= expected, "{}: {}", frequency, dots.len());
+            // 濾波器在兩端的過渡段之外，每點頻率都等於輸入頻率（每週期不足 4 個樣本時線性插值誤差約 0.1%）
+            for dot in &dots[2..dots.len() - 2] {
+                assert!((dot.frequency / frequency - 1.0).abs() < 2e-3, "{}: {:?}", frequency, dot);
+            }
+            let calls = split_calls(&dots, 5.0);
+            assert_eq!(calls, vec![(0, dots.len())]);
+        }
+    }
+
+    #[test]
+    fn linear_sweep_parameters() {
+        // 5 ms 的 80 → 30 kHz 掃頻，兩個叫聲相隔 50 ms
+        let call = sweep(80_000.0, 30_000.0, 0.005, 0.025);
+        let audio: Vec<f32> = call.iter().chain(&call).copied().collect();
+        let mut analyzer = ZcAnalyzer::new(SAMPLE_RATE);
+        let num_dots = analyzer.analyze(&audio);
+        assert_eq!(analyzer.get_num_calls(), 2);
+
+        let params = analyzer.get_call_parameters(1);
+        let (start, end, duration, fmax, fmin, fmean, fc, sc, count) =
+            (params[0], params[1], params[2], params[3], params[4], params[5], params[6], params[7], params[8]);
+        assert!(count > 30.0 && count as usize <= num_dots / 2 + 1);
+        assert!((start - 0.080).abs() < 0.0003, "{}", start);
+        assert!((end - start - duration / 1000.0).abs() < 1e-9);
+        // 每點平均 8 個週期：首尾點偏向掃頻內側，末尾不足 8 個週期的部分不產生點
+        assert!(duration > 4.4 && duration < 5.0, "{}", duration);
+        assert!(fmax < 80_000.0 && fmax > 77_000.0, "{}", fmax);
+        assert!(fmin > 30_000.0 && fmin < 33_500.0, "{}", fmin);
+        assert!(fmean > fmin && fmean < fmax);
+        // 線性掃頻的倍頻程斜率在高頻端最小，body 位於叫聲前段
+        assert!(fc > 55_000.0 && fc < 70_000.0, "{}", fc);
+        assert!(sc > 0.0, "{}", sc);
+        assert!(analyzer.get_call_parameters(2).is_empty());
+    }
+
+    #[test]
+    fn exported_zc_file_reads_back() {
+        let call = sweep(60_000.0, 40_000.0, 0.004, 0.01);
+        let audio: Vec<f32> = call.iter().chain(&call).copied().collect();
+        let mut analyzer = ZcAnalyzer::new(SAMPLE_RATE);
+        analyzer.set_div_ratio(16);
+        analyzer.analyze(&audio);
+
+        let bytes = analyzer.export_zc(Some("2024-05-01T21:30:15".to_string()), "Myotis", "exported");
+        let file = parse_anabat(&bytes).unwrap();
+        assert_eq!(file.header.div_ratio, 16);
+        assert_eq!(file.header.species, "Myotis");
+        assert_eq!(file.header.note1, "exported");
+        assert_eq!(file.header.timestamp.unwrap().to_string(), "2024-05-01T21:30:15");
+
+        // 點的時間量化為 1 µs；叫聲前與叫聲之間的空白為關閉的間隔
+        let times = analyzer.get_times();
+        let frequencies = analyzer.get_frequencies();
+        let on: Vec<&ZcDot> = file.dots.iter().filter(|d| !d.off).collect();
+        assert_eq!(on.len(), times.len());
+        assert_eq!(file.dots.len() - on.len(), 2);
+        for (dot, (&time, &frequency)) in on.iter().zip(times.iter().zip(&frequencies)) {
+            assert!((dot.time - time as f64).abs() < 1.5e-6, "{} {}", dot.time, time);
+            assert!((dot.frequency / frequency as f64 - 1.0).abs() < 0.01, "{} {}", dot.frequency, frequency);
+        }
+    }
+
+    #[test]
+    fn silence_produces_no_dots() {
+        let mut analyzer = ZcAnalyzer::new(SAMPLE_RATE);
+        assert_eq!(analyzer.analyze(&[0.0; 10_000]), 0);
+        assert_eq!(analyzer.analyze(&[]), 0);
+        assert_eq!(analyzer.get_num_calls(), 0);
+        // 低於門限的信號不觸發
+        analyzer.set_threshold(0.6);
+        assert_eq!(analyzer.analyze(&sweep(40_000.0, 40_000.0, 0.01, 0.0)), 0);
+    }
+}
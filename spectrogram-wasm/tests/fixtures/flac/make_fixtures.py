@@ -0,0 +1,450 @@
+#!/usr/bin/env python3
+"""Generate the FLAC decoder test fixtures.
+
+A small spec-based FLAC encoder (RFC 9639), kept independent of the Rust
+decoder. Every frame picks its channel assignment and subframe types
+explicitly, so the fixtures cover the decoder paths that a reference
+encoder would only hit by chance.
+
+For each `<name>.flac` it writes `<name>.pcm`: the expected samples as
+interleaved little-endian i32. Run from this directory:
+
+    python3 make_fixtures.py
+"""
+
+import math
+import struct
+
+# ------------------------------------------------------------
+# CRC
+# ------------------------------------------------------------
+
+
+def crc8(data):
+    crc = 0
+    for byte in data:
+        crc ^= byte
+        for _ in range(8):
+            crc = ((crc << 1) ^ 0x07) & 0xFF if crc & 0x80 else (crc << 1) & 0xFF
+    return crc
+
+
+def crc16(data):
+    crc = 0
+    for byte in data:
+        crc ^= byte << 8
+        for _ in range(8):
+            crc = ((crc << 1) ^ 0x8005) & 0xFFFF if crc & 0x8000 else (crc << 1) & 0xFFFF
+    return crc
+
+
+# ------------------------------------------------------------
+# Bit writer
+# ------------------------------------------------------------
+
+
+class BitWriter:
+    def __init__(self):
+        self.bits = []
+
+    def write(self, value, n):
+        for i in range(n - 1, -1, -1):
+            self.bits.append((value >> i) & 1)
+
+    def write_signed(self, value, n):
+        assert -(1 << (n - 1)) <= value < (1 << (n - 1)), (value, n)
+        self.write(value & ((1 << n) - 1), n)
+
+    def write_unary(self, zeros):
+        self.bits.extend([0] * zeros)
+        self.bits.append(1)
+
+    def write_rice(self, value, param):
+        folded = (value << 1) if value >= 0 else ((-value) << 1) - 1
+        self.write_unary(folded >> param)
+        self.write(folded & ((1 << param) - 1), param)
+
+    def align(self):
+        while len(self.bits) % 8:
+            self.bits.append(0)
+
+    def to_bytes(self):
+        assert len(self.bits) % 8 == 0
+        out = bytearray()
+        for i in range(0, len(self.bits), 8):
+            byte = 0
+            for bit in self.bits[i:i + 8]:
+                byte = (byte << 1) | bit
+            out.append(byte)
+        return bytes(out)
+
+
+# ------------------------------------------------------------
+# Subframes
+# ------------------------------------------------------------
+
+FIXED_COEFFICIENTS = [[], [1], [2, -1], [3, -3, 1], [4, -6, 4, -1]]
+
+
+def best_rice_param(values, max_param):
+    best = None
+    for param in range(max_param):
+        cost = sum(((v << 1 if v >= 0 else ((-v) << 1) - 1) >> param) + 1 + param for v in values)
+        if best is None or cost < best[0]:
+            best = (cost, param)
+    return best[1]
+
+
+def write_residual(w, residual, order, block_size, partition_order, escape_partitions=(), wide=False):
+    """escape_partitions: partition indices stored as raw signed values."""
+    param_bits, escape = (5, 0x1F) if wide else (4, 0xF)
+    w.write(1 if wide else 0, 2)
+    w.write(partition_order, 4)
+    partition_len = block_size >> partition_order
+    assert partition_len << partition_order == block_size and partition_len >= order
+    idx = 0
+    for p in range(1 << partition_order):
+        count = partition_len - order if p == 0 else partition_len
+        part = residual[idx:idx + count]
+        idx += count
+        if p in escape_partitions:
+            raw_bits = max((max(abs(v) for v in part) if part else 0).bit_length() + 1, 1)
+            w.write(escape, param_bits)
+            w.write(raw_bits, 5)
+            for v in part:
+                w.write_signed(v, raw_bits)
+        else:
+            param = best_rice_param(part, escape) if part else 0
+            w.write(param, param_bits)
+            for v in part:
+                w.write_rice(v, param)
+
+
+def write_subframe(w, samples, bits, kind, wasted=0, residual=None, **options):
+    """Encode one subframe.
+
+    kind: ("constant",), ("verbatim",), ("fixed", order) or
+    ("lpc", coefficients, precision, shift). For the predicted kinds,
+    `residual` replaces the computed residual (samples then only supply
+    the warm-up values).
+    """
+    if wasted:
+        assert all(s % (1 << wasted) == 0 for s in samples)
+        samples = [s >> wasted for s in samples]
+        bits -= wasted
+    block_size = len(samples)
+
+    w.write(0, 1)
+    if kind[0] == "constant":
+        assert len(set(samples)) == 1
+        w.write(0b000000, 6)
+    elif kind[0] == "verbatim":
+        w.write(0b000001, 6)
+    elif kind[0] == "fixed":
+        w.write(0b001000 | kind[1], 6)
+    elif kind[0] == "lpc":
+        w.write(0b100000 | (len(kind[1]) - 1), 6)
+    if wasted:
+        w.write(1, 1)
+        w.write_unary(wasted - 1)
+    else:
+        w.write(0, 1)
+
+    if kind[0] == "constant":
+        w.write_signed(samples[0], bits)
+    elif kind[0] == "verbatim":
+        for s in samples:
+            w.write_signed(s, bits)
+    elif kind[0] == "fixed":
+        order = kind[1]
+        coefficients = FIXED_COEFFICIENTS[order]
+        for s in samples[:order]:
+            w.write_signed(s, bits)
+        if residual is None:
+            residual = [
+                samples[i] - sum(c * samples[i - 1 - j] for j, c in enumerate(coefficients))
+                for i in range(order, block_size)
+            ]
+        write_residual(w, residual, order, block_size, **options)
+    else:
+        _, coefficients, precision, shift = kind
+        order = len(coefficients)
+        for s in samples[:order]:
+            w.write_signed(s, bits)
+        w.write(precision - 1, 4)
+        w.write_signed(shift, 5)
+        for c in coefficients:
+            w.write_signed(c, precision)
+        if residual is None:
+            residual = [
+                samples[i] - (sum(c * samples[i - 1 - j] for j, c in enumerate(coefficients)) >> shift)
+                for i in range(order, block_size)
+            ]
+        write_residual(w, residual, order, block_size, **options)
+
+
+# ------------------------------------------------------------
+# Frames and stream
+# ------------------------------------------------------------
+
+SAMPLE_SIZE_CODES = {8: 1, 12: 2, 16: 4, 20: 5, 24: 6, 32: 7}
+
+
+def utf8_number(n):
+    if n < 0x80:
+        return bytes([n])
+    assert n < 0x800
+    return bytes([0xC0 | (n >> 6), 0x80 | (n & 0x3F)])
+
+
+def encode_frame(frame_number, channels, bits, assignment, subframes, header_sample_size=True):
+    """channels: per-channel samples; subframes: (kind, wasted, options) per encoded channel."""
+    block_size = len(channels[0])
+    if assignment < 8:
+        encoded = [(c, bits) for c in channels]
+    else:
+        left, right = channels
+        side = [l - r for l, r in zip(left, right)]
+        if assignment == 8:
+            encoded = [(left, bits), (side, bits + 1)]
+        elif assignment == 9:
+            encoded = [(side, bits + 1), (right, bits)]
+        else:
+            mid = [(l + r) >> 1 for l, r in zip(left, right)]
+            encoded = [(mid, bits), (side, bits + 1)]
+
+    if block_size == 4096:
+        block_code, extra = 12, b""
+    elif block_size <= 256:
+        block_code, extra = 6, bytes([block_size - 1])
+    else:
+        block_code, extra = 7, struct.pack(">H", block_size - 1)
+    sample_size_code = SAMPLE_SIZE_CODES[bits] if header_sample_size else 0
+
+    header = bytearray(b"\xFF\xF8")
+    header.append((block_code << 4) | 0)  # sample rate from STREAMINFO
+    header.append((assignment << 4) | (sample_size_code << 1))
+    header += utf8_number(frame_number)
+    header += extra
+    header.append(crc8(header))
+
+    w = BitWriter()
+    for (samples, sub_bits), (kind, wasted, options) in zip(encoded, subframes):
+        write_subframe(w, samples, sub_bits, kind, wasted, **options)
+    w.align()
+    frame = bytes(header) + w.to_bytes()
+    return frame + struct.pack(">H", crc16(frame))
+
+
+def metadata_block(block_type, content, last=False):
+    return bytes([(0x80 if last else 0) | block_type]) + struct.pack(">I", len(content))[1:] + content
+
+
+def stream_info(block_size, sample_rate, num_channels, bits, total_samples):
+    w = BitWriter()
+    w.write(block_size, 16)
+    w.write(block_size, 16)
+    w.write(0, 24)
+    w.write(0, 24)
+    w.write(sample_rate, 20)
+    w.write(num_channels - 1, 3)
+    w.write(bits - 1, 5)
+    w.write(total_samples, 36)
+    w.write(0, 128)
+    return w.to_bytes()
+
+
+def vorbis_comment(vendor, comments):
+    out = struct.pack("<I", len(vendor)) + vendor
+    out += struct.pack("<I", len(comments))
+    for entry in comments:
+        out += struct.pack("<I", len(entry)) + entry
+    return out
+
+
+def riff_chunk(chunk_id, content):
+    out = chunk_id + struct.pack("<I", len(content)) + content
+    return out + (b"\0" if len(content) % 2 else b"")
+
+
+def write_fixture(name, flac_bytes, channels):
+    with open(name + ".flac", "wb") as f:
+        f.write(flac_bytes)
+    with open(name + ".pcm", "wb") as f:
+        for frame in zip(*channels):
+            f.write(struct.pack("<%di" % len(frame), *frame))
+
+
+# ------------------------------------------------------------
+# Signals
+# ------------------------------------------------------------
+
+
+def lcg(seed):
+    state = seed
+    while True:
+        state = (state * 1103515245 + 12345) & 0x7FFFFFFF
+        yield state
+
+
+def tone(n, amplitude, period, phase=0.0, noise=0, seed=1):
+    rand = lcg(seed)
+    return [
+        int(round(amplitude * math.sin(2 * math.pi * i / period + phase))) + (next(rand) % (2 * noise + 1) - noise if noise else 0)
+        for i in range(n)
+    ]
+
+
+def clamp(values, bits):
+    lo, hi = -(1 << (bits - 1)), (1 << (bits - 1)) - 1
+    return [min(max(v, lo), hi) for v in values]
+
+
+def frames_of(channels, block_size):
+    total = len(channels[0])
+    return [[c[start:start + block_size] for c in channels] for start in range(0, total, block_size)]
+
+
+# Second-order sine recurrence x[n] = 2cos(w) x[n-1] - x[n-2], quantised to 12-bit coefficients with shift 10
+def resonator(period):
+    return [int(round(2 * math.cos(2 * math.pi / period) * 1024)), -1024]
+
+
+# ------------------------------------------------------------
+# Fixtures
+# ------------------------------------------------------------
+
+
+def fixture_16bit_stereo():
+    """16-bit stereo, one frame per channel assignment plus a short last frame."""
+    bits, block_size = 16, 256
+    left = clamp(tone(1124, 12000, 37.0, noise=40, seed=3), bits)
+    right = clamp(tone(1124, 9000, 37.0, phase=0.3, noise=40, seed=7), bits)
+    # Frame 3: constant left channel, right channel in multiples of 4 (wasted bits)
+    left[512:768] = [-1234] * 256
+    right[512:768] = [v - v % 4 for v in right[512:768]]
+
+    plain = {"partition_order": 0}
+    frames = [
+        (9, [(("fixed", 0), 0, {"partition_order": 1, "wide": True}), (("verbatim",), 0, {})]),
+        (8, [(("fixed", 1), 0, plain), (("fixed", 3), 0, {"partition_order": 3, "escape_partitions": (1,)})]),
+        (1, [(("constant",), 0, {}), (("fixed", 4), 2, plain)]),
+        (10, [(("lpc", resonator(37.0), 12, 10), 0, {"partition_order": 4}),
+              (("lpc", [1536, -512, 0, 0, 0, 0, 0, 0], 13, 10), 0, plain)]),
+        (10, [(("fixed", 2), 0, {"partition_order": 2}), (("verbatim",), 0, {})]),
+    ]
+    body = b""
+    for number, (block, (assignment, subframes)) in enumerate(zip(frames_of([left, right], block_size), frames)):
+        body += encode_frame(number, block, bits, assignment, subframes)
+
+    guano = b"GUANO|Version:1.0\nMake:Test\nTimestamp:2024-05-01T21:00:00\nSpecies Auto ID:Pipkuh\n"
+    riff_header = b"RIFF" + struct.pack("<I", 0) + b"WAVE"
+    riff_header += riff_chunk(b"fmt ", struct.pack("<HHIIHH", 1, 2, 48000, 192000, 4, 16))
+    riff_header += riff_chunk(b"guan", guano)
+    riff_header += b"data" + struct.pack("<I", len(left) * 4)
+    riff_trailer = riff_chunk(b"LIST", b"INFO" + riff_chunk(b"ICMT", b"trailer\0"))
+
+    flac = b"fLaC"
+    flac += metadata_block(0, stream_info(block_size, 48000, 2, bits, len(left)))
+    flac += metadata_block(4, vorbis_comment(b"fixture encoder", [b"TITLE=Sixteen", b"artist=Someone"]))
+    flac += metadata_block(2, b"riff" + riff_header)
+    flac += metadata_block(2, b"riff" + riff_trailer)
+    flac += metadata_block(1, bytes(16), last=True)
+    flac += body
+    return flac, [left, right]
+
+
+def fixture_24bit_stereo():
+    """24-bit stereo at 384 kHz, with GUANO stored as Vorbis comments."""
+    bits, block_size = 24, 4096
+    left = clamp(tone(4096 * 2, 3_000_000, 53.0, noise=500, seed=11), bits)
+    right = clamp(tone(4096 * 2, 2_500_000, 53.0, phase=1.1, noise=500, seed=13), bits)
+    frames = [
+        (10, [(("lpc", resonator(53.0), 12, 10), 0, {"partition_order": 5}),
+              (("fixed", 2), 0, {"partition_order": 3, "wide": True})]),
+        (8, [(("fixed", 2), 0, {"partition_order": 6}), (("lpc", resonator(53.0), 12, 10), 0, {"partition_order": 0})]),
+    ]
+    body = b""
+    for number, (block, (assignment, subframes)) in enumerate(zip(frames_of([left, right], block_size), frames)):
+        body += encode_frame(number, block, bits, assignment, subframes, header_sample_size=number == 0)
+
+    comments = [b"GUANO|Version=1.0", b"Make=Test", b"Samplerate=384000", b"TE=10"]
+    flac = b"fLaC"
+    flac += metadata_block(0, stream_info(block_size, 384000, 2, bits, len(left)))
+    flac += metadata_block(4, vorbis_comment(b"fixture encoder", comments), last=True)
+    flac += body
+    return flac, [left, right]
+
+
+def fixture_32bit_stereo():
+    """32-bit stereo whose side channel needs all 33 bits."""
+    bits, block_size = 32, 192
+    left = clamp([2_000_000_000 + v for v in tone(576, 100_000_000, 29.0, noise=1000, seed=17)], bits)
+    right = clamp([-2_000_000_000 + v for v in tone(576, 100_000_000, 29.0, phase=0.5, noise=1000, seed=19)], bits)
+    frames = [
+        (8, [(("verbatim",), 0, {}), (("verbatim",), 0, {})]),
+        (9, [(("fixed", 1), 0, {"partition_order": 1, "wide": True}), (("fixed", 2), 0, {"partition_order": 0, "wide": True})]),
+        (10, [(("fixed", 2), 0, {"partition_order": 2, "wide": True}), (("verbatim",), 0, {})]),
+    ]
+    body = b""
+    for number, (block, (assignment, subframes)) in enumerate(zip(frames_of([left, right], block_size), frames)):
+        body += encode_frame(number, block, bits, assignment, subframes)
+
+    flac = b"fLaC"
+    flac += metadata_block(0, stream_info(block_size, 250000, 2, bits, len(left)), last=True)
+    flac += body
+    return flac, [left, right]
+
+
+def fixture_corrupt_frame():
+    """16-bit mono with a damaged second frame: the decoder should substitute silence."""
+    bits, block_size = 16, 256
+    mono = clamp(tone(768, 10000, 41.0, noise=20, seed=23), bits)
+    frames = []
+    for number, block in enumerate(frames_of([mono], block_size)):
+        frames.append(encode_frame(number, block, bits, 0, [(("fixed", 2), 0, {"partition_order": 2})]))
+    # Damage frame 2's subframe data: the header CRC-8 still matches, the frame CRC-16 does not
+    damaged = bytearray(frames[1])
+    damaged[len(damaged) // 2] ^= 0x5A
+    frames[1] = bytes(damaged)
+
+    flac = b"fLaC"
+    flac += metadata_block(0, stream_info(block_size, 96000, 1, bits, len(mono)), last=True)
+    flac += b"".join(frames)
+    expected = mono[:block_size] + [0] * block_size + mono[2 * block_size:]
+    return flac, [expected]
+
+
+def fixture_overflow_residual():
+    """32-bit mono whose 2nd and 3rd frames carry valid CRCs but residuals that overflow i64.
+
+    Frame 2: FIXED order 4 with every residual at the 32-bit maximum; the
+    quartic growth passes 2^63 well before the end of the 4096-sample block.
+    Frame 3: LPC order 1 with coefficient 16383 and shift 0, which grows
+    exponentially. The decoder should substitute silence for both.
+    """
+    bits, block_size = 32, 4096
+    mono = clamp(tone(4 * block_size, 1_000_000_000, 61.0, noise=1000, seed=29), bits)
+    blocks = frames_of([mono], block_size)
+    frames = [
+        encode_frame(0, blocks[0], bits, 0, [(("fixed", 2), 0, {"partition_order": 0, "wide": True})]),
+        encode_frame(1, blocks[1], bits, 0, [(("fixed", 4), 0, {
+            "partition_order": 0, "escape_partitions": (0,), "residual": [(1 << 31) - 1] * (block_size - 4)})]),
+        encode_frame(2, blocks[2], bits, 0, [(("lpc", [16383], 15, 0), 0, {
+            "partition_order": 0, "residual": [1] * (block_size - 1)})]),
+        encode_frame(3, blocks[3], bits, 0, [(("fixed", 2), 0, {"partition_order": 0, "wide": True})]),
+    ]
+
+    flac = b"fLaC"
+    flac += metadata_block(0, stream_info(block_size, 192000, 1, bits, len(mono)), last=True)
+    flac += b"".join(frames)
+    expected = mono[:block_size] + [0] * (2 * block_size) + mono[3 * block_size:]
+    return flac, [expected]
+
+
+if __name__ == "__main__":
+    write_fixture("stereo16", *fixture_16bit_stereo())
+    write_fixture("stereo24", *fixture_24bit_stereo())
+    write_fixture("stereo32", *fixture_32bit_stereo())
+    write_fixture("corrupt_frame", *fixture_corrupt_frame())
+    write_fixture("overflow_residual", *fixture_overflow_residual())